@@ -0,0 +1,410 @@
+//! Aces: the odd named enemy pilot who flies better than the rest. One that breaks off and makes it
+//! out past the border is remembered in the profile, and comes back later with a better ship.
+
+use std::{
+    f32::consts::{PI, TAU},
+    time::Duration,
+};
+
+use bevy::{
+    app::{Plugin, Update},
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::{Or, With, Without},
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, OnEnter,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::DespawnRecursiveExt,
+    math::Vec2,
+    prelude::App,
+    render::color::Color,
+    time::{Time, Timer, TimerMode, Virtual},
+};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    barks::Bark,
+    dialogue::{BarkKind, Dialogue},
+    events::{BossArrived, ShipDestroyed},
+    gameplay::{
+        handle_npc_logic, kill_dead_ships, Captured, EnemySpacecraftBundle, GameState, GameplaySet,
+        PlayerMarker, ShipTextures, ShipType, Spacecraft, BORDER_KILL_RADIUS, TURN_SPEED,
+    },
+    interpolation::frame_steps,
+    practice::CapturePractice,
+    range::TargetRange,
+    seed::RunSeed,
+    storage, GameLifecycleState,
+};
+
+const ACES_KEY: &str = "aces.ron";
+
+/// How often the game considers sending in an ace
+pub const ACE_CHECK_TIME: Duration = Duration::from_secs(30);
+pub const ACE_CHANCE: f64 = 0.3;
+/// Chance that the ace sent in is a rival who got away before, if there are any
+pub const RIVAL_RETURN_CHANCE: f64 = 0.6;
+/// Most rivals a profile remembers at once
+pub const MAX_RIVALS: usize = 3;
+/// How far from the player an ace warps in
+const ACE_SPAWN_DISTANCE: f32 = 2.;
+
+/// Hulls an ace flies, each escape moving it one further up the list
+const ACE_HULLS: [ShipType; 4] = [
+    ShipType::Ship3,
+    ShipType::Ship4,
+    ShipType::Ship5,
+    ShipType::Ship6,
+];
+const ACE_NAMES: [&str; 8] = [
+    "Vesper",
+    "Red Jackal",
+    "Nightjar",
+    "Old Rust",
+    "Kestrel",
+    "Halcyon",
+    "Widowmaker",
+    "Gravedigger",
+];
+const ACE_TINT: Color = Color::rgb(1., 0.85, 0.3);
+
+pub struct AcePlugin;
+
+impl Plugin for AcePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AceRoster::load())
+            .add_systems(OnEnter(GameLifecycleState::Game), reset_aces)
+            .add_systems(
+                Update,
+                (
+                    fly_aces.after(handle_npc_logic).in_set(GameplaySet::Input),
+                    call_in_aces
+                        .run_if(not(resource_exists::<CapturePractice>))
+                        .run_if(not(resource_exists::<TargetRange>))
+                        .in_set(GameplaySet::Simulation),
+                    (ground_aces, let_aces_escape)
+                        .before(kill_dead_ships)
+                        .in_set(GameplaySet::Cleanup),
+                )
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+/// An ace pilot and what they've picked up from getting away
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ace {
+    pub name: String,
+    pub ship_type: ShipType,
+    pub escapes: u32,
+}
+
+impl Ace {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ship_type: ACE_HULLS[0],
+            escapes: 0,
+        }
+    }
+
+    /// Each escape toughens the hull and moves the ace up to a better ship
+    pub fn escaped(&mut self) {
+        self.escapes += 1;
+        let hull = ACE_HULLS
+            .iter()
+            .position(|hull| *hull == self.ship_type)
+            .unwrap_or(0);
+        self.ship_type = ACE_HULLS[(hull + 1).min(ACE_HULLS.len() - 1)];
+    }
+
+    pub fn max_health(&self) -> i32 {
+        Spacecraft::from_template(self.ship_type, Vec2::ZERO).health + self.escapes as i32
+    }
+
+    /// Multiplier on the speed the regular AI would fly at
+    pub fn speed(&self) -> f32 {
+        1.3 + 0.1 * self.escapes.min(4) as f32
+    }
+
+    /// Multiplier on how far the regular AI would turn each frame
+    pub fn turn_rate(&self) -> f32 {
+        1.5 + 0.25 * self.escapes.min(4) as f32
+    }
+
+    /// Extra reload progress, as a fraction of real time
+    pub fn reload_bonus(&self) -> f32 {
+        0.3 + 0.15 * self.escapes.min(4) as f32
+    }
+}
+
+/// Aces who got away, kept between runs
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AceRoster {
+    pub rivals: Vec<Ace>,
+}
+
+impl AceRoster {
+    pub fn load() -> Self {
+        storage::read(ACES_KEY)
+            .and_then(|contents| ron::from_str::<AceRoster>(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(ACES_KEY, &contents),
+            Err(e) => println!("Could not serialise aces: {e}"),
+        }
+    }
+
+    /// Remembers an ace that got away, upgraded for next time
+    pub fn record_escape(&mut self, mut ace: Ace) {
+        ace.escaped();
+        self.rivals.retain(|rival| rival.name != ace.name);
+        self.rivals.push(ace);
+        if self.rivals.len() > MAX_RIVALS {
+            self.rivals.remove(0);
+        }
+    }
+
+    /// An ace that was shot down or captured is gone for good
+    pub fn record_defeat(&mut self, name: &str) {
+        self.rivals.retain(|rival| rival.name != name);
+    }
+
+    /// A rival to send back in, or a fresh ace with a name nobody is using
+    pub fn pick(&self, out: &[String], rand: &mut impl Rng) -> Option<Ace> {
+        let waiting = self
+            .rivals
+            .iter()
+            .filter(|rival| !out.contains(&rival.name))
+            .collect::<Vec<_>>();
+        if !waiting.is_empty() && rand.gen_bool(RIVAL_RETURN_CHANCE) {
+            return waiting.choose(rand).map(|rival| (*rival).clone());
+        }
+        let free = ACE_NAMES
+            .iter()
+            .filter(|name| {
+                !out.iter().any(|taken| taken == *name)
+                    && !self.rivals.iter().any(|rival| rival.name == **name)
+            })
+            .collect::<Vec<_>>();
+        free.choose(rand).map(|name| Ace::new(name))
+    }
+}
+
+#[derive(Component)]
+pub struct AcePilot {
+    pub ace: Ace,
+    /// Set once the ace is hurt badly enough to make a run for the border
+    escaping: bool,
+}
+
+#[derive(Resource)]
+pub struct AceSchedule {
+    timer: Timer,
+    /// Aces in the arena this run, kept by name so they can be told apart after they're despawned
+    out: Vec<(Entity, String)>,
+}
+
+fn reset_aces(mut commands: Commands) {
+    commands.insert_resource(AceSchedule {
+        timer: Timer::new(ACE_CHECK_TIME, TimerMode::Repeating),
+        out: vec![],
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn call_in_aces(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut schedule: ResMut<AceSchedule>,
+    roster: Res<AceRoster>,
+    aces: Query<&AcePilot>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    textures: Res<ShipTextures>,
+    mut dialogue: ResMut<Dialogue>,
+    mut bosses: EventWriter<BossArrived>,
+    mut seed: ResMut<RunSeed>,
+) {
+    if !schedule.timer.tick(time.delta()).just_finished() || !aces.is_empty() {
+        return;
+    }
+    let rand = seed.rng();
+    if !rand.gen_bool(ACE_CHANCE) {
+        return;
+    }
+    let out = schedule
+        .out
+        .iter()
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<_>>();
+    if let (Ok(player), Some(ace)) = (player.get_single(), roster.pick(&out, rand)) {
+        let position =
+            player.position + Vec2::from_angle(rand.gen_range(0. ..TAU)) * ACE_SPAWN_DISTANCE;
+        let ship = EnemySpacecraftBundle::create_ship(ace.ship_type, position, &textures)
+            .with_tint(ACE_TINT)
+            .with_health(ace.max_health());
+        let line = match ace.escapes {
+            0 => format!(
+                "That one's flying like an ace. Comms say they go by {}.",
+                ace.name
+            ),
+            _ => format!("{} is back, and they've brought a better ship.", ace.name),
+        };
+        dialogue.queue_lines([line.into()]);
+        let name = ace.name.clone();
+        let ship_type = ace.ship_type;
+        let entity = commands
+            .spawn(ship)
+            .insert((
+                Name::new(format!("Ace {name}")),
+                AcePilot {
+                    ace,
+                    escaping: false,
+                },
+            ))
+            .id();
+        schedule.out.push((entity, name));
+        bosses.send(BossArrived {
+            ship: entity,
+            ship_type,
+        });
+    }
+}
+
+/// Aces turn tighter, fly faster and reload quicker than the pilot logic they share with the rest,
+/// and break off for the border once their hull is down to half
+#[allow(clippy::type_complexity)]
+fn fly_aces(
+    time: Res<Time>,
+    virtual_time: Res<Time<Virtual>>,
+    mut aces: Query<
+        (Entity, &mut AcePilot, &mut Spacecraft),
+        (Without<Captured>, Without<PlayerMarker>),
+    >,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    mut barks: EventWriter<Bark>,
+) {
+    for (ship, mut pilot, mut craft) in aces.iter_mut() {
+        if !pilot.escaping && craft.health * 2 <= pilot.ace.max_health() {
+            pilot.escaping = true;
+            barks.send(Bark {
+                ship,
+                kind: BarkKind::Retreating,
+            });
+        }
+        let target = match (pilot.escaping, player.get_single()) {
+            (false, Ok(player)) => player.position,
+            // Straight out from the middle of the arena is the shortest way past the border
+            _ => craft.position + craft.position.normalize_or_zero() + Vec2::Y * 0.01,
+        };
+        let direction = target - craft.position;
+        let extra_turn = TURN_SPEED * (pilot.ace.turn_rate() - 1.) * frame_steps(&virtual_time);
+        let delta =
+            (f32::atan2(direction.x, direction.y) - craft.heading + PI).rem_euclid(TAU) - PI;
+        craft.rotate(delta.clamp(-extra_turn, extra_turn));
+        craft.velocity = match pilot.escaping {
+            true => craft.profile().max_velocity * pilot.ace.speed(),
+            false => craft.velocity * pilot.ace.speed(),
+        };
+        let bonus = time.delta().mul_f32(pilot.ace.reload_bonus());
+        craft.weapon_cooldown.tick(bonus);
+    }
+}
+
+/// Aces that go down, or end up on the player's side, are struck off the roster
+#[allow(clippy::type_complexity)]
+fn ground_aces(
+    mut commands: Commands,
+    mut destroyed: EventReader<ShipDestroyed>,
+    mut roster: ResMut<AceRoster>,
+    mut schedule: ResMut<AceSchedule>,
+    turned: Query<Entity, (With<AcePilot>, Or<(With<Captured>, With<PlayerMarker>)>)>,
+    mut dialogue: ResMut<Dialogue>,
+) {
+    let mut lost = destroyed.read().map(|event| event.ship).collect::<Vec<_>>();
+    for entity in turned.iter() {
+        commands.entity(entity).remove::<AcePilot>();
+        lost.push(entity);
+    }
+    let (grounded, still_out) = schedule
+        .out
+        .drain(..)
+        .partition::<Vec<_>, _>(|(entity, _)| lost.contains(entity));
+    schedule.out = still_out;
+    for (_, name) in grounded.iter() {
+        roster.record_defeat(name);
+        dialogue.queue_lines([format!("That's the last we'll see of {name}.").into()]);
+    }
+    if !grounded.is_empty() {
+        roster.save();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn let_aces_escape(
+    mut commands: Commands,
+    aces: Query<(Entity, &AcePilot, &Spacecraft), (Without<Captured>, Without<PlayerMarker>)>,
+    mut roster: ResMut<AceRoster>,
+    mut schedule: ResMut<AceSchedule>,
+    mut dialogue: ResMut<Dialogue>,
+) {
+    for (entity, pilot, craft) in aces.iter() {
+        if craft.position.length() < BORDER_KILL_RADIUS {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        schedule.out.retain(|(out, _)| *out != entity);
+        dialogue.queue_lines([format!(
+            "{} got away. We haven't seen the last of them.",
+            pilot.ace.name
+        )
+        .into()]);
+        roster.record_escape(pilot.ace.clone());
+        roster.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aces_who_escape_come_back_stronger() {
+        let mut rand = rand::thread_rng();
+        let mut roster = AceRoster::default();
+        let ace = roster.pick(&[], &mut rand).unwrap();
+        assert_eq!(ace.escapes, 0);
+        let first_health = ace.max_health();
+
+        roster.record_escape(ace.clone());
+        roster.record_escape(roster.rivals[0].clone());
+        let rival = &roster.rivals[0];
+        assert_eq!(roster.rivals.len(), 1);
+        assert_eq!(rival.escapes, 2);
+        assert_ne!(rival.ship_type, ace.ship_type);
+        assert!(rival.max_health() > first_health);
+        assert!(rival.speed() > ace.speed());
+
+        // A rival already in the arena isn't sent in twice
+        let out = [rival.name.clone()];
+        for _ in 0..20 {
+            let picked = roster.pick(&out, &mut rand).unwrap();
+            assert_ne!(picked.name, rival.name);
+        }
+        let name = rival.name.clone();
+        roster.record_defeat(&name);
+        assert!(roster.rivals.is_empty());
+    }
+}
@@ -0,0 +1,432 @@
+//! Assists for players who find rapid or simultaneous inputs hard going. [F] on the main menu has
+//! the guns fire by themselves whenever an enemy is lined up, and [C] turns the 1/2/3 capture
+//! choice into a slow hold on one key, that steps through the options and takes whichever is
+//! showing when it's let go. [G] eases off turns that would point the ship straight into another
+//! one or the border close by, for newer pilots. [J] draws the nose a little towards an enemy just
+//! off it while firing on a gamepad, making up for how rough a stick is to aim with. How hard it
+//! pulls is `aim_assist_strength` in the settings file. Hard is flown without either of those last
+//! two, as it's the setting scores are chased on, and runs flown with aim assist don't go on the
+//! high score table at all.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Local, Query, Res, ResMut},
+    },
+    hierarchy::DespawnRecursiveExt,
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    time::{Real, Time},
+    ui::{node_bundles::TextBundle, PositionType, Style, Val, ZIndex},
+};
+
+use crate::{
+    bindings::{key_name, Action, InputBindings},
+    controls::Pads,
+    gameplay::{
+        handle_inputs, CaptureMoment, GameState, GameplaySet, ShipUsageDecision, BORDER_KILL_RADIUS,
+    },
+    settings::{Difficulty, Settings},
+    GameLifecycleState, MainMenuMarker,
+};
+
+/// How far ahead auto-fire looks for something to shoot
+pub const AUTO_FIRE_RANGE: f32 = 1.2;
+/// How far either side of the nose, in radians, an enemy can be and still count as lined up
+pub const AUTO_FIRE_ARC: f32 = 0.2;
+/// Real time the capture key has to be held to step on to the next option
+pub const HOLD_STEP: Duration = Duration::from_secs(1);
+/// How close a ship or the border has to be for flight assist to steer clear of it
+pub const ASSIST_RANGE: f32 = 0.2;
+/// How far either side of the nose, in radians, flight assist counts as heading straight in
+pub const ASSIST_CONE: f32 = 0.6;
+/// Share of a turn towards a hazard that flight assist lets through
+pub const ASSIST_DAMPING: f32 = 0.35;
+/// How far either side of the nose, in radians, aim assist looks for something to pull towards
+pub const AIM_ASSIST_CONE: f32 = 0.35;
+
+pub struct AssistPlugin;
+
+impl Plugin for AssistPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_assist_text)
+            .add_systems(
+                Update,
+                toggle_assists.run_if(in_state(GameLifecycleState::MainMenu)),
+            )
+            .add_systems(
+                Update,
+                hold_to_capture
+                    .after(handle_inputs)
+                    .in_set(GameplaySet::Input)
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+/// Whether a target is close in front of a ship pointing along `heading`
+pub fn in_forward_arc(position: Vec2, heading: f32, target: Vec2) -> bool {
+    let offset = target - position;
+    let distance = offset.length();
+    if distance > AUTO_FIRE_RANGE || distance <= f32::EPSILON {
+        return false;
+    }
+    let aim = Vec2::new(heading.sin(), heading.cos());
+    aim.angle_between(offset).abs() <= AUTO_FIRE_ARC
+}
+
+/// Whether flight assist flies with a run at `difficulty`
+pub fn flight_assist_on(settings: &Settings, difficulty: Difficulty) -> bool {
+    settings.flight_assist && difficulty != Difficulty::Hard
+}
+
+/// Whether aim assist flies with a run at `difficulty`
+pub fn aim_assist_on(settings: &Settings, difficulty: Difficulty) -> bool {
+    settings.aim_assist && difficulty != Difficulty::Hard
+}
+
+/// The turn that brings the nose towards the nearest enemy within [`AIM_ASSIST_CONE`] and
+/// [`AUTO_FIRE_RANGE`], never more than `max_pull` either way. Nothing there, no pull.
+pub fn aim_pull(
+    position: Vec2,
+    heading: f32,
+    max_pull: f32,
+    enemies: impl Iterator<Item = Vec2>,
+) -> f32 {
+    let aim = Vec2::new(heading.sin(), heading.cos());
+    enemies
+        .map(|enemy| enemy - position)
+        .filter(|offset| offset.length() <= AUTO_FIRE_RANGE && offset.length() > f32::EPSILON)
+        .filter(|offset| aim.angle_between(*offset).abs() <= AIM_ASSIST_CONE)
+        .min_by(|a, b| a.length().total_cmp(&b.length()))
+        // Headings run clockwise, angles between vectors anticlockwise
+        .map_or(0., |offset| {
+            (-aim.angle_between(offset)).clamp(-max_pull, max_pull)
+        })
+}
+
+/// Eases off a turn that would bring the nose round onto something close by. Turns away, or past
+/// it, go through untouched, so it never fights the pilot getting out of trouble.
+pub fn damp_turn(
+    position: Vec2,
+    heading: f32,
+    turn: f32,
+    ships: impl Iterator<Item = Vec2>,
+) -> f32 {
+    if turn == 0. {
+        return turn;
+    }
+    let border = match BORDER_KILL_RADIUS - position.length() <= ASSIST_RANGE {
+        true => Some(position * BORDER_KILL_RADIUS / position.length().max(f32::EPSILON)),
+        false => None,
+    };
+    let aim = Vec2::new(heading.sin(), heading.cos());
+    let turned = Vec2::new((heading + turn).sin(), (heading + turn).cos());
+    let towards_hazard = ships
+        .filter(|ship| ship.distance(position) <= ASSIST_RANGE)
+        .chain(border)
+        .any(|hazard| {
+            let offset = hazard - position;
+            turned.angle_between(offset).abs() < aim.angle_between(offset).abs()
+                && turned.angle_between(offset).abs() <= ASSIST_CONE
+        });
+    match towards_hazard {
+        true => turn * ASSIST_DAMPING,
+        false => turn,
+    }
+}
+
+/// The option showing after the capture key has been held this long. It carries on round past
+/// the last, so overshooting is never a mistake that can't be taken back.
+pub fn held_choice(held: Duration) -> ShipUsageDecision {
+    match (held.as_secs_f32() / HOLD_STEP.as_secs_f32()) as usize % 3 {
+        0 => ShipUsageDecision::Transfer,
+        1 => ShipUsageDecision::Keep,
+        _ => ShipUsageDecision::Destroy,
+    }
+}
+
+fn choice_name(choice: ShipUsageDecision) -> &'static str {
+    match choice {
+        ShipUsageDecision::Transfer => "Transfer",
+        ShipUsageDecision::Keep => "Keep",
+        ShipUsageDecision::Destroy => "Destroy",
+    }
+}
+
+/// One of the assist toggles on the main menu
+#[derive(Component, Clone, Copy)]
+pub enum AssistLabel {
+    AutoFire,
+    HoldToCapture,
+    FlightAssist,
+    AimAssist,
+}
+
+impl AssistLabel {
+    const ALL: [AssistLabel; 4] = [
+        AssistLabel::AutoFire,
+        AssistLabel::HoldToCapture,
+        AssistLabel::FlightAssist,
+        AssistLabel::AimAssist,
+    ];
+
+    fn key(self) -> KeyCode {
+        match self {
+            AssistLabel::AutoFire => KeyCode::KeyF,
+            AssistLabel::HoldToCapture => KeyCode::KeyC,
+            AssistLabel::FlightAssist => KeyCode::KeyG,
+            AssistLabel::AimAssist => KeyCode::KeyJ,
+        }
+    }
+
+    fn setting(self, settings: &mut Settings) -> &mut bool {
+        match self {
+            AssistLabel::AutoFire => &mut settings.auto_fire,
+            AssistLabel::HoldToCapture => &mut settings.hold_to_capture,
+            AssistLabel::FlightAssist => &mut settings.flight_assist,
+            AssistLabel::AimAssist => &mut settings.aim_assist,
+        }
+    }
+
+    fn text(self, settings: &Settings) -> String {
+        let (name, on) = match self {
+            AssistLabel::AutoFire => ("[F] Auto-fire", settings.auto_fire),
+            AssistLabel::HoldToCapture => ("[C] Hold to capture", settings.hold_to_capture),
+            AssistLabel::FlightAssist => ("[G] Flight assist", settings.flight_assist),
+            AssistLabel::AimAssist => ("[J] Pad aim assist", settings.aim_assist),
+        };
+        let state = match on {
+            true => "On",
+            false => "Off",
+        };
+        let mut text = format!("{name}: {state}");
+        let kept_off = match self {
+            AssistLabel::FlightAssist => !flight_assist_on(settings, settings.difficulty),
+            AssistLabel::AimAssist => !aim_assist_on(settings, settings.difficulty),
+            _ => false,
+        };
+        if on && kept_off {
+            text.push_str(" (not on Hard)");
+        }
+        text
+    }
+}
+
+#[derive(Component)]
+pub struct HoldPromptMarker;
+
+fn spawn_assist_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    let font = asset_server.load("alphbeta.ttf");
+    for (row, label) in AssistLabel::ALL.into_iter().enumerate() {
+        commands
+            .spawn(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(225. + 30. * row as f32),
+                    right: Val::Px(15.),
+                    ..default()
+                },
+                ..TextBundle::from_section(
+                    label.text(&settings),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 20.,
+                        color: Color::WHITE,
+                    },
+                )
+            })
+            .insert((MainMenuMarker, label));
+    }
+}
+
+/// Also catches the settings being swapped out by a profile switch, or the difficulty changing
+fn toggle_assists(
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut labels: Query<(&mut Text, &AssistLabel)>,
+) {
+    for label in AssistLabel::ALL {
+        if inputs.just_pressed(label.key()) {
+            let setting = label.setting(&mut settings);
+            *setting = !*setting;
+        }
+    }
+    if settings.is_changed() {
+        for (mut text, label) in labels.iter_mut() {
+            text.sections[0].value = label.text(&settings);
+        }
+    }
+}
+
+/// Holds are timed in real time, as the game is crawling along while the choice is up. Only the
+/// first capture choice's key or button counts, so a quick tap still takes that one.
+#[allow(clippy::too_many_arguments)]
+pub fn hold_to_capture(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    capture: Option<Res<CaptureMoment>>,
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    bindings: Res<InputBindings>,
+    real_time: Res<Time<Real>>,
+    mut held: Local<Option<Duration>>,
+    asset_server: Res<AssetServer>,
+    mut prompt: Query<(Entity, &mut Text), With<HoldPromptMarker>>,
+) {
+    let deciding = matches!(capture.as_deref(), Some(CaptureMoment::Deciding));
+    if !settings.hold_to_capture || !deciding {
+        *held = None;
+        for (entity, _) in prompt.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    let action = Action::CaptureOption1;
+    let key = bindings.key(action);
+    let button = bindings.button(action);
+    if inputs.pressed(key) || pads.pressed(button) {
+        let so_far = held.unwrap_or_default();
+        *held = Some(so_far + real_time.delta());
+    } else if let Some(so_far) = held.take() {
+        commands.spawn(held_choice(so_far));
+    }
+    let showing = held_choice(held.unwrap_or_default());
+    let options = [
+        ShipUsageDecision::Transfer,
+        ShipUsageDecision::Keep,
+        ShipUsageDecision::Destroy,
+    ]
+    .into_iter()
+    .map(|option| match option == showing {
+        true => format!("> {} <", choice_name(option)),
+        false => choice_name(option).to_string(),
+    })
+    .collect::<Vec<_>>()
+    .join("   ");
+    let value = format!("Hold [{}]   {options}", key_name(key));
+    if let Ok((_, mut text)) = prompt.get_single_mut() {
+        text.sections[0].value = value;
+    } else {
+        commands.spawn((
+            TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(20.),
+                    left: Val::Percent(35.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                z_index: ZIndex::Global(5),
+                ..TextBundle::from_section(
+                    value,
+                    TextStyle {
+                        font: asset_server.load("alphbeta.ttf"),
+                        font_size: 22.,
+                        color: Color::WHITE,
+                    },
+                )
+            },
+            HoldPromptMarker,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn auto_fire_waits_for_a_target_ahead_and_a_hold_steps_through_the_choice() {
+        // Heading 0 points up the screen
+        assert!(in_forward_arc(Vec2::ZERO, 0., Vec2::new(0., 0.8)));
+        assert!(in_forward_arc(Vec2::ZERO, 0., Vec2::new(0.1, 0.8)));
+        assert!(!in_forward_arc(Vec2::ZERO, 0., Vec2::new(0.8, 0.)));
+        assert!(!in_forward_arc(Vec2::ZERO, 0., Vec2::new(0., -0.8)));
+        assert!(!in_forward_arc(Vec2::ZERO, 0., Vec2::new(0., 3.)));
+        assert!(in_forward_arc(Vec2::ZERO, PI / 2., Vec2::new(0.8, 0.)));
+
+        assert_eq!(held_choice(Duration::ZERO), ShipUsageDecision::Transfer);
+        assert_eq!(
+            held_choice(HOLD_STEP / 10),
+            ShipUsageDecision::Transfer,
+            "a quick tap takes the first option"
+        );
+        assert_eq!(held_choice(HOLD_STEP * 3 / 2), ShipUsageDecision::Keep);
+        assert_eq!(held_choice(HOLD_STEP * 5 / 2), ShipUsageDecision::Destroy);
+        assert_eq!(held_choice(HOLD_STEP * 7 / 2), ShipUsageDecision::Transfer);
+
+        let settings = Settings::parse("(auto_fire: true, hold_to_capture: \"yes\")");
+        assert!(settings.auto_fire);
+        assert!(!settings.hold_to_capture);
+    }
+
+    #[test]
+    fn flight_assist_eases_turns_into_nearby_ships_and_the_border() {
+        let near = [Vec2::new(0.05, 0.15)];
+        // Pointing up, turning right swings the nose onto the ship just up and to the right
+        let into = damp_turn(Vec2::ZERO, 0., 0.1, near.into_iter());
+        assert!((into - 0.1 * ASSIST_DAMPING).abs() < 1e-6);
+        assert_eq!(damp_turn(Vec2::ZERO, 0., -0.1, near.into_iter()), -0.1);
+        let far = [Vec2::new(1., 1.)];
+        assert_eq!(damp_turn(Vec2::ZERO, 0., 0.1, far.into_iter()), 0.1);
+
+        // The border only counts right at its edge
+        let edge = Vec2::new(BORDER_KILL_RADIUS - 0.1, 0.);
+        let into_wall = damp_turn(edge, PI / 3., 0.1, std::iter::empty());
+        assert!(into_wall < 0.1);
+        let inside = Vec2::new(BORDER_KILL_RADIUS - 2., 0.);
+        assert_eq!(damp_turn(inside, PI / 3., 0.1, std::iter::empty()), 0.1);
+
+        let settings = Settings {
+            flight_assist: true,
+            ..default()
+        };
+        assert!(flight_assist_on(&settings, settings.difficulty));
+        assert!(!flight_assist_on(&settings, Difficulty::Hard));
+    }
+
+    #[test]
+    fn aim_assist_nudges_towards_the_nearest_enemy_ahead() {
+        let ahead = Vec2::new(0.1, 1.);
+        let pull = aim_pull(Vec2::ZERO, 0., 0.5, [ahead].into_iter());
+        // Off to the right, so the pull is clockwise, and no further than the enemy
+        assert!(pull > 0. && pull <= f32::atan2(ahead.x, ahead.y) + 1e-5);
+        assert_eq!(aim_pull(Vec2::ZERO, 0., 0.01, [ahead].into_iter()), 0.01);
+        // The nearer of two wins
+        let pull = aim_pull(
+            Vec2::ZERO,
+            0.,
+            0.5,
+            [Vec2::new(0.2, 1.), Vec2::new(-0.05, 0.5)].into_iter(),
+        );
+        assert!(pull < 0.);
+        // Nothing behind, too far off the nose or out of range
+        let elsewhere = [Vec2::new(0., -1.), Vec2::new(1., 0.2), Vec2::new(0., 5.)];
+        assert_eq!(aim_pull(Vec2::ZERO, 0., 0.5, elsewhere.into_iter()), 0.);
+
+        let settings = Settings {
+            aim_assist: true,
+            ..default()
+        };
+        assert!(aim_assist_on(&settings, settings.difficulty));
+        assert!(!aim_assist_on(&settings, Difficulty::Hard));
+    }
+}
@@ -0,0 +1,722 @@
+//! Music for each part of the game and sound effects for the action, all synthesised at runtime
+//! so there are no audio files to ship. Gameplay systems only send their usual events, and this
+//! plugin picks the sound to go with each one. The biggest moments also get a short stinger over
+//! the music, which drops back while it plays. Everything is played at the levels set in
+//! [`AudioSettings`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::TAU,
+    time::Duration,
+};
+
+use bevy::{
+    app::{Plugin, Startup, Update},
+    asset::{Asset, Assets, Handle},
+    audio::{
+        AddAudioSource, AudioSink, AudioSinkPlayback, AudioSourceBundle, Decodable,
+        PlaybackSettings, Source, Volume,
+    },
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{With, Without},
+        schedule::{
+            common_conditions::{in_state, state_changed},
+            IntoSystemConfigs, State,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    prelude::App,
+    reflect::TypePath,
+    time::{Real, Time},
+};
+
+use crate::{
+    events::{
+        BossArrived, DamageCause, HighScoreBeaten, NearDeathEscape, ShieldRechargeFinished,
+        ShieldRechargeStarted, ShipCaptured, ShipDamaged, ShipDestroyed, ShotFired,
+    },
+    gameplay::{GameplaySet, PlayerMarker},
+    volume::AudioSettings,
+    GameLifecycleState,
+};
+
+pub const SAMPLE_RATE: u32 = 44_100;
+/// Every note fades in over this long, so it doesn't click
+const ATTACK_SECS: f32 = 0.005;
+const MUSIC_VOLUME: f32 = 0.2;
+/// How far the music drops under a stinger
+pub const DUCKED_VOLUME: f32 = 0.3;
+/// The music comes back up over the last part of a stinger
+pub const DUCK_RELEASE: Duration = Duration::from_millis(400);
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Synth>()
+            .insert_resource(StingerPlayback::default())
+            .add_systems(Startup, (load_sounds, spawn_music_player).chain())
+            .add_systems(
+                Update,
+                (
+                    change_music.run_if(state_changed::<GameLifecycleState>),
+                    set_volumes,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (play_sound_effects, play_stingers.before(set_volumes))
+                    .in_set(GameplaySet::Presentation)
+                    .run_if(in_state(GameLifecycleState::Game)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wave {
+    Sine,
+    Square,
+    Triangle,
+    /// Random levels held for one cycle each, so it still has a pitch to sweep
+    Noise,
+}
+
+/// A single note, its pitch sliding from `from_hz` to `to_hz` as it fades out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Note {
+    pub wave: Wave,
+    pub from_hz: f32,
+    pub to_hz: f32,
+    pub secs: f32,
+    pub volume: f32,
+}
+
+impl Note {
+    pub fn tone(wave: Wave, hz: f32, secs: f32, volume: f32) -> Self {
+        Self::sweep(wave, hz, hz, secs, volume)
+    }
+
+    pub fn sweep(wave: Wave, from_hz: f32, to_hz: f32, secs: f32, volume: f32) -> Self {
+        Self {
+            wave,
+            from_hz,
+            to_hz,
+            secs,
+            volume,
+        }
+    }
+
+    pub fn rest(secs: f32) -> Self {
+        Self::tone(Wave::Sine, 0., secs, 0.)
+    }
+
+    fn samples(&self) -> u32 {
+        (self.secs * SAMPLE_RATE as f32) as u32
+    }
+}
+
+/// Frequency of the note this many semitones away from A4
+pub fn pitch(semitones: i32) -> f32 {
+    440. * 2_f32.powf(semitones as f32 / 12.)
+}
+
+/// Notes played one after another
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct Synth {
+    pub notes: Vec<Note>,
+}
+
+impl Synth {
+    /// Evenly spaced notes of the same wave, given in semitones from A4
+    pub fn arpeggio(wave: Wave, secs: f32, volume: f32, semitones: &[i32]) -> Self {
+        Self {
+            notes: semitones
+                .iter()
+                .map(|semitones| Note::tone(wave, pitch(*semitones), secs, volume))
+                .collect(),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        let samples: u32 = self.notes.iter().map(Note::samples).sum();
+        Duration::from_secs_f64(samples as f64 / SAMPLE_RATE as f64)
+    }
+}
+
+impl Decodable for Synth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthDecoder {
+            notes: self.notes.clone(),
+            note: 0,
+            sample: 0,
+            phase: 0.,
+            noise: 0x9E37_79B9,
+            level: 0.,
+        }
+    }
+}
+
+pub struct SynthDecoder {
+    notes: Vec<Note>,
+    note: usize,
+    /// Samples into the current note
+    sample: u32,
+    /// How far through the current cycle, from 0 to 1
+    phase: f32,
+    noise: u32,
+    /// The noise level held for this cycle
+    level: f32,
+}
+
+impl SynthDecoder {
+    fn next_noise(&mut self) -> f32 {
+        // xorshift32, no need for anything better to make hiss
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        self.noise as f32 / u32::MAX as f32 * 2. - 1.
+    }
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        loop {
+            let note = *self.notes.get(self.note)?;
+            if self.sample >= note.samples() {
+                self.note += 1;
+                self.sample = 0;
+                continue;
+            }
+            let secs = self.sample as f32 / SAMPLE_RATE as f32;
+            let progress = secs / note.secs;
+            let hz = note.from_hz + (note.to_hz - note.from_hz) * progress;
+            let phase = self.phase + hz / SAMPLE_RATE as f32;
+            if phase >= 1. || self.sample == 0 {
+                self.level = self.next_noise();
+            }
+            self.phase = phase.fract();
+            self.sample += 1;
+
+            let value = match note.wave {
+                Wave::Sine => (self.phase * TAU).sin(),
+                Wave::Square if self.phase < 0.5 => 1.,
+                Wave::Square => -1.,
+                Wave::Triangle => 1. - 4. * (self.phase - 0.5).abs(),
+                Wave::Noise => self.level,
+            };
+            let envelope = (secs / ATTACK_SECS).min(1.) * (1. - progress);
+            return Some(value * envelope * note.volume);
+        }
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Track {
+    Menu,
+    Tutorial,
+    Game,
+    EndScreen,
+}
+
+impl Track {
+    pub const ALL: [Track; 4] = [Track::Menu, Track::Tutorial, Track::Game, Track::EndScreen];
+
+    pub fn for_state(state: &GameLifecycleState) -> Self {
+        match state {
+            GameLifecycleState::MainMenu | GameLifecycleState::Loadout => Track::Menu,
+            GameLifecycleState::Tutorial => Track::Tutorial,
+            GameLifecycleState::Game => Track::Game,
+            GameLifecycleState::EndScreen => Track::EndScreen,
+        }
+    }
+
+    /// One loop of the track
+    pub fn synth(self) -> Synth {
+        match self {
+            // Am, F, G, E
+            Track::Menu => Synth::arpeggio(
+                Wave::Triangle,
+                0.4,
+                MUSIC_VOLUME,
+                &[
+                    -12, -9, -5, 0, -5, -9, -16, -12, -9, -4, -9, -12, -14, -10, -7, -2, -7, -10,
+                    -17, -13, -10, -5, -10, -13,
+                ],
+            ),
+            // C, F, G, C
+            Track::Tutorial => Synth::arpeggio(
+                Wave::Sine,
+                0.35,
+                MUSIC_VOLUME,
+                &[
+                    -9, -5, -2, -5, -16, -12, -9, -12, -14, -10, -7, -10, -9, -5, -2, 3,
+                ],
+            ),
+            // A driving bass line, two bars on A and one each on F and G
+            Track::Game => Synth::arpeggio(
+                Wave::Square,
+                0.15,
+                MUSIC_VOLUME * 0.6,
+                &[
+                    -24, -12, -24, -12, -24, -12, -21, -19, -24, -12, -24, -12, -24, -12, -17, -14,
+                    -28, -16, -28, -16, -28, -16, -26, -24, -26, -14, -26, -14, -26, -14, -22, -19,
+                ],
+            ),
+            // A slow fall from A4 and a breath before it starts again
+            Track::EndScreen => {
+                let mut synth = Synth::arpeggio(
+                    Wave::Sine,
+                    0.8,
+                    MUSIC_VOLUME,
+                    &[0, -2, -5, -7, -9, -12, -10, -12],
+                );
+                synth.notes.push(Note::rest(1.6));
+                synth
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    PlayerShot,
+    EnemyShot,
+    Impact,
+    ShieldRechargeStarted,
+    ShieldRechargeFinished,
+    Explosion,
+    Capture,
+}
+
+impl SoundEffect {
+    pub const ALL: [SoundEffect; 7] = [
+        SoundEffect::PlayerShot,
+        SoundEffect::EnemyShot,
+        SoundEffect::Impact,
+        SoundEffect::ShieldRechargeStarted,
+        SoundEffect::ShieldRechargeFinished,
+        SoundEffect::Explosion,
+        SoundEffect::Capture,
+    ];
+
+    pub fn synth(self) -> Synth {
+        let notes = match self {
+            SoundEffect::PlayerShot => vec![Note::sweep(Wave::Square, 880., 220., 0.08, 0.25)],
+            SoundEffect::EnemyShot => vec![Note::sweep(Wave::Square, 660., 180., 0.07, 0.1)],
+            SoundEffect::Impact => vec![Note::sweep(Wave::Noise, 2000., 400., 0.06, 0.3)],
+            SoundEffect::ShieldRechargeStarted => {
+                vec![Note::sweep(Wave::Sine, 300., 900., 0.3, 0.3)]
+            }
+            SoundEffect::ShieldRechargeFinished => vec![
+                Note::tone(Wave::Sine, 900., 0.08, 0.3),
+                Note::tone(Wave::Sine, 1320., 0.12, 0.3),
+            ],
+            SoundEffect::Explosion => vec![Note::sweep(Wave::Noise, 800., 40., 0.6, 0.5)],
+            SoundEffect::Capture => {
+                return Synth::arpeggio(Wave::Triangle, 0.08, 0.3, &[3, 7, 10, 15]);
+            }
+        };
+        Synth { notes }
+    }
+}
+
+/// Short phrases over the music for the big moments, least important first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stinger {
+    Capture,
+    NearDeathEscape,
+    BossArrived,
+    NewHighScore,
+}
+
+impl Stinger {
+    pub const ALL: [Stinger; 4] = [
+        Stinger::Capture,
+        Stinger::NearDeathEscape,
+        Stinger::BossArrived,
+        Stinger::NewHighScore,
+    ];
+
+    pub fn synth(self) -> Synth {
+        // Semitones from A4, and how long each is held
+        let (wave, volume, phrase): (_, _, &[(i32, f32)]) = match self {
+            Stinger::Capture => (
+                Wave::Sine,
+                0.3,
+                &[(-2, 0.15), (2, 0.15), (5, 0.15), (10, 0.6)],
+            ),
+            Stinger::NearDeathEscape => (
+                Wave::Triangle,
+                0.3,
+                &[(-5, 0.1), (0, 0.1), (4, 0.1), (7, 0.1), (12, 0.5)],
+            ),
+            Stinger::BossArrived => (
+                Wave::Square,
+                0.2,
+                &[(-24, 0.4), (-23, 0.4), (-24, 0.4), (-29, 0.9)],
+            ),
+            Stinger::NewHighScore => (
+                Wave::Square,
+                0.15,
+                &[(0, 0.1), (0, 0.1), (0, 0.1), (5, 0.3), (7, 0.3), (12, 0.8)],
+            ),
+        };
+        Synth {
+            notes: phrase
+                .iter()
+                .map(|(semitones, secs)| Note::tone(wave, pitch(*semitones), *secs, volume))
+                .collect(),
+        }
+    }
+
+    /// The stinger to start, if any of this frame's calls outranks what's already playing
+    pub fn next(
+        playing: Option<Stinger>,
+        called: impl IntoIterator<Item = Stinger>,
+    ) -> Option<Self> {
+        called
+            .into_iter()
+            .max()
+            .filter(|called| playing.is_none_or(|playing| *called > playing))
+    }
+}
+
+/// The stinger playing over the music, and how long until it's done
+#[derive(Resource, Default)]
+pub struct StingerPlayback {
+    pub playing: Option<(Stinger, Entity)>,
+    pub remaining: Duration,
+}
+
+impl StingerPlayback {
+    /// Held down until the end of the stinger draws near, then brought back up
+    pub fn music_volume(&self) -> f32 {
+        let held = (self.remaining.as_secs_f32() / DUCK_RELEASE.as_secs_f32()).min(1.);
+        1. - (1. - DUCKED_VOLUME) * held
+    }
+}
+
+/// Everything that can be played, synthesised once at startup
+#[derive(Resource, Default)]
+pub struct SoundBank {
+    music: HashMap<Track, Handle<Synth>>,
+    effects: HashMap<SoundEffect, Handle<Synth>>,
+    stingers: HashMap<Stinger, (Handle<Synth>, Duration)>,
+}
+
+/// The one long-lived entity the music plays from, kept across runs
+#[derive(Component)]
+pub struct MusicMarker(Option<Track>);
+
+/// Which volume setting a one-off sound follows
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundChannel {
+    /// Stingers, which play over the music
+    Music,
+    Effects,
+}
+
+impl SoundChannel {
+    pub fn volume(self, settings: &AudioSettings) -> f32 {
+        match self {
+            SoundChannel::Music => settings.music_volume(),
+            SoundChannel::Effects => settings.effects_volume(),
+        }
+    }
+
+    /// Starts at the right level, before [`set_volumes`] first gets to it
+    fn playback(self, settings: &AudioSettings) -> PlaybackSettings {
+        PlaybackSettings::DESPAWN.with_volume(Volume::new(self.volume(settings)))
+    }
+}
+
+fn load_sounds(mut commands: Commands, mut synths: ResMut<Assets<Synth>>) {
+    let mut bank = SoundBank::default();
+    for track in Track::ALL {
+        bank.music.insert(track, synths.add(track.synth()));
+    }
+    for effect in SoundEffect::ALL {
+        bank.effects.insert(effect, synths.add(effect.synth()));
+    }
+    for stinger in Stinger::ALL {
+        let synth = stinger.synth();
+        let length = synth.duration();
+        bank.stingers.insert(stinger, (synths.add(synth), length));
+    }
+    commands.insert_resource(bank);
+}
+
+fn spawn_music_player(mut commands: Commands) {
+    commands.spawn(MusicMarker(None));
+}
+
+/// Swaps the track over, carrying on with the same one between states that share it
+fn change_music(
+    mut commands: Commands,
+    state: Res<State<GameLifecycleState>>,
+    bank: Res<SoundBank>,
+    mut player: Query<(Entity, &mut MusicMarker)>,
+) {
+    let track = Track::for_state(state.get());
+    if let Ok((entity, mut playing)) = player.get_single_mut() {
+        if playing.0 == Some(track) {
+            return;
+        }
+        playing.0 = Some(track);
+        commands
+            .entity(entity)
+            .remove::<AudioSink>()
+            .insert(AudioSourceBundle {
+                source: bank.music[&track].clone(),
+                settings: PlaybackSettings::LOOP,
+            });
+    }
+}
+
+/// The sound effects the frame's events call for. Each plays at most once a frame, so a crowd of
+/// ships firing together doesn't get any louder.
+pub fn sound_effects_for(
+    fired: &[ShotFired],
+    damaged: &[ShipDamaged],
+    recharges_started: usize,
+    recharges_finished: usize,
+    destroyed: usize,
+    captured: usize,
+) -> HashSet<SoundEffect> {
+    let mut effects = HashSet::new();
+    for shot in fired {
+        effects.insert(match shot.player_shot {
+            true => SoundEffect::PlayerShot,
+            false => SoundEffect::EnemyShot,
+        });
+    }
+    if damaged
+        .iter()
+        .any(|hit| matches!(hit.cause, DamageCause::Weapon(_)))
+    {
+        effects.insert(SoundEffect::Impact);
+    }
+    for (count, effect) in [
+        (recharges_started, SoundEffect::ShieldRechargeStarted),
+        (recharges_finished, SoundEffect::ShieldRechargeFinished),
+        (destroyed, SoundEffect::Explosion),
+        (captured, SoundEffect::Capture),
+    ] {
+        if count > 0 {
+            effects.insert(effect);
+        }
+    }
+    effects
+}
+
+/// Only the player's own shield is heard, the rest happen too far from the action to matter
+#[allow(clippy::too_many_arguments)]
+fn play_sound_effects(
+    mut commands: Commands,
+    bank: Res<SoundBank>,
+    mut fired: EventReader<ShotFired>,
+    mut damaged: EventReader<ShipDamaged>,
+    mut recharges_started: EventReader<ShieldRechargeStarted>,
+    mut recharges_finished: EventReader<ShieldRechargeFinished>,
+    mut destroyed: EventReader<ShipDestroyed>,
+    mut captured: EventReader<ShipCaptured>,
+    player: Query<Entity, With<PlayerMarker>>,
+    settings: Res<AudioSettings>,
+) {
+    let player = player.get_single().ok();
+    let effects = sound_effects_for(
+        &fired.read().copied().collect::<Vec<_>>(),
+        &damaged.read().copied().collect::<Vec<_>>(),
+        recharges_started
+            .read()
+            .filter(|event| Some(event.ship) == player)
+            .count(),
+        recharges_finished
+            .read()
+            .filter(|event| Some(event.ship) == player)
+            .count(),
+        destroyed.read().count(),
+        captured.read().count(),
+    );
+    for effect in effects {
+        commands.spawn((
+            AudioSourceBundle {
+                source: bank.effects[&effect].clone(),
+                settings: SoundChannel::Effects.playback(&settings),
+            },
+            SoundChannel::Effects,
+        ));
+    }
+}
+
+/// A stinger cuts off the one before it only if it's for something bigger
+#[allow(clippy::too_many_arguments)]
+fn play_stingers(
+    mut commands: Commands,
+    bank: Res<SoundBank>,
+    mut playback: ResMut<StingerPlayback>,
+    mut captured: EventReader<ShipCaptured>,
+    mut escapes: EventReader<NearDeathEscape>,
+    mut bosses: EventReader<BossArrived>,
+    mut beaten: EventReader<HighScoreBeaten>,
+    settings: Res<AudioSettings>,
+) {
+    let called = [
+        (captured.read().count(), Stinger::Capture),
+        (escapes.read().count(), Stinger::NearDeathEscape),
+        (bosses.read().count(), Stinger::BossArrived),
+        (beaten.read().count(), Stinger::NewHighScore),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(_, stinger)| stinger);
+    let playing = playback.playing.map(|(stinger, _)| stinger);
+    if let Some(stinger) = Stinger::next(playing, called) {
+        if let Some((_, entity)) = playback.playing {
+            if let Some(mut entity) = commands.get_entity(entity) {
+                entity.despawn();
+            }
+        }
+        let (source, length) = bank.stingers[&stinger].clone();
+        let entity = commands
+            .spawn((
+                AudioSourceBundle {
+                    source,
+                    settings: SoundChannel::Music.playback(&settings),
+                },
+                SoundChannel::Music,
+            ))
+            .id();
+        playback.playing = Some((stinger, entity));
+        playback.remaining = length;
+    }
+}
+
+/// Brings every sink in line with the settings, with the music ducked under a stinger. Counts in
+/// real time, as stingers carry on through the pause menu.
+fn set_volumes(
+    time: Res<Time<Real>>,
+    settings: Res<AudioSettings>,
+    mut playback: ResMut<StingerPlayback>,
+    music: Query<&AudioSink, With<MusicMarker>>,
+    sounds: Query<(&AudioSink, &SoundChannel), Without<MusicMarker>>,
+) {
+    playback.remaining = playback.remaining.saturating_sub(time.delta());
+    if playback.remaining.is_zero() {
+        playback.playing = None;
+    }
+    for sink in music.iter() {
+        sink.set_volume(settings.music_volume() * playback.music_volume());
+    }
+    for (sink, channel) in sounds.iter() {
+        sink.set_volume(channel.volume(&settings));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{HighScoreBeaten, ShotFired};
+    use crate::gameplay::{testing::test_app, PlayerScore};
+    use crate::records::{watch_for_new_best, BestToBeat};
+    use bevy::{audio::Decodable, ecs::event::Events};
+
+    #[test]
+    fn sounds_are_synthesised_and_played_once_a_frame() {
+        let synth = Synth {
+            notes: vec![
+                Note::tone(Wave::Square, 440., 0.1, 0.5),
+                Note::rest(0.05),
+                Note::sweep(Wave::Noise, 800., 40., 0.1, 0.5),
+            ],
+        };
+        let samples = synth.decoder().collect::<Vec<_>>();
+        assert_eq!(samples.len(), (0.25 * SAMPLE_RATE as f32) as usize);
+        assert!(samples.iter().all(|sample| sample.abs() <= 0.5));
+        let rest = SAMPLE_RATE as usize / 10..SAMPLE_RATE as usize * 3 / 20;
+        assert!(samples[rest].iter().all(|sample| *sample == 0.));
+        assert_eq!(synth.duration(), Duration::from_millis(250));
+
+        let ship = Entity::from_raw(1);
+        let volley = [false, false, true, false].map(|player_shot| ShotFired { ship, player_shot });
+        let hits = [ShipDamaged {
+            ship,
+            attacker: None,
+            cause: DamageCause::Border,
+            amount: 1,
+        }];
+        assert_eq!(
+            sound_effects_for(&volley, &hits, 0, 1, 3, 0),
+            HashSet::from([
+                SoundEffect::PlayerShot,
+                SoundEffect::EnemyShot,
+                SoundEffect::ShieldRechargeFinished,
+                SoundEffect::Explosion,
+            ])
+        );
+    }
+
+    #[test]
+    fn stingers_duck_the_music_and_only_give_way_to_bigger_ones() {
+        assert_eq!(
+            Stinger::next(None, [Stinger::Capture, Stinger::BossArrived]),
+            Some(Stinger::BossArrived)
+        );
+        assert_eq!(
+            Stinger::next(Some(Stinger::BossArrived), [Stinger::Capture]),
+            None
+        );
+        assert_eq!(
+            Stinger::next(Some(Stinger::Capture), [Stinger::NewHighScore]),
+            Some(Stinger::NewHighScore)
+        );
+        assert_eq!(Stinger::next(None, []), None);
+
+        let mut playback = StingerPlayback::default();
+        assert_eq!(playback.music_volume(), 1.);
+        playback.remaining = Duration::from_secs(1);
+        assert_eq!(playback.music_volume(), DUCKED_VOLUME);
+        playback.remaining = DUCK_RELEASE / 2;
+        assert!((playback.music_volume() - (1. + DUCKED_VOLUME) / 2.).abs() < 1e-5);
+
+        let mut app = test_app();
+        app.insert_resource(BestToBeat(Some(500)))
+            .add_systems(Update, watch_for_new_best);
+        app.world.resource_mut::<PlayerScore>().score = 500;
+        app.update();
+        app.world.resource_mut::<PlayerScore>().score = 520;
+        app.update();
+        app.world.resource_mut::<PlayerScore>().score = 600;
+        app.update();
+        let beaten = app.world.resource::<Events<HighScoreBeaten>>();
+        let beaten = beaten.get_reader().read(beaten).count();
+        assert_eq!(beaten, 1);
+    }
+}
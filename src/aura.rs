@@ -0,0 +1,186 @@
+//! Projectors: lightly armed support hulls that throw a shield aura over the ships on their side
+//! close by, so every hit on them lands softer while the projector lives. Capture one and the aura
+//! covers the player and their allies instead.
+
+use std::collections::HashSet;
+
+use bevy::{
+    app::{Plugin, Startup, Update},
+    asset::{AssetServer, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Has, With},
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::{Commands, Query, Res, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt, Parent},
+    math::{Vec2, Vec3},
+    prelude::{default, App},
+    render::{color::Color, texture::Image},
+    sprite::{Sprite, SpriteBundle},
+    transform::components::Transform,
+};
+
+use crate::{
+    gameplay::{Captured, GameState, GameplaySet, PlayerMarker, ShipType, Spacecraft},
+    GameLifecycleState,
+};
+
+/// How close a ship has to stay to a projector on its side to be covered
+pub const AURA_RADIUS: f32 = 0.6;
+/// Share of each hit that still gets through to a ship inside an aura
+pub const AURA_DAMAGE_TAKEN: f32 = 0.5;
+
+pub struct ShieldAuraPlugin;
+
+impl Plugin for ShieldAuraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_aura_texture).add_systems(
+            Update,
+            (
+                project_auras.in_set(GameplaySet::Simulation),
+                show_aura_icons.in_set(GameplaySet::Presentation),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct AuraTexture(pub Handle<Image>);
+
+/// Shown over a ship while an aura covers it
+#[derive(Component)]
+pub struct AuraIcon;
+
+fn load_aura_texture(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AuraTexture(asset_server.load("shield_full.png")));
+}
+
+/// Whether a ship is inside the aura of a projector on its side. A projector doesn't cover
+/// itself, so it can always be worn down.
+pub fn covered_by_aura(
+    ship: Entity,
+    position: Vec2,
+    friendly: bool,
+    projectors: &[(Entity, Vec2, bool)],
+) -> bool {
+    projectors.iter().any(|(projector, at, side)| {
+        *projector != ship && *side == friendly && at.distance(position) < AURA_RADIUS
+    })
+}
+
+pub fn project_auras(
+    mut ships: Query<(Entity, &mut Spacecraft, Has<Captured>, Has<PlayerMarker>)>,
+) {
+    let projectors = ships
+        .iter()
+        .filter(|(_, ship, _, _)| ship.ship_type == ShipType::Projector)
+        .map(|(entity, ship, captured, piloted)| (entity, ship.position, captured || piloted))
+        .collect::<Vec<_>>();
+    for (entity, mut ship, captured, piloted) in ships.iter_mut() {
+        let covered = covered_by_aura(entity, ship.position, captured || piloted, &projectors);
+        // Only written on a change, so nothing else sees the ship as changed every frame
+        if ship.in_aura != covered {
+            ship.in_aura = covered;
+        }
+    }
+}
+
+fn show_aura_icons(
+    mut commands: Commands,
+    texture: Res<AuraTexture>,
+    ships: Query<(Entity, &Spacecraft)>,
+    icons: Query<(Entity, &Parent), With<AuraIcon>>,
+) {
+    let mut shown = HashSet::new();
+    for (icon, ship) in icons.iter() {
+        match ships.get(ship.get()) {
+            Ok((_, craft)) if craft.in_aura => {
+                shown.insert(ship.get());
+            }
+            _ => commands.entity(icon).despawn_recursive(),
+        }
+    }
+    for (entity, ship) in ships.iter() {
+        if ship.in_aura && !shown.contains(&entity) {
+            commands.entity(entity).with_children(|parent| {
+                parent.spawn((
+                    SpriteBundle {
+                        transform: Transform::from_xyz(0., -30., 80.)
+                            .with_scale(Vec3::new(2., 2., 1.)),
+                        texture: texture.0.clone(),
+                        sprite: Sprite {
+                            color: Color::rgba(0.5, 0.8, 1., 0.7),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    AuraIcon,
+                ));
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::damage::DamageType;
+    use crate::gameplay::testing::test_app;
+    use crate::score::ScoreEvent;
+    use bevy::ecs::event::Events;
+
+    #[test]
+    fn projectors_shield_their_side_until_captured() {
+        let mut app = test_app();
+        app.add_systems(Update, project_auras);
+        let projector = app
+            .world
+            .spawn(Spacecraft::from_template(ShipType::Projector, Vec2::ZERO))
+            .id();
+        let escort = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship1,
+                Vec2::new(AURA_RADIUS / 2., 0.),
+            ))
+            .id();
+        let straggler = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship1,
+                Vec2::new(AURA_RADIUS * 2., 0.),
+            ))
+            .id();
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship1, Vec2::new(0., AURA_RADIUS / 2.)),
+                PlayerMarker,
+            ))
+            .id();
+        app.update();
+        let covered = |app: &App, ship: Entity| app.world.get::<Spacecraft>(ship).unwrap().in_aura;
+        assert!(covered(&app, escort));
+        assert!(!covered(&app, straggler));
+        assert!(!covered(&app, player));
+        assert!(!covered(&app, projector));
+
+        // Half of each hit gets through, so it takes two to chip off one point of hull
+        let mut events = Events::<ScoreEvent>::default();
+        let mut shielded = app.world.get_mut::<Spacecraft>(escort).unwrap();
+        let health = shielded.health;
+        shielded.take_hit(1, DamageType::Energy, false, &mut events);
+        assert_eq!(shielded.health, health);
+        shielded.take_hit(1, DamageType::Energy, false, &mut events);
+        assert_eq!(shielded.health, health - 1);
+
+        app.world.entity_mut(projector).insert(Captured);
+        app.update();
+        assert!(!covered(&app, escort));
+        assert!(covered(&app, player));
+    }
+}
@@ -0,0 +1,314 @@
+//! Enemy radio barks: short lines that pop up over an enemy ship when it arrives, spots the
+//! player, breaks off or sees a wingmate go down. The lines live in `enemy.dialogue.ron`.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Startup, Update},
+    asset::{AssetServer, Assets, Handle},
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        query::{Added, With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    text::{Font, Text, Text2dBundle, TextStyle},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    dialogue::{BarkKind, DialogueScript},
+    events::{Allegiance, ShipDestroyed},
+    gameplay::{Captured, GameState, GameplaySet, PlayerMarker, Spacecraft},
+    GameLifecycleState,
+};
+
+/// Enemies this close to the player have spotted it
+pub const SPOT_RANGE: f32 = 1.2;
+/// How near a wingmate has to be to react to a ship going down
+pub const ALLY_LOST_RANGE: f32 = 1.5;
+/// Shortest gap between two barks, so the arena doesn't turn into a shouting match
+pub const BARK_COOLDOWN: Duration = Duration::from_secs(3);
+pub const BARK_TIME: Duration = Duration::from_millis(2500);
+const BARK_COLOR: Color = Color::rgb(1., 0.6, 0.55);
+
+pub struct EnemyBarksPlugin;
+
+impl Plugin for EnemyBarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Bark>()
+            .add_systems(Startup, load_bark_assets)
+            .add_systems(OnEnter(GameLifecycleState::Game), reset_bark_cooldown)
+            .add_systems(
+                Update,
+                (
+                    notice_bark_moments.in_set(GameplaySet::Cleanup),
+                    (show_barks, update_bark_popups)
+                        .chain()
+                        .in_set(GameplaySet::Presentation),
+                )
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+/// Asks for an enemy to call something out. Whether it does depends on [`BarkKind::chance`] and
+/// how recently someone else did.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Bark {
+    pub ship: Entity,
+    pub kind: BarkKind,
+}
+
+impl BarkKind {
+    fn chance(self) -> f64 {
+        match self {
+            BarkKind::Spawned => 0.3,
+            BarkKind::Spotted => 0.5,
+            BarkKind::Retreating => 1.,
+            BarkKind::AllyLost => 0.6,
+            BarkKind::Outclassed => 1.,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct BarkAssets {
+    script: Handle<DialogueScript>,
+    font: Handle<Font>,
+}
+
+#[derive(Resource)]
+pub struct BarkCooldown(Timer);
+
+/// Set on an enemy once it has seen the player, so it only says so the once
+#[derive(Component)]
+pub struct Spotted;
+
+#[derive(Component)]
+pub struct BarkPopup {
+    ship: Entity,
+    timer: Timer,
+}
+
+fn load_bark_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BarkAssets {
+        script: asset_server.load("dialogue/enemy.dialogue.ron"),
+        font: asset_server.load("alphbeta.ttf"),
+    });
+}
+
+fn reset_bark_cooldown(mut commands: Commands) {
+    let mut cooldown = Timer::new(BARK_COOLDOWN, TimerMode::Once);
+    cooldown.tick(BARK_COOLDOWN);
+    commands.insert_resource(BarkCooldown(cooldown));
+}
+
+#[allow(clippy::type_complexity)]
+pub fn notice_bark_moments(
+    mut commands: Commands,
+    mut destroyed: EventReader<ShipDestroyed>,
+    mut barks: EventWriter<Bark>,
+    arrived: Query<Entity, (Added<Spacecraft>, Without<Captured>, Without<PlayerMarker>)>,
+    enemies: Query<
+        (Entity, &Spacecraft, Option<&Spotted>),
+        (Without<Captured>, Without<PlayerMarker>),
+    >,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+) {
+    for ship in arrived.iter() {
+        barks.send(Bark {
+            ship,
+            kind: BarkKind::Spawned,
+        });
+    }
+    if let Ok(player) = player.get_single() {
+        for (ship, enemy, spotted) in enemies.iter() {
+            if spotted.is_none() && enemy.position.distance(player.position) < SPOT_RANGE {
+                commands.entity(ship).insert(Spotted);
+                barks.send(Bark {
+                    ship,
+                    kind: BarkKind::Spotted,
+                });
+            }
+        }
+    }
+    for loss in destroyed.read() {
+        if loss.allegiance != Allegiance::Enemy {
+            continue;
+        }
+        let others = enemies
+            .iter()
+            .map(|(ship, enemy, _)| (ship, enemy.position));
+        if let Some(ship) = nearest_wingmate(loss.ship, loss.position, others) {
+            barks.send(Bark {
+                ship,
+                kind: BarkKind::AllyLost,
+            });
+        }
+    }
+}
+
+/// The closest of `enemies` within [`ALLY_LOST_RANGE`] of where `lost` went down
+pub fn nearest_wingmate(
+    lost: Entity,
+    position: Vec2,
+    enemies: impl Iterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    enemies
+        .filter(|(ship, _)| *ship != lost)
+        .map(|(ship, enemy)| (ship, enemy.distance(position)))
+        .filter(|(_, distance)| *distance < ALLY_LOST_RANGE)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(ship, _)| ship)
+}
+
+fn show_barks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut requests: EventReader<Bark>,
+    mut cooldown: ResMut<BarkCooldown>,
+    assets: Res<BarkAssets>,
+    scripts: Res<Assets<DialogueScript>>,
+    popups: Query<&BarkPopup>,
+) {
+    cooldown.0.tick(time.delta());
+    let mut rand = rand::thread_rng();
+    for bark in requests.read() {
+        if !cooldown.0.finished()
+            || popups.iter().any(|popup| popup.ship == bark.ship)
+            || !rand.gen_bool(bark.kind.chance())
+        {
+            continue;
+        }
+        let line = scripts
+            .get(&assets.script)
+            .and_then(|script| script.barks.lines(bark.kind).choose(&mut rand));
+        if let Some(line) = line {
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        line.clone(),
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: 14.,
+                            color: BARK_COLOR,
+                        },
+                    ),
+                    ..default()
+                },
+                BarkPopup {
+                    ship: bark.ship,
+                    timer: Timer::new(BARK_TIME, TimerMode::Once),
+                },
+            ));
+            cooldown.0.reset();
+        }
+    }
+}
+
+/// Popups ride above their ship, kept upright on screen however the camera has turned
+#[allow(clippy::type_complexity)]
+fn update_bark_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut popups: Query<(Entity, &mut BarkPopup, &mut Transform, &mut Text), Without<Spacecraft>>,
+    ships: Query<&Transform, (With<Spacecraft>, Without<Camera2d>)>,
+    camera: Query<&Transform, (With<Camera2d>, Without<BarkPopup>, Without<Spacecraft>)>,
+) {
+    let rotation = camera.get_single().map(|camera| camera.rotation).ok();
+    for (entity, mut popup, mut transform, mut text) in popups.iter_mut() {
+        let ship = ships.get(popup.ship);
+        if popup.timer.tick(time.delta()).finished() || ship.is_err() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if let (Ok(ship), Some(rotation)) = (ship, rotation) {
+            let above = rotation * (Vec2::Y * 30. * ship.scale.y).extend(0.);
+            transform.translation = (ship.translation + above).truncate().extend(90.);
+            transform.rotation = rotation;
+        }
+        let alpha = (popup.timer.fraction_remaining() * 2.).min(1.);
+        text.sections[0].style.color = BARK_COLOR.with_a(alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, ShipType};
+    use bevy::ecs::event::Events;
+
+    #[test]
+    fn enemies_bark_when_they_spot_the_player_and_lose_a_wingmate() {
+        let script: DialogueScript =
+            ron::from_str(include_str!("../assets/dialogue/enemy.dialogue.ron")).unwrap();
+        for kind in [
+            BarkKind::Spawned,
+            BarkKind::Spotted,
+            BarkKind::Retreating,
+            BarkKind::AllyLost,
+            BarkKind::Outclassed,
+        ] {
+            assert!(!script.barks.lines(kind).is_empty(), "no {kind:?} lines");
+        }
+
+        let mut app = test_app();
+        app.add_event::<Bark>()
+            .add_systems(Update, notice_bark_moments);
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        let near = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship2,
+                Vec2::new(0.5, 0.),
+            ))
+            .id();
+        let far = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship2,
+                Vec2::new(5., 0.),
+            ))
+            .id();
+        let barks = |app: &mut App| {
+            app.world
+                .resource_mut::<Events<Bark>>()
+                .drain()
+                .map(|bark| (bark.ship, bark.kind))
+                .collect::<Vec<_>>()
+        };
+        app.update();
+        let first = barks(&mut app);
+        assert!(first.contains(&(near, BarkKind::Spawned)));
+        assert!(first.contains(&(near, BarkKind::Spotted)));
+        assert!(!first.contains(&(far, BarkKind::Spotted)));
+        assert!(app.world.get::<Spotted>(near).is_some());
+
+        // Spotting only gets called out once
+        app.update();
+        assert!(barks(&mut app).is_empty());
+
+        app.world.send_event(ShipDestroyed {
+            ship: Entity::PLACEHOLDER,
+            ship_type: ShipType::Ship2,
+            allegiance: Allegiance::Enemy,
+            position: Vec2::new(0.8, 0.),
+            killer: None,
+        });
+        app.update();
+        assert_eq!(barks(&mut app), vec![(near, BarkKind::AllyLost)]);
+    }
+}
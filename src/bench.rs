@@ -0,0 +1,203 @@
+//! Stress test scene, only compiled with `--features bench`.
+//! Start it with [B] on the main menu or by passing `--bench`, and it will print frame time
+//! statistics and write them to `bench_output.txt` before quitting.
+
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::{
+    app::{AppExit, Plugin, Startup, Update},
+    ecs::{
+        entity::Entity,
+        event::EventWriter,
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, NextState},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::App,
+    time::{Real, Time, Timer, TimerMode},
+};
+use rand::Rng;
+
+use crate::{
+    events::ShotFired,
+    gameplay::{
+        ship_fire, Bullet, BulletTexture, Captured, EnemySpacecraftBundle, PlayerMarker,
+        ShipTextures, ShipType, Spacecraft,
+    },
+    GameLifecycleState,
+};
+
+const BENCH_ENEMIES: usize = 300;
+const BENCH_ALLIES: usize = 100;
+const BENCH_BULLETS: usize = 3000;
+const BENCH_WARMUP: Duration = Duration::from_secs(3);
+const BENCH_DURATION: Duration = Duration::from_secs(20);
+
+pub struct BenchPlugin;
+
+impl Plugin for BenchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_bench_from_args)
+            .add_systems(
+                Update,
+                start_bench_from_menu.run_if(in_state(GameLifecycleState::MainMenu)),
+            )
+            .add_systems(
+                Update,
+                (populate_bench, keep_bullets_flowing, record_frame_times)
+                    .chain()
+                    .run_if(in_state(GameLifecycleState::Game)),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct BenchRun {
+    populated: bool,
+    warmup: Timer,
+    duration: Timer,
+    frame_times: Vec<f32>,
+}
+
+impl BenchRun {
+    fn new() -> Self {
+        Self {
+            populated: false,
+            warmup: Timer::new(BENCH_WARMUP, TimerMode::Once),
+            duration: Timer::new(BENCH_DURATION, TimerMode::Once),
+            frame_times: vec![],
+        }
+    }
+}
+
+fn start_bench_from_args(mut commands: Commands, mut state: ResMut<NextState<GameLifecycleState>>) {
+    if std::env::args().any(|arg| arg == "--bench") {
+        commands.insert_resource(BenchRun::new());
+        state.set(GameLifecycleState::Game);
+    }
+}
+
+fn start_bench_from_menu(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+) {
+    if inputs.just_pressed(KeyCode::KeyB) {
+        commands.insert_resource(BenchRun::new());
+        state.set(GameLifecycleState::Game);
+    }
+}
+
+fn random_ship_type() -> ShipType {
+    match rand::thread_rng().gen_range(0..6) {
+        0 => ShipType::Ship1,
+        1 => ShipType::Ship2,
+        2 => ShipType::Ship3,
+        3 => ShipType::Ship4,
+        4 => ShipType::Ship5,
+        _ => ShipType::Ship6,
+    }
+}
+
+fn populate_bench(
+    mut commands: Commands,
+    bench: Option<ResMut<BenchRun>>,
+    textures: Option<Res<ShipTextures>>,
+    mut player: Query<&mut Spacecraft, With<PlayerMarker>>,
+) {
+    if let (Some(mut bench), Some(textures)) = (bench, textures) {
+        if bench.populated {
+            return;
+        }
+        if let Ok(mut player) = player.get_single_mut() {
+            // Keep the player alive for the whole run
+            player.health = i32::MAX / 2;
+        }
+        let mut rand = rand::thread_rng();
+        for i in 0..BENCH_ENEMIES + BENCH_ALLIES {
+            let angle = rand.gen_range(0.0..2. * PI);
+            let pos = Vec2::new(angle.sin(), angle.cos()) * rand.gen_range(0.3..1.8);
+            let mut ship = commands.spawn(EnemySpacecraftBundle::create_ship(
+                random_ship_type(),
+                pos,
+                &textures,
+            ));
+            if i >= BENCH_ENEMIES {
+                ship.insert(Captured);
+            }
+        }
+        bench.populated = true;
+    }
+}
+
+fn keep_bullets_flowing(
+    mut commands: Commands,
+    bench: Option<Res<BenchRun>>,
+    bullet_texture: Option<Res<BulletTexture>>,
+    bullets: Query<(), With<Bullet>>,
+    mut ships: Query<(Entity, &mut Spacecraft), Without<PlayerMarker>>,
+    mut fired: EventWriter<ShotFired>,
+) {
+    if let (Some(_), Some(bullet_texture)) = (bench, bullet_texture) {
+        let mut missing = BENCH_BULLETS.saturating_sub(bullets.iter().count());
+        for (entity, mut ship) in ships.iter_mut() {
+            if missing == 0 {
+                break;
+            }
+            ship_fire(
+                &mut commands,
+                &mut fired,
+                &mut ship,
+                entity,
+                &bullet_texture,
+                false,
+            );
+            missing = missing.saturating_sub(3);
+        }
+    }
+}
+
+fn record_frame_times(
+    real_time: Res<Time<Real>>,
+    bench: Option<ResMut<BenchRun>>,
+    ships: Query<(), With<Spacecraft>>,
+    bullets: Query<(), With<Bullet>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if let Some(mut bench) = bench {
+        if !bench.warmup.tick(real_time.delta()).finished() {
+            return;
+        }
+        bench.frame_times.push(real_time.delta_seconds() * 1000.);
+        if bench.duration.tick(real_time.delta()).finished() {
+            let report = frame_time_report(
+                &mut bench.frame_times,
+                ships.iter().count(),
+                bullets.iter().count(),
+            );
+            println!("{report}");
+            if let Err(e) = std::fs::write("bench_output.txt", &report) {
+                println!("Could not write bench_output.txt: {e}");
+            }
+            exit.send(AppExit);
+        }
+    }
+}
+
+fn frame_time_report(frame_times: &mut [f32], ships: usize, bullets: usize) -> String {
+    frame_times.sort_by(|a, b| a.total_cmp(b));
+    let percentile = |p: f32| frame_times[((frame_times.len() - 1) as f32 * p) as usize];
+    let mean = frame_times.iter().sum::<f32>() / frame_times.len() as f32;
+    format!(
+        "Bench: {} frames, {ships} ships, {bullets} bullets\n\
+         mean {mean:.2}ms, min {:.2}ms, p50 {:.2}ms, p95 {:.2}ms, p99 {:.2}ms, max {:.2}ms",
+        frame_times.len(),
+        frame_times[0],
+        percentile(0.5),
+        percentile(0.95),
+        percentile(0.99),
+        frame_times[frame_times.len() - 1],
+    )
+}
@@ -0,0 +1,621 @@
+//! Which key and which gamepad button each action in flight is on, rebound from a screen that
+//! opens with [K] over the main menu or the pause menu. Bindings are saved by name, so a file
+//! still reads after the list of keys that can be bound grows.
+
+use std::collections::{BTreeMap, HashMap};
+
+use bevy::{
+    app::{Plugin, PreUpdate},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            Condition, IntoSystemConfigs, OnEnter, OnExit, State,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    input::{
+        gamepad::{GamepadButton, GamepadButtonType},
+        keyboard::KeyCode,
+        ButtonInput, InputSystem,
+    },
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, FlexDirection, JustifyContent, PositionType, Style, UiRect, Val, ZIndex,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::GameState, hud::HudEditor, seed::HighScoreScreen, storage, upgrades::UpgradeScreen,
+    volume::AudioScreen, GameLifecycleState, MainMenuMarker,
+};
+
+const BINDINGS_KEY: &str = "bindings.ron";
+
+/// Every key an action can go on. [Esc] is left off, as it backs out of the screen, and so is
+/// [Backspace], which puts the defaults back. So are the keys already doing something in flight,
+/// [F] [R] [C] [V] [I] [P] [Tab] and the zoom keys, as an action on one would go off alongside it.
+pub const BINDABLE_KEYS: [KeyCode; 49] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyQ,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+];
+/// [Start] is left off, so the pause menu can always be reached
+pub const BINDABLE_BUTTONS: [GamepadButtonType; 12] = [
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+pub struct InputBindingsPlugin;
+
+impl Plugin for InputBindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputBindings::load())
+            .add_systems(
+                PreUpdate,
+                (
+                    open_bindings_screen,
+                    handle_bindings_screen.run_if(resource_exists::<BindingsScreen>),
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .run_if(not(resource_exists::<AudioScreen>))
+                    .run_if(not(resource_exists::<HudEditor>))
+                    .run_if(not(resource_exists::<UpgradeScreen>))
+                    .run_if(not(resource_exists::<HighScoreScreen>))
+                    .run_if(in_state(GameLifecycleState::MainMenu).or_else(
+                        in_state(GameLifecycleState::Game).and_then(in_state(GameState::PauseMenu)),
+                    )),
+            )
+            .add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_bindings_hint)
+            .add_systems(OnExit(GameLifecycleState::MainMenu), close_bindings_screen)
+            .add_systems(OnExit(GameState::PauseMenu), close_bindings_screen);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    TurnLeft,
+    TurnRight,
+    ThrottleUp,
+    ThrottleDown,
+    Fire,
+    Shield,
+    CaptureOption1,
+    CaptureOption2,
+    CaptureOption3,
+    AdvanceDialogue,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::ThrottleUp,
+        Action::ThrottleDown,
+        Action::Fire,
+        Action::Shield,
+        Action::CaptureOption1,
+        Action::CaptureOption2,
+        Action::CaptureOption3,
+        Action::AdvanceDialogue,
+    ];
+
+    pub fn is_capture_choice(self) -> bool {
+        matches!(
+            self,
+            Action::CaptureOption1 | Action::CaptureOption2 | Action::CaptureOption3
+        )
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Action::TurnLeft => "Turn left",
+            Action::TurnRight => "Turn right",
+            Action::ThrottleUp => "Throttle up",
+            Action::ThrottleDown => "Throttle down",
+            Action::Fire => "Fire",
+            Action::Shield => "Recharge shield",
+            Action::CaptureOption1 => "Capture: transfer",
+            Action::CaptureOption2 => "Capture: keep",
+            Action::CaptureOption3 => "Capture: scuttle",
+            Action::AdvanceDialogue => "Advance dialogue",
+        }
+    }
+}
+
+/// The capture choices share buttons with flying, which steps aside while a capture is being
+/// decided
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct InputBindings {
+    keys: HashMap<Action, KeyCode>,
+    buttons: HashMap<Action, GamepadButtonType>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let bindings = [
+            (
+                Action::TurnLeft,
+                KeyCode::ArrowLeft,
+                GamepadButtonType::DPadLeft,
+            ),
+            (
+                Action::TurnRight,
+                KeyCode::ArrowRight,
+                GamepadButtonType::DPadRight,
+            ),
+            (
+                Action::ThrottleUp,
+                KeyCode::ArrowUp,
+                GamepadButtonType::DPadUp,
+            ),
+            (
+                Action::ThrottleDown,
+                KeyCode::ArrowDown,
+                GamepadButtonType::DPadDown,
+            ),
+            (Action::Fire, KeyCode::Space, GamepadButtonType::South),
+            (Action::Shield, KeyCode::KeyS, GamepadButtonType::East),
+            (
+                Action::CaptureOption1,
+                KeyCode::Digit1,
+                GamepadButtonType::West,
+            ),
+            (
+                Action::CaptureOption2,
+                KeyCode::Digit2,
+                GamepadButtonType::North,
+            ),
+            (
+                Action::CaptureOption3,
+                KeyCode::Digit3,
+                GamepadButtonType::East,
+            ),
+            (
+                Action::AdvanceDialogue,
+                KeyCode::Enter,
+                GamepadButtonType::North,
+            ),
+        ];
+        Self {
+            keys: bindings
+                .iter()
+                .map(|(action, key, _)| (*action, *key))
+                .collect(),
+            buttons: bindings
+                .iter()
+                .map(|(action, _, button)| (*action, *button))
+                .collect(),
+        }
+    }
+}
+
+/// How bindings are written to disk, by name, as neither keys nor buttons serialise themselves
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SavedBindings {
+    keys: BTreeMap<Action, String>,
+    buttons: BTreeMap<Action, String>,
+}
+
+impl InputBindings {
+    pub fn load() -> Self {
+        storage::read(BINDINGS_KEY)
+            .map(|contents| InputBindings::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    /// Anything missing or unknown keeps its default
+    pub fn parse(contents: &str) -> Self {
+        let mut bindings = InputBindings::default();
+        let saved = match ron::from_str::<SavedBindings>(contents) {
+            Ok(saved) => saved,
+            Err(e) => {
+                println!("Could not read key bindings ({e}), using the defaults");
+                return bindings;
+            }
+        };
+        for (action, name) in saved.keys {
+            match BINDABLE_KEYS.iter().find(|key| format!("{key:?}") == name) {
+                Some(key) => {
+                    bindings.keys.insert(action, *key);
+                }
+                None => println!("Unknown key {name} for {action:?}, keeping the default"),
+            }
+        }
+        for (action, name) in saved.buttons {
+            match BINDABLE_BUTTONS
+                .iter()
+                .find(|button| format!("{button:?}") == name)
+            {
+                Some(button) => {
+                    bindings.buttons.insert(action, *button);
+                }
+                None => println!("Unknown button {name} for {action:?}, keeping the default"),
+            }
+        }
+        bindings
+    }
+
+    pub fn save(&self) {
+        let saved = SavedBindings {
+            keys: self
+                .keys
+                .iter()
+                .map(|(action, key)| (*action, format!("{key:?}")))
+                .collect(),
+            buttons: self
+                .buttons
+                .iter()
+                .map(|(action, button)| (*action, format!("{button:?}")))
+                .collect(),
+        };
+        match ron::ser::to_string_pretty(&saved, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(BINDINGS_KEY, &contents),
+            Err(e) => println!("Could not serialise key bindings: {e}"),
+        }
+    }
+
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.keys[&action]
+    }
+
+    pub fn button(&self, action: Action) -> GamepadButtonType {
+        self.buttons[&action]
+    }
+
+    /// Any action already on the key swaps onto the old one, so nothing is ever left unbound
+    pub fn bind_key(&mut self, action: Action, key: KeyCode) {
+        let old = self.key(action);
+        for bound in self.keys.values_mut() {
+            if *bound == key {
+                *bound = old;
+            }
+        }
+        self.keys.insert(action, key);
+    }
+
+    /// Buttons swap like keys, but only with actions needed at the same time, as the capture
+    /// choices borrow buttons from flying
+    pub fn bind_button(&mut self, action: Action, button: GamepadButtonType) {
+        let old = self.button(action);
+        for (other, bound) in self.buttons.iter_mut() {
+            if *bound == button && other.is_capture_choice() == action.is_capture_choice() {
+                *bound = old;
+            }
+        }
+        self.buttons.insert(action, button);
+    }
+
+    fn row_text(&self, action: Action) -> String {
+        format!(
+            "{}: {} / {}",
+            action.label(),
+            key_name(self.key(action)),
+            button_name(self.button(action))
+        )
+    }
+}
+
+/// How a key reads in a prompt, without the prefixes winit puts on letters, digits and arrows
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    ["Key", "Digit", "Arrow"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// Face buttons go by the letters on an Xbox pad, as the other prompts do
+pub fn button_name(button: GamepadButtonType) -> String {
+    match button {
+        GamepadButtonType::South => "A".to_string(),
+        GamepadButtonType::East => "B".to_string(),
+        GamepadButtonType::West => "X".to_string(),
+        GamepadButtonType::North => "Y".to_string(),
+        GamepadButtonType::LeftTrigger => "LB".to_string(),
+        GamepadButtonType::RightTrigger => "RB".to_string(),
+        GamepadButtonType::LeftTrigger2 => "LT".to_string(),
+        GamepadButtonType::RightTrigger2 => "RT".to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+/// The controls screen is open, with this row picked, waiting on a new key or button if listening
+#[derive(Resource)]
+pub struct BindingsScreen {
+    selected: usize,
+    listening: bool,
+}
+
+#[derive(Component)]
+pub struct BindingsScreenMarker;
+
+#[derive(Component)]
+pub struct BindingsText;
+
+fn spawn_bindings_hint(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(135.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                "[K] Controls",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert(MainMenuMarker);
+}
+
+fn bindings_screen_text(bindings: &InputBindings, screen: &BindingsScreen) -> String {
+    let rows = Action::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, action)| {
+            let cursor = match (i == screen.selected, screen.listening) {
+                (true, true) => "? ",
+                (true, false) => "> ",
+                (false, _) => "  ",
+            };
+            format!("{cursor}{}", bindings.row_text(*action))
+        })
+        .collect::<Vec<_>>();
+    let help = match screen.listening {
+        true => "Press a key or a button to bind it, or [Esc] to leave it be",
+        false => "[Up/Down] Choose  [Enter] Rebind  [Backspace] Defaults\n[K/Esc] Back",
+    };
+    format!("{}\n\n{help}", rows.join("\n"))
+}
+
+fn open_bindings_screen(
+    mut commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    screen: Option<Res<BindingsScreen>>,
+    bindings: Res<InputBindings>,
+    asset_server: Res<AssetServer>,
+    lifecycle: Res<State<GameLifecycleState>>,
+) {
+    if screen.is_some() || !inputs.just_pressed(KeyCode::KeyK) {
+        return;
+    }
+    // Nothing under the screen should see the key that opened it
+    inputs.reset_all();
+    let screen = BindingsScreen {
+        selected: 0,
+        listening: false,
+    };
+    let alphbeta = asset_server.load("alphbeta.ttf");
+    let jupitercrash = asset_server.load("jupiterc.ttf");
+    // The pause menu already darkens the battlefield
+    let backdrop = match lifecycle.get() {
+        GameLifecycleState::MainMenu => Color::rgba(0., 0., 0., 0.8),
+        _ => Color::rgba(0., 0., 0., 0.5),
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: backdrop.into(),
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(BindingsScreenMarker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                style: Style {
+                    padding: UiRect::bottom(Val::Px(30.)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Controls",
+                    TextStyle {
+                        font: jupitercrash,
+                        font_size: 56.,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        bindings_screen_text(&bindings, &screen),
+                        TextStyle {
+                            font: alphbeta,
+                            font_size: 24.,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    ..default()
+                },
+                BindingsText,
+            ));
+        });
+    commands.insert_resource(screen);
+}
+
+/// The screen takes every key and button while it's open, so the menu under it stays put. The
+/// d-pad and face buttons get around it too, so a gamepad can rebind its own buttons.
+fn handle_bindings_screen(
+    commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    mut buttons: ResMut<ButtonInput<GamepadButton>>,
+    mut screen: ResMut<BindingsScreen>,
+    mut bindings: ResMut<InputBindings>,
+    mut text: Query<&mut Text, With<BindingsText>>,
+    screens: Query<Entity, With<BindingsScreenMarker>>,
+) {
+    let rows = Action::ALL.len();
+    let action = Action::ALL[screen.selected];
+    let button = BINDABLE_BUTTONS
+        .into_iter()
+        .find(|button| buttons.get_just_pressed().any(|b| b.button_type == *button));
+    if screen.listening {
+        let key = BINDABLE_KEYS
+            .into_iter()
+            .find(|key| inputs.just_pressed(*key));
+        if let Some(key) = key {
+            bindings.bind_key(action, key);
+        } else if let Some(button) = button {
+            bindings.bind_button(action, button);
+        }
+        if key.is_some() || button.is_some() {
+            bindings.save();
+        }
+        if key.is_some() || button.is_some() || inputs.just_pressed(KeyCode::Escape) {
+            screen.listening = false;
+        }
+    } else {
+        if inputs.just_pressed(KeyCode::Escape)
+            || inputs.just_pressed(KeyCode::KeyK)
+            || button == Some(GamepadButtonType::East)
+        {
+            inputs.reset_all();
+            buttons.reset_all();
+            close_bindings_screen(commands, screens);
+            return;
+        }
+        if inputs.just_pressed(KeyCode::ArrowUp) || button == Some(GamepadButtonType::DPadUp) {
+            screen.selected = (screen.selected + rows - 1) % rows;
+        }
+        if inputs.just_pressed(KeyCode::ArrowDown) || button == Some(GamepadButtonType::DPadDown) {
+            screen.selected = (screen.selected + 1) % rows;
+        }
+        if inputs.just_pressed(KeyCode::Enter) || button == Some(GamepadButtonType::South) {
+            screen.listening = true;
+        }
+        if inputs.just_pressed(KeyCode::Backspace) {
+            *bindings = InputBindings::default();
+            bindings.save();
+        }
+    }
+    inputs.reset_all();
+    buttons.reset_all();
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = bindings_screen_text(&bindings, &screen);
+    }
+}
+
+fn close_bindings_screen(
+    mut commands: Commands,
+    screens: Query<Entity, With<BindingsScreenMarker>>,
+) {
+    commands.remove_resource::<BindingsScreen>();
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_swaps_clashes_and_survives_unknown_names() {
+        let mut bindings = InputBindings::default();
+        bindings.bind_key(Action::Fire, KeyCode::KeyS);
+        assert_eq!(bindings.key(Action::Fire), KeyCode::KeyS);
+        assert_eq!(bindings.key(Action::Shield), KeyCode::Space);
+        assert_eq!(key_name(KeyCode::Digit1), "1");
+
+        // Scuttling shares [B] with the shield, which doesn't move for it
+        bindings.bind_button(Action::CaptureOption3, GamepadButtonType::West);
+        assert_eq!(
+            bindings.button(Action::CaptureOption1),
+            GamepadButtonType::East
+        );
+        assert_eq!(bindings.button(Action::Shield), GamepadButtonType::East);
+
+        let saved = InputBindings::parse(
+            "(keys: {Fire: \"KeyA\", Shield: \"NotAKey\", TurnRight: \"KeyF\"}, buttons: {Fire: \"RightTrigger2\"})",
+        );
+        assert_eq!(saved.key(Action::Fire), KeyCode::KeyA);
+        assert_eq!(saved.key(Action::Shield), KeyCode::KeyS);
+        // [F] already mans the turret, so it can't be bound over
+        assert_eq!(saved.key(Action::TurnRight), KeyCode::ArrowRight);
+        assert_eq!(saved.key(Action::TurnLeft), KeyCode::ArrowLeft);
+        assert_eq!(saved.button(Action::Fire), GamepadButtonType::RightTrigger2);
+        assert_eq!(InputBindings::parse("not ron"), InputBindings::default());
+    }
+}
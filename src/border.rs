@@ -0,0 +1,102 @@
+//! Draws the edge of the arena as an energy wall, so how close the border is can be seen rather
+//! than only heard about from the crew.
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::{Query, Res},
+    },
+    gizmos::gizmos::Gizmos,
+    math::Vec2,
+    prelude::App,
+    render::color::Color,
+    time::Time,
+};
+
+use crate::{
+    gameplay::{
+        GameplaySet, PlayerMarker, Spacecraft, BORDER_KILL_RADIUS, BORDER_WARNING_RADIUS,
+        PIXELS_PER_UNIT,
+    },
+    GameLifecycleState,
+};
+
+/// How far inside the warning radius the wall starts to brighten
+pub const WALL_FADE_DISTANCE: f32 = 3.;
+/// How bright the wall is from anywhere in the arena, so it can still be found
+const WALL_MIN_INTENSITY: f32 = 0.15;
+/// Depth of the band of rings that make up the wall, outwards from the kill radius
+const WALL_THICKNESS: f32 = 0.4;
+const WALL_RINGS: usize = 5;
+/// Rings drifting outwards through the wall per second
+const WALL_DRIFT_RATE: f32 = 0.3;
+const WALL_SEGMENTS: usize = 512;
+
+pub struct BorderWallPlugin;
+
+impl Plugin for BorderWallPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            draw_border_wall
+                .in_set(GameplaySet::Presentation)
+                .run_if(in_state(GameLifecycleState::Game)),
+        );
+    }
+}
+
+/// How strongly the wall shows for a ship this far from the middle of the arena, from 0 to 1
+pub fn wall_intensity(distance: f32) -> f32 {
+    let start = BORDER_WARNING_RADIUS - WALL_FADE_DISTANCE;
+    let closeness = ((distance - start) / (BORDER_KILL_RADIUS - start)).clamp(0., 1.);
+    WALL_MIN_INTENSITY + (1. - WALL_MIN_INTENSITY) * closeness
+}
+
+/// A circle in world units, which is an ellipse once stretched out to pixels
+fn ring(gizmos: &mut Gizmos, radius: f32, color: Color) {
+    gizmos
+        .ellipse_2d(Vec2::ZERO, 0., PIXELS_PER_UNIT * radius, color)
+        .segments(WALL_SEGMENTS);
+}
+
+fn draw_border_wall(
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+) {
+    let intensity = player.get_single().map_or(WALL_MIN_INTENSITY, |ship| {
+        wall_intensity(ship.position.length())
+    });
+    ring(
+        &mut gizmos,
+        BORDER_WARNING_RADIUS,
+        Color::rgba(1., 0.7, 0.2, 0.3 * intensity),
+    );
+    for index in 0..WALL_RINGS {
+        let depth =
+            (time.elapsed_seconds() * WALL_DRIFT_RATE + index as f32 / WALL_RINGS as f32).fract();
+        ring(
+            &mut gizmos,
+            BORDER_KILL_RADIUS + depth * WALL_THICKNESS,
+            Color::rgba(0.4, 0.8, 1., 0.8 * intensity * (1. - depth)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn border_wall_brightens_on_approach() {
+        let middle = wall_intensity(0.);
+        assert!(middle > 0.);
+        assert_eq!(wall_intensity(BORDER_WARNING_RADIUS - 5.), middle);
+        assert!(wall_intensity(BORDER_WARNING_RADIUS) > middle);
+        assert!(wall_intensity(BORDER_KILL_RADIUS - 1.) > wall_intensity(BORDER_WARNING_RADIUS));
+        assert_eq!(wall_intensity(BORDER_KILL_RADIUS), 1.);
+        assert_eq!(wall_intensity(BORDER_KILL_RADIUS + 5.), 1.);
+    }
+}
@@ -0,0 +1,638 @@
+//! Boss fights: at score and survival milestones a dreadnought warps in and the regular spawns hold
+//! off until it's dealt with. It fights in phases as its hull goes, from aimed broadsides to a
+//! sweeping spiral to an all-round barrage as it closes in. Glowing weak points on the hull take
+//! extra damage through to it, and a bar across the top of the HUD tracks what's left.
+
+use std::{
+    f32::consts::{PI, TAU},
+    time::Duration,
+};
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::{With, Without},
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, OnEnter, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt, Parent},
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    sprite::{Sprite, SpriteBundle},
+    text::TextStyle,
+    time::{Time, Timer, TimerMode, Virtual},
+    transform::components::Transform,
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, BackgroundColor, FlexDirection, PositionType, Style, Val,
+    },
+};
+use bevy_rapier2d::{
+    geometry::{ActiveCollisionTypes, ActiveEvents, ActiveHooks, Collider},
+    pipeline::CollisionEvent,
+};
+use rand::Rng;
+
+use crate::{
+    dialogue::Dialogue,
+    events::{BossArrived, ShipDestroyed, ShotFired},
+    gameplay::{
+        collide_bullets, handle_npc_logic, kill_dead_ships, ship_fire, Bullet, BulletTexture,
+        Captured, EnemySpacecraftBundle, ExplosionMarker, GameState, GameplaySet, LastHitBy,
+        NPCLogic, PlayerMarker, PlayerScore, ShipTextures, ShipType, Spacecraft, SpentBullets,
+        TURN_SPEED,
+    },
+    interpolation::frame_steps,
+    practice::CapturePractice,
+    range::TargetRange,
+    score::{ScoreEvent, ScoreSource},
+    seed::RunSeed,
+    GameLifecycleState,
+};
+
+/// Points between boss fights
+pub const BOSS_SCORE_EVERY: u32 = 1500;
+/// Survived time between boss fights, for runs that aren't scoring much
+pub const BOSS_TIME_EVERY: Duration = Duration::from_secs(360);
+pub const BOSS_HEALTH: i32 = 60;
+/// Paid on top of the hull's usual points for a kill
+pub const BOSS_BONUS: u32 = 150;
+/// Hull damage each hit on a weak point does on top of the hit itself
+pub const WEAK_POINT_DAMAGE: i32 = 2;
+pub const WEAK_POINT_HEALTH: i32 = 5;
+const BOSS_HULL: ShipType = ShipType::Ship6;
+const BOSS_SCALE: f32 = 4.;
+const BOSS_TINT: Color = Color::rgb(0.8, 0.35, 0.45);
+const BOSS_SPAWN_DISTANCE: f32 = 2.5;
+/// How far off the player the boss likes to sit until it's enraged
+const BOSS_STANDOFF: f32 = 1.;
+/// Where the weak points sit, in the hull sprite's own pixels with the nose towards -x
+const WEAK_POINT_LAYOUT: [Vec2; 3] = [Vec2::new(-10., 0.), Vec2::new(14., 9.), Vec2::new(14., -9.)];
+const WEAK_POINT_COLOR: Color = Color::rgb(1., 0.85, 0.3);
+/// Angle between the shots of a broadside
+const BROADSIDE_SPREAD: f32 = 0.15;
+const BROADSIDE_SHOTS: usize = 5;
+/// How far the spiral turns between bursts
+const SPIRAL_STEP: f32 = 0.35;
+const BARRAGE_SHOTS: usize = 10;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameLifecycleState::Game), reset_bosses)
+            .add_systems(OnExit(GameLifecycleState::Game), end_run_boss_fight)
+            .add_systems(
+                Update,
+                (
+                    fly_bosses
+                        .after(handle_npc_logic)
+                        .in_set(GameplaySet::Input),
+                    call_in_boss
+                        .run_if(not(resource_exists::<BossFight>))
+                        .run_if(not(resource_exists::<CapturePractice>))
+                        .run_if(not(resource_exists::<TargetRange>))
+                        .in_set(GameplaySet::Simulation),
+                    hit_weak_points
+                        .after(collide_bullets)
+                        .in_set(GameplaySet::Collision),
+                    (
+                        lose_weak_points.before(kill_dead_ships),
+                        end_boss_fight
+                            .after(kill_dead_ships)
+                            .run_if(resource_exists::<BossFight>),
+                    )
+                        .in_set(GameplaySet::Cleanup),
+                    update_boss_health_bar.in_set(GameplaySet::Presentation),
+                )
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BossPhase {
+    /// Aimed fans of fire from a distance
+    Broadside,
+    /// A stream of fire sweeping round and round
+    Spiral,
+    /// Fire in every direction while it closes in
+    Enraged,
+}
+
+impl BossPhase {
+    /// The phase a boss fights in with this much of its hull left
+    pub fn for_health(health: i32, max_health: i32) -> Self {
+        match health as f32 / max_health as f32 {
+            left if left > 2. / 3. => BossPhase::Broadside,
+            left if left > 1. / 3. => BossPhase::Spiral,
+            _ => BossPhase::Enraged,
+        }
+    }
+
+    fn attack_interval(self) -> Duration {
+        match self {
+            BossPhase::Broadside => Duration::from_millis(1800),
+            BossPhase::Spiral => Duration::from_millis(250),
+            BossPhase::Enraged => Duration::from_millis(1000),
+        }
+    }
+
+    fn line(self) -> &'static str {
+        match self {
+            BossPhase::Broadside => "Dreadnought inbound! Regular traffic is keeping well clear.",
+            BossPhase::Spiral => "Its guns are coming round. Watch the sweep!",
+            BossPhase::Enraged => "It's coming apart, and it's coming for you!",
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BossLogic {
+    pub phase: BossPhase,
+    pub max_health: i32,
+    attack: Timer,
+    /// Where the spiral has got to
+    sweep: f32,
+}
+
+impl BossLogic {
+    pub fn new(max_health: i32) -> Self {
+        let phase = BossPhase::Broadside;
+        Self {
+            phase,
+            max_health,
+            attack: Timer::new(phase.attack_interval(), TimerMode::Repeating),
+            sweep: 0.,
+        }
+    }
+}
+
+/// A spot on a boss's hull that passes extra damage through to it, spawned as a child of the hull
+#[derive(Component)]
+pub struct WeakPoint {
+    pub health: i32,
+}
+
+/// The boss being fought. Regular spawns wait while it's here.
+#[derive(Resource)]
+pub struct BossFight {
+    pub boss: Entity,
+}
+
+/// When the next boss is due, at whichever of the two comes first
+#[derive(Resource)]
+pub struct NextBoss {
+    pub score: u32,
+    pub survived: Duration,
+}
+
+impl Default for NextBoss {
+    fn default() -> Self {
+        Self {
+            score: BOSS_SCORE_EVERY,
+            survived: BOSS_TIME_EVERY,
+        }
+    }
+}
+
+impl NextBoss {
+    pub fn due(&self, score: u32, survived: Duration) -> bool {
+        score >= self.score || survived >= self.survived
+    }
+
+    /// Puts the next fight a full interval of both past where the run is now
+    pub fn advance(&mut self, score: u32, survived: Duration) {
+        self.score = score + BOSS_SCORE_EVERY;
+        self.survived = survived + BOSS_TIME_EVERY;
+    }
+}
+
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Component)]
+pub struct BossHealthFill;
+
+fn reset_bosses(mut commands: Commands) {
+    commands.insert_resource(NextBoss::default());
+}
+
+fn end_run_boss_fight(mut commands: Commands) {
+    commands.remove_resource::<BossFight>();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn call_in_boss(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    score: Res<PlayerScore>,
+    next: Res<NextBoss>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    textures: Res<ShipTextures>,
+    mut dialogue: ResMut<Dialogue>,
+    mut bosses: EventWriter<BossArrived>,
+    mut seed: ResMut<RunSeed>,
+) {
+    if !next.due(score.score, score.survived_time.elapsed()) {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let rand = seed.rng();
+    let position =
+        player.position + Vec2::from_angle(rand.gen_range(0. ..TAU)) * BOSS_SPAWN_DISTANCE;
+    let ship = EnemySpacecraftBundle::create_ship(BOSS_HULL, position, &textures)
+        .with_tint(BOSS_TINT)
+        .with_health(BOSS_HEALTH)
+        .with_scale(BOSS_SCALE);
+    let boss = commands
+        .spawn(ship)
+        .remove::<NPCLogic>()
+        .insert((BossLogic::new(BOSS_HEALTH), Name::new("Dreadnought")))
+        .with_children(|parent| {
+            for offset in WEAK_POINT_LAYOUT {
+                parent.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: WEAK_POINT_COLOR,
+                            custom_size: Some(Vec2::splat(6.)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(offset.extend(1.)),
+                        ..default()
+                    },
+                    Collider::cuboid(3., 3.),
+                    ActiveEvents::all(),
+                    ActiveHooks::all(),
+                    ActiveCollisionTypes::STATIC_STATIC,
+                    WeakPoint {
+                        health: WEAK_POINT_HEALTH,
+                    },
+                    Name::new("Weak point"),
+                ));
+            }
+        })
+        .id();
+    commands.insert_resource(BossFight { boss });
+    bosses.send(BossArrived {
+        ship: boss,
+        ship_type: BOSS_HULL,
+    });
+    dialogue.queue_lines([BossPhase::Broadside.line().to_string().into()]);
+    spawn_boss_health_bar(&mut commands, &asset_server);
+}
+
+fn spawn_boss_health_bar(commands: &mut Commands, asset_server: &AssetServer) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                left: Val::Percent(25.),
+                width: Val::Percent(50.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert((BossHealthBar, Name::new("Boss health bar")))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "DREADNOUGHT",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: BOSS_TINT,
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Px(10.),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::rgba(0.1, 0.1, 0.1, 0.8)),
+                    ..default()
+                })
+                .with_children(|bar| {
+                    bar.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(BOSS_TINT),
+                        ..default()
+                    })
+                    .insert(BossHealthFill);
+                });
+        });
+}
+
+/// Bosses keep their distance and turn slowly, until they're enraged and come straight in.
+/// Each phase has its own attack pattern, fired from the middle of the hull.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn fly_bosses(
+    mut commands: Commands,
+    time: Res<Time>,
+    virtual_time: Res<Time<Virtual>>,
+    mut bosses: Query<
+        (Entity, &mut BossLogic, &mut Spacecraft),
+        (Without<Captured>, Without<PlayerMarker>),
+    >,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    bullet_texture: Res<BulletTexture>,
+    mut fired: EventWriter<ShotFired>,
+    mut dialogue: ResMut<Dialogue>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    for (entity, mut logic, mut craft) in bosses.iter_mut() {
+        let phase = BossPhase::for_health(craft.health, logic.max_health);
+        if phase != logic.phase {
+            logic.phase = phase;
+            logic.attack = Timer::new(phase.attack_interval(), TimerMode::Repeating);
+            dialogue.queue_lines([phase.line().to_string().into()]);
+        }
+
+        craft.end_frame();
+        let heading = craft.heading;
+        let aim = player.position - craft.position;
+        let aim_heading = f32::atan2(aim.x, aim.y);
+        let turn = TURN_SPEED * 0.5 * frame_steps(&virtual_time);
+        let delta = (aim_heading - heading + PI).rem_euclid(TAU) - PI;
+        craft.rotate(delta.clamp(-turn, turn));
+        let max_speed = craft.profile().max_velocity;
+        craft.velocity = match (phase, aim.length() > BOSS_STANDOFF) {
+            (BossPhase::Enraged, _) => max_speed * 0.15,
+            (_, true) => max_speed * 0.08,
+            (_, false) => 0.,
+        };
+
+        if !logic.attack.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let headings = match phase {
+            BossPhase::Broadside => (0..BROADSIDE_SHOTS)
+                .map(|shot| {
+                    let offset = shot as f32 - (BROADSIDE_SHOTS - 1) as f32 / 2.;
+                    aim_heading + offset * BROADSIDE_SPREAD
+                })
+                .collect::<Vec<_>>(),
+            BossPhase::Spiral => {
+                logic.sweep = (logic.sweep + SPIRAL_STEP) % TAU;
+                vec![logic.sweep, logic.sweep + TAU / 2.]
+            }
+            BossPhase::Enraged => (0..BARRAGE_SHOTS)
+                .map(|shot| aim_heading + shot as f32 * TAU / BARRAGE_SHOTS as f32)
+                .collect(),
+        };
+        for heading in headings {
+            let mut gun = Spacecraft::from_template(ShipType::Turret, craft.position);
+            gun.heading = heading;
+            ship_fire(
+                &mut commands,
+                &mut fired,
+                &mut gun,
+                entity,
+                &bullet_texture,
+                false,
+            );
+        }
+    }
+}
+
+/// Bullets landing on a weak point wear it down and hit the hull harder than anywhere else. The
+/// hull around a weak point usually takes the bullet first, in which case the weak point adds to
+/// that hit rather than making one of its own.
+pub fn hit_weak_points(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut weak_points: Query<(&Parent, &mut WeakPoint)>,
+    mut hulls: Query<&mut Spacecraft>,
+    bullets: Query<&Bullet>,
+    mut spent: ResMut<SpentBullets>,
+) {
+    let mut landed = vec![];
+    for event in collision_events.read() {
+        if let CollisionEvent::Started(a, b, _) = event {
+            for (bullet_entity, target) in [(*a, *b), (*b, *a)] {
+                if landed.contains(&bullet_entity) {
+                    continue;
+                }
+                let (Ok(bullet), Ok((hull, mut weak_point))) =
+                    (bullets.get(bullet_entity), weak_points.get_mut(target))
+                else {
+                    continue;
+                };
+                let hull = hull.get();
+                if bullet.shooter() == hull {
+                    continue;
+                }
+                weak_point.health -= 1;
+                if let Ok(mut craft) = hulls.get_mut(hull) {
+                    craft.health -= WEAK_POINT_DAMAGE;
+                }
+                commands.entity(hull).insert(LastHitBy(bullet.shooter()));
+                landed.push(bullet_entity);
+                if !spent.0.contains(&bullet_entity) {
+                    commands.entity(bullet_entity).despawn();
+                    spent.0.push(bullet_entity);
+                }
+            }
+        }
+    }
+}
+
+/// Shot out weak points go dark
+pub fn lose_weak_points(mut commands: Commands, weak_points: Query<(Entity, &Parent, &WeakPoint)>) {
+    for (entity, hull, weak_point) in weak_points.iter() {
+        if weak_point.health > 0 {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        commands.entity(hull.get()).insert(ExplosionMarker);
+    }
+}
+
+/// The fight is over once the boss is gone or on the player's side. A kill pays a bonus, and the
+/// next boss is due a full interval on from here.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn end_boss_fight(
+    mut commands: Commands,
+    fight: Res<BossFight>,
+    mut next: ResMut<NextBoss>,
+    score: Res<PlayerScore>,
+    bosses: Query<(), (With<BossLogic>, Without<Captured>, Without<PlayerMarker>)>,
+    bars: Query<Entity, With<BossHealthBar>>,
+    mut destroyed: EventReader<ShipDestroyed>,
+    mut score_events: EventWriter<ScoreEvent>,
+) {
+    if destroyed.read().any(|event| event.ship == fight.boss) {
+        score_events.send(ScoreEvent {
+            source: ScoreSource::Kills,
+            points: BOSS_BONUS,
+        });
+    }
+    if bosses.contains(fight.boss) {
+        return;
+    }
+    commands.remove_resource::<BossFight>();
+    next.advance(score.score, score.survived_time.elapsed());
+    for bar in bars.iter() {
+        commands.entity(bar).despawn_recursive();
+    }
+}
+
+fn update_boss_health_bar(
+    fight: Option<Res<BossFight>>,
+    bosses: Query<(&Spacecraft, &BossLogic)>,
+    mut fill: Query<&mut Style, With<BossHealthFill>>,
+) {
+    let Some((craft, logic)) = fight.and_then(|fight| bosses.get(fight.boss).ok()) else {
+        return;
+    };
+    let left = (craft.health.max(0) as f32 / logic.max_health as f32).min(1.);
+    for mut style in fill.iter_mut() {
+        style.width = Val::Percent(left * 100.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Allegiance;
+    use crate::gameplay::{
+        testing::{drain_score, stray_bullet, test_app},
+        ShipProfile,
+    };
+    use crate::status::ApplyStatus;
+    use bevy::{ecs::event::Events, hierarchy::BuildWorldChildren};
+    use bevy_rapier2d::rapier::geometry::CollisionEventFlags;
+
+    #[test]
+    fn bosses_fight_in_phases_and_hold_up_the_next_one() {
+        assert_eq!(
+            BossPhase::for_health(BOSS_HEALTH, BOSS_HEALTH),
+            BossPhase::Broadside
+        );
+        assert_eq!(
+            BossPhase::for_health(BOSS_HEALTH / 2, BOSS_HEALTH),
+            BossPhase::Spiral
+        );
+        assert_eq!(BossPhase::for_health(1, BOSS_HEALTH), BossPhase::Enraged);
+
+        let next = NextBoss::default();
+        assert!(!next.due(BOSS_SCORE_EVERY - 1, Duration::ZERO));
+        assert!(next.due(BOSS_SCORE_EVERY, Duration::ZERO));
+        assert!(next.due(0, BOSS_TIME_EVERY));
+
+        let mut app = test_app();
+        app.init_resource::<NextBoss>()
+            .add_systems(Update, end_boss_fight.run_if(resource_exists::<BossFight>));
+        let boss = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship6, Vec2::ZERO),
+                BossLogic::new(BOSS_HEALTH),
+            ))
+            .id();
+        app.world.insert_resource(BossFight { boss });
+        app.update();
+        assert!(app.world.contains_resource::<BossFight>());
+
+        app.world.resource_mut::<PlayerScore>().score = BOSS_SCORE_EVERY + 40;
+        app.world.despawn(boss);
+        app.world.send_event(ShipDestroyed {
+            ship: boss,
+            ship_type: ShipType::Ship6,
+            allegiance: Allegiance::Enemy,
+            position: Vec2::ZERO,
+            killer: None,
+        });
+        app.update();
+        assert!(!app.world.contains_resource::<BossFight>());
+        assert_eq!(
+            app.world.resource::<NextBoss>().score,
+            BOSS_SCORE_EVERY * 2 + 40
+        );
+        let mut events = app.world.resource_mut::<Events<ScoreEvent>>();
+        assert_eq!(drain_score(&mut events), [(ScoreSource::Kills, BOSS_BONUS)]);
+    }
+
+    #[test]
+    fn bosses_turn_the_short_way_round_to_the_player() {
+        let mut app = test_app();
+        app.insert_resource(Dialogue::init())
+            .add_systems(Update, fly_bosses);
+        // Wound a full turn round, with the player behind and just off to its right
+        let mut craft = Spacecraft::from_template(ShipType::Ship6, Vec2::ZERO);
+        craft.rotate(TAU);
+        let boss = app.world.spawn((craft, BossLogic::new(BOSS_HEALTH))).id();
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::new(0.1, -1.)),
+            PlayerMarker,
+        ));
+        // Nothing has passed on the first frame
+        app.update();
+        app.update();
+        assert!(app.world.get::<Spacecraft>(boss).unwrap().heading > TAU);
+    }
+
+    #[test]
+    fn weak_points_add_to_the_hull_hit_under_them() {
+        let mut app = test_app();
+        app.add_event::<CollisionEvent>()
+            .add_event::<ApplyStatus>()
+            .add_systems(
+                Update,
+                (collide_bullets, hit_weak_points).chain_ignore_deferred(),
+            );
+        let hull = app
+            .world
+            .spawn(Spacecraft::from_template(ShipType::Ship6, Vec2::ZERO))
+            .id();
+        let mut weak_point = Entity::PLACEHOLDER;
+        app.world.entity_mut(hull).with_children(|parent| {
+            weak_point = parent
+                .spawn(WeakPoint {
+                    health: WEAK_POINT_HEALTH,
+                })
+                .id();
+        });
+        let shooter = app.world.spawn_empty().id();
+        let bullet = app.world.spawn(stray_bullet(shooter)).id();
+        for (a, b) in [(hull, bullet), (bullet, weak_point)] {
+            app.world
+                .send_event(CollisionEvent::Started(a, b, CollisionEventFlags::empty()));
+        }
+        app.update();
+        assert!(app.world.get_entity(bullet).is_none());
+        assert_eq!(app.world.resource::<SpentBullets>().0, [bullet]);
+        assert_eq!(
+            app.world.get::<WeakPoint>(weak_point).unwrap().health,
+            WEAK_POINT_HEALTH - 1
+        );
+        // Too early in the run for the hull hit itself to count, so it's only the weak point's share
+        let full = ShipProfile::from_type(ShipType::Ship6).max_health;
+        assert_eq!(
+            app.world.get::<Spacecraft>(hull).unwrap().health,
+            full - WEAK_POINT_DAMAGE
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Capital ships: huge, slow enemies whose turrets, engines and shield generator are separate
+//! targets with their own hull. While the generator runs, shots on the main hull are absorbed;
+//! knock it out and the hull can be disabled and captured like any other ship, after which it
+//! serves as a mobile base that patches up ships on its side.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::{Has, With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::{Commands, Query, Res},
+    },
+    hierarchy::{BuildChildren, Children, DespawnRecursiveExt, Parent},
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    sprite::{Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+    transform::components::{GlobalTransform, Transform},
+};
+use bevy_rapier2d::{
+    geometry::{ActiveCollisionTypes, ActiveEvents, ActiveHooks, Collider},
+    pipeline::CollisionEvent,
+};
+
+use crate::{
+    events::ShotFired,
+    gameplay::{
+        collide_bullets, handle_inputs, handle_npc_logic, kill_dead_ships, ship_fire, Bullet,
+        BulletTexture, Captured, ExplosionMarker, GameState, GameplaySet, PlayerMarker, ShipType,
+        Spacecraft, PIXELS_PER_UNIT,
+    },
+    GameLifecycleState,
+};
+
+/// How far a capital ship's turrets reach
+pub const CAPITAL_TURRET_RANGE: f32 = 1.2;
+/// Ships on a captured capital's side this close to it get their hull patched up
+pub const BASE_REPAIR_RADIUS: f32 = 0.8;
+pub const BASE_REPAIR_TIME: Duration = Duration::from_secs(5);
+
+/// Where each subsystem sits, in the hull sprite's own pixels with the nose towards -x
+const SUBSYSTEM_LAYOUT: [(SubsystemKind, Vec2); 6] = [
+    (SubsystemKind::Turret, Vec2::new(-18., 10.)),
+    (SubsystemKind::Turret, Vec2::new(-18., -10.)),
+    (SubsystemKind::Turret, Vec2::new(4., 0.)),
+    (SubsystemKind::Engine, Vec2::new(24., 8.)),
+    (SubsystemKind::Engine, Vec2::new(24., -8.)),
+    (SubsystemKind::ShieldGenerator, Vec2::new(12., 0.)),
+];
+
+pub struct CapitalShipPlugin;
+
+impl Plugin for CapitalShipPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                limit_thrust
+                    .after(handle_inputs)
+                    .after(handle_npc_logic)
+                    .in_set(GameplaySet::Input),
+                (fit_subsystems, fire_capital_turrets, repair_near_bases)
+                    .chain()
+                    .in_set(GameplaySet::Simulation),
+                hit_subsystems
+                    .after(collide_bullets)
+                    .in_set(GameplaySet::Collision),
+                lose_subsystems
+                    .before(kill_dead_ships)
+                    .in_set(GameplaySet::Cleanup),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubsystemKind {
+    /// Fires at the other side on its own
+    Turret,
+    /// Each one lost takes a share of the hull's top speed with it
+    Engine,
+    /// Keeps the main hull from taking damage, or being captured
+    ShieldGenerator,
+}
+
+impl SubsystemKind {
+    pub fn max_health(self) -> i32 {
+        match self {
+            SubsystemKind::Turret => 3,
+            SubsystemKind::Engine => 4,
+            SubsystemKind::ShieldGenerator => 6,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            SubsystemKind::Turret => Color::rgb(0.9, 0.4, 0.3),
+            SubsystemKind::Engine => Color::rgb(1., 0.7, 0.2),
+            SubsystemKind::ShieldGenerator => Color::rgb(0.3, 0.8, 1.),
+        }
+    }
+}
+
+/// A part of a capital ship that can be shot off, spawned as a child of the hull
+#[derive(Component)]
+pub struct Subsystem {
+    pub kind: SubsystemKind,
+    pub health: i32,
+    reload: Timer,
+}
+
+#[derive(Component)]
+pub struct CapitalShip {
+    repair: Timer,
+}
+
+/// The hull is behind its shield generator, so hits on it are soaked up
+#[derive(Component)]
+pub struct ShieldedHull;
+
+/// Capital ships can arrive from a warp or a cheat, so they're fitted out wherever one turns up
+pub fn fit_subsystems(
+    mut commands: Commands,
+    ships: Query<(Entity, &Spacecraft), Without<CapitalShip>>,
+) {
+    for (entity, ship) in ships.iter() {
+        if ship.ship_type != ShipType::Capital {
+            continue;
+        }
+        commands
+            .entity(entity)
+            .insert((
+                CapitalShip {
+                    repair: Timer::new(BASE_REPAIR_TIME, TimerMode::Repeating),
+                },
+                ShieldedHull,
+            ))
+            .with_children(|parent| {
+                for (kind, offset) in SUBSYSTEM_LAYOUT {
+                    parent.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: kind.color(),
+                                custom_size: Some(Vec2::splat(6.)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(offset.extend(1.)),
+                            ..default()
+                        },
+                        Collider::cuboid(3., 3.),
+                        ActiveEvents::all(),
+                        ActiveHooks::all(),
+                        ActiveCollisionTypes::STATIC_STATIC,
+                        Subsystem {
+                            kind,
+                            health: kind.max_health(),
+                            reload: Timer::new(Duration::from_millis(1200), TimerMode::Once),
+                        },
+                        Name::new(format!("{kind:?}")),
+                    ));
+                }
+            });
+    }
+}
+
+/// Fraction of the hull's top speed its remaining engines can give it
+pub fn engine_thrust(engines_left: usize) -> f32 {
+    let engines = SUBSYSTEM_LAYOUT
+        .iter()
+        .filter(|(kind, _)| *kind == SubsystemKind::Engine)
+        .count();
+    engines_left as f32 / engines as f32
+}
+
+fn limit_thrust(
+    mut capitals: Query<(&mut Spacecraft, &Children, Has<PlayerMarker>), With<CapitalShip>>,
+    subsystems: Query<&Subsystem>,
+) {
+    for (mut ship, children, piloted) in capitals.iter_mut() {
+        let engines = children
+            .iter()
+            .filter_map(|child| subsystems.get(*child).ok())
+            .filter(|subsystem| subsystem.kind == SubsystemKind::Engine)
+            .count();
+        let thrust = engine_thrust(engines);
+        // The AI sets its speed fresh every frame, while the player's throttle carries over
+        match piloted {
+            true => ship.velocity = ship.velocity.min(ship.profile().max_velocity * thrust),
+            false => ship.velocity *= thrust,
+        }
+    }
+}
+
+/// Turrets fight for whoever holds the hull, at the nearest ship on the other side
+#[allow(clippy::type_complexity)]
+fn fire_capital_turrets(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut turrets: Query<(&Parent, &GlobalTransform, &mut Subsystem)>,
+    ships: Query<(Entity, &Spacecraft, Has<Captured>, Has<PlayerMarker>), Without<ExplosionMarker>>,
+    bullet_texture: Res<BulletTexture>,
+    mut fired: EventWriter<ShotFired>,
+) {
+    for (hull, transform, mut turret) in turrets.iter_mut() {
+        if turret.kind != SubsystemKind::Turret || !turret.reload.tick(time.delta()).finished() {
+            continue;
+        }
+        if let Ok((hull, _, captured, piloted)) = ships.get(hull.get()) {
+            let friendly = captured || piloted;
+            let position = transform.translation().truncate() / PIXELS_PER_UNIT;
+            let target = ships
+                .iter()
+                .filter(|(_, _, captured, piloted)| (*captured || *piloted) != friendly)
+                .map(|(_, ship, _, _)| ship.position)
+                .filter(|target| target.distance(position) < CAPITAL_TURRET_RANGE)
+                .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+            if let Some(target) = target {
+                let aim = target - position;
+                let mut gun = Spacecraft::from_template(ShipType::Turret, position);
+                gun.heading = f32::atan2(aim.x, aim.y);
+                ship_fire(
+                    &mut commands,
+                    &mut fired,
+                    &mut gun,
+                    hull,
+                    &bullet_texture,
+                    false,
+                );
+                turret.reload.reset();
+            }
+        }
+    }
+}
+
+/// A captured capital ship slowly repairs the player and allies flying close to it
+fn repair_near_bases(
+    time: Res<Time>,
+    mut capitals: Query<(Entity, &mut CapitalShip)>,
+    mut ships: Query<(Entity, &mut Spacecraft, Has<Captured>, Has<PlayerMarker>)>,
+) {
+    for (base, mut capital) in capitals.iter_mut() {
+        if !capital.repair.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let position = match ships.get(base) {
+            Ok((_, ship, captured, piloted)) if captured || piloted => ship.position,
+            _ => continue,
+        };
+        for (entity, mut ship, captured, piloted) in ships.iter_mut() {
+            if entity != base
+                && (captured || piloted)
+                && ship.position.distance(position) < BASE_REPAIR_RADIUS
+            {
+                ship.health = (ship.health + 1).min(ship.profile().max_health);
+            }
+        }
+    }
+}
+
+/// Bullets landing on a subsystem damage it instead of the hull, and a shielded hull soaks up the rest
+fn hit_subsystems(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut subsystems: Query<(&Parent, &mut Subsystem)>,
+    shielded: Query<(), With<ShieldedHull>>,
+    bullets: Query<&Bullet>,
+) {
+    let mut spent = vec![];
+    for event in collision_events.read() {
+        if let CollisionEvent::Started(a, b, _) = event {
+            for (bullet_entity, target) in [(*a, *b), (*b, *a)] {
+                if spent.contains(&bullet_entity) {
+                    continue;
+                }
+                if let Ok(bullet) = bullets.get(bullet_entity) {
+                    let hull = match subsystems.get(target) {
+                        Ok((hull, _)) => hull.get(),
+                        Err(_) => target,
+                    };
+                    if bullet.shooter() == hull {
+                        continue;
+                    }
+                    if let Ok((_, mut subsystem)) = subsystems.get_mut(target) {
+                        subsystem.health -= 1;
+                    } else if !shielded.contains(target) {
+                        continue;
+                    }
+                    commands.entity(bullet_entity).despawn();
+                    spent.push(bullet_entity);
+                }
+            }
+        }
+    }
+}
+
+/// Shot off subsystems are removed, and losing the generator drops the hull's shield
+pub fn lose_subsystems(mut commands: Commands, subsystems: Query<(Entity, &Parent, &Subsystem)>) {
+    for (entity, hull, subsystem) in subsystems.iter() {
+        if subsystem.health > 0 {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        let mut hull = commands.entity(hull.get());
+        hull.insert(ExplosionMarker);
+        if subsystem.kind == SubsystemKind::ShieldGenerator {
+            hull.remove::<ShieldedHull>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::testing::test_app;
+
+    #[test]
+    fn capital_hull_is_shielded_until_its_generator_falls() {
+        let mut app = test_app();
+        app.add_systems(Update, (fit_subsystems, lose_subsystems).chain());
+        let hull = app
+            .world
+            .spawn(Spacecraft::from_template(ShipType::Capital, Vec2::ZERO))
+            .id();
+        app.update();
+        assert!(app.world.get::<ShieldedHull>(hull).is_some());
+        let mut subsystems = app.world.query::<&mut Subsystem>();
+        assert_eq!(subsystems.iter(&app.world).count(), 6);
+
+        for mut subsystem in subsystems.iter_mut(&mut app.world) {
+            if subsystem.kind == SubsystemKind::ShieldGenerator {
+                subsystem.health = 0;
+            }
+        }
+        app.update();
+        assert!(app.world.get::<ShieldedHull>(hull).is_none());
+        assert_eq!(subsystems.iter(&app.world).count(), 5);
+        assert_eq!(engine_thrust(1), 0.5);
+    }
+}
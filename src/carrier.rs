@@ -0,0 +1,302 @@
+use std::{
+    f32::consts::{PI, TAU},
+    time::Duration,
+};
+
+use bevy::{
+    app::{Plugin, Update},
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventWriter,
+        query::{Has, With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::{Commands, Query, Res},
+    },
+    hierarchy::DespawnRecursiveExt,
+    math::Vec2,
+    prelude::App,
+    time::{Time, Timer, TimerMode, Virtual},
+};
+
+use crate::{
+    events::ShotFired,
+    gameplay::{
+        handle_npc_logic, kill_dead_ships, make_ally, ship_fire, AllyTexture, BulletTexture,
+        Captured, EnemySpacecraftBundle, ExplosionMarker, GameState, GameplaySet, NPCLogic,
+        PlayerMarker, ShipTextures, ShipType, Spacecraft, TURN_SPEED,
+    },
+    interpolation::frame_steps,
+    GameLifecycleState,
+};
+
+/// How often a carrier puts another drone into the fight
+pub const DRONE_LAUNCH_TIME: Duration = Duration::from_secs(6);
+/// Most drones one carrier keeps out at once
+pub const DRONES_PER_CARRIER: usize = 3;
+/// Enemy carriers back off rather than close in past this
+pub const CARRIER_STANDOFF: f32 = 1.;
+/// Drones only go for the player while it's this close to their carrier
+pub const DRONE_LEASH: f32 = 2.;
+const DRONE_FIRE_RANGE: f32 = 0.8;
+/// Fraction of its top speed a drone flies at
+const DRONE_THROTTLE: f32 = 0.2;
+
+pub struct CarrierPlugin;
+
+impl Plugin for CarrierPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (hold_carriers_off, fly_drones)
+                    .after(handle_npc_logic)
+                    .in_set(GameplaySet::Input),
+                (equip_drone_bays, launch_drones)
+                    .chain()
+                    .in_set(GameplaySet::Simulation),
+                ground_orphaned_drones
+                    .before(kill_dead_ships)
+                    .in_set(GameplaySet::Cleanup),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct DroneBay(Timer);
+
+/// Enemy drones fly on this instead of [`NPCLogic`]: never far from their carrier, and darting at
+/// the player whenever it strays within [`DRONE_LEASH`]. Drones launched for the player's side
+/// keep the usual ally logic, and carry this only so their carrier can count them.
+#[derive(Component)]
+pub struct DroneLogic {
+    pub carrier: Entity,
+}
+
+/// Carriers can arrive from a warp, a cheat or a capture, so bays are fitted wherever one turns up
+pub fn equip_drone_bays(
+    mut commands: Commands,
+    ships: Query<(Entity, &Spacecraft), Without<DroneBay>>,
+) {
+    for (entity, ship) in ships.iter() {
+        if matches!(ship.ship_type, ShipType::Carrier) {
+            commands.entity(entity).insert(DroneBay(Timer::new(
+                DRONE_LAUNCH_TIME,
+                TimerMode::Repeating,
+            )));
+        }
+    }
+}
+
+/// Drones fight for whoever owns the carrier, so a captured or commandeered carrier launches allies
+#[allow(clippy::type_complexity)]
+pub fn launch_drones(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut carriers: Query<
+        (
+            Entity,
+            &Spacecraft,
+            &mut DroneBay,
+            Has<Captured>,
+            Has<PlayerMarker>,
+        ),
+        Without<ExplosionMarker>,
+    >,
+    drones: Query<&DroneLogic>,
+    textures: Res<ShipTextures>,
+    ally_texture: Res<AllyTexture>,
+) {
+    for (entity, carrier, mut bay, captured, piloted) in carriers.iter_mut() {
+        if !bay.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let launched = drones
+            .iter()
+            .filter(|drone| drone.carrier == entity)
+            .count();
+        if launched >= DRONES_PER_CARRIER {
+            continue;
+        }
+        let behind = -Vec2::new(carrier.heading.sin(), carrier.heading.cos()) * 0.3;
+        let mut drone = commands.spawn(EnemySpacecraftBundle::create_ship(
+            ShipType::Drone,
+            carrier.position + behind,
+            &textures,
+        ));
+        drone.insert((
+            Name::new("Drone".to_string()),
+            DroneLogic { carrier: entity },
+        ));
+        match captured || piloted {
+            true => make_ally(&mut drone, &ally_texture),
+            false => {
+                drone.remove::<NPCLogic>();
+            }
+        }
+    }
+}
+
+/// Carriers are no good up close, so enemy ones back away from the player while still facing it
+#[allow(clippy::type_complexity)]
+pub fn hold_carriers_off(
+    mut carriers: Query<
+        &mut Spacecraft,
+        (
+            With<DroneBay>,
+            With<NPCLogic>,
+            Without<Captured>,
+            Without<PlayerMarker>,
+        ),
+    >,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    for mut carrier in carriers.iter_mut() {
+        if carrier.position.distance(player.position) < CARRIER_STANDOFF {
+            carrier.velocity = -carrier.profile().max_velocity * 0.15;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn fly_drones(
+    mut commands: Commands,
+    mut drones: Query<
+        (Entity, &DroneLogic, &mut Spacecraft),
+        (Without<NPCLogic>, Without<Captured>, Without<PlayerMarker>),
+    >,
+    carriers: Query<&Spacecraft, Without<DroneLogic>>,
+    player: Query<&Spacecraft, (With<PlayerMarker>, Without<DroneLogic>)>,
+    bullet_texture: Res<BulletTexture>,
+    mut fired: EventWriter<ShotFired>,
+    virtual_time: Res<Time<Virtual>>,
+) {
+    let turn = TURN_SPEED * frame_steps(&virtual_time);
+    let player = player.get_single().ok().map(|player| player.position);
+    for (entity, logic, mut craft) in drones.iter_mut() {
+        let Ok(carrier) = carriers.get(logic.carrier) else {
+            continue;
+        };
+        craft.end_frame();
+        let attacking = player.filter(|player| player.distance(carrier.position) < DRONE_LEASH);
+        let to_goal = attacking.unwrap_or(carrier.position) - craft.position;
+        let heading_delta =
+            (f32::atan2(to_goal.x, to_goal.y) - craft.heading + PI).rem_euclid(TAU) - PI;
+        craft.rotate(heading_delta.clamp(-turn, turn));
+        craft.velocity = craft.profile().max_velocity * DRONE_THROTTLE;
+        let lined_up = heading_delta.abs() < 0.3 && to_goal.length() < DRONE_FIRE_RANGE;
+        if attacking.is_some() && lined_up && craft.weapon_cooldown.finished() {
+            ship_fire(
+                &mut commands,
+                &mut fired,
+                &mut craft,
+                entity,
+                &bullet_texture,
+                false,
+            );
+        }
+    }
+}
+
+/// Drones can't fly without their carrier. They're lost with it, and enemy ones are lost too if
+/// the player takes it. A drone the player takes for themselves goes over to the usual ally logic.
+/// Lost drones just drop out rather than going through [`kill_dead_ships`], so nobody's paid for them.
+#[allow(clippy::type_complexity)]
+pub fn ground_orphaned_drones(
+    mut commands: Commands,
+    drones: Query<
+        (
+            Entity,
+            &DroneLogic,
+            Has<Captured>,
+            Has<PlayerMarker>,
+            Has<NPCLogic>,
+        ),
+        Without<ExplosionMarker>,
+    >,
+    carriers: Query<(Has<Captured>, Has<PlayerMarker>), Without<ExplosionMarker>>,
+) {
+    for (entity, drone, captured, piloted, has_logic) in drones.iter() {
+        let drone_allied = captured || piloted;
+        let carrier_allied = match carriers.get(drone.carrier) {
+            Ok((captured, piloted)) => captured || piloted,
+            Err(_) if piloted => {
+                commands.entity(entity).remove::<DroneLogic>();
+                continue;
+            }
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+        match (drone_allied, carrier_allied) {
+            (true, false) => {
+                commands.entity(entity).remove::<DroneLogic>();
+                if captured && !has_logic {
+                    commands.entity(entity).insert(NPCLogic::new(Vec2::ZERO));
+                }
+            }
+            (false, true) => commands.entity(entity).despawn_recursive(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::ShipDestroyed;
+    use crate::gameplay::testing::test_app;
+    use crate::settings::Settings;
+    use bevy::ecs::event::Events;
+
+    #[test]
+    fn carriers_cap_their_drones_and_take_them_down_with_them() {
+        let mut app = test_app();
+        app.init_resource::<Settings>().add_systems(
+            Update,
+            (
+                equip_drone_bays,
+                launch_drones,
+                ground_orphaned_drones,
+                kill_dead_ships,
+            )
+                .chain(),
+        );
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        let carrier = app
+            .world
+            .spawn(Spacecraft::from_template(ShipType::Carrier, Vec2::ZERO))
+            .id();
+        let launches = DRONE_LAUNCH_TIME.as_secs() as usize * (DRONES_PER_CARRIER + 2);
+        for _ in 0..launches + 1 {
+            app.update();
+        }
+        let mut drones = app
+            .world
+            .query_filtered::<(&DroneLogic, Has<NPCLogic>), With<Spacecraft>>();
+        assert_eq!(drones.iter(&app.world).count(), DRONES_PER_CARRIER);
+        for (drone, flown_by_npc_logic) in drones.iter(&app.world) {
+            assert_eq!(drone.carrier, carrier);
+            assert!(!flown_by_npc_logic);
+        }
+
+        app.world.despawn(carrier);
+        app.update();
+        let mut drones = app.world.query_filtered::<(), With<DroneLogic>>();
+        assert_eq!(drones.iter(&app.world).count(), 0);
+        // Grounded, not shot down, so they don't count as anyone's kills
+        let destroyed = app.world.resource::<Events<ShipDestroyed>>();
+        assert!(destroyed.is_empty());
+    }
+}
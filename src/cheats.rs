@@ -0,0 +1,125 @@
+//! Developer shortcuts for testing late-run content, only compiled with `--features dev_cheats`.
+//!
+//! [F1] god mode, [F2] +100 score, [F3] capture the nearest enemy, [F4] teleport to the origin,
+//! [F5]-[F10] spawn a Ship1-Ship6 enemy in front of the player, [F11] spawns a carrier.
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        entity::Entity,
+        event::EventWriter,
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::App,
+};
+
+use crate::{
+    gameplay::{
+        kill_dead_ships, Captured, EnemySpacecraftBundle, GameState, GameplaySet,
+        MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker, PlayerMarker,
+        ShipTextures, ShipType, Spacecraft,
+    },
+    score::{ScoreEvent, ScoreSource},
+    GameLifecycleState,
+};
+
+pub struct CheatsPlugin;
+
+impl Plugin for CheatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameLifecycleState::Game), reset_cheats)
+            .add_systems(
+                Update,
+                (
+                    handle_cheat_inputs
+                        .run_if(in_state(GameState::Regular))
+                        .in_set(GameplaySet::Input),
+                    apply_god_mode.before(kill_dead_ships),
+                )
+                    .run_if(in_state(GameLifecycleState::Game)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct GodMode(bool);
+
+fn reset_cheats(mut commands: Commands) {
+    commands.insert_resource(GodMode::default());
+}
+
+const SPAWN_KEYS: [(KeyCode, ShipType); 8] = [
+    (KeyCode::F5, ShipType::Ship1),
+    (KeyCode::F6, ShipType::Ship2),
+    (KeyCode::F7, ShipType::Ship3),
+    (KeyCode::F8, ShipType::Ship4),
+    (KeyCode::F9, ShipType::Ship5),
+    (KeyCode::F10, ShipType::Ship6),
+    (KeyCode::F11, ShipType::Carrier),
+    (KeyCode::F12, ShipType::Capital),
+];
+
+#[allow(clippy::type_complexity)]
+fn handle_cheat_inputs(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut god_mode: ResMut<GodMode>,
+    mut score_events: EventWriter<ScoreEvent>,
+    textures: Res<ShipTextures>,
+    mut player: Query<&mut Spacecraft, With<PlayerMarker>>,
+    mut enemies: Query<(Entity, &mut Spacecraft), (Without<PlayerMarker>, Without<Captured>)>,
+) {
+    if inputs.just_pressed(KeyCode::F1) {
+        god_mode.0 = !god_mode.0;
+        println!("God mode: {}", god_mode.0);
+    }
+    if inputs.just_pressed(KeyCode::F2) {
+        score_events.send(ScoreEvent {
+            source: ScoreSource::Cheats,
+            points: 100,
+        });
+    }
+    if let Ok(mut player) = player.get_single_mut() {
+        if inputs.just_pressed(KeyCode::F3) {
+            let nearest = enemies.iter_mut().min_by(|(_, a), (_, b)| {
+                a.position
+                    .distance(player.position)
+                    .total_cmp(&b.position.distance(player.position))
+            });
+            if let Some((entity, mut enemy)) = nearest {
+                enemy.health = 1;
+                commands.entity(entity).insert(
+                    MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+                );
+            }
+        }
+        if inputs.just_pressed(KeyCode::F4) {
+            player.position = Vec2::ZERO;
+        }
+        for (key, ship_type) in SPAWN_KEYS {
+            if inputs.just_pressed(key) {
+                let ahead = Vec2::new(player.heading.sin(), player.heading.cos()) * 0.8;
+                commands.spawn(EnemySpacecraftBundle::create_ship(
+                    ship_type,
+                    player.position + ahead,
+                    &textures,
+                ));
+            }
+        }
+    }
+}
+
+fn apply_god_mode(
+    god_mode: Option<Res<GodMode>>,
+    mut player: Query<&mut Spacecraft, With<PlayerMarker>>,
+) {
+    if let (Some(god_mode), Ok(mut player)) = (god_mode, player.get_single_mut()) {
+        if god_mode.0 {
+            player.health = player.profile().max_health;
+        }
+    }
+}
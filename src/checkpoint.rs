@@ -0,0 +1,396 @@
+//! Runs are checkpointed to save data every [`CHECKPOINT_INTERVAL`] of survived time, so a crash
+//! or a closed window part way through a long run isn't the end of it. The main menu offers the
+//! last checkpoint with [Enter], which puts the player's ship, their allies and the score back as
+//! they were, along with any upgrades bought. Any run that ends properly throws its checkpoint away.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    core::Name,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, NextState, OnEnter, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::BindingsScreen,
+    gameplay::{
+        make_ally, setup, AllyTexture, Captured, DeathSequence, EnemySpacecraftBundle, GameState,
+        GameplaySet, PlayerBundle, PlayerMarker, PlayerScore, ShipTextures, ShipType, ShipVariant,
+        Spacecraft,
+    },
+    loadout::Loadout,
+    practice::CapturePractice,
+    profile::Profiles,
+    range::TargetRange,
+    seed::{HighScoreScreen, ReplayConfig, ReplaySeed, RunConfig, RunSeed},
+    settings::Difficulty,
+    spectate::Spectating,
+    storage,
+    upgrades::RunUpgrades,
+    volume::AudioScreen,
+    GameLifecycleState, MainMenuMarker,
+};
+
+const CHECKPOINT_KEY: &str = "checkpoint.ron";
+/// Survived time between checkpoints
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameLifecycleState::Game),
+            (
+                reset_next_checkpoint,
+                resume_checkpoint
+                    .after(setup)
+                    .run_if(resource_exists::<ResumeCheckpoint>),
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            take_checkpoint
+                .in_set(GameplaySet::Cleanup)
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular))
+                .run_if(not(resource_exists::<DeathSequence>))
+                .run_if(not(resource_exists::<Spectating>))
+                .run_if(not(resource_exists::<CapturePractice>))
+                .run_if(not(resource_exists::<TargetRange>)),
+        )
+        .add_systems(
+            OnExit(GameLifecycleState::Game),
+            clear_checkpoint.run_if(not(resource_exists::<TargetRange>)),
+        )
+        .add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_resume_hint)
+        .add_systems(
+            Update,
+            (resume_from_menu, update_resume_hint)
+                .run_if(not(resource_exists::<AudioScreen>))
+                .run_if(not(resource_exists::<BindingsScreen>))
+                .run_if(not(resource_exists::<HighScoreScreen>))
+                .run_if(in_state(GameLifecycleState::MainMenu)),
+        );
+    }
+}
+
+/// A ship as it was when the checkpoint was taken
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShipSnapshot {
+    pub ship_type: ShipType,
+    #[serde(default)]
+    pub variant: Option<ShipVariant>,
+    pub position: [f32; 2],
+    pub heading: f32,
+    pub health: i32,
+    /// Whether it's the ship the run started in, which is the only one carrying the loadout
+    #[serde(default)]
+    pub fitted: bool,
+}
+
+impl ShipSnapshot {
+    pub fn of(craft: &Spacecraft) -> Self {
+        Self {
+            ship_type: craft.ship_type,
+            variant: craft.variant,
+            position: craft.position.to_array(),
+            heading: craft.heading,
+            health: craft.health,
+            fitted: craft.fitting.is_some(),
+        }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        Vec2::from_array(self.position)
+    }
+}
+
+/// Enough of a run to carry on with it. Enemies aren't kept; they warp back in, as spawning
+/// already follows the score and survived time.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    pub seed: u32,
+    pub difficulty: Difficulty,
+    pub loadout: Loadout,
+    /// Checkpoints from before this was kept count as flown without it
+    #[serde(default)]
+    pub aim_assist: bool,
+    pub score: u32,
+    #[serde(default)]
+    pub scrap: u32,
+    pub survived_secs: f32,
+    #[serde(default)]
+    pub upgrades: RunUpgrades,
+    pub player: ShipSnapshot,
+    pub allies: Vec<ShipSnapshot>,
+}
+
+impl RunCheckpoint {
+    /// The profile's checkpoint, if it has one waiting. A cleared checkpoint doesn't parse.
+    pub fn load() -> Option<Self> {
+        storage::read(CHECKPOINT_KEY).and_then(|contents| ron::from_str(&contents).ok())
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(CHECKPOINT_KEY, &contents),
+            Err(e) => println!("Could not serialise checkpoint: {e}"),
+        }
+    }
+
+    pub fn clear() {
+        storage::write(CHECKPOINT_KEY, "");
+    }
+
+    fn menu_text(&self) -> String {
+        format!(
+            "[Enter] Resume checkpoint: {} in {:.0}s",
+            self.score, self.survived_secs
+        )
+    }
+}
+
+/// Survived time the next checkpoint is due at
+#[derive(Resource, Default)]
+pub struct NextCheckpoint(pub Duration);
+
+/// The checkpoint the run being started picks up from
+#[derive(Resource)]
+pub struct ResumeCheckpoint(pub RunCheckpoint);
+
+fn reset_next_checkpoint(mut commands: Commands) {
+    commands.insert_resource(NextCheckpoint(CHECKPOINT_INTERVAL));
+}
+
+pub fn take_checkpoint(
+    score: Res<PlayerScore>,
+    mut next: ResMut<NextCheckpoint>,
+    seed: Res<RunSeed>,
+    run: Res<RunConfig>,
+    upgrades: Res<RunUpgrades>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    allies: Query<&Spacecraft, With<Captured>>,
+) {
+    let survived = score.survived_time.elapsed();
+    if survived < next.0 {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    next.0 = survived + CHECKPOINT_INTERVAL;
+    RunCheckpoint {
+        seed: seed.seed,
+        difficulty: run.difficulty,
+        loadout: run.loadout,
+        aim_assist: run.aim_assist,
+        score: score.score,
+        scrap: score.scrap,
+        survived_secs: survived.as_secs_f32(),
+        upgrades: *upgrades,
+        player: ShipSnapshot::of(player),
+        allies: allies.iter().map(ShipSnapshot::of).collect(),
+    }
+    .save();
+}
+
+/// Swaps the ship the run was set up with for the one in the checkpoint, and brings the allies back
+#[allow(clippy::too_many_arguments)]
+pub fn resume_checkpoint(
+    mut commands: Commands,
+    resume: Res<ResumeCheckpoint>,
+    mut score: ResMut<PlayerScore>,
+    mut next: ResMut<NextCheckpoint>,
+    mut upgrades: ResMut<RunUpgrades>,
+    run: Res<RunConfig>,
+    textures: Res<ShipTextures>,
+    ally_texture: Res<AllyTexture>,
+    player: Query<Entity, With<PlayerMarker>>,
+) {
+    let checkpoint = &resume.0;
+    commands.remove_resource::<ResumeCheckpoint>();
+    score.score = checkpoint.score;
+    score.scrap = checkpoint.scrap;
+    *upgrades = checkpoint.upgrades;
+    let survived = Duration::from_secs_f32(checkpoint.survived_secs);
+    score.survived_time.set_elapsed(survived);
+    next.0 = survived + CHECKPOINT_INTERVAL;
+
+    let snapshot = checkpoint.player;
+    let mut ship = PlayerBundle::create_ship(snapshot.ship_type, snapshot.position(), &textures);
+    if snapshot.fitted {
+        ship = ship.with_fitting(run.loadout.fitting());
+    }
+    let ship = ship.with_health(snapshot.health).turned(snapshot.heading);
+    if let Ok(player) = player.get_single() {
+        commands.entity(player).insert(ship);
+    }
+
+    for ally in &checkpoint.allies {
+        let mut ship =
+            EnemySpacecraftBundle::create_ship(ally.ship_type, ally.position(), &textures);
+        if let Some(variant) = ally.variant {
+            ship = ship.with_variant(variant);
+        }
+        let mut ship = commands.spawn(ship.with_health(ally.health).turned(ally.heading));
+        ship.insert(Name::new("Ally"));
+        make_ally(&mut ship, &ally_texture);
+    }
+}
+
+fn clear_checkpoint() {
+    RunCheckpoint::clear();
+}
+
+#[derive(Component)]
+pub struct ResumeHintMarker;
+
+/// Sits above the profile text, and stays empty while there's nothing to resume
+fn spawn_resume_hint(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let text = RunCheckpoint::load()
+        .map(|checkpoint| checkpoint.menu_text())
+        .unwrap_or_default();
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(75.),
+                left: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert((MainMenuMarker, ResumeHintMarker));
+}
+
+/// Each profile keeps its own checkpoint
+fn update_resume_hint(profiles: Res<Profiles>, mut hint: Query<&mut Text, With<ResumeHintMarker>>) {
+    if !profiles.is_changed() {
+        return;
+    }
+    if let Ok(mut hint) = hint.get_single_mut() {
+        hint.sections[0].value = RunCheckpoint::load()
+            .map(|checkpoint| checkpoint.menu_text())
+            .unwrap_or_default();
+    }
+}
+
+/// Picks the run up at the seed, difficulty and loadout it was flown at
+fn resume_from_menu(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+) {
+    if !inputs.just_pressed(KeyCode::Enter) {
+        return;
+    }
+    if let Some(checkpoint) = RunCheckpoint::load() {
+        commands.insert_resource(ReplayConfig(RunConfig {
+            difficulty: checkpoint.difficulty,
+            loadout: checkpoint.loadout,
+            aim_assist: checkpoint.aim_assist,
+        }));
+        commands.insert_resource(ReplaySeed(checkpoint.seed));
+        commands.insert_resource(ResumeCheckpoint(checkpoint));
+        state.set(GameLifecycleState::Game);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::testing::test_app;
+    use crate::loadout::Loadout;
+
+    #[test]
+    fn resuming_a_checkpoint_puts_the_ship_fleet_and_score_back() {
+        let mut flown = Spacecraft::from_template(ShipType::Ship4, Vec2::new(1., -0.5));
+        flown.rotate(1.2);
+        flown.health = 2;
+        let checkpoint = RunCheckpoint {
+            seed: 7,
+            difficulty: Default::default(),
+            loadout: Loadout::default(),
+            aim_assist: true,
+            score: 340,
+            scrap: 12,
+            survived_secs: 125.,
+            upgrades: RunUpgrades {
+                hull: 1,
+                spent: 10,
+                ..default()
+            },
+            player: ShipSnapshot::of(&flown),
+            allies: vec![ShipSnapshot::of(&Spacecraft::from_template(
+                ShipType::Ship2,
+                Vec2::new(0.5, 0.5),
+            ))],
+        };
+        // What's written to save data reads back the same
+        let contents = ron::ser::to_string(&checkpoint).unwrap();
+        let read = ron::from_str::<RunCheckpoint>(&contents).unwrap();
+        assert_eq!(read.player, checkpoint.player);
+        assert!(read.aim_assist);
+
+        let mut app = test_app();
+        app.insert_resource(NextCheckpoint(CHECKPOINT_INTERVAL))
+            .insert_resource(ResumeCheckpoint(read))
+            .add_systems(
+                Update,
+                resume_checkpoint.run_if(resource_exists::<ResumeCheckpoint>),
+            );
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship2, Vec2::ZERO),
+                PlayerMarker,
+            ))
+            .id();
+        app.update();
+
+        let craft = app.world.get::<Spacecraft>(player).unwrap();
+        assert_eq!(craft.ship_type, ShipType::Ship4);
+        assert_eq!(craft.position, Vec2::new(1., -0.5));
+        assert_eq!(craft.health, 2);
+        assert!((craft.heading - 1.2).abs() < 1e-5);
+        let mut allies = app.world.query_filtered::<&Spacecraft, With<Captured>>();
+        assert_eq!(allies.iter(&app.world).count(), 1);
+        let score = app.world.resource::<PlayerScore>();
+        assert_eq!(score.score, 340);
+        assert_eq!(score.scrap, 12);
+        assert_eq!(app.world.resource::<RunUpgrades>().hull, 1);
+        assert_eq!(score.survived_time.elapsed(), Duration::from_secs(125));
+        assert_eq!(
+            app.world.resource::<NextCheckpoint>().0,
+            Duration::from_secs(125) + CHECKPOINT_INTERVAL
+        );
+        assert!(!app.world.contains_resource::<ResumeCheckpoint>());
+    }
+}
@@ -0,0 +1,180 @@
+//! Gamepad controls alongside the keyboard. The left stick turns and throttles, [A] fires, [B]
+//! recharges the shield, and [X], [Y] and [B] pick what to do with a captured ship. [Start] pauses.
+//! Whichever was touched last is kept in [`ControlScheme`], so prompts can name the right buttons.
+
+use bevy::{
+    app::{Plugin, PreUpdate},
+    ecs::{
+        schedule::IntoSystemConfigs,
+        system::{Res, ResMut, Resource, SystemParam},
+    },
+    input::{
+        gamepad::{GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, Gamepads},
+        keyboard::KeyCode,
+        Axis, ButtonInput, InputSystem,
+    },
+    math::Vec2,
+    prelude::App,
+};
+
+/// Stick travel below this is ignored, so a worn stick doesn't drift the ship
+pub const STICK_DEADZONE: f32 = 0.2;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControlScheme>()
+            .add_systems(PreUpdate, track_control_scheme.after(InputSystem));
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControlScheme {
+    #[default]
+    Keyboard,
+    Gamepad,
+}
+
+impl ControlScheme {
+    /// Whichever was used this frame takes over, with the keyboard winning a tie
+    pub fn last_used(self, keyboard: bool, gamepad: bool) -> Self {
+        match (keyboard, gamepad) {
+            (true, _) => ControlScheme::Keyboard,
+            (false, true) => ControlScheme::Gamepad,
+            (false, false) => self,
+        }
+    }
+
+    /// The key or the button to show in a prompt
+    pub fn prompt(self, key: &'static str, button: &'static str) -> &'static str {
+        match self {
+            ControlScheme::Keyboard => key,
+            ControlScheme::Gamepad => button,
+        }
+    }
+}
+
+/// Every connected gamepad at once, as there's only ever the one player
+#[derive(SystemParam)]
+pub struct Pads<'w> {
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl Pads<'_> {
+    pub fn pressed(&self, button: GamepadButtonType) -> bool {
+        self.gamepads
+            .iter()
+            .any(|gamepad| self.buttons.pressed(GamepadButton::new(gamepad, button)))
+    }
+
+    pub fn just_pressed(&self, button: GamepadButtonType) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            self.buttons
+                .just_pressed(GamepadButton::new(gamepad, button))
+        })
+    }
+
+    pub fn just_released(&self, button: GamepadButtonType) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            self.buttons
+                .just_released(GamepadButton::new(gamepad, button))
+        })
+    }
+
+    /// The left stick, right and up being positive, with the deadzone taken out
+    pub fn stick(&self) -> Vec2 {
+        self.gamepads
+            .iter()
+            .map(|gamepad| {
+                let axis = |axis_type| {
+                    self.axes
+                        .get(GamepadAxis::new(gamepad, axis_type))
+                        .unwrap_or_default()
+                };
+                Vec2::new(
+                    axis(GamepadAxisType::LeftStickX),
+                    axis(GamepadAxisType::LeftStickY),
+                )
+            })
+            .find(|stick| stick.length() > STICK_DEADZONE)
+            .unwrap_or_default()
+    }
+
+    fn touched(&self) -> bool {
+        self.buttons.get_just_pressed().next().is_some() || self.stick() != Vec2::ZERO
+    }
+}
+
+fn track_control_scheme(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    mut scheme: ResMut<ControlScheme>,
+) {
+    let used = scheme.last_used(keys.get_just_pressed().next().is_some(), pads.touched());
+    // Left alone otherwise, so prompts only redraw when the scheme really changes
+    if *scheme != used {
+        *scheme = used;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::testing::test_app;
+    use bevy::{
+        app::Update,
+        input::{
+            gamepad::{
+                Gamepad, GamepadAxisType, GamepadConnection, GamepadConnectionEvent, GamepadInfo,
+            },
+            InputPlugin,
+        },
+    };
+
+    #[test]
+    fn gamepad_sticks_ignore_drift_and_take_over_the_prompts() {
+        #[derive(Resource, Default)]
+        struct Read(Vec2, bool);
+
+        let mut app = test_app();
+        let pad = Gamepad::new(0);
+        app.add_plugins(InputPlugin)
+            .init_resource::<Read>()
+            .add_systems(Update, |pads: Pads, mut read: ResMut<Read>| {
+                *read = Read(pads.stick(), pads.pressed(GamepadButtonType::South));
+            });
+        app.world.send_event(GamepadConnectionEvent::new(
+            pad,
+            GamepadConnection::Connected(GamepadInfo {
+                name: "Pad".to_string(),
+            }),
+        ));
+        app.update();
+        let stick_x = GamepadAxis::new(pad, GamepadAxisType::LeftStickX);
+        app.world
+            .resource_mut::<Axis<GamepadAxis>>()
+            .set(stick_x, STICK_DEADZONE / 2.);
+        app.update();
+        assert_eq!(app.world.resource::<Read>().0, Vec2::ZERO);
+        assert!(!app.world.resource::<Read>().1);
+
+        app.world
+            .resource_mut::<Axis<GamepadAxis>>()
+            .set(stick_x, -0.8);
+        app.world
+            .resource_mut::<ButtonInput<GamepadButton>>()
+            .press(GamepadButton::new(pad, GamepadButtonType::South));
+        app.update();
+        assert_eq!(app.world.resource::<Read>().0, Vec2::new(-0.8, 0.));
+        assert!(app.world.resource::<Read>().1);
+
+        let scheme = ControlScheme::Keyboard.last_used(false, true);
+        assert_eq!(scheme, ControlScheme::Gamepad);
+        assert_eq!(scheme.last_used(false, false), ControlScheme::Gamepad);
+        assert_eq!(scheme.prompt("Esc", "Start"), "Start");
+        assert_eq!(scheme.last_used(true, true), ControlScheme::Keyboard);
+    }
+}
@@ -0,0 +1,367 @@
+//! Pirate convoys: now and then a line of freighters crosses the arena under escort. The freighters
+//! don't fight back and the escort keeps to the convoy until the player comes close or opens fire.
+//! Each freighter shot down pays [`FREIGHTER_SCRAP`], and the escort leader flies a hull that
+//! never turns up otherwise, so capturing it is the prize. Leaving the convoy be costs nothing.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{
+    app::{Plugin, Update},
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{Or, With, Without},
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, OnEnter,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::DespawnRecursiveExt,
+    math::Vec2,
+    prelude::App,
+    render::color::Color,
+    time::{Time, Timer, TimerMode},
+};
+use rand::Rng;
+
+use crate::{
+    dialogue::Dialogue,
+    events::ShipDestroyed,
+    gameplay::{
+        handle_npc_logic, kill_dead_ships, Captured, EnemySpacecraftBundle, GameState, GameplaySet,
+        NPCLogic, PlayerMarker, PlayerScore, ShipTextures, ShipType, ShipVariant, Spacecraft,
+    },
+    practice::CapturePractice,
+    range::TargetRange,
+    seed::RunSeed,
+    GameLifecycleState,
+};
+
+/// How often a convoy may come through
+pub const CONVOY_INTERVAL: Duration = Duration::from_secs(120);
+/// Scrap paid for every freighter shot down, on top of its salvage
+pub const FREIGHTER_SCRAP: u32 = 15;
+/// How close the player gets before the escort breaks off to fight
+pub const ESCORT_ALERT_RANGE: f32 = 1.5;
+/// How far a convoy runs before it's gone
+pub const CONVOY_CROSSING: f32 = 14.;
+/// How far to one side of the player the convoy starts out
+const CONVOY_START_DISTANCE: f32 = 7.;
+/// Fraction of its top speed each ship in the convoy keeps to
+const CONVOY_THROTTLE: f32 = 0.1;
+const FREIGHTERS: usize = 3;
+const FREIGHTER_HULL: ShipType = ShipType::Ship5;
+const FREIGHTER_SPACING: f32 = 0.7;
+const FREIGHTER_TINT: Color = Color::rgb(0.75, 0.7, 0.55);
+const ESCORT_HULL: ShipType = ShipType::Ship2;
+/// Ship 6s only ever come as juggernauts, apart from this one
+const LEADER_HULL: (ShipType, ShipVariant) = (ShipType::Ship6, ShipVariant::Gunboat);
+const LEADER_TINT: Color = Color::rgb(1., 0.55, 0.35);
+
+pub struct ConvoyPlugin;
+
+impl Plugin for ConvoyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameLifecycleState::Game), reset_convoys)
+            .add_systems(
+                Update,
+                (
+                    (fly_convoys, rouse_escorts)
+                        .chain()
+                        .after(handle_npc_logic)
+                        .in_set(GameplaySet::Input),
+                    send_convoys
+                        .run_if(not(resource_exists::<CapturePractice>))
+                        .run_if(not(resource_exists::<TargetRange>))
+                        .in_set(GameplaySet::Simulation),
+                    (pay_for_freighters, take_convoy_leader, see_off_convoys)
+                        .before(kill_dead_ships)
+                        .in_set(GameplaySet::Cleanup),
+                )
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+/// A ship keeping to a convoy's course. Escorts lose it once they break off to fight.
+#[derive(Component, Clone, Copy)]
+pub struct ConvoyShip {
+    pub start: Vec2,
+    /// Unit direction the convoy is crossing in
+    pub course: Vec2,
+}
+
+impl ConvoyShip {
+    pub fn heading(&self) -> f32 {
+        f32::atan2(self.course.x, self.course.y)
+    }
+}
+
+#[derive(Component)]
+pub struct Freighter;
+
+#[derive(Component)]
+pub struct ConvoyLeader;
+
+#[derive(Resource)]
+pub struct ConvoySchedule {
+    timer: Timer,
+    /// Freighters still out, kept so the ones shot down can be told apart after they're despawned
+    pub freighters: Vec<Entity>,
+}
+
+impl Default for ConvoySchedule {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(CONVOY_INTERVAL, TimerMode::Repeating),
+            freighters: vec![],
+        }
+    }
+}
+
+fn reset_convoys(mut commands: Commands) {
+    commands.insert_resource(ConvoySchedule::default());
+}
+
+/// Spawns a convoy off to one side of the player, heading across in front of it
+#[allow(clippy::too_many_arguments)]
+fn send_convoys(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut schedule: ResMut<ConvoySchedule>,
+    convoy: Query<(), With<ConvoyShip>>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    textures: Res<ShipTextures>,
+    mut dialogue: ResMut<Dialogue>,
+    mut seed: ResMut<RunSeed>,
+) {
+    if !schedule.timer.tick(time.delta()).just_finished() || !convoy.is_empty() {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let rand = seed.rng();
+    let course = Vec2::from_angle(rand.gen_range(0. ..TAU));
+    let start = player.position - course * CONVOY_START_DISTANCE;
+    let flank = course.perp() * FREIGHTER_SPACING;
+    for i in 0..FREIGHTERS {
+        let position = start - course * FREIGHTER_SPACING * i as f32;
+        let freighter = commands
+            .spawn(
+                EnemySpacecraftBundle::create_ship(FREIGHTER_HULL, position, &textures)
+                    .with_tint(FREIGHTER_TINT),
+            )
+            .remove::<NPCLogic>()
+            .insert((
+                ConvoyShip { start, course },
+                Freighter,
+                Name::new("Freighter"),
+            ))
+            .id();
+        schedule.freighters.push(freighter);
+    }
+    let middle = start - course * FREIGHTER_SPACING * (FREIGHTERS - 1) as f32 / 2.;
+    for side in [flank, -flank] {
+        commands
+            .spawn(EnemySpacecraftBundle::create_ship(
+                ESCORT_HULL,
+                middle + side,
+                &textures,
+            ))
+            .remove::<NPCLogic>()
+            .insert((ConvoyShip { start, course }, Name::new("Escort")));
+    }
+    let (hull, variant) = LEADER_HULL;
+    commands
+        .spawn(
+            EnemySpacecraftBundle::create_ship(hull, start + course * FREIGHTER_SPACING, &textures)
+                .with_variant(variant)
+                .with_tint(LEADER_TINT),
+        )
+        .remove::<NPCLogic>()
+        .insert((
+            ConvoyShip { start, course },
+            ConvoyLeader,
+            Name::new("Escort leader"),
+        ));
+    dialogue.queue_lines([
+        "Pirate convoy crossing. Those freighters are stuffed with scrap."
+            .to_string()
+            .into(),
+        "And whoever's leading the escort is flying something you don't see every day."
+            .to_string()
+            .into(),
+    ]);
+}
+
+/// The convoy holds its course at an easy pace
+#[allow(clippy::type_complexity)]
+pub fn fly_convoys(
+    mut convoy: Query<
+        (&ConvoyShip, &mut Spacecraft),
+        (Without<NPCLogic>, Without<Captured>, Without<PlayerMarker>),
+    >,
+) {
+    for (convoy, mut craft) in convoy.iter_mut() {
+        craft.end_frame();
+        let turn = convoy.heading() - craft.heading;
+        craft.rotate(turn);
+        craft.velocity = craft.profile().max_velocity * CONVOY_THROTTLE;
+    }
+}
+
+/// The whole escort turns on the player once they come close or land a hit on any of the convoy
+#[allow(clippy::type_complexity)]
+pub fn rouse_escorts(
+    mut commands: Commands,
+    convoy: Query<&Spacecraft, With<ConvoyShip>>,
+    escorts: Query<Entity, (With<ConvoyShip>, Without<Freighter>, Without<NPCLogic>)>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let alerted = convoy.iter().any(|craft| {
+        craft.position.distance(player.position) < ESCORT_ALERT_RANGE
+            || craft.health < craft.profile().max_health
+    });
+    if !alerted {
+        return;
+    }
+    let mut rand = rand::thread_rng();
+    for escort in escorts.iter() {
+        commands
+            .entity(escort)
+            .remove::<ConvoyShip>()
+            .insert(NPCLogic::new(Vec2::new(
+                rand.gen_range(-0.3..0.3),
+                rand.gen_range(-0.3..0.3),
+            )));
+    }
+}
+
+pub fn pay_for_freighters(
+    mut destroyed: EventReader<ShipDestroyed>,
+    mut schedule: ResMut<ConvoySchedule>,
+    mut score: ResMut<PlayerScore>,
+) {
+    for event in destroyed.read() {
+        if let Some(index) = schedule.freighters.iter().position(|f| *f == event.ship) {
+            schedule.freighters.swap_remove(index);
+            score.scrap += FREIGHTER_SCRAP;
+        }
+    }
+}
+
+/// Whatever the convoy loses to the player's side stops following it, and flies like any other ally
+#[allow(clippy::type_complexity)]
+fn take_convoy_leader(
+    mut commands: Commands,
+    taken: Query<Entity, (With<ConvoyShip>, Or<(With<Captured>, With<PlayerMarker>)>)>,
+    leaders: Query<Entity, (With<ConvoyLeader>, Or<(With<Captured>, With<PlayerMarker>)>)>,
+    mut schedule: ResMut<ConvoySchedule>,
+    mut dialogue: ResMut<Dialogue>,
+) {
+    for entity in taken.iter() {
+        commands
+            .entity(entity)
+            .remove::<ConvoyShip>()
+            .insert(NPCLogic::new(Vec2::ZERO));
+        schedule.freighters.retain(|freighter| *freighter != entity);
+    }
+    for leader in leaders.iter() {
+        commands.entity(leader).remove::<ConvoyLeader>();
+        dialogue.queue_lines([
+            "The convoy's flagship is ours. Look after it, there aren't many like it."
+                .to_string()
+                .into(),
+        ]);
+    }
+}
+
+/// Once across, the convoy jumps out
+fn see_off_convoys(
+    mut commands: Commands,
+    convoy: Query<(Entity, &ConvoyShip, &Spacecraft)>,
+    mut schedule: ResMut<ConvoySchedule>,
+) {
+    for (entity, convoy, craft) in convoy.iter() {
+        if craft.position.distance(convoy.start) < CONVOY_CROSSING {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        schedule.freighters.retain(|freighter| *freighter != entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Allegiance;
+    use crate::gameplay::testing::test_app;
+
+    #[test]
+    fn convoys_keep_course_until_the_player_closes_in() {
+        let mut app = test_app();
+        app.init_resource::<ConvoySchedule>().add_systems(
+            Update,
+            ((fly_convoys, rouse_escorts).chain(), pay_for_freighters),
+        );
+        let convoy = ConvoyShip {
+            start: Vec2::ZERO,
+            course: Vec2::X,
+        };
+        let freighter = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship5, Vec2::ZERO),
+                convoy,
+                Freighter,
+            ))
+            .id();
+        let escort = app
+            .world
+            .spawn((Spacecraft::from_template(ShipType::Ship2, Vec2::Y), convoy))
+            .id();
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship1, Vec2::Y * 5.),
+                PlayerMarker,
+            ))
+            .id();
+        app.update();
+        let craft = app.world.get::<Spacecraft>(escort).unwrap();
+        assert!((craft.heading - convoy.heading()).abs() < 1e-5);
+        assert!(craft.velocity > 0.);
+        assert!(app.world.get::<NPCLogic>(escort).is_none());
+
+        app.world.get_mut::<Spacecraft>(player).unwrap().position =
+            Vec2::Y * (1. + ESCORT_ALERT_RANGE / 2.);
+        app.update();
+        assert!(app.world.get::<NPCLogic>(escort).is_some());
+        assert!(app.world.get::<ConvoyShip>(escort).is_none());
+        // Freighters carry on regardless
+        assert!(app.world.get::<NPCLogic>(freighter).is_none());
+
+        app.world
+            .resource_mut::<ConvoySchedule>()
+            .freighters
+            .push(freighter);
+        app.world.send_event(ShipDestroyed {
+            ship: freighter,
+            ship_type: ShipType::Ship5,
+            allegiance: Allegiance::Enemy,
+            position: Vec2::ZERO,
+            killer: Some(player),
+        });
+        app.update();
+        assert_eq!(app.world.resource::<PlayerScore>().scrap, FREIGHTER_SCRAP);
+        assert!(app.world.resource::<ConvoySchedule>().freighters.is_empty());
+    }
+}
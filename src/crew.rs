@@ -0,0 +1,135 @@
+use std::ops::Range;
+
+use bevy::{
+    app::{Plugin, Startup, Update},
+    asset::{AssetServer, Assets, Handle},
+    ecs::{
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    prelude::App,
+    time::{Time, Timer, TimerMode},
+};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    dialogue::{Dialogue, DialogueScript},
+    gameplay::{Captured, GameState, PlayerMarker, PlayerScore, Spacecraft},
+    GameLifecycleState,
+};
+
+/// Enemies closer than this to the player keep the crew focused on the fight
+const CHATTER_QUIET_RANGE: f32 = 1.5;
+/// Seconds of uninterrupted quiet before the crew starts talking
+const CHATTER_QUIET_SECS: Range<f32> = 20.0..40.0;
+
+pub struct CrewCommsPlugin;
+
+impl Plugin for CrewCommsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_crew_script)
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                (reset_briefings, reset_chatter),
+            )
+            .add_systems(
+                Update,
+                (deliver_briefings, ambient_chatter)
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct CrewScript(pub Handle<DialogueScript>);
+
+/// Index of the next briefing in the crew script that hasn't been delivered yet
+#[derive(Resource, Default)]
+pub struct BriefingProgress(usize);
+
+fn load_crew_script(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CrewScript(asset_server.load("dialogue/crew.dialogue.ron")));
+}
+
+fn reset_briefings(mut commands: Commands) {
+    commands.insert_resource(BriefingProgress::default());
+}
+
+fn deliver_briefings(
+    score: Res<PlayerScore>,
+    crew_script: Res<CrewScript>,
+    scripts: Res<Assets<DialogueScript>>,
+    mut progress: ResMut<BriefingProgress>,
+    mut dialogue: ResMut<Dialogue>,
+) {
+    if let Some(script) = scripts.get(&crew_script.0) {
+        while let Some(briefing) = script.briefings.get(progress.0) {
+            if score.survived_time.elapsed_secs() < briefing.at_secs {
+                break;
+            }
+            dialogue.queue_lines(briefing.lines.iter().cloned());
+            progress.0 += 1;
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ChatterState {
+    /// Script indices of chatter lines that haven't been used since the last refill
+    unplayed: Vec<usize>,
+    quiet: Timer,
+}
+
+impl ChatterState {
+    fn quiet_timer() -> Timer {
+        let secs = rand::thread_rng().gen_range(CHATTER_QUIET_SECS);
+        Timer::from_seconds(secs, TimerMode::Once)
+    }
+
+    /// Draws a random line, refilling the pool once every line has been heard
+    fn draw(&mut self, pool_size: usize) -> Option<usize> {
+        if self.unplayed.is_empty() {
+            self.unplayed = (0..pool_size).collect();
+            self.unplayed.shuffle(&mut rand::thread_rng());
+        }
+        self.unplayed.pop()
+    }
+}
+
+fn reset_chatter(mut commands: Commands) {
+    commands.insert_resource(ChatterState {
+        unplayed: vec![],
+        quiet: ChatterState::quiet_timer(),
+    });
+}
+
+fn ambient_chatter(
+    time: Res<Time>,
+    crew_script: Res<CrewScript>,
+    scripts: Res<Assets<DialogueScript>>,
+    mut chatter: ResMut<ChatterState>,
+    mut dialogue: ResMut<Dialogue>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    enemies: Query<&Spacecraft, (Without<PlayerMarker>, Without<Captured>)>,
+) {
+    if let (Some(script), Ok(player)) = (scripts.get(&crew_script.0), player.get_single()) {
+        let enemy_nearby = enemies
+            .iter()
+            .any(|enemy| enemy.position.distance(player.position) < CHATTER_QUIET_RANGE);
+        if enemy_nearby || script.chatter.is_empty() {
+            chatter.quiet = ChatterState::quiet_timer();
+            return;
+        }
+        chatter.quiet.tick(time.delta());
+        if chatter.quiet.finished() {
+            if let Some(index) = chatter.draw(script.chatter.len()) {
+                if !dialogue.chatter(script.chatter[index].clone()) {
+                    chatter.unplayed.push(index);
+                }
+            }
+            chatter.quiet = ChatterState::quiet_timer();
+        }
+    }
+}
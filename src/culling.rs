@@ -0,0 +1,123 @@
+//! Keeps big fights away from the camera cheap to draw. Bullets out of view carry on flying and
+//! hitting things but aren't drawn, and sparks and explosions nobody would see aren't spawned.
+
+use bevy::{
+    app::{Plugin, Update},
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::Query,
+    },
+    math::Vec2,
+    prelude::App,
+    render::view::Visibility,
+    transform::components::Transform,
+    window::Window,
+};
+
+use crate::{
+    gameplay::{camera_follow, Bullet, CameraView, GameplaySet},
+    GameLifecycleState,
+};
+
+/// Pixels past the edge of the screen things are still drawn, so nothing pops in at the edge
+pub const CULL_MARGIN: f32 = 64.;
+/// Ship explosions playing at once, any more go off without one
+pub const MAX_EXPLOSIONS: usize = 40;
+
+pub struct CullingPlugin;
+
+impl Plugin for CullingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            cull_offscreen_bullets
+                .after(camera_follow)
+                .in_set(GameplaySet::Presentation)
+                .run_if(in_state(GameLifecycleState::Game)),
+        );
+    }
+}
+
+/// Whether a world position is close enough to the view to be worth drawing anything at.
+/// Without a view to go by, everything is.
+pub fn on_screen(view: Option<&CameraView>, position: Vec2) -> bool {
+    view.is_none_or(|view| view.contains(position, CULL_MARGIN))
+}
+
+/// Only touches the bullets crossing the edge of the view, so the rest don't count as changed
+pub fn cull_offscreen_bullets(
+    camera: Query<&Transform, With<Camera2d>>,
+    window: Query<&Window>,
+    mut bullets: Query<(&Bullet, &mut Visibility)>,
+) {
+    let view = CameraView::find(&camera, &window);
+    for (bullet, mut visibility) in bullets.iter_mut() {
+        let wanted = match on_screen(view.as_ref(), bullet.position()) {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{
+        move_bullets,
+        testing::{bullet_at, test_app},
+    };
+    use crate::interpolation::Interpolated;
+    use bevy::{ecs::system::RunSystemOnce, prelude::default, render::view::Visibility};
+
+    #[test]
+    fn bullets_out_of_view_are_hidden_but_keep_flying() {
+        let mut app = test_app();
+        app.world.spawn((Camera2d, Transform::default()));
+        app.world.spawn(Window {
+            resolution: (800., 800.).into(),
+            ..default()
+        });
+        let near = app
+            .world
+            .spawn((
+                bullet_at(Vec2::ZERO),
+                Interpolated::new(Vec2::ZERO, 30.),
+                Visibility::Inherited,
+            ))
+            .id();
+        let far = app
+            .world
+            .spawn((
+                bullet_at(Vec2::new(0., 5.)),
+                Interpolated::new(Vec2::new(0., 5.), 30.),
+                Visibility::Inherited,
+            ))
+            .id();
+        app.world.run_system_once(cull_offscreen_bullets);
+        assert_eq!(
+            app.world.get::<Visibility>(near),
+            Some(&Visibility::Inherited)
+        );
+        assert_eq!(app.world.get::<Visibility>(far), Some(&Visibility::Hidden));
+
+        app.world.run_system_once(move_bullets);
+        assert!(app.world.get::<Bullet>(far).unwrap().position().y > 5.);
+        // Flown back into view
+        app.world
+            .entity_mut(far)
+            .insert(bullet_at(Vec2::new(0., 0.5)));
+        app.world.run_system_once(cull_offscreen_bullets);
+        assert_eq!(
+            app.world.get::<Visibility>(far),
+            Some(&Visibility::Inherited)
+        );
+
+        // With no camera around, nothing gets culled
+        assert!(on_screen(None, Vec2::splat(100.)));
+    }
+}
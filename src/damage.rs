@@ -0,0 +1,42 @@
+//! How well each kind of weapon gets through each class of armour.
+
+use bevy::reflect::Reflect;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize)]
+pub enum DamageType {
+    Kinetic,
+    Energy,
+    Explosive,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Reflect)]
+pub enum Armor {
+    Light,
+    Medium,
+    Heavy,
+}
+
+impl Armor {
+    pub fn heavier(self) -> Self {
+        match self {
+            Armor::Light => Armor::Medium,
+            Armor::Medium | Armor::Heavy => Armor::Heavy,
+        }
+    }
+}
+
+/// Multiplier on damage of `damage_type` landing on a hull with `armor`
+pub fn effectiveness(damage_type: DamageType, armor: Armor) -> f32 {
+    match (damage_type, armor) {
+        (DamageType::Kinetic, Armor::Light) => 1.,
+        (DamageType::Kinetic, Armor::Medium) => 0.75,
+        (DamageType::Kinetic, Armor::Heavy) => 0.5,
+        (DamageType::Energy, Armor::Light) => 1.25,
+        (DamageType::Energy, Armor::Medium) => 1.,
+        (DamageType::Energy, Armor::Heavy) => 0.75,
+        (DamageType::Explosive, Armor::Light) => 0.75,
+        (DamageType::Explosive, Armor::Medium) => 1.,
+        (DamageType::Explosive, Armor::Heavy) => 1.5,
+    }
+}
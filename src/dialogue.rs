@@ -1,49 +1,45 @@
+use std::{collections::VecDeque, fmt, time::Duration};
+
 use bevy::{
     app::{Plugin, Startup, Update},
-    asset::{AssetServer, Handle},
+    asset::{io::Reader, Asset, AssetApp, AssetLoader, AssetServer, AsyncReadExt, LoadContext},
     core::Name,
     ecs::{
         component::Component,
         query::With,
-        system::{Commands, Query, Res, Resource},
+        schedule::IntoSystemConfigs,
+        system::{Commands, Query, Res, ResMut, Resource},
     },
     hierarchy::BuildChildren,
     prelude::default,
-    render::{color::Color, texture::Image, view::Visibility},
-    sprite::{BorderRect, ImageScaleMode, TextureSlicer},
-    text::{Text, TextStyle},
-    ui::{
-        node_bundles::{ButtonBundle, ImageBundle, NodeBundle, TextBundle},
-        Style, UiImage, UiRect, Val,
-    },
+    reflect::TypePath,
+    render::view::Visibility,
+    text::Text,
+    time::{Time, Timer, TimerMode},
+    ui::{node_bundles::NodeBundle, Style, UiRect, Val},
+    utils::BoxedFuture,
 };
+use serde::Deserialize;
+
+use crate::ui::{panel, panel_slice, portrait_text_row, PanelAssets};
+
+/// How long a queued line stays on screen before the next one replaces it
+pub const QUEUED_LINE_TIME: Duration = Duration::from_secs(5);
 
 pub struct DialoguePlugin;
 
 impl Plugin for DialoguePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Startup, init_dialogue_system)
-            .add_systems(Update, update_dialogue)
+        app.init_asset::<DialogueScript>()
+            .register_asset_loader(DialogueScriptLoader)
+            .add_systems(Startup, init_dialogue_system)
+            .add_systems(Update, (play_dialogue_queue, update_dialogue).chain())
             .insert_resource(Dialogue::init());
     }
 }
 
 fn init_dialogue_system(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let dialogue_background: Handle<Image> = asset_server.load("dialogue_box.png");
-    let jupiter_crash = asset_server.load("jupiterc.ttf");
-    let char_spin: Handle<Image> = asset_server.load("char_spin.png");
-    let dialogue_bg_slice = TextureSlicer {
-        border: BorderRect {
-            left: 3.,
-            right: 3.,
-            top: 3.,
-            bottom: 3.,
-        },
-        center_scale_mode: bevy::sprite::SliceScaleMode::Stretch,
-        sides_scale_mode: bevy::sprite::SliceScaleMode::Stretch,
-        max_corner_scale: 24.,
-    };
-
+    let assets = PanelAssets::load(&asset_server);
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -59,51 +55,59 @@ fn init_dialogue_system(mut commands: Commands, asset_server: Res<AssetServer>)
         .insert(DialogueMarker)
         .with_children(|parent| {
             parent
-                .spawn((
-                    ButtonBundle {
-                        style: Style {
-                            position_type: bevy::ui::PositionType::Absolute,
-                            bottom: Val::Px(30.),
-                            width: Val::Percent(60.),
-                            height: Val::Percent(25.),
-                            justify_content: bevy::ui::JustifyContent::Start,
-                            align_items: bevy::ui::AlignItems::Center,
-                            padding: UiRect::all(Val::Percent(2.)),
-                            ..default()
-                        },
-                        visibility: Visibility::Inherited,
-                        image: UiImage::new(dialogue_background),
+                .spawn(panel(
+                    Style {
+                        position_type: bevy::ui::PositionType::Absolute,
+                        bottom: Val::Px(30.),
+                        width: Val::Percent(60.),
+                        height: Val::Percent(25.),
+                        justify_content: bevy::ui::JustifyContent::Start,
+                        align_items: bevy::ui::AlignItems::Center,
+                        padding: UiRect::all(Val::Percent(2.)),
                         ..default()
                     },
-                    ImageScaleMode::Sliced(dialogue_bg_slice.clone()),
+                    assets.frame.clone(),
+                    panel_slice(),
                 ))
                 .with_children(|parent| {
-                    parent.spawn(ImageBundle {
-                        style: Style {
-                            width: Val::Percent(17.),
-                            height: Val::Percent(95.),
-                            ..default()
-                        },
-                        image: UiImage::new(char_spin),
-                        ..default()
-                    });
-
-                    parent.spawn(TextBundle::from_section(
+                    portrait_text_row(
+                        parent,
+                        &assets,
                         "I'd just like to interject for a moment. What you're refering to as Linux, is in fact, GNU/Linux",
-                        TextStyle {
-                            font: jupiter_crash,
-                            font_size: 40.,
-                            color: Color::WHITE,
-                        },
-                    )).insert(DialogueTextMarker);
+                        40.,
+                    )
+                    .insert(DialogueTextMarker);
                 });
         });
+    commands.insert_resource(assets);
 }
 
 #[derive(Resource)]
 pub struct Dialogue {
     visible: bool,
     contents: String,
+    queue: VecDeque<DialogueLine>,
+    queued_line: Option<QueuedLine>,
+    warning: Option<String>,
+    suppressed: bool,
+}
+
+struct QueuedLine {
+    text: String,
+    timer: Timer,
+    chatter: bool,
+    modal: bool,
+}
+
+impl QueuedLine {
+    fn new(line: DialogueLine, chatter: bool) -> Self {
+        Self {
+            text: line.text,
+            timer: Timer::new(QUEUED_LINE_TIME, TimerMode::Once),
+            chatter,
+            modal: line.modal,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -117,6 +121,10 @@ impl Dialogue {
         Self {
             visible: false,
             contents: "What you've been referring to".to_string(),
+            queue: VecDeque::new(),
+            queued_line: None,
+            warning: None,
+            suppressed: false,
         }
     }
 
@@ -131,6 +139,92 @@ impl Dialogue {
     pub fn set_text(&mut self, contents: String) {
         self.contents = contents;
     }
+
+    /// Keeps the dialogue box off screen without losing what it's showing
+    pub fn set_suppressed(&mut self, suppressed: bool) {
+        self.suppressed = suppressed;
+    }
+
+    /// Lines queued here are shown one after another for [`QUEUED_LINE_TIME`] each,
+    /// except modal lines which stay up until advanced
+    pub fn queue_lines(&mut self, lines: impl IntoIterator<Item = DialogueLine>) {
+        self.drop_chatter();
+        self.queue.extend(lines);
+    }
+
+    /// Shows a low priority line, but only if nothing else is being said
+    pub fn chatter(&mut self, line: String) -> bool {
+        if self.visible || self.warning.is_some() || self.queued_line.is_some() {
+            return false;
+        }
+        self.set_text(line.clone());
+        self.show();
+        self.queued_line = Some(QueuedLine::new(line.into(), true));
+        true
+    }
+
+    /// Whether the line on screen should hold up the game until it is advanced
+    pub fn is_modal(&self) -> bool {
+        self.warning.is_none() && self.queued_line.as_ref().is_some_and(|line| line.modal)
+    }
+
+    /// Warnings take over the dialogue box until cleared, pausing any queued lines
+    pub fn warn(&mut self, warning: String) {
+        self.drop_chatter();
+        self.set_text(warning.clone());
+        self.show();
+        self.warning = Some(warning);
+    }
+
+    pub fn clear_warning(&mut self) {
+        if self.warning.take().is_some() {
+            match self.queued_line {
+                Some(ref line) => self.contents = line.text.clone(),
+                None => self.hide(),
+            }
+        }
+    }
+
+    /// Ends the current line early, moving on to the next queued line if there is one
+    pub fn advance(&mut self) {
+        if self.warning.is_some() {
+            return;
+        }
+        match self.queued_line {
+            Some(ref mut line) => line.timer.set_elapsed(QUEUED_LINE_TIME),
+            None => self.hide(),
+        }
+    }
+
+    fn drop_chatter(&mut self) {
+        if self.queued_line.as_ref().is_some_and(|line| line.chatter) {
+            self.queued_line = None;
+            self.hide();
+        }
+    }
+}
+
+pub fn play_dialogue_queue(time: Res<Time>, mut dialogue: ResMut<Dialogue>) {
+    if dialogue.warning.is_some() {
+        return;
+    }
+    if let Some(ref mut line) = dialogue.queued_line {
+        if !line.modal {
+            line.timer.tick(time.delta());
+        }
+        if !line.timer.finished() {
+            return;
+        }
+        dialogue.queued_line = None;
+        if dialogue.queue.is_empty() {
+            dialogue.hide();
+        }
+    }
+    if let Some(line) = dialogue.queue.pop_front() {
+        dialogue.set_text(line.text.clone());
+        dialogue.show();
+        dialogue.queued_line = Some(QueuedLine::new(line, false));
+    }
 }
 
 pub fn update_dialogue(
@@ -139,7 +233,7 @@ pub fn update_dialogue(
     mut text: Query<&mut Text, With<DialogueTextMarker>>,
 ) {
     if let Ok(mut vis) = vis.get_single_mut() {
-        *vis = match dialogue.visible {
+        *vis = match dialogue.visible && !dialogue.suppressed {
             true => Visibility::Visible,
             false => Visibility::Hidden,
         };
@@ -148,3 +242,124 @@ pub fn update_dialogue(
         text.sections[0].value = dialogue.contents.clone();
     }
 }
+
+/// Crew and enemy lines authored in `assets/dialogue/*.dialogue.ron`
+#[derive(Asset, TypePath, Deserialize)]
+pub struct DialogueScript {
+    #[serde(default)]
+    pub briefings: Vec<Briefing>,
+    /// Flavour lines the crew trades during quiet moments
+    #[serde(default)]
+    pub chatter: Vec<String>,
+    /// Called in by the fleet when morale climbs into high spirits
+    #[serde(default)]
+    pub rallying: Vec<String>,
+    #[serde(default)]
+    pub barks: Barks,
+}
+
+/// What set off an enemy's radio call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarkKind {
+    Spawned,
+    Spotted,
+    Retreating,
+    AllyLost,
+    /// Taken down by a hull well below its own
+    Outclassed,
+}
+
+/// Short radio calls enemies shout out, shown above their ships
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Barks {
+    pub spawned: Vec<String>,
+    pub spotted: Vec<String>,
+    pub retreating: Vec<String>,
+    pub ally_lost: Vec<String>,
+    pub outclassed: Vec<String>,
+}
+
+impl Barks {
+    pub fn lines(&self, kind: BarkKind) -> &[String] {
+        match kind {
+            BarkKind::Spawned => &self.spawned,
+            BarkKind::Spotted => &self.spotted,
+            BarkKind::Retreating => &self.retreating,
+            BarkKind::AllyLost => &self.ally_lost,
+            BarkKind::Outclassed => &self.outclassed,
+        }
+    }
+}
+
+/// Lines delivered once the run has lasted `at_secs` seconds
+#[derive(Deserialize)]
+pub struct Briefing {
+    pub at_secs: f32,
+    pub lines: Vec<DialogueLine>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct DialogueLine {
+    pub text: String,
+    /// Modal lines pause the game until the player presses [Enter]
+    #[serde(default)]
+    pub modal: bool,
+}
+
+impl From<String> for DialogueLine {
+    fn from(text: String) -> Self {
+        Self { text, modal: false }
+    }
+}
+
+#[derive(Debug)]
+pub enum DialogueScriptError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for DialogueScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DialogueScriptError::Io(e) => write!(f, "Could not read dialogue script: {e}"),
+            DialogueScriptError::Parse(e) => write!(f, "Could not parse dialogue script: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DialogueScriptError {}
+
+#[derive(Default)]
+pub struct DialogueScriptLoader;
+
+impl AssetLoader for DialogueScriptLoader {
+    type Asset = DialogueScript;
+    type Settings = ();
+    type Error = DialogueScriptError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(DialogueScriptError::Io)?;
+            let mut script: DialogueScript =
+                ron::de::from_bytes(&bytes).map_err(DialogueScriptError::Parse)?;
+            script
+                .briefings
+                .sort_by(|a, b| a.at_secs.total_cmp(&b.at_secs));
+            Ok(script)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.ron"]
+    }
+}
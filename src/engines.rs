@@ -0,0 +1,304 @@
+//! Engine exhaust behind each ship, so they're seen thrusting, turning and braking rather than just
+//! sliding around. Frames come from the exhaust art in `assets/ships`, laid out as an atlas per hull.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Startup, Update},
+    asset::{AssetServer, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::Added,
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, Children},
+    math::{Quat, Vec2, Vec3},
+    prelude::{default, App},
+    render::{color::Color, texture::Image, view::Visibility},
+    sprite::{Anchor, Sprite, SpriteSheetBundle, TextureAtlas, TextureAtlasLayout},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+};
+
+use crate::{
+    gameplay::{GameState, GameplaySet, ShipType, Spacecraft, TURN_SPEED},
+    GameLifecycleState,
+};
+
+/// Throttle at which the engines switch over to their turbo plume
+pub const TURBO_THROTTLE: f32 = 0.95;
+/// Below this the engines are idling, and show nothing
+const IDLE_THROTTLE: f32 = 0.02;
+/// Plume length, relative to its art, while the ship is slowing down
+const BRAKING_LENGTH: f32 = 0.3;
+/// How much longer the outside plume burns in a full-rate turn, and the inside one shorter
+const TURN_STRETCH: f32 = 0.35;
+/// How far, in radians, the plumes trail outwards in a full-rate turn
+const TURN_SWING: f32 = 0.3;
+const EXHAUST_FRAMES: usize = 4;
+const FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// Where the front of each plume sits on a hull's art, in its own pixels. Art faces along +x.
+const NOZZLES: [&[Vec2]; 6] = [
+    &[Vec2::new(-25., -1.)],
+    &[Vec2::new(-34., 8.), Vec2::new(-34., -6.)],
+    &[Vec2::new(-39., 0.)],
+    &[Vec2::new(-46., 6.), Vec2::new(-46., -10.)],
+    &[Vec2::new(-52., -7.)],
+    &[Vec2::new(-55., 14.), Vec2::new(-55., -9.)],
+];
+
+pub struct EngineEffectsPlugin;
+
+impl Plugin for EngineEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_exhaust_textures).add_systems(
+            Update,
+            (attach_engines, animate_engines)
+                .chain()
+                .in_set(GameplaySet::Presentation)
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+/// What a ship's engines are doing this frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EngineState {
+    /// Speed as a fraction of the hull's top speed
+    pub throttle: f32,
+    pub braking: bool,
+    /// Turn this frame as a fraction of the standard turn rate, positive to the right
+    pub turn: f32,
+}
+
+impl EngineState {
+    pub fn idle(&self) -> bool {
+        self.throttle < IDLE_THROTTLE
+    }
+
+    pub fn turbo(&self) -> bool {
+        !self.braking && self.throttle >= TURBO_THROTTLE
+    }
+
+    /// Length of a plume relative to its art, for a nozzle on `side` (1 left, -1 right, 0 centre)
+    pub fn plume_length(&self, side: f32) -> f32 {
+        let length = match self.braking {
+            true => BRAKING_LENGTH,
+            false => 0.4 + 0.6 * self.throttle.min(1.),
+        };
+        length * (1. + TURN_STRETCH * side * self.turn)
+    }
+
+    /// Rotation of the plumes, trailing them outwards through a turn
+    pub fn plume_swing(&self) -> f32 {
+        -TURN_SWING * self.turn
+    }
+}
+
+/// Each art hull's plumes at cruise and turbo, with the atlas layouts they share
+#[derive(Resource)]
+pub struct ExhaustTextures {
+    normal: Vec<Handle<Image>>,
+    turbo: Vec<Handle<Image>>,
+    normal_layout: Handle<TextureAtlasLayout>,
+    turbo_layout: Handle<TextureAtlasLayout>,
+}
+
+#[derive(Component)]
+pub struct Engines {
+    frame: usize,
+    frame_timer: Timer,
+    last_velocity: f32,
+}
+
+#[derive(Component)]
+pub struct Plume {
+    /// 1 for a nozzle left of the centreline, -1 for one to the right and 0 on it
+    side: f32,
+    turbo: bool,
+}
+
+/// Ships without art of their own borrow another hull's, and their exhaust along with it
+fn engine_art(ship_type: ShipType) -> Option<usize> {
+    match ship_type {
+        ShipType::Ship1 | ShipType::Drone => Some(0),
+        ShipType::Ship2 => Some(1),
+        ShipType::Ship3 => Some(2),
+        ShipType::Ship4 | ShipType::Projector => Some(3),
+        ShipType::Ship5 => Some(4),
+        ShipType::Ship6 | ShipType::Carrier | ShipType::Capital => Some(5),
+        // Turrets are bolted in place
+        ShipType::Turret => None,
+    }
+}
+
+fn load_exhaust_textures(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let (mut normal, mut turbo) = (vec![], vec![]);
+    for hull in 1..=NOZZLES.len() {
+        normal.push(asset_server.load(format!("ships/Ship{hull}/Exhaust/normal_flight_atlas.png")));
+        turbo.push(asset_server.load(format!("ships/Ship{hull}/Exhaust/turbo_flight_atlas.png")));
+    }
+    commands.insert_resource(ExhaustTextures {
+        normal,
+        turbo,
+        normal_layout: texture_atlases.add(TextureAtlasLayout::from_grid(
+            Vec2::splat(32.),
+            EXHAUST_FRAMES,
+            1,
+            None,
+            None,
+        )),
+        turbo_layout: texture_atlases.add(TextureAtlasLayout::from_grid(
+            Vec2::splat(64.),
+            EXHAUST_FRAMES,
+            1,
+            None,
+            None,
+        )),
+    });
+}
+
+fn attach_engines(
+    mut commands: Commands,
+    ships: Query<(Entity, &Spacecraft), Added<Spacecraft>>,
+    textures: Res<ExhaustTextures>,
+) {
+    for (entity, ship) in ships.iter() {
+        if let Some(art) = engine_art(ship.ship_type) {
+            let nozzles = NOZZLES[art];
+            commands
+                .entity(entity)
+                .insert(Engines {
+                    frame: 0,
+                    frame_timer: Timer::new(FRAME_TIME, TimerMode::Repeating),
+                    last_velocity: ship.velocity,
+                })
+                .with_children(|parent| {
+                    for nozzle in nozzles {
+                        let side = match nozzles.len() {
+                            1 => 0.,
+                            _ => nozzle.y.signum(),
+                        };
+                        for turbo in [false, true] {
+                            let (texture, layout) = match turbo {
+                                false => (&textures.normal[art], &textures.normal_layout),
+                                true => (&textures.turbo[art], &textures.turbo_layout),
+                            };
+                            parent.spawn((
+                                SpriteSheetBundle {
+                                    sprite: Sprite {
+                                        anchor: Anchor::CenterRight,
+                                        ..default()
+                                    },
+                                    atlas: TextureAtlas {
+                                        layout: layout.clone(),
+                                        index: 0,
+                                    },
+                                    texture: texture.clone(),
+                                    transform: Transform::from_translation(nozzle.extend(-1.)),
+                                    visibility: Visibility::Hidden,
+                                    ..default()
+                                },
+                                Plume { side, turbo },
+                            ));
+                        }
+                    }
+                });
+        }
+    }
+}
+
+fn animate_engines(
+    time: Res<Time>,
+    mut ships: Query<(&Spacecraft, &mut Engines, &Children)>,
+    mut plumes: Query<(
+        &Plume,
+        &mut Transform,
+        &mut Sprite,
+        &mut TextureAtlas,
+        &mut Visibility,
+    )>,
+) {
+    for (ship, mut engines, children) in ships.iter_mut() {
+        let state = EngineState {
+            throttle: ship.velocity / ship.profile().max_velocity,
+            braking: ship.velocity < engines.last_velocity - f32::EPSILON,
+            turn: (-ship.delta_rotation / TURN_SPEED).clamp(-1., 1.),
+        };
+        engines.last_velocity = ship.velocity;
+        if engines.frame_timer.tick(time.delta()).just_finished() {
+            engines.frame = (engines.frame + 1) % EXHAUST_FRAMES;
+        }
+        for child in children.iter() {
+            if let Ok((plume, mut transform, mut sprite, mut atlas, mut visibility)) =
+                plumes.get_mut(*child)
+            {
+                *visibility = match !state.idle() && plume.turbo == state.turbo() {
+                    true => Visibility::Inherited,
+                    false => Visibility::Hidden,
+                };
+                atlas.index = engines.frame;
+                transform.scale = Vec3::new(state.plume_length(plume.side), 1., 1.);
+                transform.rotation = Quat::from_rotation_z(state.plume_swing());
+                sprite.color = match state.braking {
+                    true => Color::rgba(1., 1., 1., 0.5),
+                    false => Color::WHITE,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engines_flare_on_thrust_and_die_down_when_braking() {
+        let cruising = EngineState {
+            throttle: 0.6,
+            braking: false,
+            turn: 0.,
+        };
+        let braking = EngineState {
+            braking: true,
+            ..cruising
+        };
+        assert!(braking.plume_length(0.) < cruising.plume_length(0.));
+        assert!(!cruising.turbo());
+        let flat_out = EngineState {
+            throttle: 1.,
+            ..cruising
+        };
+        assert!(flat_out.turbo());
+        assert!(!EngineState {
+            braking: true,
+            ..flat_out
+        }
+        .turbo());
+        assert!(EngineState {
+            throttle: 0.,
+            ..cruising
+        }
+        .idle());
+
+        // Turning right, the left plume is on the outside of the turn
+        let turning = EngineState {
+            turn: 1.,
+            ..cruising
+        };
+        assert!(turning.plume_length(1.) > cruising.plume_length(1.));
+        assert!(turning.plume_length(-1.) < cruising.plume_length(-1.));
+        assert_eq!(turning.plume_length(0.), cruising.plume_length(0.));
+        assert_eq!(cruising.plume_swing(), 0.);
+        assert!(turning.plume_swing() != 0.);
+    }
+}
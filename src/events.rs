@@ -0,0 +1,138 @@
+//! The big moments of a run, sent as events so audio, stats and UI can react to them without
+//! reaching into the marker components the gameplay systems use among themselves.
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::{Event, EventWriter},
+    },
+    math::Vec2,
+};
+use serde::Serialize;
+
+use crate::{damage::DamageType, gameplay::ShipType};
+
+/// Whose side a ship was on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Allegiance {
+    Player,
+    Ally,
+    Enemy,
+}
+
+/// A ship ran out of hull. The entity is despawned straight after, so only use it to match
+/// against ids you stored earlier.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShipDestroyed {
+    pub ship: Entity,
+    pub ship_type: ShipType,
+    pub allegiance: Allegiance,
+    pub position: Vec2,
+    /// Whoever fired the last bullet to hit it, if it was shot down
+    pub killer: Option<Entity>,
+}
+
+/// The player chose to keep a disabled enemy, and it now fights on their side
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShipCaptured {
+    pub ship: Entity,
+    pub ship_type: ShipType,
+}
+
+/// The player moved into another ship, leaving the old one to the AI
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShipSwapped {
+    pub from: Entity,
+    pub to: Entity,
+    pub ship_type: ShipType,
+}
+
+/// A capital ship warped in, or an ace was called in to hunt the player
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BossArrived {
+    pub ship: Entity,
+    pub ship_type: ShipType,
+}
+
+/// The player climbed back off their last point of hull without dying
+#[derive(Event, Clone, Copy, Debug)]
+pub struct NearDeathEscape;
+
+/// The run's score just passed the best this profile had before it
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HighScoreBeaten {
+    pub previous_best: u32,
+}
+
+/// A ship let off a volley, one event however many guns it has
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShotFired {
+    pub ship: Entity,
+    pub player_shot: bool,
+}
+
+/// A ship started patching its hull with its shield
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShieldRechargeStarted {
+    pub ship: Entity,
+}
+
+/// A ship's shield recharge ran its full time and restored a point of hull
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShieldRechargeFinished {
+    pub ship: Entity,
+}
+
+/// What took hull off a ship
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum DamageCause {
+    Weapon(DamageType),
+    Ram,
+    Burning,
+    Border,
+    Scuttled,
+}
+
+impl DamageCause {
+    pub fn describe(self) -> &'static str {
+        match self {
+            DamageCause::Weapon(DamageType::Kinetic) => "kinetic rounds",
+            DamageCause::Weapon(DamageType::Energy) => "energy fire",
+            DamageCause::Weapon(DamageType::Explosive) => "explosives",
+            DamageCause::Ram => "a collision",
+            DamageCause::Burning => "burning",
+            DamageCause::Border => "the arena border",
+            DamageCause::Scuttled => "being scuttled",
+        }
+    }
+}
+
+/// A ship lost hull. `amount` doesn't count damage past what the ship had left.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShipDamaged {
+    pub ship: Entity,
+    /// The ship that fired the shot or rammed it, if anyone did
+    pub attacker: Option<Entity>,
+    pub cause: DamageCause,
+    pub amount: i32,
+}
+
+/// Sends a [`ShipDamaged`] for the hull a ship lost going from `before` to `after`, if it lost any
+pub fn report_damage(
+    damaged: &mut EventWriter<ShipDamaged>,
+    ship: Entity,
+    attacker: Option<Entity>,
+    cause: DamageCause,
+    before: i32,
+    after: i32,
+) {
+    let amount = before.max(0) - after.max(0);
+    if amount > 0 {
+        damaged.send(ShipDamaged {
+            ship,
+            attacker,
+            cause,
+            amount,
+        });
+    }
+}
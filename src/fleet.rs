@@ -0,0 +1,488 @@
+//! The allied fleet. Capturing one of the big hulls brings its crew over too, and they make the
+//! whole fleet better for the rest of the run. The fleet panel sets how each ally fights.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    prelude::{default, App},
+    render::{color::Color, view::Visibility},
+    text::{Text, TextStyle},
+    time::{Time, Timer, TimerMode},
+    ui::{node_bundles::TextBundle, Style, UiRect, Val},
+};
+
+use crate::{
+    dialogue::Dialogue,
+    events::{ShipCaptured, ShipSwapped},
+    gameplay::{
+        handle_npc_logic, Captured, GameState, GameplaySet, PlayerMarker, ShipType, Spacecraft,
+    },
+    ui::{spawn_ui, HudAnchor, HudElement},
+    GameLifecycleState,
+};
+
+/// Extra reload progress for allies under [`FleetBuff::Gunnery`], as a fraction of real time
+pub const GUNNERY_RELOAD_BONUS: f32 = 0.1;
+/// How often allies under [`FleetBuff::DamageControl`] patch up a point of hull
+pub const DAMAGE_CONTROL_TIME: Duration = Duration::from_secs(20);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FleetBuff {
+    Gunnery,
+    DamageControl,
+}
+
+impl FleetBuff {
+    /// The buff a captured hull brings with it, if it's a flagship
+    pub fn from_flagship(ship_type: ShipType) -> Option<Self> {
+        match ship_type {
+            ShipType::Ship5 => Some(FleetBuff::Gunnery),
+            ShipType::Ship6 => Some(FleetBuff::DamageControl),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FleetBuff::Gunnery => "Gunnery: allies reload 10% faster",
+            FleetBuff::DamageControl => "Damage control: allies repair over time",
+        }
+    }
+
+    fn announcement(self) -> &'static str {
+        match self {
+            FleetBuff::Gunnery => {
+                "Their gunnery crew has come over with the ship. The whole fleet's firing faster."
+            }
+            FleetBuff::DamageControl => {
+                "That flagship had a damage control team aboard. They'll keep our allies patched up."
+            }
+        }
+    }
+}
+
+/// Buffs picked up this run. Each one only counts once, however many flagships are taken.
+#[derive(Resource)]
+pub struct FleetBuffs {
+    pub active: Vec<FleetBuff>,
+    repair: Timer,
+}
+
+impl Default for FleetBuffs {
+    fn default() -> Self {
+        Self {
+            active: vec![],
+            repair: Timer::new(DAMAGE_CONTROL_TIME, TimerMode::Repeating),
+        }
+    }
+}
+
+impl FleetBuffs {
+    pub fn has(&self, buff: FleetBuff) -> bool {
+        self.active.contains(&buff)
+    }
+
+    /// Adds the buff, returning false if the fleet already had it
+    pub fn grant(&mut self, buff: FleetBuff) -> bool {
+        if self.has(buff) {
+            return false;
+        }
+        self.active.push(buff);
+        true
+    }
+}
+
+/// How an ally picks its fights, set per ship from the fleet panel
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AllyStance {
+    Defensive,
+    #[default]
+    Balanced,
+    Aggressive,
+}
+
+impl AllyStance {
+    pub fn next(self) -> Self {
+        match self {
+            AllyStance::Defensive => AllyStance::Balanced,
+            AllyStance::Balanced => AllyStance::Aggressive,
+            AllyStance::Aggressive => AllyStance::Defensive,
+        }
+    }
+
+    /// Only enemies this close to the player are picked as targets
+    pub fn engagement_range(self) -> f32 {
+        match self {
+            AllyStance::Defensive => 1.5,
+            AllyStance::Balanced | AllyStance::Aggressive => f32::INFINITY,
+        }
+    }
+
+    /// Below this fraction of its hull, the ally breaks off and falls back on the player
+    pub fn retreat_health(self) -> f32 {
+        match self {
+            AllyStance::Defensive => 0.5,
+            AllyStance::Balanced => 0.25,
+            AllyStance::Aggressive => 0.,
+        }
+    }
+
+    /// How close the target has to be before the ally opens fire
+    pub fn fire_range(self) -> f32 {
+        match self {
+            AllyStance::Defensive => 0.9,
+            AllyStance::Balanced => 1.2,
+            AllyStance::Aggressive => 1.5,
+        }
+    }
+
+    /// Whether the ally holds its fire while the player is in front of its guns
+    pub fn checks_fire(self) -> bool {
+        self != AllyStance::Aggressive
+    }
+}
+
+pub struct FleetBuffPlugin;
+
+impl Plugin for FleetBuffPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameLifecycleState::Game),
+            (
+                reset_fleet_buffs,
+                spawn_buff_strip.after(spawn_ui),
+                spawn_fleet_panel.after(spawn_buff_strip),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                (grant_flagship_buffs, apply_fleet_buffs)
+                    .chain()
+                    .after(handle_npc_logic)
+                    .in_set(GameplaySet::Input),
+                command_fleet
+                    .before(handle_npc_logic)
+                    .in_set(GameplaySet::Input),
+                (update_buff_strip, update_fleet_panel).in_set(GameplaySet::Presentation),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct BuffStripMarker;
+
+#[derive(Component)]
+pub struct FleetPanelMarker;
+
+/// The ally whose stance the fleet panel changes
+#[derive(Resource, Default)]
+pub struct FleetPanel {
+    pub selected: Option<Entity>,
+}
+
+fn reset_fleet_buffs(mut commands: Commands) {
+    commands.insert_resource(FleetBuffs::default());
+    commands.insert_resource(FleetPanel::default());
+}
+
+/// A flagship counts whether it's kept as an ally or taken over, which is the only way into a swap
+pub fn grant_flagship_buffs(
+    mut captured: EventReader<ShipCaptured>,
+    mut swapped: EventReader<ShipSwapped>,
+    mut buffs: ResMut<FleetBuffs>,
+    mut dialogue: ResMut<Dialogue>,
+) {
+    let taken = captured
+        .read()
+        .map(|capture| capture.ship_type)
+        .chain(swapped.read().map(|swap| swap.ship_type));
+    for ship_type in taken {
+        if let Some(buff) = FleetBuff::from_flagship(ship_type) {
+            if buffs.grant(buff) {
+                dialogue.queue_lines([buff.announcement().to_string().into()]);
+            }
+        }
+    }
+}
+
+pub fn apply_fleet_buffs(
+    time: Res<Time>,
+    mut buffs: ResMut<FleetBuffs>,
+    mut allies: Query<&mut Spacecraft, (With<Captured>, Without<PlayerMarker>)>,
+) {
+    let repair =
+        buffs.has(FleetBuff::DamageControl) && buffs.repair.tick(time.delta()).just_finished();
+    for mut ally in allies.iter_mut() {
+        if buffs.has(FleetBuff::Gunnery) {
+            let bonus = time.delta().mul_f32(GUNNERY_RELOAD_BONUS);
+            ally.weapon_cooldown.tick(bonus);
+        }
+        if repair {
+            let max_health = ally.profile().max_health;
+            ally.health = (ally.health + 1).min(max_health);
+        }
+    }
+}
+
+fn spawn_buff_strip(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    anchors: Query<(Entity, &HudAnchor)>,
+) {
+    let strip = commands
+        .spawn(TextBundle {
+            style: Style {
+                margin: UiRect::top(Val::Px(8.)),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 16.,
+                    color: Color::rgb(0.6, 0.9, 1.),
+                },
+            )
+        })
+        .insert((BuffStripMarker, HudElement))
+        .id();
+    HudAnchor::TopLeft.attach(&mut commands, &anchors, strip);
+}
+
+fn update_buff_strip(
+    buffs: Res<FleetBuffs>,
+    mut strip: Query<(&mut Text, &mut Visibility), With<BuffStripMarker>>,
+) {
+    if let Ok((mut text, mut visibility)) = strip.get_single_mut() {
+        text.sections[0].value = buffs
+            .active
+            .iter()
+            .map(|buff| buff.label())
+            .collect::<Vec<_>>()
+            .join("\n");
+        *visibility = match buffs.active.is_empty() {
+            true => Visibility::Hidden,
+            false => Visibility::Inherited,
+        };
+    }
+}
+
+fn spawn_fleet_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    anchors: Query<(Entity, &HudAnchor)>,
+) {
+    let panel = commands
+        .spawn(TextBundle {
+            style: Style {
+                margin: UiRect::top(Val::Px(8.)),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 16.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert((FleetPanelMarker, HudElement))
+        .id();
+    HudAnchor::TopLeft.attach(&mut commands, &anchors, panel);
+}
+
+/// Allies in the order the fleet panel lists them
+fn fleet_roster(
+    allies: &Query<(Entity, &Spacecraft, Option<&AllyStance>), AllyFilter>,
+) -> Vec<Entity> {
+    let mut roster = allies
+        .iter()
+        .map(|(entity, _, _)| entity)
+        .collect::<Vec<_>>();
+    roster.sort();
+    roster
+}
+
+type AllyFilter = (With<Captured>, Without<PlayerMarker>);
+
+/// [C] picks the next ally on the panel, [V] changes its stance
+pub fn command_fleet(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut panel: ResMut<FleetPanel>,
+    allies: Query<(Entity, &Spacecraft, Option<&AllyStance>), AllyFilter>,
+) {
+    let roster = fleet_roster(&allies);
+    let position = panel
+        .selected
+        .and_then(|selected| roster.iter().position(|ally| *ally == selected));
+    if position.is_none() {
+        panel.selected = roster.first().copied();
+    }
+    if inputs.just_pressed(KeyCode::KeyC) {
+        if let Some(position) = position {
+            panel.selected = Some(roster[(position + 1) % roster.len()]);
+        }
+    }
+    if inputs.just_pressed(KeyCode::KeyV) {
+        if let Some(selected) = panel.selected {
+            if let Ok((_, _, stance)) = allies.get(selected) {
+                let stance = stance.copied().unwrap_or_default();
+                commands.entity(selected).insert(stance.next());
+            }
+        }
+    }
+}
+
+fn update_fleet_panel(
+    panel: Res<FleetPanel>,
+    allies: Query<(Entity, &Spacecraft, Option<&AllyStance>), AllyFilter>,
+    mut text: Query<(&mut Text, &mut Visibility), With<FleetPanelMarker>>,
+) {
+    if let Ok((mut text, mut visibility)) = text.get_single_mut() {
+        let roster = fleet_roster(&allies);
+        let mut lines = vec!["Fleet: [C] select, [V] stance".to_string()];
+        for ally in roster.iter() {
+            if let Ok((_, ship, stance)) = allies.get(*ally) {
+                let cursor = match panel.selected == Some(*ally) {
+                    true => ">",
+                    false => " ",
+                };
+                let stance = stance.copied().unwrap_or_default();
+                lines.push(format!("{cursor} {:?}: {stance:?}", ship.ship_type));
+            }
+        }
+        text.sections[0].value = lines.join("\n");
+        *visibility = match roster.is_empty() {
+            true => Visibility::Hidden,
+            false => Visibility::Inherited,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, Bullet, NPCLogic};
+    use crate::settings::Settings;
+    use bevy::math::Vec2;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn captured_flagships_buff_the_whole_fleet_once() {
+        let mut app = test_app();
+        app.insert_resource(Dialogue::init())
+            .insert_resource(FleetBuffs::default())
+            .add_systems(Update, (grant_flagship_buffs, apply_fleet_buffs).chain());
+        let reloading = || {
+            let mut craft = Spacecraft::from_template(ShipType::Ship2, Vec2::ZERO);
+            craft.weapon_cooldown.reset();
+            craft
+        };
+        let ally = app.world.spawn((reloading(), Captured)).id();
+        let enemy = app.world.spawn(reloading()).id();
+        for ship_type in [ShipType::Ship2, ShipType::Ship5, ShipType::Ship5] {
+            app.world.send_event(ShipCaptured {
+                ship: Entity::PLACEHOLDER,
+                ship_type,
+            });
+        }
+        app.update();
+        assert_eq!(
+            app.world.resource::<FleetBuffs>().active,
+            [FleetBuff::Gunnery]
+        );
+        // Taking a flagship over counts as much as keeping it
+        for ship_type in [ShipType::Ship5, ShipType::Ship6] {
+            app.world.send_event(ShipSwapped {
+                from: Entity::PLACEHOLDER,
+                to: Entity::PLACEHOLDER,
+                ship_type,
+            });
+        }
+        app.update();
+        assert_eq!(
+            app.world.resource::<FleetBuffs>().active,
+            [FleetBuff::Gunnery, FleetBuff::DamageControl]
+        );
+        let cooldown = |entity| {
+            app.world
+                .get::<Spacecraft>(entity)
+                .unwrap()
+                .weapon_cooldown
+                .elapsed()
+        };
+        assert_eq!(
+            cooldown(ally),
+            Duration::from_secs(1).mul_f32(GUNNERY_RELOAD_BONUS)
+        );
+        assert_eq!(cooldown(enemy), Duration::ZERO);
+    }
+
+    #[test]
+    fn ally_stances_change_who_they_fight_and_when_they_shoot() {
+        let mut app = test_app();
+        app.insert_resource(Settings::default())
+            .add_systems(Update, handle_npc_logic);
+        // Turning scales with the frame, and the first frame takes no time at all
+        app.update();
+        let ready = |position: Vec2, heading: f32| {
+            let mut craft = Spacecraft::from_template(ShipType::Ship2, position);
+            craft.heading = heading;
+            craft.weapon_cooldown.tick(Duration::from_secs(10));
+            (craft, NPCLogic::new(Vec2::ZERO), Captured)
+        };
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::new(0., 0.5)),
+            NPCLogic::new(Vec2::ZERO),
+        ));
+        // Both have the player between them and the enemy
+        let careful = app.world.spawn(ready(Vec2::new(0., -0.5), 0.)).id();
+        let reckless = app
+            .world
+            .spawn((ready(Vec2::new(0.05, -0.5), 0.), AllyStance::Aggressive))
+            .id();
+        let mut hurt = ready(Vec2::new(-2., 0.5), PI / 2.);
+        hurt.0.health = 1;
+        let hurt = app.world.spawn((hurt, AllyStance::Defensive)).id();
+        app.update();
+
+        let shooters: Vec<_> = app
+            .world
+            .query::<&Bullet>()
+            .iter(&app.world)
+            .map(|bullet| bullet.shooter())
+            .collect();
+        assert!(!shooters.is_empty());
+        assert!(shooters.iter().all(|shooter| *shooter == reckless));
+        assert!(!shooters.contains(&careful));
+        // Facing the enemy already, but it turns for home instead
+        let heading = app.world.get::<Spacecraft>(hurt).unwrap().heading;
+        assert!(heading > PI / 2. + 0.1);
+
+        assert_eq!(AllyStance::Aggressive.next(), AllyStance::Defensive);
+        assert!(AllyStance::Defensive.engagement_range().is_finite());
+    }
+}
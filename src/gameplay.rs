@@ -1,18 +1,82 @@
-use std::{f32::consts::PI, time::Duration};
+use std::{
+    collections::{HashSet, VecDeque},
+    f32::consts::PI,
+    time::Duration,
+};
 
+use crate::ace::AcePlugin;
+use crate::assist::{aim_pull, damp_turn, flight_assist_on, in_forward_arc, AssistPlugin};
+use crate::aura::{ShieldAuraPlugin, AURA_DAMAGE_TAKEN};
+use crate::barks::EnemyBarksPlugin;
+use crate::bindings::{Action, InputBindings};
+use crate::border::BorderWallPlugin;
+use crate::boss::{BossFight, BossPlugin};
+use crate::capital::{CapitalShipPlugin, ShieldedHull};
+use crate::carrier::CarrierPlugin;
+use crate::checkpoint::{CheckpointPlugin, ResumeCheckpoint};
+use crate::controls::Pads;
+use crate::convoy::ConvoyPlugin;
+use crate::crew::CrewCommsPlugin;
+use crate::culling::{on_screen, CullingPlugin, MAX_EXPLOSIONS};
+use crate::damage::{effectiveness, Armor, DamageType};
 use crate::dialogue::{update_dialogue, Dialogue, DialoguePlugin};
+use crate::engines::EngineEffectsPlugin;
+use crate::events::{
+    report_damage, Allegiance, BossArrived, DamageCause, HighScoreBeaten, NearDeathEscape,
+    ShieldRechargeFinished, ShieldRechargeStarted, ShipCaptured, ShipDamaged, ShipDestroyed,
+    ShipSwapped, ShotFired,
+};
+use crate::fleet::{AllyStance, FleetBuffPlugin};
+use crate::ghost::GhostPlugin;
+use crate::hud::HudEditor;
+use crate::impacts::ImpactEffectsPlugin;
+use crate::inspect::ShipInspectionPlugin;
+use crate::interpolation::{
+    frame_steps, interpolate_transforms, Interpolated, InterpolationPlugin, SIMULATION_HZ,
+};
+use crate::landmarks::LandmarkPlugin;
+use crate::loadout::{Fitting, LoadoutPlugin};
+use crate::morale::{FleetMorale, FleetMoralePlugin};
+use crate::nebula::{ArenaBackground, Danger, NebulaPlugin};
+use crate::objectives::ObjectivesPlugin;
+use crate::pacing::{SpawnCurve, SpawnPacing, SpawnPacingHandle, SpawnPacingPlugin};
+use crate::pause::PauseMenuPlugin;
+use crate::photo::PhotoModePlugin;
+use crate::practice::{CapturePractice, CapturePracticePlugin};
+use crate::range::{TargetRange, TargetRangePlugin};
+use crate::recoil::RecoilPlugin;
+use crate::records::MetaProgress;
+use crate::salvage::SalvagePlugin;
+use crate::score::{ScoreEvent, ScorePlugin, ScoreSource};
+use crate::seed::{settle_run_config, RunConfig, RunSeed};
+use crate::settings::{Difficulty, Settings, MAX_CAMERA_ZOOM, MIN_CAMERA_ZOOM};
+use crate::spectate::{SpectateOffer, Spectating, SpectatorPlugin};
+use crate::stats::{RunStats, StatsPlugin, TimelineEvent};
+use crate::status::{ApplyStatus, StatusKind, StatusPlugin};
+use crate::telemetry::TelemetryPlugin;
+use crate::turret::TurretPlugin;
 use crate::ui::{
-    spawn_ui, update_score_text, update_shield_ui, update_throttle_ui, update_weapon_ui,
+    apply_hud_opacity, panel, panel_slice, spawn_ui, update_consumable_ui, update_incoming_ui,
+    update_instruments, update_low_health_ui, update_radar, update_score_text, update_shield_ui,
+    update_throttle_ui, update_weapon_ui, HudOpacity, PanelAssets,
 };
+use crate::unlocks::UnlockCardsPlugin;
+use crate::upgrades::{RunUpgrades, UpgradesPlugin};
+use crate::waves::{WaveDirector, WaveMember, WavesPlugin};
+use crate::weather::{sight_between, DustClouds, WeatherPlugin};
+use crate::wrecks::WreckagePlugin;
 use crate::{BackgroundPNG, GameLifecycleState};
-use bevy::ecs::schedule::common_conditions::in_state;
-use bevy::ecs::schedule::{IntoSystemConfigs, NextState, OnEnter, OnExit, State, States};
-use bevy::hierarchy::{BuildChildren, Children, DespawnRecursiveExt};
+use bevy::ecs::schedule::common_conditions::{in_state, not, resource_exists};
+use bevy::ecs::schedule::{
+    IntoSystemConfigs, IntoSystemSetConfigs, LogLevel, NextState, OnEnter, OnExit,
+    ScheduleBuildSettings, States, SystemSet,
+};
+use bevy::hierarchy::{BuildChildren, DespawnRecursiveExt, Parent};
 use bevy::time::{Stopwatch, TimerMode};
 use bevy::ui::node_bundles::ImageBundle;
-use bevy::ui::{Style, UiImage, Val};
+use bevy::ui::{Style, UiImage, UiRect, Val};
 use bevy::{
-    app::{Plugin, PluginGroup, Update},
+    app::{FixedUpdate, Plugin, Update},
     asset::{Assets, Handle},
     core::Name,
     core_pipeline::core_2d::Camera2d,
@@ -20,31 +84,49 @@ use bevy::{
         bundle::Bundle,
         component::Component,
         entity::Entity,
-        event::EventReader,
-        query::{With, Without},
-        system::{Query, Res, ResMut, Resource},
+        event::{EventReader, EventWriter, Events},
+        query::{Added, Has, With, Without},
+        system::{EntityCommands, Local, Query, Res, ResMut, Resource},
+    },
+    input::{
+        gamepad::GamepadButtonType,
+        keyboard::KeyCode,
+        mouse::{MouseScrollUnit, MouseWheel},
+        ButtonInput,
     },
-    input::{keyboard::KeyCode, ButtonInput},
     math::{Quat, Vec2, Vec3},
     prelude::{default, App, AssetServer, Commands},
     reflect::Reflect,
-    render::texture::Image,
-    sprite::{SpriteBundle, SpriteSheetBundle, TextureAtlas, TextureAtlasLayout},
-    time::{Time, Timer},
+    render::{color::Color, texture::Image},
+    sprite::{Sprite, SpriteBundle, SpriteSheetBundle, TextureAtlas, TextureAtlasLayout},
+    time::{Real, Time, Timer, Virtual},
     transform::components::Transform,
     window::Window,
 };
 use bevy_rapier2d::{
-    geometry::{ActiveCollisionTypes, ActiveEvents, ActiveHooks, Collider},
+    geometry::{ActiveCollisionTypes, ActiveEvents, ActiveHooks, Collider, CollisionGroups, Group},
     pipeline::CollisionEvent,
     plugin::{NoUserData, RapierPhysicsPlugin},
 };
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
 
 pub const TURN_SPEED: f32 = 0.5;
 pub const ACCELERATION_SPEED: f32 = 0.005;
 pub const BULLET_SPEED: f32 = 0.015;
 pub const MAX_VELOCITY: f32 = 0.05;
+/// Pixels per world unit along each axis, half the default 1280x720 window. Fixed rather than taken
+/// from the window, so resizing it shows more or less of the arena instead of stretching the world
+/// under the pixel-sized colliders.
+pub const PIXELS_PER_UNIT: Vec2 = Vec2::new(640., 360.);
+/// Real-time length of the slow motion shot of the player's ship exploding
+pub const DEATH_SEQUENCE_TIME: Duration = Duration::from_millis(2500);
+pub const DEATH_TIME_SCALE: f32 = 0.3;
+pub const DEATH_ZOOM: f32 = 0.6;
+/// How long a portal is open before the enemy coming through it arrives
+pub const WARP_IN_TIME: Duration = Duration::from_millis(1500);
+/// How long controls take to come in after swapping ships, and the camera to swing round to it
+pub const HANDOFF_TIME: Duration = Duration::from_secs(1);
 
 pub struct GameplayPlugin;
 
@@ -53,85 +135,249 @@ impl Plugin for GameplayPlugin {
         app.register_type::<Bullet>()
             .register_type::<NPCLogic>()
             .register_type::<Spacecraft>()
+            .add_event::<ShipDestroyed>()
+            .add_event::<ShipCaptured>()
+            .add_event::<ShipSwapped>()
+            .add_event::<ShipDamaged>()
+            .add_event::<ShotFired>()
+            .add_event::<ShieldRechargeStarted>()
+            .add_event::<ShieldRechargeFinished>()
+            .add_event::<BossArrived>()
+            .add_event::<NearDeathEscape>()
+            .add_event::<HighScoreBeaten>()
+            .init_resource::<CaptureQueue>()
+            .init_resource::<SpentBullets>()
             .insert_state(GameState::Regular)
             .add_plugins((
                 RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0),
                 DialoguePlugin,
+                CrewCommsPlugin,
+                SpectatorPlugin,
+                PhotoModePlugin,
+                StatsPlugin,
+                SpawnPacingPlugin,
+                ScorePlugin,
+                ObjectivesPlugin,
+                CarrierPlugin,
+                LoadoutPlugin,
+                InterpolationPlugin,
+                StatusPlugin,
+                TurretPlugin,
+                PauseMenuPlugin,
+            ))
+            .add_plugins((
+                CapitalShipPlugin,
+                AcePlugin,
+                BorderWallPlugin,
+                CapturePracticePlugin,
+                EnemyBarksPlugin,
+                EngineEffectsPlugin,
+                LandmarkPlugin,
+                ImpactEffectsPlugin,
+                FleetBuffPlugin,
+                TelemetryPlugin,
+                GhostPlugin,
+                ShipInspectionPlugin,
+                UnlockCardsPlugin,
+                CullingPlugin,
+                ShieldAuraPlugin,
+            ))
+            .add_plugins((
+                TargetRangePlugin,
+                RecoilPlugin,
+                NebulaPlugin,
+                AssistPlugin,
+                WeatherPlugin,
+                FleetMoralePlugin,
+                CheckpointPlugin,
+                SalvagePlugin,
+                UpgradesPlugin,
+                WreckagePlugin,
+                ConvoyPlugin,
+                BossPlugin,
+                WavesPlugin,
             ))
             .add_systems(
                 OnEnter(GameLifecycleState::Game),
-                (setup, spawn_ui, init_nonfatal_explosion_images_res),
+                (
+                    remember_persistent_entities.before(setup).before(spawn_ui),
+                    setup.after(settle_run_config),
+                    spawn_ui,
+                    init_nonfatal_explosion_images_res,
+                ),
             )
-            .add_systems(OnExit(GameLifecycleState::Game), zoom_back_in)
             .add_systems(
+                OnExit(GameLifecycleState::Game),
+                (
+                    zoom_back_in,
+                    end_death_sequence,
+                    despawn_run.after(zoom_back_in),
+                ),
+            )
+            .configure_sets(
                 Update,
-                (handle_inputs, check_for_usage_decision)
-                    .run_if(in_state(GameState::Paused))
-                    .run_if(in_state(GameLifecycleState::Game)),
+                (
+                    GameplaySet::Input,
+                    GameplaySet::Simulation,
+                    GameplaySet::Collision,
+                    GameplaySet::Cleanup,
+                    GameplaySet::Presentation,
+                )
+                    .chain(),
             )
             .add_systems(
                 Update,
                 (
-                    handle_inputs,
-                    pause_for_captured_ship,
-                    move_spaceships,
-                    handle_npc_logic,
-                    move_bullets,
-                    tick_timer,
-                    collide_bullets,
-                    kill_far_bullets,
-                    swap_ships,
-                    update_score,
-                    spawn_ships,
-                    tick_bullet_immunity_time,
+                    (
+                        handle_inputs.run_if(not(resource_exists::<DeathSequence>)),
+                        handle_npc_logic,
+                    )
+                        .in_set(GameplaySet::Input),
+                    (
+                        (
+                            queue_capture_candidates,
+                            slow_for_captured_ship,
+                            check_for_usage_decision,
+                        )
+                            .chain(),
+                        turn_spaceships,
+                        tick_timer,
+                        command_nearby_allies,
+                        update_score.run_if(not(resource_exists::<Spectating>)),
+                        spawn_ships
+                            .run_if(not(resource_exists::<WaveDirector>))
+                            .run_if(not(resource_exists::<BossFight>))
+                            .run_if(not(resource_exists::<CapturePractice>))
+                            .run_if(not(resource_exists::<TargetRange>)),
+                        warp_in_enemies,
+                        tick_bullet_immunity_time,
+                        tick_control_handoff,
+                    )
+                        .in_set(GameplaySet::Simulation),
+                    collide_bullets.in_set(GameplaySet::Collision),
+                    (kill_far_bullets, swap_ships, pause_for_modal_dialogue)
+                        .in_set(GameplaySet::Cleanup),
                 )
                     .run_if(in_state(GameLifecycleState::Game))
                     .run_if(in_state(GameState::Regular)),
             )
+            .add_systems(
+                FixedUpdate,
+                (move_spaceships, move_bullets)
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            )
+            .add_systems(
+                Update,
+                advance_modal_dialogue
+                    .in_set(GameplaySet::Input)
+                    .run_if(in_state(GameState::Dialogue))
+                    .run_if(in_state(GameLifecycleState::Game)),
+            )
             .add_systems(
                 Update,
                 (
-                    camera_follow.after(move_spaceships),
-                    update_weapon_ui,
-                    update_throttle_ui,
-                    update_shield_ui,
-                    update_score_text,
-                    update_dialogue,
-                    neo_handle_explosions,
-                    recharge_shield,
-                    handle_shield_textures,
-                    kill_dead_ships,
-                    enforce_border,
-                    update_delayed_location,
+                    (
+                        update_delayed_location,
+                        recharge_shield,
+                        recover_from_capture_moment,
+                    )
+                        .in_set(GameplaySet::Simulation),
+                    enforce_border.in_set(GameplaySet::Collision),
+                    (
+                        kill_dead_ships,
+                        neo_handle_explosions,
+                        watch_for_near_death_escapes,
+                    )
+                        .in_set(GameplaySet::Cleanup),
+                    (
+                        interpolate_transforms,
+                        camera_follow
+                            .after(interpolate_transforms)
+                            .run_if(not(in_state(GameState::Photo))),
+                        zoom_camera
+                            .run_if(not(resource_exists::<DeathSequence>))
+                            .run_if(not(resource_exists::<HudEditor>))
+                            .run_if(not(in_state(GameState::Photo))),
+                        play_death_sequence,
+                        (update_low_health_ui, apply_hud_opacity).chain(),
+                        update_weapon_ui,
+                        update_throttle_ui,
+                        update_instruments.after(camera_follow),
+                        update_radar.after(camera_follow),
+                        update_shield_ui,
+                        update_score_text,
+                        update_incoming_ui,
+                        update_consumable_ui,
+                        update_dialogue,
+                        handle_shield_textures,
+                    )
+                        .in_set(GameplaySet::Presentation),
                 )
                     .run_if(in_state(GameLifecycleState::Game)),
             );
+        // Dev builds log any pair of systems that touch the same data with no order between them
+        #[cfg(debug_assertions)]
+        app.edit_schedule(Update, |schedule| {
+            schedule.set_build_settings(ScheduleBuildSettings {
+                ambiguity_detection: LogLevel::Warn,
+                ..default()
+            });
+        });
+        #[cfg(feature = "bench")]
+        app.add_plugins(crate::bench::BenchPlugin);
+        #[cfg(feature = "dev_cheats")]
+        app.add_plugins(crate::cheats::CheatsPlugin);
     }
 }
 
+/// The stages of a gameplay frame, run in this order
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, SystemSet)]
+pub enum GameplaySet {
+    /// The player's controls and the AI's decisions
+    Input,
+    /// Movement, timers, spawning and anything else that moves the game forward
+    Simulation,
+    /// Bullets and rams hitting ships, and the border catching anyone outside it
+    Collision,
+    /// Ships that died or left the playing field are cleared away, and pauses are called
+    Cleanup,
+    /// Camera, HUD and effects catching up with where everything ended up
+    Presentation,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     Regular,
-    Paused,
+    Dialogue,
+    Photo,
+    PauseMenu,
 }
 
-fn setup(
+#[allow(clippy::too_many_arguments)]
+pub fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     background: Res<BackgroundPNG>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
     mut camera: Query<&mut Transform, With<Camera2d>>,
-    _time: Res<Time>,
+    settings: Res<Settings>,
+    run: Res<RunConfig>,
+    progress: Res<MetaProgress>,
+    resume: Option<Res<ResumeCheckpoint>>,
 ) {
     if let Ok(mut camera) = camera.get_single_mut() {
-        camera.scale.x = 1.4;
-        camera.scale.y = 1.4;
+        camera.scale.x = settings.camera_zoom;
+        camera.scale.y = settings.camera_zoom;
     }
-    commands.spawn(SpriteBundle {
-        texture: background.0.clone(),
-        transform: Transform::default().with_scale(Vec3::new(15., 15., 0.)),
-        ..default()
-    });
+    commands.spawn((
+        SpriteBundle {
+            texture: background.0.clone(),
+            transform: Transform::default().with_scale(Vec3::new(15., 15., 0.)),
+            ..default()
+        },
+        ArenaBackground,
+    ));
     let textures = ShipTextures {
         ship_one: asset_server.load("ships/Ship1/Ship1.png"),
         ship_two: asset_server.load("ships/Ship2/Ship2.png"),
@@ -148,44 +394,74 @@ fn setup(
     commands.insert_resource(BulletTexture(
         asset_server.load("ships/Shots/Shot1/shot1_asset.png"),
     ));
+    commands.remove_resource::<CameraTransition>();
     commands.insert_resource(CarryoverEnemyPoints(10));
+    commands.insert_resource(WarpPortalTexture(asset_server.load("warp_portal.png")));
     commands.insert_resource(PausedWhatToDoImage(
         asset_server.load("captured_ship_options.png"),
     ));
-    commands.insert_resource(AllyTexture(asset_server.load("ally_flag.png")));
+    let ally_texture = AllyTexture(asset_server.load("ally_flag.png"));
     commands.insert_resource(DelayedPlayerLocation {
         buffered_locations: vec![],
         current_location: Vec2::ZERO,
     });
 
     commands
-        .spawn(PlayerBundle::create_ship(
-            ShipType::Ship2,
-            Vec2::new(0., 0.),
-            &textures,
-        ))
+        .spawn(
+            PlayerBundle::create_ship(run.loadout.hull, Vec2::new(0., 0.), &textures)
+                .with_fitting(run.loadout.fitting()),
+        )
         .insert(Name::new("Player"));
+    // A resumed run brings back whichever allies it still had instead
+    if let (Some(escort), None) = (progress.starting_escort(&run.loadout), resume) {
+        let mut ship = commands.spawn(EnemySpacecraftBundle::create_ship(
+            escort,
+            Vec2::new(0.3, -0.3),
+            &textures,
+        ));
+        ship.insert(Name::new("Ally"));
+        make_ally(&mut ship, &ally_texture);
+    }
+    commands.insert_resource(ally_texture);
     commands.insert_resource(PlayerScore {
         score: 0,
         add_score_timer: Timer::new(Duration::from_secs(10), TimerMode::Repeating),
         survived_time: Stopwatch::new(),
+        threat_exposure: 0.,
+        scrap: 0,
     });
 
     commands.insert_resource(textures)
 }
 
-fn remove_overly_long_tag(
+/// Everything that was already around when the run started, like the camera and the dialogue box
+#[derive(Resource, Default)]
+pub struct PersistentEntities(HashSet<Entity>);
+
+/// Runs ahead of anything that spawns the run in, so it only sees what was there before
+pub fn remember_persistent_entities(mut commands: Commands, entities: Query<Entity>) {
+    commands.insert_resource(PersistentEntities(entities.iter().collect()));
+}
+
+/// Clears away everything the run spawned, so a new one can start from scratch
+pub fn despawn_run(
     mut commands: Commands,
-    query: Query<
-        Entity,
-        With<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
-    >,
+    persistent: Option<Res<PersistentEntities>>,
+    roots: Query<Entity, Without<Parent>>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
 ) {
-    if let Ok(entity) = query.get_single() {
-        commands
-            .entity(entity)
-            .remove::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>();
+    if let Some(persistent) = persistent {
+        for entity in roots.iter() {
+            if !persistent.0.contains(&entity) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
     }
+    if let Ok(mut camera) = camera.get_single_mut() {
+        camera.translation = Vec3::new(0., 0., camera.translation.z);
+        camera.rotation = Quat::IDENTITY;
+    }
+    commands.insert_resource(Dialogue::init());
 }
 
 fn zoom_back_in(mut camera: Query<&mut Transform, With<Camera2d>>) {
@@ -195,14 +471,15 @@ fn zoom_back_in(mut camera: Query<&mut Transform, With<Camera2d>>) {
     }
 }
 
-fn spawn_enemy(
+pub fn spawn_enemy(
     commands: &mut Commands,
     base_pos: Vec2,
     ship_type: ShipType,
-    ship_textures: &ShipTextures,
-) {
-    let mut rand = rand::thread_rng();
-    let _position = Vec2::new(rand.gen_range(-2f32..2f32), rand.gen_range(-2f32..2f32));
+    portal_texture: &WarpPortalTexture,
+    view: Option<&CameraView>,
+    rand: &mut impl Rng,
+) -> Entity {
+    let variant = ShipVariant::roll(ship_type, rand);
     let poss_spawn_coords = [
         rand.gen_range(-2.5..-1.2),
         rand.gen_range(1.2..2.5),
@@ -211,14 +488,80 @@ fn spawn_enemy(
     ];
     let a = rand.gen_range(0..=1);
     let b = rand.gen_range(2..=3);
-    let pos = Vec2::new(poss_spawn_coords[a], poss_spawn_coords[b]) + base_pos;
+    let mut offset = Vec2::new(poss_spawn_coords[a], poss_spawn_coords[b]);
+    if let Some(view) = view {
+        // Half the sprite's size, so the whole ship stays off screen
+        let margin = 32. * ShipProfile::of(ship_type, variant).relative_scale;
+        while view.contains(offset + base_pos, margin) {
+            offset *= 1.1;
+        }
+    }
+    let pos = offset + base_pos;
     commands
-        .spawn(EnemySpacecraftBundle::create_ship(
+        .spawn(SpriteBundle {
+            texture: portal_texture.0.clone(),
+            transform: Transform::from_scale(Vec3::ZERO),
+            ..default()
+        })
+        .insert(WarpIn {
             ship_type,
-            pos,
-            ship_textures,
-        ))
-        .insert(Name::new("Enemy".to_string()));
+            variant,
+            position: pos,
+            timer: Timer::new(WARP_IN_TIME, TimerMode::Once),
+        })
+        .insert(Name::new("Warp In".to_string()))
+        .id()
+}
+
+#[derive(Resource)]
+pub struct WarpPortalTexture(Handle<Image>);
+
+/// An enemy that's on its way, but hasn't arrived yet
+#[derive(Component)]
+pub struct WarpIn {
+    pub ship_type: ShipType,
+    pub variant: Option<ShipVariant>,
+    pub position: Vec2,
+    timer: Timer,
+}
+
+fn warp_in_enemies(
+    mut commands: Commands,
+    mut portals: Query<(Entity, &mut WarpIn, &mut Transform, Has<WaveMember>)>,
+    time: Res<Time>,
+    textures: Res<ShipTextures>,
+    mut bosses: EventWriter<BossArrived>,
+) {
+    for (entity, mut warp, mut transform, wave_member) in portals.iter_mut() {
+        warp.timer.tick(time.delta());
+        if warp.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            let mut enemy =
+                EnemySpacecraftBundle::create_ship(warp.ship_type, warp.position, &textures);
+            let name = match warp.variant {
+                Some(variant) => {
+                    enemy = enemy.with_variant(variant);
+                    format!("Enemy {:?} {}", warp.ship_type, variant.name())
+                }
+                None => "Enemy".to_string(),
+            };
+            let ship = commands.spawn(enemy).insert(Name::new(name)).id();
+            if wave_member {
+                commands.entity(ship).insert(WaveMember);
+            }
+            if warp.ship_type == ShipType::Capital {
+                bosses.send(BossArrived {
+                    ship,
+                    ship_type: warp.ship_type,
+                });
+            }
+            continue;
+        }
+        transform.translation = (warp.position * PIXELS_PER_UNIT).extend(9.);
+        let size = ShipProfile::of(warp.ship_type, warp.variant).relative_scale * 2.;
+        transform.scale = Vec3::splat(size * warp.timer.fraction());
+        transform.rotate_z(time.delta_seconds() * 4.);
+    }
 }
 
 fn tick_timer(time: Res<Time>, mut ships: Query<&mut Spacecraft>) {
@@ -227,9 +570,36 @@ fn tick_timer(time: Res<Time>, mut ships: Query<&mut Spacecraft>) {
     }
 }
 
+/// Ships flying near a [`HullTrait::Command`] ship on their own side reload faster
+fn command_nearby_allies(
+    time: Res<Time>,
+    mut ships: Query<(Entity, &mut Spacecraft, Has<PlayerMarker>, Has<Captured>)>,
+) {
+    let commanders = ships
+        .iter()
+        .filter(|(_, ship, _, _)| ship.profile().hull_trait == Some(HullTrait::Command))
+        .map(|(entity, ship, player, captured)| (entity, ship.position, player || captured))
+        .collect::<Vec<_>>();
+    if commanders.is_empty() {
+        return;
+    }
+    let bonus = time.delta().mul_f32(COMMAND_RELOAD_BONUS);
+    for (entity, mut ship, player, captured) in ships.iter_mut() {
+        let commanded = commanders.iter().any(|(commander, position, friendly)| {
+            *commander != entity
+                && *friendly == (player || captured)
+                && position.distance(ship.position) < COMMAND_RADIUS
+        });
+        if commanded {
+            ship.weapon_cooldown.tick(bonus);
+        }
+    }
+}
+
 #[derive(Bundle)]
 pub struct EnemySpacecraftBundle {
     spacecraft: Spacecraft,
+    interpolated: Interpolated,
     sprite: SpriteBundle,
     logic: NPCLogic,
     collider: ColliderBundle,
@@ -250,22 +620,117 @@ impl EnemySpacecraftBundle {
         };
         Self {
             spacecraft: Spacecraft::from_template(ship_type, pos),
-            logic: NPCLogic(Vec2::new(
+            interpolated: Interpolated::new(pos, 10.),
+            logic: NPCLogic::new(Vec2::new(
                 rand.gen_range(-0.3..0.3),
                 rand.gen_range(-0.3..0.3),
             )),
             sprite: SpriteBundle {
                 texture: ship_textures.texture(ship_type),
+                sprite: Sprite {
+                    color: ship_type.tint(),
+                    ..default()
+                },
                 transform,
                 ..default()
             },
             collider: ship_type.collider(),
         }
     }
+
+    pub fn with_tint(mut self, color: Color) -> Self {
+        self.sprite.sprite.color = color;
+        self
+    }
+
+    /// Blows the sprite, and the collider with it, up past the hull's usual size
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.sprite.transform.scale = Vec3::new(scale, scale, 1.);
+        self
+    }
+
+    pub fn with_health(mut self, health: i32) -> Self {
+        self.spacecraft.health = health;
+        self
+    }
+
+    /// Turned to `heading` on its first frame
+    pub fn turned(mut self, heading: f32) -> Self {
+        self.spacecraft.rotate(heading);
+        self
+    }
+
+    pub fn with_variant(mut self, variant: ShipVariant) -> Self {
+        let base = self.spacecraft.ship_type;
+        let profile = ShipProfile::of(base, Some(variant));
+        self.spacecraft = Spacecraft::from_variant(base, Some(variant), self.spacecraft.position);
+        self.sprite.sprite.color = variant.tint();
+        self.sprite.transform.scale = Vec3::new(profile.relative_scale, profile.relative_scale, 1.);
+        self
+    }
 }
 
+/// How often an AI ship looks around for a better target
+pub const RETARGET_INTERVAL: Duration = Duration::from_millis(250);
+/// How far an AI ship can pick out another in clear space. Dust and the nebula both cut it down.
+pub const DETECTION_RANGE: f32 = 6.;
+/// How close a ship has to get to where it lost its quarry before it gives up and searches
+pub const LOST_TRAIL_RADIUS: f32 = 0.3;
+
 #[derive(Component, Reflect)]
-pub struct NPCLogic(Vec2);
+pub struct NPCLogic {
+    /// Where around its target the ship aims, so a pack doesn't stack up on one point
+    offset: Vec2,
+    retarget: Timer,
+    target: Option<Entity>,
+    /// Where the ship last made out the player, to head for once they're lost in the dust
+    last_seen: Option<Vec2>,
+}
+
+impl NPCLogic {
+    pub fn new(offset: Vec2) -> Self {
+        // Start each ship somewhere different in the cycle so they don't all retarget on one frame
+        let mut retarget = Timer::new(RETARGET_INTERVAL, TimerMode::Repeating);
+        retarget.tick(RETARGET_INTERVAL.mul_f32(rand::thread_rng().gen_range(0. ..1.)));
+        Self {
+            offset,
+            retarget,
+            target: None,
+            last_seen: None,
+        }
+    }
+
+    /// Whether a ship here picks out one over there, seen through `sight` (1 in clear space)
+    pub fn detects(observer: Vec2, target: Vec2, sight: f32) -> bool {
+        observer.distance(target) <= DETECTION_RANGE * sight
+    }
+
+    /// Where the player was last made out, if the trail hasn't gone cold
+    pub fn last_seen(&self) -> Option<Vec2> {
+        self.last_seen
+    }
+
+    /// Keeps track of the player while they're in sight, returning whether they are. Once they're
+    /// lost, the trail goes cold on reaching where they were last seen.
+    pub fn track(&mut self, observer: Vec2, player: Vec2, sight: f32) -> bool {
+        let seen = Self::detects(observer, player, sight);
+        if seen {
+            self.last_seen = Some(player);
+        } else if self
+            .last_seen
+            .is_some_and(|last_seen| last_seen.distance(observer) < LOST_TRAIL_RADIUS)
+        {
+            self.last_seen = None;
+        }
+        seen
+    }
+
+    /// Whether it's time to pick a new target, either on schedule or because the old one is gone
+    pub fn should_retarget(&mut self, delta: Duration, target_alive: bool) -> bool {
+        let due = self.retarget.tick(delta).just_finished();
+        due || !target_alive
+    }
+}
 
 #[derive(Component, Reflect)]
 pub struct Spacecraft {
@@ -277,18 +742,31 @@ pub struct Spacecraft {
     pub weapon_cooldown: Timer,
     pub shield_recharge: Timer,
     pub ship_type: ShipType,
+    pub variant: Option<ShipVariant>,
+    /// Loadout choices, only ever set on the ship the player starts the run in
+    pub fitting: Option<Fitting>,
+    /// Damage armour has let through that doesn't yet add up to a whole hit. Below zero, it's
+    /// hull patched by an interrupted shield recharge that the next hit has to get through first.
+    pub soaked_damage: f32,
+    /// Covered by a projector's aura, which takes the edge off every hit
+    pub in_aura: bool,
+    /// A salvaged gun pod bolted on, firing alongside the hull's own guns until it wears out
+    pub gun_pod: Option<Timer>,
+    /// Upgrades bought with scrap this run, only ever set on the ship the player is flying
+    pub upgrades: Option<RunUpgrades>,
 }
 
 #[derive(Bundle)]
 pub struct PlayerBundle {
     craft: Spacecraft,
+    interpolated: Interpolated,
     marker: PlayerMarker,
     sprite: SpriteBundle,
     collider: ColliderBundle,
 }
 
 impl PlayerBundle {
-    fn create_ship(ship_type: ShipType, pos: Vec2, ship_textures: &ShipTextures) -> Self {
+    pub fn create_ship(ship_type: ShipType, pos: Vec2, ship_textures: &ShipTextures) -> Self {
         let template_ship = ShipProfile::from_type(ship_type);
         let transform = Transform {
             translation: Vec3::new(0., 0., 10.),
@@ -302,15 +780,41 @@ impl PlayerBundle {
 
         Self {
             craft: Spacecraft::from_template(ship_type, pos),
+            interpolated: Interpolated::new(pos, 10.),
             marker: PlayerMarker,
             sprite: SpriteBundle {
                 texture: ship_textures.texture(ship_type),
+                sprite: Sprite {
+                    color: ship_type.tint(),
+                    ..default()
+                },
                 transform,
                 ..default()
             },
             collider: ship_type.collider(),
         }
     }
+
+    pub fn with_fitting(mut self, fitting: Fitting) -> Self {
+        self.craft = Spacecraft::from_parts(
+            self.craft.ship_type,
+            self.craft.variant,
+            Some(fitting),
+            self.craft.position,
+        );
+        self
+    }
+
+    pub fn with_health(mut self, health: i32) -> Self {
+        self.craft.health = health;
+        self
+    }
+
+    /// Turned to `heading` on its first frame, along with the camera
+    pub fn turned(mut self, heading: f32) -> Self {
+        self.craft.rotate(heading);
+        self
+    }
 }
 
 #[derive(Component)]
@@ -318,20 +822,56 @@ pub struct PlayerMarker;
 
 impl Spacecraft {
     pub fn from_template(template: ShipType, pos: Vec2) -> Self {
-        let template_ship = ShipProfile::from_type(template);
-        let mut shield_recharge_timer =
-            Timer::new(template_ship.shield_recharge_time, TimerMode::Once);
-        shield_recharge_timer.set_elapsed(template_ship.shield_recharge_time);
-        Self {
+        Self::from_variant(template, None, pos)
+    }
+
+    pub fn from_variant(template: ShipType, variant: Option<ShipVariant>, pos: Vec2) -> Self {
+        Self::from_parts(template, variant, None, pos)
+    }
+
+    fn from_parts(
+        template: ShipType,
+        variant: Option<ShipVariant>,
+        fitting: Option<Fitting>,
+        pos: Vec2,
+    ) -> Self {
+        let mut craft = Self {
             position: pos,
             heading: 0.,
             delta_rotation: 0.,
             velocity: 0.,
-            health: template_ship.max_health,
+            health: 0,
             ship_type: template,
-            weapon_cooldown: Timer::new(template_ship.gun_reload_time, TimerMode::Once),
-            shield_recharge: shield_recharge_timer,
+            variant,
+            fitting,
+            soaked_damage: 0.,
+            in_aura: false,
+            gun_pod: None,
+            upgrades: None,
+            weapon_cooldown: Timer::default(),
+            shield_recharge: Timer::default(),
+        };
+        let template_ship = craft.profile();
+        let mut shield_recharge_timer =
+            Timer::new(template_ship.shield_recharge_time, TimerMode::Once);
+        // Ready to use from the start
+        shield_recharge_timer.tick(template_ship.shield_recharge_time);
+        craft.health = template_ship.max_health;
+        craft.weapon_cooldown = Timer::new(template_ship.gun_reload_time, TimerMode::Once);
+        craft.shield_recharge = shield_recharge_timer;
+        craft
+    }
+
+    /// The hull's stats, with any variant, loadout and upgrade tweaks applied
+    pub fn profile(&self) -> ShipProfile {
+        let mut profile = ShipProfile::of(self.ship_type, self.variant);
+        if let Some(fitting) = self.fitting {
+            fitting.apply(&mut profile);
         }
+        if let Some(upgrades) = self.upgrades {
+            upgrades.apply(&mut profile);
+        }
+        profile
     }
 
     pub fn rotate(&mut self, amount: f32) {
@@ -343,7 +883,39 @@ impl Spacecraft {
         self.delta_rotation = 0.;
     }
 
-    pub fn collide(&mut self, damage: i32, reduce_to_one: bool, score: &mut PlayerScore) -> bool {
+    /// Like [`Spacecraft::collide`], but with the damage first cut down or boosted by the ship's armour
+    pub fn take_hit(
+        &mut self,
+        damage: i32,
+        damage_type: DamageType,
+        reduce_to_one: bool,
+        score_events: &mut Events<ScoreEvent>,
+    ) -> bool {
+        let aura = match self.in_aura {
+            true => AURA_DAMAGE_TAKEN,
+            false => 1.,
+        };
+        let dealt = damage as f32 * effectiveness(damage_type, self.profile().armor) * aura
+            + self.soaked_damage;
+        // What's left of a shield patch carries over as it is, rather than rounding down into a debt
+        if dealt < 0. {
+            self.soaked_damage = dealt;
+            return false;
+        }
+        let whole = dealt.floor();
+        self.soaked_damage = dealt - whole;
+        if whole < 1. {
+            return false;
+        }
+        self.collide(whole as i32, reduce_to_one, score_events)
+    }
+
+    pub fn collide(
+        &mut self,
+        damage: i32,
+        reduce_to_one: bool,
+        score_events: &mut Events<ScoreEvent>,
+    ) -> bool {
         println!(
             "Collision, damage: {:?}, type: {:?}",
             damage, self.ship_type
@@ -351,127 +923,243 @@ impl Spacecraft {
         // Whether to swap
         if self.health - damage <= 0 && reduce_to_one {
             self.health = 1;
-            score.score += 15;
+            score_events.send(ScoreEvent {
+                source: ScoreSource::Captures,
+                points: 15,
+            });
             true
         } else {
             self.health -= damage;
             if reduce_to_one {
-                score.score += 5
+                score_events.send(ScoreEvent {
+                    source: ScoreSource::Kills,
+                    points: 5,
+                });
             }
             false
         }
     }
 }
 
-fn camera_follow(
+#[allow(clippy::type_complexity)]
+pub fn camera_follow(
+    mut commands: Commands,
+    time: Res<Time>,
+    transition: Option<ResMut<CameraTransition>>,
     mut transforms: Query<&mut Transform, (With<Camera2d>, Without<PlayerMarker>)>,
     player_ship: Query<(&Spacecraft, &Transform), (With<PlayerMarker>, Without<Camera2d>)>,
 ) {
     if let Ok(mut cam_transform) = transforms.get_single_mut() {
         if let Ok((ship, transform)) = player_ship.get_single() {
-            cam_transform.rotate_z(ship.delta_rotation);
-            cam_transform.translation.x = transform.translation.x;
-            cam_transform.translation.y = transform.translation.y;
+            match transition {
+                Some(mut transition) => {
+                    transition.timer.tick(time.delta());
+                    let t = transition.timer.fraction();
+                    let eased = t * t * (3. - 2. * t);
+                    let rotation = transform.rotation * Quat::from_rotation_z(-3. * PI / 2.);
+                    cam_transform.rotation = transition.rotation.slerp(rotation, eased);
+                    let translation = transition
+                        .translation
+                        .lerp(transform.translation.truncate(), eased);
+                    cam_transform.translation.x = translation.x;
+                    cam_transform.translation.y = translation.y;
+                    if transition.timer.finished() {
+                        commands.remove_resource::<CameraTransition>();
+                    }
+                }
+                None => {
+                    cam_transform.rotate_z(ship.delta_rotation);
+                    cam_transform.translation.x = transform.translation.x;
+                    cam_transform.translation.y = transform.translation.y;
+                }
+            }
         }
     }
 }
 
-pub fn move_spaceships(
-    mut ships: Query<(&mut Spacecraft, &mut Transform)>,
-    window: Query<&Window>,
+fn zoom_camera(
     time: Res<Time>,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut scroll: EventReader<MouseWheel>,
+    mut settings: ResMut<Settings>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
 ) {
-    for (mut ship, mut transform) in ships.iter_mut() {
-        ship.shield_recharge.tick(time.delta());
+    let mut zoom = settings.camera_zoom;
+    for event in scroll.read() {
+        zoom -= match event.unit {
+            MouseScrollUnit::Line => event.y * 0.1,
+            MouseScrollUnit::Pixel => event.y * 0.002,
+        };
+    }
+    if inputs.any_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        zoom -= time.delta_seconds();
+    }
+    if inputs.any_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        zoom += time.delta_seconds();
+    }
+    let zoom = zoom.clamp(MIN_CAMERA_ZOOM, MAX_CAMERA_ZOOM);
+    if zoom != settings.camera_zoom {
+        settings.camera_zoom = zoom;
+    }
+    if let Ok(mut camera) = camera.get_single_mut() {
+        camera.scale.x = zoom;
+        camera.scale.y = zoom;
+    }
+}
+
+/// One simulation step. Slow motion takes fewer steps a second, rather than shorter ones.
+pub fn move_spaceships(mut ships: Query<(&mut Spacecraft, &mut Interpolated)>) {
+    for (mut ship, mut interpolated) in ships.iter_mut() {
         // Transform player in clip-space coordinates
         let delta_pos = Vec2::new(ship.heading.sin(), ship.heading.cos()) * ship.velocity;
         ship.position += delta_pos;
+        interpolated.step(ship.position);
+    }
+}
 
-        // Translate and apply to sprite component
-        if let Ok(window) = window.get_single() {
-            let window_dimensions = Vec2::new(window.width(), window.height());
-            let ship_pos = ship.position * (window_dimensions / 2.);
-            transform.translation = ship_pos.extend(10.);
-            transform.rotate_z(ship.delta_rotation);
-        }
+/// Sprites turn as soon as their ship does, once a frame, so there's nothing to interpolate
+pub fn turn_spaceships(mut ships: Query<(&Spacecraft, &mut Transform)>) {
+    for (ship, mut transform) in ships.iter_mut() {
+        transform.rotate_z(ship.delta_rotation);
     }
 }
 
 #[derive(Component)]
 pub struct RechargingShieldMarker;
 
+/// A shield recharge under way, which only makes progress while the ship keeps its speed down
 #[derive(Component)]
-pub struct ShieldTimeRemainingTimer(Timer);
+pub struct ShieldRecharge {
+    /// Speed last frame, so speeding up can be told apart from still braking
+    speed: f32,
+}
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn handle_inputs(
     mut commands: Commands,
     inputs: Res<ButtonInput<KeyCode>>,
-    mut player_ship: Query<
-        (Entity, &mut Spacecraft),
+    mut player_ship: Query<(Entity, &mut Spacecraft, Option<&ControlHandoff>), With<PlayerMarker>>,
+    mut dialogue: ResMut<Dialogue>,
+    bullet_texture: Res<BulletTexture>,
+    mut fired: EventWriter<ShotFired>,
+    capture: Option<Res<CaptureMoment>>,
+    pads: Pads,
+    bindings: Res<InputBindings>,
+    virtual_time: Res<Time<Virtual>>,
+    settings: Res<Settings>,
+    run: Res<RunConfig>,
+    others: Query<
         (
-            With<PlayerMarker>,
-            Without<RechargingShieldMarker>,
-            Without<ShieldTimeRemainingTimer>,
+            &Spacecraft,
+            Has<Captured>,
+            Has<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
         ),
+        Without<PlayerMarker>,
     >,
-    mut dialogue: ResMut<Dialogue>,
-    bullet_texture: Res<BulletTexture>,
-    state: Res<State<GameState>>,
 ) {
-    if let Ok((entity, mut player_ship)) = player_ship.get_single_mut() {
-        let max_velocity = ShipProfile::from_type(player_ship.ship_type).max_velocity;
+    let pressed =
+        |action| inputs.pressed(bindings.key(action)) || pads.pressed(bindings.button(action));
+    if let Ok((entity, mut player_ship, handoff)) = player_ship.get_single_mut() {
+        let profile = player_ship.profile();
+        let max_velocity = profile.max_velocity;
+        // Turning and throttle build up over however many simulation steps this frame covered
+        let authority =
+            handoff.map_or(1., |handoff| handoff.authority()) * frame_steps(&virtual_time);
+        // While a capture is being decided, the capture choices take over any buttons they share
+        let deciding = matches!(capture.as_deref(), Some(CaptureMoment::Deciding));
         player_ship.end_frame();
-        if inputs.pressed(KeyCode::ArrowLeft) && !state.get().eq(&GameState::Paused) {
-            player_ship.rotate(max_velocity * -TURN_SPEED);
+        let mut turn = 0.;
+        if pressed(Action::TurnLeft) {
+            turn -= max_velocity * TURN_SPEED * profile.turn_rate * authority;
         }
-        if inputs.pressed(KeyCode::ArrowRight) && !state.get().eq(&GameState::Paused) {
-            player_ship.rotate(max_velocity * TURN_SPEED);
+        if pressed(Action::TurnRight) {
+            turn += max_velocity * TURN_SPEED * profile.turn_rate * authority;
         }
-        if inputs.pressed(KeyCode::ArrowUp) {
-            player_ship.velocity += max_velocity * ACCELERATION_SPEED;
+        if pressed(Action::ThrottleUp) {
+            player_ship.velocity += max_velocity * ACCELERATION_SPEED * authority;
             player_ship.velocity = player_ship
                 .velocity
                 .clamp(-0.3 * max_velocity, max_velocity);
         }
-        if inputs.pressed(KeyCode::ArrowDown) {
-            player_ship.velocity -= max_velocity * ACCELERATION_SPEED;
+        if pressed(Action::ThrottleDown) {
+            player_ship.velocity -= max_velocity * ACCELERATION_SPEED * authority;
             player_ship.velocity = player_ship
                 .velocity
                 .clamp(-0.3 * max_velocity, max_velocity);
         }
-        if inputs.pressed(KeyCode::Space)
-            && !state.get().eq(&GameState::Paused)
-            && player_ship.weapon_cooldown.finished()
-        {
+        // The stick is analogue, so a light touch turns and throttles gently
+        let stick = pads.stick();
+        if stick.x != 0. {
+            turn += max_velocity * TURN_SPEED * profile.turn_rate * authority * stick.x;
+        }
+        if flight_assist_on(&settings, run.difficulty) {
+            let ships = others.iter().map(|(ship, _, _)| ship.position);
+            turn = damp_turn(player_ship.position, player_ship.heading, turn, ships);
+        }
+        // Only for fire held on a pad, where the stick makes fine aim hard
+        if run.aim_assist && pads.pressed(bindings.button(Action::Fire)) {
+            let max_pull =
+                settings.aim_assist_strength.to_radians() * authority / SIMULATION_HZ as f32;
+            let enemies = others
+                .iter()
+                .filter(|(_, captured, disabled)| !captured && !disabled)
+                .map(|(ship, _, _)| ship.position);
+            turn += aim_pull(player_ship.position, player_ship.heading, max_pull, enemies);
+        }
+        player_ship.rotate(turn);
+        if stick.y != 0. {
+            player_ship.velocity += max_velocity * ACCELERATION_SPEED * authority * stick.y;
+            player_ship.velocity = player_ship
+                .velocity
+                .clamp(-0.3 * max_velocity, max_velocity);
+        }
+        let lined_up = || {
+            others.iter().any(|(enemy, captured, disabled)| {
+                !captured
+                    && !disabled
+                    && in_forward_arc(player_ship.position, player_ship.heading, enemy.position)
+            })
+        };
+        let firing = pressed(Action::Fire) || (settings.auto_fire && lined_up());
+        if firing && player_ship.weapon_cooldown.finished() {
             ship_fire(
                 &mut commands,
+                &mut fired,
                 &mut player_ship,
                 entity,
                 &bullet_texture,
                 true,
             )
         }
-        if inputs.pressed(KeyCode::KeyS)
-            && !state.get().eq(&GameState::Paused)
-            && player_ship.shield_recharge.finished()
-        {
+        let shielding = inputs.pressed(bindings.key(Action::Shield))
+            || (!deciding && pads.pressed(bindings.button(Action::Shield)));
+        if shielding && player_ship.shield_recharge.finished() {
             commands.entity(entity).insert(RechargingShieldMarker);
             player_ship.shield_recharge.reset();
         }
-        if inputs.pressed(KeyCode::Enter) {
-            dialogue.hide()
+        if inputs.just_pressed(bindings.key(Action::AdvanceDialogue))
+            || (!deciding && pads.just_pressed(bindings.button(Action::AdvanceDialogue)))
+        {
+            dialogue.advance()
         }
-        if inputs.just_released(KeyCode::Digit1) && state.get().eq(&GameState::Paused) {
-            commands.spawn(ShipUsageDecision::Transfer);
-        } else if inputs.just_released(KeyCode::Digit2) && state.get().eq(&GameState::Paused) {
-            commands.spawn(ShipUsageDecision::Keep);
-        } else if inputs.just_released(KeyCode::Digit3) && state.get().eq(&GameState::Paused) {
-            commands.spawn(ShipUsageDecision::Destroy);
+        // Held captures are picked by the assist instead, as letting go is how it takes one
+        if deciding && !settings.hold_to_capture {
+            let released = |action| {
+                inputs.just_released(bindings.key(action))
+                    || pads.just_released(bindings.button(action))
+            };
+            if released(Action::CaptureOption1) {
+                commands.spawn(ShipUsageDecision::Transfer);
+            } else if released(Action::CaptureOption2) {
+                commands.spawn(ShipUsageDecision::Keep);
+            } else if released(Action::CaptureOption3) {
+                commands.spawn(ShipUsageDecision::Destroy);
+            }
         }
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ShipUsageDecision {
     Transfer,
     Keep,
@@ -479,7 +1167,18 @@ pub enum ShipUsageDecision {
 }
 
 #[derive(Resource)]
-pub struct AllyTexture(Handle<Image>);
+pub struct AllyTexture(pub Handle<Image>);
+
+/// Turns a ship to the player's side, flying the ally flag above it
+pub fn make_ally(ship: &mut EntityCommands, ally_texture: &AllyTexture) {
+    ship.insert(Captured).with_children(|parent| {
+        parent.spawn(SpriteBundle {
+            transform: Transform::from_xyz(0., 30., 80.).with_scale(Vec3::new(3., 3., 1.)),
+            texture: ally_texture.0.clone(),
+            ..default()
+        });
+    });
+}
 
 #[derive(Component)]
 pub struct ShipUsageImageMarker;
@@ -487,83 +1186,145 @@ pub struct ShipUsageImageMarker;
 #[derive(Component)]
 pub struct MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker;
 
+/// Disabled ships waiting on the 1/2/3 choice, in the order they went down. Only the one at the
+/// front is being decided; the rest hang on to their marker, so nothing finishes them off while
+/// they wait their turn.
+#[derive(Resource, Default)]
+pub struct CaptureQueue(pub VecDeque<Entity>);
+
+impl CaptureQueue {
+    pub fn current(&self) -> Option<Entity> {
+        self.0.front().copied()
+    }
+}
+
+/// Also drops ships that lost their marker some other way, like a death calling the choice off
+fn queue_capture_candidates(
+    mut queue: ResMut<CaptureQueue>,
+    disabled: Query<
+        Entity,
+        Added<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
+    >,
+    held: Query<(), With<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>>,
+) {
+    queue.0.retain(|entity| held.contains(*entity));
+    for entity in disabled.iter() {
+        if !queue.0.contains(&entity) {
+            queue.0.push_back(entity);
+        }
+    }
+}
+
+/// Settles the ship at the front of the queue. The choice stays up, still in slow motion, while
+/// there are more ships to get through.
+#[allow(clippy::too_many_arguments)]
 fn check_for_usage_decision(
     mut commands: Commands,
-    mut state: ResMut<NextState<GameState>>,
     usage: Query<(Entity, &ShipUsageDecision)>,
     image: Query<Entity, With<ShipUsageImageMarker>>,
     mut ship: Query<
-        (Entity, &mut Spacecraft),
+        &mut Spacecraft,
         With<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
     >,
-    mut score: ResMut<PlayerScore>,
+    mut queue: ResMut<CaptureQueue>,
+    score: Res<PlayerScore>,
+    mut score_events: ResMut<Events<ScoreEvent>>,
+    mut stats: ResMut<RunStats>,
     ally_texture: Res<AllyTexture>,
+    mut captured: EventWriter<ShipCaptured>,
+    mut damaged: EventWriter<ShipDamaged>,
 ) {
-    if let Ok((entity, decision)) = usage.get_single() {
-        match decision {
-            ShipUsageDecision::Transfer => {
-                for (transfer_entity, _) in ship.iter() {
-                    commands.entity(transfer_entity).insert(SwapToShipMarker).remove::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>();
+    let Ok((entity, decision)) = usage.get_single() else {
+        return;
+    };
+    commands.entity(entity).despawn();
+    if let Some(current) = queue.0.pop_front() {
+        if let Ok(mut craft) = ship.get_mut(current) {
+            let ship_type = craft.ship_type;
+            let event = match decision {
+                ShipUsageDecision::Transfer => TimelineEvent::SwappedInto { ship: ship_type },
+                ShipUsageDecision::Keep => TimelineEvent::Captured { ship: ship_type },
+                ShipUsageDecision::Destroy => TimelineEvent::Scuttled { ship: ship_type },
+            };
+            stats.record(&score, event);
+            let mut decided = commands.entity(current);
+            decided
+                .remove::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>(
+                );
+            match decision {
+                ShipUsageDecision::Transfer => {
+                    decided.insert(SwapToShipMarker);
                 }
-            }
-            ShipUsageDecision::Keep => {
-                for (new_ally_entity, _) in ship.iter() {
-                    commands.entity(new_ally_entity).insert(Captured).remove::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>().with_children(|parent|
-                    {
-                        parent.spawn(SpriteBundle {
-                            transform: Transform::from_xyz(0., 30., 80.).with_scale(Vec3::new(3., 3., 1.)),
-                            texture: ally_texture.0.clone(),
-                            ..default()
-                        });
+                ShipUsageDecision::Keep => {
+                    captured.send(ShipCaptured {
+                        ship: current,
+                        ship_type,
                     });
+                    make_ally(&mut decided, &ally_texture);
                 }
-            }
-            ShipUsageDecision::Destroy => {
-                for (future_destruction_entity, mut im_about_to_explode) in ship.iter_mut() {
-                    commands.entity(future_destruction_entity).remove::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>();
-                    im_about_to_explode.collide(100, false, &mut score);
+                ShipUsageDecision::Destroy => {
+                    let before = craft.health;
+                    craft.collide(100, false, &mut score_events);
+                    report_damage(
+                        &mut damaged,
+                        current,
+                        None,
+                        DamageCause::Scuttled,
+                        before,
+                        craft.health,
+                    );
                 }
             }
         }
-        commands.entity(image.single()).despawn();
-        commands.entity(entity).despawn();
-        print!("Despawn menu image");
-        state.set(GameState::Regular);
     }
+    queue.0.retain(|waiting| ship.contains(*waiting));
+    if !queue.0.is_empty() {
+        return;
+    }
+    for image in image.iter() {
+        commands.entity(image).despawn_recursive();
+    }
+    print!("Despawn menu image");
+    commands.insert_resource(CaptureMoment::Recovering(Timer::new(
+        CAPTURE_RAMP_TIME,
+        TimerMode::Once,
+    )));
 }
 
-#[derive(Component)]
-pub struct TimerComp(Timer);
+/// Recharges hit this far along still patch the hull part way
+pub const LATE_INTERRUPT: f32 = 0.5;
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn recharge_shield(
     mut commands: Commands,
     time: Res<Time>,
-    mut unsetup_recharging: Query<
+    mut damaged: EventReader<ShipDamaged>,
+    starting: Query<
         (Entity, &Spacecraft, &Transform),
-        (
-            With<RechargingShieldMarker>,
-            Without<ShieldTimeRemainingTimer>,
-        ),
+        (With<RechargingShieldMarker>, Without<ShieldRecharge>),
     >,
-    mut setup_recharging: Query<
-        (Entity, &mut Spacecraft, &mut ShieldTimeRemainingTimer),
+    mut recharging: Query<
+        (Entity, &mut Spacecraft, &mut ShieldRecharge),
         Without<RechargingShieldMarker>,
     >,
+    mut cooling: Query<&mut Spacecraft, (Without<ShieldRecharge>, Without<RechargingShieldMarker>)>,
     shield_textures: Res<ShieldRechargeTextures>,
+    mut started: EventWriter<ShieldRechargeStarted>,
+    mut finished: EventWriter<ShieldRechargeFinished>,
 ) {
-    for (entity, spacecraft, transform) in unsetup_recharging.iter_mut() {
-        let time = ShipProfile::from_type(spacecraft.ship_type)
-            .shield_recharge_time
-            .as_micros()
-            / 5;
-        let mut transform = transform.clone();
+    let hit = damaged.read().map(|event| event.ship).collect::<Vec<_>>();
+    // An interrupted recharge has to run its full time again before the next one can start
+    for mut ship in cooling.iter_mut() {
+        if !ship.shield_recharge.finished() {
+            ship.shield_recharge.tick(time.delta());
+        }
+    }
+    for (entity, spacecraft, transform) in starting.iter() {
+        let mut transform = *transform;
         transform.translation.z = 50.;
         transform.scale = Vec3::new(3., 3., 1.);
         commands.spawn(ShieldRenderBundle {
-            frame_time: SoloShieldMarker(Timer::new(
-                Duration::from_micros(time as u64),
-                TimerMode::Repeating,
-            )),
+            ship: SoloShieldMarker(entity),
             atlas: SpriteSheetBundle {
                 transform,
                 texture: shield_textures.image.clone(),
@@ -577,44 +1338,66 @@ pub fn recharge_shield(
         commands
             .entity(entity)
             .remove::<RechargingShieldMarker>()
-            .insert(ShieldTimeRemainingTimer(Timer::new(
-                ShipProfile::from_type(spacecraft.ship_type).shield_recharge_time,
-                TimerMode::Once,
-            )));
+            .insert(ShieldRecharge {
+                speed: spacecraft.velocity.abs(),
+            });
+        started.send(ShieldRechargeStarted { ship: entity });
     }
-    for (entity, mut ship, mut timer) in setup_recharging.iter_mut() {
-        timer.0.tick(time.delta());
-        ship.velocity = 0.;
-        ship.delta_rotation = 0.;
-        if ship.shield_recharge.just_finished() {
-            commands.entity(entity).remove::<ShieldTimeRemainingTimer>();
+    for (entity, mut ship, mut recharge) in recharging.iter_mut() {
+        if hit.contains(&entity) {
+            // Taking a hit spends the attempt, but one that was nearly done takes the edge off the next hit
+            let progress = ship.shield_recharge.fraction();
+            if progress >= LATE_INTERRUPT {
+                ship.soaked_damage -= progress;
+            }
+            ship.shield_recharge.reset();
+            commands.entity(entity).remove::<ShieldRecharge>();
+            continue;
+        }
+        let limit = ship.profile().recharge_speed();
+        let speed = ship.velocity.abs();
+        if speed > limit && speed > recharge.speed {
+            // Throttling up calls the recharge off, and it can be started again straight away
+            let remaining = ship.shield_recharge.remaining();
+            ship.shield_recharge.tick(remaining);
+            commands.entity(entity).remove::<ShieldRecharge>();
+            continue;
+        }
+        recharge.speed = speed;
+        if speed <= limit && ship.shield_recharge.tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<ShieldRecharge>();
             ship.health += 1;
-            ship.health = ship
-                .health
-                .min(ShipProfile::from_type(ship.ship_type).max_health);
+            ship.health = ship.health.min(ship.profile().max_health);
+            finished.send(ShieldRechargeFinished { ship: entity });
         }
     }
 }
+
 #[derive(Bundle)]
 pub struct ShieldRenderBundle {
-    frame_time: SoloShieldMarker,
+    ship: SoloShieldMarker,
     atlas: SpriteSheetBundle,
 }
 
+/// The recharge effect drawn over a ship, filling up as its recharge does
 #[derive(Component)]
-pub struct SoloShieldMarker(Timer);
+pub struct SoloShieldMarker(Entity);
 
 fn handle_shield_textures(
     mut commands: Commands,
-    time: Res<Time>,
-    mut shield_assets: Query<(Entity, &mut TextureAtlas, &mut SoloShieldMarker)>,
+    mut shield_assets: Query<
+        (Entity, &SoloShieldMarker, &mut TextureAtlas, &mut Transform),
+        Without<Spacecraft>,
+    >,
+    ships: Query<(&Spacecraft, &Transform), With<ShieldRecharge>>,
 ) {
-    for (entity, mut atlas, mut timer) in shield_assets.iter_mut() {
-        timer.0.tick(time.delta());
-        if timer.0.just_finished() && atlas.index < 4 {
-            atlas.index += 1;
-        } else if timer.0.just_finished() {
-            commands.entity(entity).despawn();
+    for (entity, shield, mut atlas, mut transform) in shield_assets.iter_mut() {
+        match ships.get(shield.0) {
+            Ok((ship, ship_transform)) => {
+                transform.translation = ship_transform.translation.truncate().extend(50.);
+                atlas.index = ((ship.shield_recharge.fraction() * 5.) as usize).min(4);
+            }
+            Err(_) => commands.entity(entity).despawn(),
         }
     }
 }
@@ -628,15 +1411,55 @@ pub struct DelayedPlayerLocation {
     current_location: Vec2,
 }
 
+/// How quickly enemies pick up on what the player is doing, set by the difficulty
+pub struct AiReaction {
+    /// How far behind the player's real position enemies are chasing
+    pub latency_secs: f32,
+    /// Multiplier on how hard enemies can turn towards their target in one go
+    pub turn_rate: f32,
+    /// Chance an enemy with its guns ready actually opens fire
+    pub fire_chance: f64,
+}
+
+impl AiReaction {
+    pub fn for_difficulty(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Easy => AiReaction {
+                latency_secs: 2.6,
+                turn_rate: 0.6,
+                fire_chance: 0.35,
+            },
+            Difficulty::Normal => AiReaction {
+                latency_secs: 2.,
+                turn_rate: 1.,
+                fire_chance: 0.5,
+            },
+            Difficulty::Hard => AiReaction {
+                latency_secs: 1.2,
+                turn_rate: 1.3,
+                fire_chance: 0.7,
+            },
+        }
+    }
+}
+
 fn update_delayed_location(
     mut player_location: ResMut<DelayedPlayerLocation>,
+    run: Res<RunConfig>,
     timer: Res<PlayerScore>,
     player: Query<&Spacecraft, With<PlayerMarker>>,
+    spectating: Option<Res<Spectating>>,
 ) {
+    if let Some(spectating) = spectating {
+        // With nobody left to chase, enemies go straight for whoever the camera follows
+        player_location.current_location = spectating.position;
+        return;
+    }
     if let Ok(player) = player.get_single() {
+        let latency = AiReaction::for_difficulty(run.difficulty).latency_secs;
         let time_elapsed = timer.survived_time.elapsed_secs();
-        while let Some((_, timestamp)) = player_location.buffered_locations.get(0) {
-            if *timestamp < time_elapsed - 2. {
+        while let Some((_, timestamp)) = player_location.buffered_locations.first() {
+            if *timestamp < time_elapsed - latency {
                 // This is out of date
                 player_location.buffered_locations.remove(0);
             } else {
@@ -649,11 +1472,12 @@ fn update_delayed_location(
                 .push((player.position, time_elapsed));
         }
     }
-    if let Some((pos, _)) = player_location.buffered_locations.get(0) {
+    if let Some((pos, _)) = player_location.buffered_locations.first() {
         player_location.current_location = *pos;
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn handle_npc_logic(
     mut commands: Commands,
     mut enemies: Query<
@@ -661,21 +1485,45 @@ pub fn handle_npc_logic(
         (Without<Captured>, Without<PlayerMarker>),
     >,
     mut captured: Query<
-        (Entity, &mut NPCLogic, &mut Spacecraft),
+        (Entity, &mut NPCLogic, &mut Spacecraft, Option<&AllyStance>),
         (With<Captured>, Without<PlayerMarker>),
     >,
     player: Res<DelayedPlayerLocation>,
+    player_ship: Query<&Spacecraft, With<PlayerMarker>>,
     bullet_texture: Res<BulletTexture>,
+    mut fired: EventWriter<ShotFired>,
+    run: Res<RunConfig>,
+    time: Res<Time>,
+    virtual_time: Res<Time<Virtual>>,
+    dust: Option<Res<DustClouds>>,
+    danger: Option<Res<Danger>>,
+    morale: Option<Res<FleetMorale>>,
 ) {
-    for (entity, logic, mut craft) in enemies.iter_mut() {
+    let dust = dust.as_deref();
+    let danger = danger.map_or(0., |danger| danger.0);
+    let reaction = AiReaction::for_difficulty(run.difficulty);
+    let steps = frame_steps(&virtual_time);
+    let enemy_turn = TURN_SPEED * reaction.turn_rate * steps;
+    let ally_turn = TURN_SPEED * steps;
+    for (entity, mut logic, mut craft) in enemies.iter_mut() {
         craft.end_frame();
-        let ideal_direction = player.current_location - craft.position + logic.0;
-        let ideal_heading = f32::atan2(ideal_direction.x, ideal_direction.y);
-        let ideal_heading_delta = ideal_heading - craft.heading;
-        let delta_heading = ideal_heading_delta.clamp(-TURN_SPEED, TURN_SPEED);
+        let sight = sight_between(craft.position, player.current_location, dust, danger);
+        let seen = logic.track(craft.position, player.current_location, sight);
+        // With no trail left to follow it circles, looking
+        let (delta_heading, dist) = match logic.last_seen {
+            Some(goal) => {
+                let ideal_direction = goal - craft.position + logic.offset;
+                let ideal_heading = f32::atan2(ideal_direction.x, ideal_direction.y);
+                let ideal_heading_delta = ideal_heading - craft.heading;
+                (
+                    ideal_heading_delta.clamp(-enemy_turn, enemy_turn),
+                    craft.position.distance(goal),
+                )
+            }
+            None => (enemy_turn * 0.5, 1.),
+        };
         craft.rotate(delta_heading);
-        let max_speed = ShipProfile::from_type(craft.ship_type).max_velocity;
-        let dist = craft.position.distance(player.current_location);
+        let max_speed = craft.profile().max_velocity;
         let ideal_speed = match dist {
             x if x > 1.2 => (1. * max_speed).min(max_speed),
             x if (0.5..=1.2).contains(&x) => (x * (1. / 0.7) * max_speed).min(max_speed),
@@ -683,112 +1531,172 @@ pub fn handle_npc_logic(
             _ => max_speed,
         };
         craft.velocity = ideal_speed * 0.15;
-        if craft.weapon_cooldown.finished() && dist < 1.2 {
+        if craft.weapon_cooldown.finished() && seen && dist < 1.2 {
             let mut rand = rand::thread_rng();
-            let fire_chance = rand.gen_range(0.0..1.0);
-            if fire_chance > 0.5 {
-                ship_fire(&mut commands, &mut craft, entity, &bullet_texture, false)
+            if rand.gen_bool(reaction.fire_chance) {
+                ship_fire(
+                    &mut commands,
+                    &mut fired,
+                    &mut craft,
+                    entity,
+                    &bullet_texture,
+                    false,
+                )
             } else {
                 craft.weapon_cooldown.reset();
             }
         }
     }
-    for (entity, _logic, mut craft) in captured.iter_mut() {
-        let mut enemies = enemies.iter().collect::<Vec<_>>();
-        enemies.sort_by(|(_, _, enemy_one), (_, _, enemy_two)| {
-            craft
-                .position
-                .distance(enemy_one.position)
-                .partial_cmp(&craft.position.distance(enemy_two.position))
-                .unwrap()
+    let player_position = player_ship.get_single().ok().map(|ship| ship.position);
+    for (entity, mut logic, mut craft, stance) in captured.iter_mut() {
+        let stance = stance.copied().unwrap_or_default();
+        // Targets are judged from the player, who the fleet is there to cover
+        let anchor = player_position.unwrap_or(craft.position);
+        let target_alive = logic.target.is_some_and(|target| enemies.contains(target));
+        if logic.should_retarget(time.delta(), target_alive) {
+            logic.target = enemies
+                .iter()
+                .filter(|(_, _, enemy)| enemy.position.distance(anchor) < stance.engagement_range())
+                .filter(|(_, _, enemy)| {
+                    let sight = sight_between(craft.position, enemy.position, dust, danger);
+                    NPCLogic::detects(craft.position, enemy.position, sight)
+                })
+                .min_by(|(_, _, enemy_one), (_, _, enemy_two)| {
+                    craft
+                        .position
+                        .distance(enemy_one.position)
+                        .partial_cmp(&craft.position.distance(enemy_two.position))
+                        .unwrap()
+                })
+                .map(|(enemy, _, _)| enemy);
+        }
+        let profile = craft.profile();
+        let retreat_health = morale.as_deref().map_or(stance.retreat_health(), |morale| {
+            morale.retreat_health(stance)
         });
-        if let Some((_, _, target)) = enemies.first() {
+        let retreating = (craft.health as f32) < profile.max_health as f32 * retreat_health;
+        let target = match retreating {
+            true => None,
+            false => logic.target.and_then(|target| enemies.get(target).ok()),
+        };
+        if let Some((_, _, target)) = target {
             craft.end_frame();
-            let ideal_direction = target.position - craft.position;
+            let mut ideal_direction = target.position - craft.position;
+            let mobile = profile.max_velocity > 0.;
+            if let (true, Ok(player_ship)) = (mobile, player_ship.get_single()) {
+                ideal_direction +=
+                    line_of_fire_nudge(craft.position, player_ship.position, player_ship.heading);
+            }
             let ideal_heading = f32::atan2(ideal_direction.x, ideal_direction.y);
             let ideal_heading_delta = ideal_heading - craft.heading;
-            let delta_heading = ideal_heading_delta.clamp(-TURN_SPEED, TURN_SPEED);
+            let delta_heading = ideal_heading_delta.clamp(-ally_turn, ally_turn);
             craft.rotate(delta_heading);
-            let max_speed = ShipProfile::from_type(craft.ship_type).max_velocity;
             let dist = craft.position.distance(target.position);
-            let ideal_speed = match dist {
-                x if x > 1.2 => 1. * max_speed,
-                x if (0.5..=1.2).contains(&x) => x * (1. / 0.7) * max_speed,
-                x if x < 0.5 => 0. * max_speed,
-                _ => max_speed,
-            };
-            craft.velocity = ideal_speed * 0.15;
-            if craft.weapon_cooldown.finished() && dist < 1.2 {
-                ship_fire(&mut commands, &mut craft, entity, &bullet_texture, false)
+            craft.velocity = closing_speed(dist, profile.max_velocity);
+            // Its shots would have to go through the player to get there
+            let player_in_the_way = player_position.is_some_and(|player| {
+                line_of_fire_nudge(player, craft.position, craft.heading) != Vec2::ZERO
+            });
+            let clear = !(stance.checks_fire() && player_in_the_way);
+            if craft.weapon_cooldown.finished() && dist < stance.fire_range() && clear {
+                ship_fire(
+                    &mut commands,
+                    &mut fired,
+                    &mut craft,
+                    entity,
+                    &bullet_texture,
+                    false,
+                )
             }
+        } else if let (true, Some(player)) = (retreating, player_position) {
+            craft.end_frame();
+            let ideal_direction = player - craft.position;
+            let ideal_heading = f32::atan2(ideal_direction.x, ideal_direction.y);
+            let delta_heading = (ideal_heading - craft.heading).clamp(-ally_turn, ally_turn);
+            craft.rotate(delta_heading);
+            let dist = craft.position.distance(player);
+            craft.velocity = closing_speed(dist, profile.max_velocity);
         }
     }
 }
 
+/// How fast an ally flies at something this far away, stopping short once it's close
+fn closing_speed(dist: f32, max_speed: f32) -> f32 {
+    let ideal_speed = match dist {
+        x if x > 1.2 => 1. * max_speed,
+        x if (0.5..=1.2).contains(&x) => x * (1. / 0.7) * max_speed,
+        x if x < 0.5 => 0. * max_speed,
+        _ => max_speed,
+    };
+    ideal_speed * 0.15
+}
+
+/// How far ahead of the player allies keep out of the way of their guns
+pub const LINE_OF_FIRE_RANGE: f32 = 1.2;
+/// Half the width of the lane in front of the player that allies steer out of
+pub const LINE_OF_FIRE_WIDTH: f32 = 0.15;
+
+/// A push sideways out of the lane in front of a shooter, stronger the closer to the middle
+pub fn line_of_fire_nudge(position: Vec2, shooter: Vec2, heading: f32) -> Vec2 {
+    let aim = Vec2::new(heading.sin(), heading.cos());
+    let relative = position - shooter;
+    let along = relative.dot(aim);
+    if !(0. ..LINE_OF_FIRE_RANGE).contains(&along) {
+        return Vec2::ZERO;
+    }
+    let across = relative - aim * along;
+    let gap = across.length();
+    if gap >= LINE_OF_FIRE_WIDTH {
+        return Vec2::ZERO;
+    }
+    let away = match gap > f32::EPSILON {
+        true => across / gap,
+        false => aim.perp(),
+    };
+    away * (1. - gap / LINE_OF_FIRE_WIDTH)
+}
+
 #[derive(Resource)]
 pub struct BulletTexture(Handle<Image>);
 
 pub fn ship_fire(
     commands: &mut Commands,
+    fired: &mut EventWriter<ShotFired>,
     parent: &mut Spacecraft,
     parent_entity: Entity,
     bullet_texture: &BulletTexture,
     player_shot: bool,
 ) {
-    match parent.ship_type {
-        ShipType::Ship1 | ShipType::Ship3 | ShipType::Ship5 => spawn_bullet(
+    let profile = parent.profile();
+    for gun in profile.gun_positions() {
+        spawn_bullet(
             commands,
             parent,
             parent_entity,
             bullet_texture,
-            0.,
+            gun,
             player_shot,
-        ),
-        ShipType::Ship2 => {
-            spawn_bullet(
-                commands,
-                parent,
-                parent_entity,
-                bullet_texture,
-                -0.03,
-                player_shot,
-            );
-            spawn_bullet(
-                commands,
-                parent,
-                parent_entity,
-                bullet_texture,
-                0.03,
-                player_shot,
-            );
-        }
-        ShipType::Ship4 | ShipType::Ship6 => {
-            spawn_bullet(
-                commands,
-                parent,
-                parent_entity,
-                bullet_texture,
-                -0.05,
-                player_shot,
-            );
-            spawn_bullet(
-                commands,
-                parent,
-                parent_entity,
-                bullet_texture,
-                0.,
-                player_shot,
-            );
-            spawn_bullet(
-                commands,
-                parent,
-                parent_entity,
-                bullet_texture,
-                0.05,
-                player_shot,
-            );
-        }
+        );
+    }
+    if parent.gun_pod.is_some() {
+        spawn_bullet(
+            commands,
+            parent,
+            parent_entity,
+            bullet_texture,
+            GUN_POD_MOUNT * profile.relative_scale,
+            player_shot,
+        );
     }
+    fired.send(ShotFired {
+        ship: parent_entity,
+        player_shot,
+    });
+}
+
+/// How far a gun off the centreline is turned in, so its shots cross the nose at [`GUN_CONVERGENCE`]
+pub fn toe_in(gun: Vec2) -> f32 {
+    f32::atan2(-gun.x, (GUN_CONVERGENCE - gun.y).max(0.01))
 }
 
 pub fn spawn_bullet(
@@ -796,18 +1704,14 @@ pub fn spawn_bullet(
     parent: &mut Spacecraft,
     parent_entity: Entity,
     bullet_texture: &BulletTexture,
-    lateral_offset: f32,
+    gun: Vec2,
     player_shot: bool,
 ) {
-    let parent_template = ShipProfile::from_type(parent.ship_type);
-    let lateral_heading = parent.heading - (PI / 2.);
-    let lateral_offset_vec =
-        Vec2::new(lateral_heading.sin(), lateral_heading.cos()) * lateral_offset;
-    let bullet_offset = lateral_offset_vec
-        + Vec2::new(parent.heading.sin(), parent.heading.cos()).normalize()
-            * 0.12
-            * parent_template.relative_scale;
-    let mut heading = parent.heading;
+    let parent_template = parent.profile();
+    let forward = Vec2::new(parent.heading.sin(), parent.heading.cos());
+    let right = Vec2::new(parent.heading.cos(), -parent.heading.sin());
+    let bullet_offset = right * gun.x + forward * gun.y;
+    let mut heading = parent.heading + toe_in(gun);
     if !player_shot {
         let mut rand = rand::thread_rng();
         heading += rand.gen_range(-0.4..0.4);
@@ -822,7 +1726,18 @@ pub fn spawn_bullet(
                 shooter: parent_entity,
                 immunity_time: Timer::from_seconds(0.25, TimerMode::Once),
                 player_shot,
+                pierces_left: match parent_template.hull_trait {
+                    Some(HullTrait::Piercing) => 1,
+                    _ => 0,
+                },
+                capture_chance: match parent_template.hull_trait {
+                    Some(HullTrait::Salvager) => SALVAGER_CAPTURE_CHANCE,
+                    _ => 0.,
+                },
+                damage_type: parent_template.damage_type,
+                status: parent_template.on_hit,
             },
+            interpolated: Interpolated::new(parent.position + bullet_offset, 30.),
             sprite: SpriteBundle {
                 texture: bullet_texture.0.clone(),
                 transform: Transform::from_xyz(0., 0., 30.)
@@ -835,12 +1750,18 @@ pub fn spawn_bullet(
                 events: ActiveEvents::all(),
                 hooks: ActiveHooks::all(),
                 types: ActiveCollisionTypes::all(),
+                groups: CollisionGroups::new(BULLET_GROUP, Group::ALL),
             },
         })
         .insert(Name::new("Bullet"));
     parent.weapon_cooldown.reset();
 }
 
+/// Bullets used up on something this frame. Anything reading the same collisions after
+/// [`collide_bullets`] checks here first, so a bullet over two things only lands once.
+#[derive(Resource, Default)]
+pub struct SpentBullets(pub Vec<Entity>);
+
 #[derive(Component, Reflect)]
 pub struct Bullet {
     heading: f32,
@@ -848,79 +1769,182 @@ pub struct Bullet {
     velocity: f32,
     shooter: Entity,
     immunity_time: Timer,
-    player_shot: bool,
+    pub player_shot: bool,
+    /// Ships this bullet can still pass through
+    pierces_left: u32,
+    capture_chance: f64,
+    damage_type: DamageType,
+    #[reflect(ignore)]
+    status: Option<StatusKind>,
+}
+
+impl Bullet {
+    pub fn shooter(&self) -> Entity {
+        self.shooter
+    }
+
+    pub fn heading(&self) -> f32 {
+        self.heading
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        self.damage_type
+    }
+
+    /// Whether the bullet is clear of its own shooter, and can hit it
+    pub fn immune_time_over(&self) -> bool {
+        self.immunity_time.finished()
+    }
+
+    /// Uses up one of the bullet's pierces, if it has any left
+    fn pierce(&mut self) -> bool {
+        if self.pierces_left > 0 {
+            self.pierces_left -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn lucky_shot(&self) -> bool {
+        rand::thread_rng().gen_bool(self.capture_chance)
+    }
 }
 
 #[derive(Bundle)]
 pub struct BulletBundle {
     bullet: Bullet,
+    interpolated: Interpolated,
     sprite: SpriteBundle,
     collider: ColliderBundle,
 }
 
+/// Ships and bullets meet everything. Wrecks only meet bullets, so ships pass through them.
+pub const SHIP_GROUP: Group = Group::GROUP_1;
+pub const BULLET_GROUP: Group = Group::GROUP_2;
+pub const WRECK_GROUP: Group = Group::GROUP_3;
+
 #[derive(Bundle)]
 pub struct ColliderBundle {
     collider: Collider,
     events: ActiveEvents,
     hooks: ActiveHooks,
     types: ActiveCollisionTypes,
+    groups: CollisionGroups,
 }
 
-pub fn move_bullets(mut bullets: Query<(&mut Bullet, &mut Transform)>, window: Query<&Window>) {
-    for (mut bullet, mut transform) in bullets.iter_mut() {
+pub fn move_bullets(mut bullets: Query<(&mut Bullet, &mut Interpolated)>) {
+    for (mut bullet, mut interpolated) in bullets.iter_mut() {
         // Transform player in clip-space coordinates
         let delta_pos = Vec2::new(bullet.heading.sin(), bullet.heading.cos()) * bullet.velocity;
         bullet.position += delta_pos;
-
-        // Translate and apply to sprite component
-        if let Ok(window) = window.get_single() {
-            let window_dimensions = Vec2::new(window.width(), window.height());
-            let bullet_pos = bullet.position * (window_dimensions / 2.);
-            transform.translation = bullet_pos.extend(30.);
-        }
+        interpolated.step(bullet.position);
     }
 }
 
 #[derive(Resource)]
 pub struct PausedWhatToDoImage(Handle<Image>);
 
-fn pause_for_captured_ship(
+/// Game speed while the player decides what to do with a disabled ship
+pub const CAPTURE_TIME_SCALE: f32 = 0.1;
+/// Real time it takes to get back up to full speed once they've decided
+pub const CAPTURE_RAMP_TIME: Duration = Duration::from_millis(600);
+
+/// Bullet time around a ship becoming capturable, so the fight carries on while the player decides
+#[derive(Resource)]
+pub enum CaptureMoment {
+    /// The 1/2/3 choice is on screen and the game is crawling along
+    Deciding,
+    /// Speeding back up after the choice was made
+    Recovering(Timer),
+}
+
+fn slow_for_captured_ship(
     mut commands: Commands,
-    mut state: ResMut<NextState<GameState>>,
-    ship_killed: Query<
-        Entity,
-        With<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
-    >,
+    capture: Option<Res<CaptureMoment>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    queue: Res<CaptureQueue>,
     image: Res<PausedWhatToDoImage>,
+    panel_assets: Res<PanelAssets>,
 ) {
-    if ship_killed.get_single().is_ok() {
-        state.set(GameState::Paused);
+    let deciding = matches!(capture.as_deref(), Some(CaptureMoment::Deciding));
+    if queue.current().is_some() && !deciding {
+        commands.insert_resource(CaptureMoment::Deciding);
+        virtual_time.set_relative_speed(CAPTURE_TIME_SCALE);
         commands
-            .spawn(ImageBundle {
-                style: Style {
+            .spawn(panel(
+                Style {
                     width: Val::Percent(60.),
                     height: Val::Percent(40.),
                     align_self: bevy::ui::AlignSelf::Center,
                     justify_self: bevy::ui::JustifySelf::Center,
+                    padding: UiRect::all(Val::Percent(1.)),
                     ..default()
                 },
-                image: UiImage::new(image.0.clone()),
-                ..default()
-            })
-            .insert(ShipUsageImageMarker);
+                panel_assets.frame.clone(),
+                panel_slice(),
+            ))
+            .insert(ShipUsageImageMarker)
+            .with_children(|parent| {
+                parent.spawn(ImageBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    image: UiImage::new(image.0.clone()),
+                    ..default()
+                });
+            });
     }
 }
 
-fn collide_bullets(
+fn pause_for_modal_dialogue(dialogue: Res<Dialogue>, mut state: ResMut<NextState<GameState>>) {
+    if dialogue.is_modal() {
+        state.set(GameState::Dialogue);
+    }
+}
+
+fn advance_modal_dialogue(
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    bindings: Res<InputBindings>,
+    mut dialogue: ResMut<Dialogue>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if inputs.just_pressed(bindings.key(Action::AdvanceDialogue))
+        || pads.just_pressed(GamepadButtonType::South)
+    {
+        dialogue.advance();
+    }
+    if !dialogue.is_modal() {
+        state.set(GameState::Regular);
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn collide_bullets(
     mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
     mut ships: Query<
         (Entity, &mut Spacecraft),
-        Without<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
+        (
+            Without<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
+            Without<ShieldedHull>,
+        ),
     >,
-    bullets: Query<(Entity, &Bullet)>,
-    mut score: ResMut<PlayerScore>,
+    mut bullets: Query<(Entity, &mut Bullet)>,
+    score: Res<PlayerScore>,
+    mut score_events: ResMut<Events<ScoreEvent>>,
+    mut status_events: EventWriter<ApplyStatus>,
+    mut damaged: EventWriter<ShipDamaged>,
+    mut spent: ResMut<SpentBullets>,
 ) {
+    spent.0.clear();
     for event in collision_events.read() {
         match event {
             CollisionEvent::Started(a, b, _) => {
@@ -944,8 +1968,38 @@ fn collide_bullets(
                                 }
                             }
                             if score.survived_time.elapsed_secs() > 3. {
-                                entity.insert(ExplosionMarker);
-                                if ship.collide(1, b_shotby_p, &mut score) {
+                                let mut damage_type = DamageType::Kinetic;
+                                let mut lucky = false;
+                                let mut attacker = (*b, DamageCause::Ram);
+                                if let Ok((_, bullet)) = bullets.get(*b) {
+                                    entity.insert(LastHitBy(bullet.shooter));
+                                    damage_type = bullet.damage_type;
+                                    attacker = (bullet.shooter, DamageCause::Weapon(damage_type));
+                                    lucky = b_shotby_p && bullet.lucky_shot();
+                                    if let Some(kind) = bullet.status {
+                                        status_events.send(ApplyStatus { target: *a, kind });
+                                    }
+                                } else {
+                                    // Bullet hits get a spark at the point of impact instead
+                                    entity.insert(ExplosionMarker);
+                                }
+                                let whole_hull = ship.health.max(1);
+                                let before = ship.health;
+                                let captured = match lucky {
+                                    true => ship.collide(whole_hull, b_shotby_p, &mut score_events),
+                                    false => {
+                                        ship.take_hit(1, damage_type, b_shotby_p, &mut score_events)
+                                    }
+                                };
+                                report_damage(
+                                    &mut damaged,
+                                    *a,
+                                    Some(attacker.0),
+                                    attacker.1,
+                                    before,
+                                    ship.health,
+                                );
+                                if captured {
                                     entity.insert(MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker);
                                 }
                             }
@@ -957,13 +2011,29 @@ fn collide_bullets(
                                 .map(|(e, s)| (s, &e == b))
                                 .find(|(_, x)| *x)
                             {
-                                s.collide(1, a_shotby_p, &mut score);
+                                let before = s.health;
+                                s.take_hit(1, DamageType::Kinetic, a_shotby_p, &mut score_events);
+                                report_damage(
+                                    &mut damaged,
+                                    *b,
+                                    Some(*a),
+                                    DamageCause::Ram,
+                                    before,
+                                    s.health,
+                                );
                             } else {
                                 println!(
                                     "Kill (theoretically) bullet in collision {:?}",
                                     b_shotby_p
                                 );
-                                entity.despawn();
+                                let pierced = bullets
+                                    .get_mut(*b)
+                                    .is_ok_and(|(_, mut bullet)| bullet.pierce());
+                                // Shielded hulls and subsystems are handled by the capital ship plugin
+                                if !pierced && bullets.contains(*b) {
+                                    entity.despawn();
+                                    spent.0.push(*b);
+                                }
                             }
                         }
                         return;
@@ -976,8 +2046,38 @@ fn collide_bullets(
                                 }
                             }
                             if score.survived_time.elapsed_secs() > 3. {
-                                entity.insert(ExplosionMarker);
-                                if ship.collide(1, a_shotby_p, &mut score) {
+                                let mut damage_type = DamageType::Kinetic;
+                                let mut lucky = false;
+                                let mut attacker = (*a, DamageCause::Ram);
+                                if let Ok((_, bullet)) = bullets.get(*a) {
+                                    entity.insert(LastHitBy(bullet.shooter));
+                                    damage_type = bullet.damage_type;
+                                    attacker = (bullet.shooter, DamageCause::Weapon(damage_type));
+                                    lucky = a_shotby_p && bullet.lucky_shot();
+                                    if let Some(kind) = bullet.status {
+                                        status_events.send(ApplyStatus { target: *b, kind });
+                                    }
+                                } else {
+                                    // Bullet hits get a spark at the point of impact instead
+                                    entity.insert(ExplosionMarker);
+                                }
+                                let whole_hull = ship.health.max(1);
+                                let before = ship.health;
+                                let captured = match lucky {
+                                    true => ship.collide(whole_hull, a_shotby_p, &mut score_events),
+                                    false => {
+                                        ship.take_hit(1, damage_type, a_shotby_p, &mut score_events)
+                                    }
+                                };
+                                report_damage(
+                                    &mut damaged,
+                                    *b,
+                                    Some(attacker.0),
+                                    attacker.1,
+                                    before,
+                                    ship.health,
+                                );
+                                if captured {
                                     entity.insert(MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker);
                                 }
                             }
@@ -989,13 +2089,29 @@ fn collide_bullets(
                                 .map(|(e, s)| (s, &e == b))
                                 .find(|(_, x)| *x)
                             {
-                                s.collide(1, a_shotby_p, &mut score);
+                                let before = s.health;
+                                s.take_hit(1, DamageType::Kinetic, a_shotby_p, &mut score_events);
+                                report_damage(
+                                    &mut damaged,
+                                    *b,
+                                    Some(*a),
+                                    DamageCause::Ram,
+                                    before,
+                                    s.health,
+                                );
                             } else {
                                 println!(
                                     "Kill (theoretically) bullet in collision {:?}",
                                     a_shotby_p
                                 );
-                                entity.despawn();
+                                let pierced = bullets
+                                    .get_mut(*a)
+                                    .is_ok_and(|(_, mut bullet)| bullet.pierce());
+                                // Shielded hulls and subsystems are handled by the capital ship plugin
+                                if !pierced && bullets.contains(*a) {
+                                    entity.despawn();
+                                    spent.0.push(*a);
+                                }
                             }
                         }
 
@@ -1040,15 +2156,27 @@ pub struct ExplosionBundle {
 #[derive(Component)]
 pub struct SoloExplosionMarker(Timer);
 
+#[allow(clippy::type_complexity)]
 fn neo_handle_explosions(
     mut commands: Commands,
     time: Res<Time>,
     explosion_ships: Query<(Entity, &Transform), (With<ExplosionMarker>, With<Spacecraft>)>,
     mut explosions: Query<(Entity, &mut TextureAtlas, &mut SoloExplosionMarker)>,
     assets: Res<NonfatalExplosionImages>,
+    camera: Query<&Transform, With<Camera2d>>,
+    window: Query<&Window>,
 ) {
+    let view = CameraView::find(&camera, &window);
+    let mut playing = explosions.iter().count();
     for (entity, transform) in explosion_ships.iter() {
-        let mut transform = transform.clone();
+        commands.entity(entity).remove::<ExplosionMarker>();
+        let position = transform.translation.truncate() / PIXELS_PER_UNIT;
+        // Past the cap, or where nobody will see it, the ship just goes
+        if playing >= MAX_EXPLOSIONS || !on_screen(view.as_ref(), position) {
+            continue;
+        }
+        playing += 1;
+        let mut transform = *transform;
         transform.translation.z = 30.;
         commands.spawn(ExplosionBundle {
             frame_time: SoloExplosionMarker(Timer::new(
@@ -1065,7 +2193,6 @@ fn neo_handle_explosions(
                 ..default()
             },
         });
-        commands.entity(entity).remove::<ExplosionMarker>();
     }
 
     for (entity, mut atlas, mut timer) in explosions.iter_mut() {
@@ -1081,50 +2208,300 @@ fn neo_handle_explosions(
 #[derive(Component)]
 pub struct ExplosionMarker;
 
+/// The ship that fired the last bullet to damage this one
+#[derive(Component)]
+pub struct LastHitBy(pub Entity);
+
+#[allow(clippy::type_complexity)]
 fn enforce_border(
     mut commands: Commands,
     mut player: Query<(Entity, &mut Spacecraft), (With<PlayerMarker>, Without<ExplosionMarker>)>,
     mut dialogue: ResMut<Dialogue>,
-    mut score: ResMut<PlayerScore>,
+    mut score_events: ResMut<Events<ScoreEvent>>,
+    mut damaged: EventWriter<ShipDamaged>,
 ) {
     if let Ok((entity, mut player)) = player.get_single_mut() {
-        let dist = player.position.distance(Vec2::new(0., 0.));
-        if dist >= 10. {
-            if dist >= 15. {
-                commands.entity(entity).insert(ExplosionMarker);
-                player.collide(100, false, &mut score);
+        match border_status(player.position) {
+            BorderStatus::Inside => dialogue.clear_warning(),
+            status => {
+                if status == BorderStatus::Beyond {
+                    commands.entity(entity).insert(ExplosionMarker);
+                    let before = player.health;
+                    player.collide(100, false, &mut score_events);
+                    report_damage(
+                        &mut damaged,
+                        entity,
+                        None,
+                        DamageCause::Border,
+                        before,
+                        player.health,
+                    );
+                }
+                dialogue.warn("Captain! If we go much further out, we'll explode.".to_string());
             }
-            dialogue.set_text("Captain! If we go much further out, we'll explode.".to_string());
-            dialogue.show();
-        } else {
-            dialogue.hide()
         }
     }
 }
 
-fn kill_dead_ships(
+pub const BORDER_WARNING_RADIUS: f32 = 10.;
+pub const BORDER_KILL_RADIUS: f32 = 15.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderStatus {
+    Inside,
+    Warning,
+    Beyond,
+}
+
+pub fn border_status(position: Vec2) -> BorderStatus {
+    match position.distance(Vec2::ZERO) {
+        dist if dist >= BORDER_KILL_RADIUS => BorderStatus::Beyond,
+        dist if dist >= BORDER_WARNING_RADIUS => BorderStatus::Warning,
+        _ => BorderStatus::Inside,
+    }
+}
+
+/// What the gameplay camera can see right now
+pub struct CameraView {
+    camera: Transform,
+    window_dimensions: Vec2,
+}
+
+impl CameraView {
+    pub fn new(camera: &Transform, window: &Window) -> Self {
+        Self {
+            camera: *camera,
+            window_dimensions: Vec2::new(window.width(), window.height()),
+        }
+    }
+
+    /// The view from the one gameplay camera, if there's a camera and a window to see it in
+    pub fn find(
+        camera: &Query<&Transform, With<Camera2d>>,
+        window: &Query<&Window>,
+    ) -> Option<Self> {
+        match (camera.get_single(), window.get_single()) {
+            (Ok(camera), Ok(window)) => Some(CameraView::new(camera, window)),
+            _ => None,
+        }
+    }
+
+    /// Whether a world position, padded by `margin` pixels, would be on screen
+    pub fn contains(&self, position: Vec2, margin: f32) -> bool {
+        let pixels = position * PIXELS_PER_UNIT;
+        let relative = (pixels - self.camera.translation.truncate()).extend(0.);
+        let local = self.camera.rotation.inverse() * relative;
+        let half_extents = self.window_dimensions / 2. * self.camera.scale.truncate();
+        local.x.abs() < half_extents.x + margin && local.y.abs() < half_extents.y + margin
+    }
+}
+
+/// Where the action is centred: the player's ship, or the ally being spectated after death
+pub fn focus_position(
+    player: &Query<&Spacecraft, With<PlayerMarker>>,
+    spectating: &Option<Res<Spectating>>,
+) -> Option<Vec2> {
+    match player.get_single() {
+        Ok(player) => Some(player.position),
+        Err(_) => spectating.as_ref().map(|s| s.position),
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn kill_dead_ships(
     mut commands: Commands,
     ships: Query<
-        (Entity, &Spacecraft),
+        (Entity, &Spacecraft, Has<Captured>, Option<&LastHitBy>),
         (
             Without<ExplosionMarker>,
             Without<PlayerMarker>,
             Without<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
         ),
     >,
-    player: Query<(Entity, &Spacecraft), (Without<ExplosionMarker>, With<PlayerMarker>)>,
-    mut state: ResMut<NextState<GameLifecycleState>>,
+    player: Query<
+        (Entity, &Spacecraft, Option<&LastHitBy>),
+        (Without<ExplosionMarker>, With<PlayerMarker>),
+    >,
+    death: Option<Res<DeathSequence>>,
+    spectating: Option<Res<Spectating>>,
+    settings: Res<Settings>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    score: Res<PlayerScore>,
+    mut stats: ResMut<RunStats>,
+    mut destroyed: EventWriter<ShipDestroyed>,
 ) {
-    if let Ok((entity, player)) = player.get_single() {
-        for (entity, ship) in ships.iter() {
-            if ship.health <= 0 || ship.position.distance(player.position) >= 10. {
+    let focus = match player.get_single() {
+        Ok((_, player, _)) => Some(player.position),
+        Err(_) => spectating.map(|s| s.position),
+    };
+    if let Some(focus) = focus {
+        for (entity, ship, is_ally, last_hit) in ships.iter() {
+            if ship.health <= 0 {
+                destroyed.send(ShipDestroyed {
+                    ship: entity,
+                    ship_type: ship.ship_type,
+                    allegiance: match is_ally {
+                        true => Allegiance::Ally,
+                        false => Allegiance::Enemy,
+                    },
+                    position: ship.position,
+                    killer: last_hit.map(|hit| hit.0),
+                });
+                let event = match is_ally {
+                    true => TimelineEvent::AllyDestroyed {
+                        ship: ship.ship_type,
+                    },
+                    false => TimelineEvent::EnemyDestroyed {
+                        ship: ship.ship_type,
+                    },
+                };
+                stats.record(&score, event);
+            }
+            if ship.health <= 0 || ship.position.distance(focus) >= 10. {
                 commands.entity(entity).despawn_recursive();
             }
         }
-        if player.health <= 0 {
+    }
+    if let Ok((entity, player, last_hit)) = player.get_single() {
+        if player.health <= 0 && death.is_none() {
             println!("Kill player when dead");
-            commands.entity(entity).despawn();
-            state.set(GameLifecycleState::EndScreen);
+            destroyed.send(ShipDestroyed {
+                ship: entity,
+                ship_type: player.ship_type,
+                allegiance: Allegiance::Player,
+                position: player.position,
+                killer: last_hit.map(|hit| hit.0),
+            });
+            stats.record(
+                &score,
+                TimelineEvent::PlayerDied {
+                    ship: player.ship_type,
+                },
+            );
+            commands.entity(entity).insert(ExplosionMarker);
+            commands.insert_resource(DeathSequence {
+                timer: Timer::new(DEATH_SEQUENCE_TIME, TimerMode::Once),
+                explosion_timer: Timer::new(Duration::from_millis(400), TimerMode::Repeating),
+                start_zoom: settings.camera_zoom,
+            });
+            virtual_time.set_relative_speed(DEATH_TIME_SCALE);
+        }
+    }
+}
+
+/// Sends [`NearDeathEscape`] when the ship the player is flying climbs back off its last point of
+/// hull. Taking over a healthier ship leaves the wounded one behind, which is no escape.
+pub fn watch_for_near_death_escapes(
+    mut on_last_hit: Local<Option<Entity>>,
+    player: Query<(Entity, &Spacecraft), With<PlayerMarker>>,
+    mut escapes: EventWriter<NearDeathEscape>,
+) {
+    let Ok((entity, ship)) = player.get_single() else {
+        *on_last_hit = None;
+        return;
+    };
+    if ship.health <= 1 {
+        *on_last_hit = Some(entity);
+    } else if let Some(wounded) = on_last_hit.take() {
+        if wounded == entity {
+            escapes.send(NearDeathEscape);
+        }
+    }
+}
+
+/// Slow motion zoom onto the player's wreck, played before switching to the end screen
+#[derive(Resource)]
+pub struct DeathSequence {
+    timer: Timer,
+    explosion_timer: Timer,
+    start_zoom: f32,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_death_sequence(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    death: Option<ResMut<DeathSequence>>,
+    player: Query<Entity, With<PlayerMarker>>,
+    allies: Query<(), With<Captured>>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+    mut hud_opacity: ResMut<HudOpacity>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+) {
+    if let Some(mut death) = death {
+        death.timer.tick(real_time.delta());
+        death.explosion_timer.tick(real_time.delta());
+        let progress = death.timer.fraction();
+        if let Ok(mut camera) = camera.get_single_mut() {
+            let zoom = death.start_zoom + (DEATH_ZOOM - death.start_zoom) * progress;
+            camera.scale.x = zoom;
+            camera.scale.y = zoom;
+        }
+        hud_opacity.0 = 1. - progress;
+        if let Ok(entity) = player.get_single() {
+            if death.explosion_timer.just_finished() {
+                commands.entity(entity).insert(ExplosionMarker);
+            }
+            if death.timer.finished() {
+                commands.entity(entity).despawn_recursive();
+                if allies.is_empty() {
+                    state.set(GameLifecycleState::EndScreen);
+                } else {
+                    commands.remove_resource::<DeathSequence>();
+                    commands.insert_resource(SpectateOffer);
+                    virtual_time.set_relative_speed(1.);
+                }
+            }
+        }
+    }
+}
+
+fn end_death_sequence(
+    mut commands: Commands,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut queue: ResMut<CaptureQueue>,
+) {
+    commands.remove_resource::<DeathSequence>();
+    commands.remove_resource::<CaptureMoment>();
+    queue.0.clear();
+    virtual_time.set_relative_speed(1.);
+}
+
+/// Ramps the game back up to speed after a capture, or calls it off if the player died deciding
+#[allow(clippy::too_many_arguments)]
+fn recover_from_capture_moment(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    capture: Option<ResMut<CaptureMoment>>,
+    death: Option<Res<DeathSequence>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    undecided: Query<
+        Entity,
+        With<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
+    >,
+    image: Query<Entity, With<ShipUsageImageMarker>>,
+    mut queue: ResMut<CaptureQueue>,
+) {
+    if let Some(mut capture) = capture {
+        if death.is_some() {
+            // The death sequence has its own slow motion, and nobody is left to make the call
+            for entity in undecided.iter() {
+                commands.entity(entity).remove::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>();
+            }
+            for entity in image.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            commands.remove_resource::<CaptureMoment>();
+            queue.0.clear();
+        } else if let CaptureMoment::Recovering(timer) = &mut *capture {
+            timer.tick(real_time.delta());
+            virtual_time.set_relative_speed(
+                CAPTURE_TIME_SCALE + (1. - CAPTURE_TIME_SCALE) * timer.fraction(),
+            );
+            if timer.finished() {
+                commands.remove_resource::<CaptureMoment>();
+            }
         }
     }
 }
@@ -1133,10 +2510,11 @@ fn kill_far_bullets(
     mut commands: Commands,
     bullets: Query<(Entity, &Bullet)>,
     player_pos: Query<&Spacecraft, With<PlayerMarker>>,
+    spectating: Option<Res<Spectating>>,
 ) {
-    if let Ok(player) = player_pos.get_single() {
+    if let Some(focus) = focus_position(&player_pos, &spectating) {
         for (entity, bullet) in bullets.iter() {
-            if bullet.position.distance(player.position) > 2. {
+            if bullet.position.distance(focus) > 2. {
                 commands.entity(entity).despawn_recursive();
             }
         }
@@ -1146,32 +2524,82 @@ fn kill_far_bullets(
 #[derive(Component)]
 pub struct SwapToShipMarker;
 
+/// Controls come in gradually on a freshly swapped-into ship, which carries on as it was flying
+#[derive(Component)]
+pub struct ControlHandoff(Timer);
+
+impl ControlHandoff {
+    pub fn new() -> Self {
+        Self(Timer::new(HANDOFF_TIME, TimerMode::Once))
+    }
+
+    /// How much of the pilot's input gets through, from 0 to 1
+    pub fn authority(&self) -> f32 {
+        self.0.fraction()
+    }
+}
+
+impl Default for ControlHandoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The camera swinging over from the old ship to the new one
+#[derive(Resource)]
+pub struct CameraTransition {
+    rotation: Quat,
+    translation: Vec2,
+    timer: Timer,
+}
+
+fn tick_control_handoff(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut handoffs: Query<(Entity, &mut ControlHandoff)>,
+) {
+    for (entity, mut handoff) in handoffs.iter_mut() {
+        if handoff.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<ControlHandoff>();
+        }
+    }
+}
+
 fn swap_ships(
     mut commands: Commands,
     swap_from: Query<Entity, With<PlayerMarker>>,
-    mut swap_to: Query<(Entity, &Transform, &mut Spacecraft), With<SwapToShipMarker>>,
-    mut camera: Query<&mut Transform, (With<Camera2d>, Without<Spacecraft>)>,
+    swap_to: Query<(Entity, &Spacecraft), With<SwapToShipMarker>>,
+    camera: Query<&Transform, With<Camera2d>>,
+    mut swapped: EventWriter<ShipSwapped>,
 ) {
-    if let Ok((dest_entity, dest_transform, _dest_spacecraft)) = swap_to.get_single_mut() {
+    if let Ok((dest_entity, dest_spacecraft)) = swap_to.get_single() {
         if let Ok(curr_entity) = swap_from.get_single() {
+            swapped.send(ShipSwapped {
+                from: curr_entity,
+                to: dest_entity,
+                ship_type: dest_spacecraft.ship_type,
+            });
             commands
                 .entity(curr_entity)
                 .remove::<PlayerMarker>()
-                .insert(NPCLogic(Vec2::ZERO));
+                .insert(NPCLogic::new(Vec2::ZERO));
             commands
                 .entity(dest_entity)
                 .remove::<NPCLogic>()
                 .remove::<SwapToShipMarker>()
-                .insert(PlayerMarker);
+                .insert((PlayerMarker, ControlHandoff::new()));
         }
-        if let Ok(mut cam_pos) = camera.get_single_mut() {
-            cam_pos.rotation = dest_transform.rotation;
-            cam_pos.rotate_z(-3. * PI / 2.)
+        if let Ok(camera) = camera.get_single() {
+            commands.insert_resource(CameraTransition {
+                rotation: camera.rotation,
+                translation: camera.translation.truncate(),
+                timer: Timer::new(HANDOFF_TIME, TimerMode::Once),
+            });
         }
     }
 }
 
-#[derive(Clone, Component, Copy, Debug, Reflect)]
+#[derive(Clone, Component, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 pub enum ShipType {
     Ship1,
     Ship2,
@@ -1179,30 +2607,146 @@ pub enum ShipType {
     Ship4,
     Ship5,
     Ship6,
+    /// Slow and lightly armed, but launches drones that fight for its side
+    Carrier,
+    Drone,
+    /// Stationary gun the player can deploy
+    Turret,
+    /// Huge and slow, with subsystems that have to be shot off before it can be taken
+    Capital,
+    /// Barely armed, but shields the ships around it from part of every hit
+    Projector,
 }
 
 impl ShipType {
+    /// Carriers, drones, turrets, capital ships and projectors borrow another hull's art, tinted so
+    /// they read differently
+    pub fn tint(&self) -> Color {
+        match self {
+            ShipType::Carrier => Color::rgb(0.6, 0.7, 1.),
+            ShipType::Drone => Color::rgb(1., 0.75, 0.5),
+            ShipType::Turret => Color::rgb(0.7, 0.9, 0.7),
+            ShipType::Capital => Color::rgb(0.55, 0.55, 0.65),
+            ShipType::Projector => Color::rgb(0.5, 0.9, 1.),
+            _ => Color::WHITE,
+        }
+    }
+
+    /// Rough weight class, in line with [`points_for_ship`]. The numbered hulls are their own tier.
+    pub fn tier(&self) -> u8 {
+        match self {
+            ShipType::Drone => 0,
+            ShipType::Ship1 | ShipType::Turret => 1,
+            ShipType::Ship2 => 2,
+            ShipType::Ship3 => 3,
+            ShipType::Ship4 | ShipType::Projector => 4,
+            ShipType::Ship5 | ShipType::Carrier => 5,
+            ShipType::Ship6 => 6,
+            ShipType::Capital => 7,
+        }
+    }
+
     pub fn collider(&self) -> ColliderBundle {
         ColliderBundle {
             collider: Collider::cuboid(40., 20.),
             events: ActiveEvents::all(),
             hooks: ActiveHooks::all(),
             types: ActiveCollisionTypes::STATIC_STATIC,
+            groups: CollisionGroups::new(SHIP_GROUP, Group::ALL),
         }
     }
 }
 
 pub struct ShipProfile {
     pub max_health: i32,
-    max_velocity: f32,
-    shield_recharge_time: Duration,
-    gun_reload_time: Duration,
-    shots: i32,
-    base_bullet_velocity: f32,
-    relative_scale: f32,
+    pub max_velocity: f32,
+    pub shield_recharge_time: Duration,
+    pub gun_reload_time: Duration,
+    pub shots: i32,
+    pub base_bullet_velocity: f32,
+    pub relative_scale: f32,
+    /// Multiplier on how quickly the pilot can turn the ship
+    pub turn_rate: f32,
+    /// Multiplier on the gap between guns firing side by side
+    pub spread: f32,
+    pub mounts: WeaponMounts,
+    pub hull_trait: Option<HullTrait>,
+    pub armor: Armor,
+    /// What the ship's guns deal
+    pub damage_type: DamageType,
+    /// Effect the ship's shots put on whatever they hit
+    pub on_hit: Option<StatusKind>,
 }
 
+/// Where a hull's guns sit on its sprite, in world units for a ship at a relative scale of 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WeaponMounts {
+    /// How far ahead of the middle the centreline gun sits, used when firing one or three shots
+    pub nose: f32,
+    /// The right-hand gun of the pair either side of the nose, with x across and y forward.
+    /// The left-hand one mirrors it.
+    pub wing: Vec2,
+}
+
+/// Distance ahead of a ship that shots from guns either side of its nose cross, whatever its size
+pub const GUN_CONVERGENCE: f32 = 0.8;
+/// Where a salvaged gun pod is slung, under the right wing
+pub const GUN_POD_MOUNT: Vec2 = Vec2::new(0.06, 0.05);
+
+/// Built-in quirks that set each hull apart beyond its raw stats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HullTrait {
+    /// Shots that don't disable a ship still have a [`SALVAGER_CAPTURE_CHANCE`] to capture it
+    Salvager,
+    /// Shields keep recharging up to [`SLOW_CHARGE_SPEED`], rather than [`RECHARGE_SPEED`]
+    SlowCharge,
+    /// Bullets carry on through the first ship they hit
+    Piercing,
+    /// Allies within [`COMMAND_RADIUS`] reload faster
+    Command,
+}
+
+pub const SALVAGER_CAPTURE_CHANCE: f64 = 0.2;
+/// Fraction of top speed a ship can be doing and still have its shield recharge
+pub const RECHARGE_SPEED: f32 = 0.1;
+/// Fraction of top speed a [`HullTrait::SlowCharge`] ship can keep while recharging
+pub const SLOW_CHARGE_SPEED: f32 = 0.3;
+pub const COMMAND_RADIUS: f32 = 1.;
+/// Extra reload progress for ships under command, as a fraction of real time
+pub const COMMAND_RELOAD_BONUS: f32 = 0.5;
+
 impl ShipProfile {
+    /// Where each shot starts relative to the ship, with x across and y forward
+    pub fn gun_positions(&self) -> Vec<Vec2> {
+        let nose = Vec2::new(0., self.mounts.nose);
+        let wing = self.mounts.wing * Vec2::new(self.spread, 1.);
+        let guns = match self.shots {
+            1 => vec![nose],
+            2 => vec![wing * Vec2::new(-1., 1.), wing],
+            _ => vec![wing * Vec2::new(-1., 1.), nose, wing],
+        };
+        guns.into_iter()
+            .map(|gun| gun * self.relative_scale)
+            .collect()
+    }
+
+    /// Fastest the ship can go without its shield recharge stalling
+    pub fn recharge_speed(&self) -> f32 {
+        let fraction = match self.hull_trait {
+            Some(HullTrait::SlowCharge) => SLOW_CHARGE_SPEED,
+            _ => RECHARGE_SPEED,
+        };
+        self.max_velocity * fraction
+    }
+
+    pub fn of(ship_type: ShipType, variant: Option<ShipVariant>) -> Self {
+        let mut profile = Self::from_type(ship_type);
+        if let Some(variant) = variant {
+            variant.apply(&mut profile);
+        }
+        profile
+    }
+
     pub fn from_type(ship_type: ShipType) -> Self {
         match ship_type {
             ShipType::Ship1 => ShipProfile {
@@ -1213,6 +2757,16 @@ impl ShipProfile {
                 shots: 1,
                 base_bullet_velocity: BULLET_SPEED,
                 relative_scale: 1.,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.12,
+                    wing: Vec2::new(0.03, 0.1),
+                },
+                hull_trait: Some(HullTrait::Salvager),
+                armor: Armor::Light,
+                damage_type: DamageType::Kinetic,
+                on_hit: None,
             },
             ShipType::Ship2 => ShipProfile {
                 max_health: 3,
@@ -1222,6 +2776,16 @@ impl ShipProfile {
                 shots: 2,
                 base_bullet_velocity: BULLET_SPEED * 0.9,
                 relative_scale: 1.2,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.11,
+                    wing: Vec2::new(0.025, 0.1),
+                },
+                hull_trait: Some(HullTrait::SlowCharge),
+                armor: Armor::Light,
+                damage_type: DamageType::Kinetic,
+                on_hit: None,
             },
             ShipType::Ship3 => ShipProfile {
                 max_health: 5,
@@ -1231,6 +2795,16 @@ impl ShipProfile {
                 shots: 1,
                 base_bullet_velocity: BULLET_SPEED * 1.3,
                 relative_scale: 1.4,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.12,
+                    wing: Vec2::new(0.03, 0.1),
+                },
+                hull_trait: None,
+                armor: Armor::Light,
+                damage_type: DamageType::Energy,
+                on_hit: None,
             },
             ShipType::Ship4 => ShipProfile {
                 max_health: 6,
@@ -1240,6 +2814,16 @@ impl ShipProfile {
                 shots: 3,
                 base_bullet_velocity: BULLET_SPEED * 1.,
                 relative_scale: 1.6,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.12,
+                    wing: Vec2::new(0.03, 0.08),
+                },
+                hull_trait: None,
+                armor: Armor::Medium,
+                damage_type: DamageType::Kinetic,
+                on_hit: None,
             },
             ShipType::Ship5 => ShipProfile {
                 max_health: 7,
@@ -1249,6 +2833,16 @@ impl ShipProfile {
                 shots: 1,
                 base_bullet_velocity: BULLET_SPEED * 3.,
                 relative_scale: 1.8,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.13,
+                    wing: Vec2::new(0.03, 0.1),
+                },
+                hull_trait: Some(HullTrait::Piercing),
+                armor: Armor::Medium,
+                damage_type: DamageType::Explosive,
+                on_hit: Some(StatusKind::Burning),
             },
             ShipType::Ship6 => ShipProfile {
                 max_health: 10,
@@ -1258,11 +2852,190 @@ impl ShipProfile {
                 shots: 3,
                 base_bullet_velocity: BULLET_SPEED * 2.,
                 relative_scale: 2.4,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.12,
+                    wing: Vec2::new(0.02, 0.09),
+                },
+                hull_trait: Some(HullTrait::Command),
+                armor: Armor::Heavy,
+                damage_type: DamageType::Energy,
+                on_hit: Some(StatusKind::Slowed),
+            },
+            ShipType::Carrier => ShipProfile {
+                max_health: 12,
+                max_velocity: MAX_VELOCITY * 0.8,
+                shield_recharge_time: Duration::from_secs(3),
+                gun_reload_time: Duration::from_millis(2000),
+                shots: 1,
+                base_bullet_velocity: BULLET_SPEED * 0.8,
+                relative_scale: 2.8,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.1,
+                    wing: Vec2::new(0.03, 0.06),
+                },
+                hull_trait: None,
+                armor: Armor::Heavy,
+                damage_type: DamageType::Explosive,
+                on_hit: Some(StatusKind::WeaponJammed),
+            },
+            ShipType::Drone => ShipProfile {
+                max_health: 1,
+                max_velocity: MAX_VELOCITY * 2.5,
+                shield_recharge_time: Duration::from_secs(4),
+                gun_reload_time: Duration::from_millis(900),
+                shots: 1,
+                base_bullet_velocity: BULLET_SPEED,
+                relative_scale: 0.5,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.1,
+                    wing: Vec2::new(0.03, 0.08),
+                },
+                hull_trait: None,
+                armor: Armor::Light,
+                damage_type: DamageType::Energy,
+                on_hit: Some(StatusKind::Stunned),
+            },
+            ShipType::Turret => ShipProfile {
+                max_health: 3,
+                max_velocity: 0.,
+                shield_recharge_time: Duration::from_secs(4),
+                gun_reload_time: Duration::from_millis(800),
+                shots: 1,
+                base_bullet_velocity: BULLET_SPEED * 1.2,
+                relative_scale: 0.8,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.1,
+                    wing: Vec2::new(0.03, 0.08),
+                },
+                hull_trait: None,
+                armor: Armor::Medium,
+                damage_type: DamageType::Kinetic,
+                on_hit: None,
+            },
+            ShipType::Capital => ShipProfile {
+                max_health: 16,
+                max_velocity: MAX_VELOCITY * 0.5,
+                shield_recharge_time: Duration::from_secs(5),
+                gun_reload_time: Duration::from_millis(2500),
+                shots: 1,
+                base_bullet_velocity: BULLET_SPEED * 0.8,
+                relative_scale: 5.,
+                turn_rate: 0.5,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.04,
+                    wing: Vec2::new(0.015, 0.02),
+                },
+                hull_trait: None,
+                armor: Armor::Heavy,
+                damage_type: DamageType::Explosive,
+                on_hit: None,
+            },
+            ShipType::Projector => ShipProfile {
+                max_health: 6,
+                max_velocity: MAX_VELOCITY * 0.9,
+                shield_recharge_time: Duration::from_secs(4),
+                gun_reload_time: Duration::from_millis(2400),
+                shots: 1,
+                base_bullet_velocity: BULLET_SPEED * 0.8,
+                relative_scale: 1.6,
+                turn_rate: 1.,
+                spread: 1.,
+                mounts: WeaponMounts {
+                    nose: 0.1,
+                    wing: Vec2::new(0.03, 0.07),
+                },
+                hull_trait: None,
+                armor: Armor::Medium,
+                damage_type: DamageType::Energy,
+                on_hit: None,
             },
         }
     }
 }
 
+/// Tinted reworks of a base hull with tweaked stats, adding variety without new art
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum ShipVariant {
+    /// Faster and quicker to reload, but more fragile
+    Interceptor,
+    /// An extra gun and a little more armour, at the cost of speed
+    Gunboat,
+    /// Heavily armoured and slow
+    Juggernaut,
+}
+
+/// Chance that a freshly spawned enemy is a variant rather than the stock hull
+pub const VARIANT_CHANCE: f64 = 0.25;
+
+impl ShipVariant {
+    pub fn name(self) -> &'static str {
+        match self {
+            ShipVariant::Interceptor => "Interceptor",
+            ShipVariant::Gunboat => "Gunboat",
+            ShipVariant::Juggernaut => "Juggernaut",
+        }
+    }
+
+    pub fn tint(self) -> Color {
+        match self {
+            ShipVariant::Interceptor => Color::rgb(0.7, 1., 0.8),
+            ShipVariant::Gunboat => Color::rgb(1., 0.8, 0.6),
+            ShipVariant::Juggernaut => Color::rgb(1., 0.6, 0.6),
+        }
+    }
+
+    /// The variants each hull can come in
+    pub fn for_hull(ship_type: ShipType) -> &'static [ShipVariant] {
+        match ship_type {
+            ShipType::Ship2 | ShipType::Ship3 => &[ShipVariant::Interceptor, ShipVariant::Gunboat],
+            ShipType::Ship4 | ShipType::Ship5 => &[
+                ShipVariant::Interceptor,
+                ShipVariant::Gunboat,
+                ShipVariant::Juggernaut,
+            ],
+            ShipType::Ship6 => &[ShipVariant::Juggernaut],
+            _ => &[],
+        }
+    }
+
+    pub fn roll(ship_type: ShipType, rand: &mut impl Rng) -> Option<ShipVariant> {
+        match rand.gen_bool(VARIANT_CHANCE) {
+            true => Self::for_hull(ship_type).choose(rand).copied(),
+            false => None,
+        }
+    }
+
+    fn apply(self, profile: &mut ShipProfile) {
+        match self {
+            ShipVariant::Interceptor => {
+                profile.max_health = (profile.max_health - 1).max(1);
+                profile.max_velocity *= 1.3;
+                profile.gun_reload_time = profile.gun_reload_time.mul_f32(0.8);
+            }
+            ShipVariant::Gunboat => {
+                profile.max_health += 1;
+                profile.max_velocity *= 0.8;
+                profile.shots = (profile.shots + 1).min(3);
+            }
+            ShipVariant::Juggernaut => {
+                profile.max_health = profile.max_health * 3 / 2;
+                profile.max_velocity *= 0.7;
+                profile.armor = profile.armor.heavier();
+                profile.relative_scale *= 1.15;
+            }
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct ShipTextures {
     ship_one: Handle<Image>,
@@ -1288,34 +3061,62 @@ impl ShipTextures {
             ShipType::Ship4 => self.ship_four.clone(),
             ShipType::Ship5 => self.ship_five.clone(),
             ShipType::Ship6 => self.ship_six.clone(),
+            ShipType::Carrier => self.ship_six.clone(),
+            ShipType::Drone => self.ship_one.clone(),
+            ShipType::Turret => self.ship_three.clone(),
+            ShipType::Capital => self.ship_six.clone(),
+            ShipType::Projector => self.ship_four.clone(),
         }
     }
 }
 
+/// Spawn points saved up towards the next enemy
 #[derive(Resource)]
-pub struct CarryoverEnemyPoints(i32);
+pub struct CarryoverEnemyPoints(pub i32);
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_ships(
     mut commands: Commands,
     enemies: Query<&Spacecraft, (Without<PlayerMarker>, Without<Captured>)>,
+    warping: Query<&WarpIn>,
     player: Query<&Spacecraft, With<PlayerMarker>>,
+    spectating: Option<Res<Spectating>>,
     score: Res<PlayerScore>,
     mut spawn_points: ResMut<CarryoverEnemyPoints>,
-    textures: Res<ShipTextures>,
+    portal_texture: Res<WarpPortalTexture>,
+    camera: Query<&Transform, With<Camera2d>>,
+    window: Query<&Window>,
+    mut stats: ResMut<RunStats>,
+    run: Res<RunConfig>,
+    pacing: Res<SpawnPacingHandle>,
+    pacings: Res<Assets<SpawnPacing>>,
+    mut seed: ResMut<RunSeed>,
 ) {
-    if let Ok(player) = player.get_single() {
-        spawn_points.0 += ((0.4 * player.position.distance(Vec2::new(0., 0.))
-            + (score.survived_time.elapsed().as_secs_f32() / 20.)
-            + (score.score as f32 / 50.))
-            * 0.25)
-            .ceil() as i32
-            - points_currently_deployed(enemies.iter().map(|s| &s.ship_type).collect::<Vec<_>>());
+    let default_curve = SpawnCurve::default();
+    let curve = pacings
+        .get(&pacing.0)
+        .map(|p| p.curve(run.difficulty))
+        .unwrap_or(&default_curve);
+    let view = CameraView::find(&camera, &window);
+    if let Some(focus) = focus_position(&player, &spectating) {
+        spawn_points.0 += curve.points_earned(
+            focus.distance(Vec2::new(0., 0.)),
+            score.survived_time.elapsed().as_secs_f32(),
+            score.score,
+        ) - points_currently_deployed(deployed_ship_types(&enemies, &warping));
         loop {
-            let next_ship =
-                take_ship_stock(enemies.iter().map(|s| &s.ship_type).collect::<Vec<_>>());
+            let next_ship = take_ship_stock(deployed_ship_types(&enemies, &warping));
             let points_req = points_for_ship(&next_ship);
             if spawn_points.0 > points_req {
-                spawn_enemy(&mut commands, player.position, next_ship, &textures);
+                spawn_enemy(
+                    &mut commands,
+                    focus,
+                    next_ship,
+                    &portal_texture,
+                    view.as_ref(),
+                    seed.rng(),
+                );
+                stats.record(&score, TimelineEvent::EnemySpawned { ship: next_ship });
                 spawn_points.0 -= points_req;
             } else {
                 break;
@@ -1324,11 +3125,23 @@ pub fn spawn_ships(
     }
 }
 
+/// Enemies already out, including the ones still warping in
+fn deployed_ship_types<'a>(
+    enemies: &'a Query<&Spacecraft, (Without<PlayerMarker>, Without<Captured>)>,
+    warping: &'a Query<&WarpIn>,
+) -> Vec<&'a ShipType> {
+    enemies
+        .iter()
+        .map(|s| &s.ship_type)
+        .chain(warping.iter().map(|w| &w.ship_type))
+        .collect()
+}
+
 fn points_currently_deployed(ships: Vec<&ShipType>) -> i32 {
-    ships.iter().map(|s| points_for_ship(s)).count() as i32
+    ships.len() as i32
 }
 
-fn points_for_ship(ship: &ShipType) -> i32 {
+pub fn points_for_ship(ship: &ShipType) -> i32 {
     match ship {
         ShipType::Ship1 => 4,
         ShipType::Ship2 => 7,
@@ -1336,11 +3149,19 @@ fn points_for_ship(ship: &ShipType) -> i32 {
         ShipType::Ship4 => 21,
         ShipType::Ship5 => 31,
         ShipType::Ship6 => 50,
+        ShipType::Carrier => 40,
+        ShipType::Drone => 2,
+        ShipType::Turret => 5,
+        ShipType::Capital => 90,
+        ShipType::Projector => 30,
     }
 }
 
-fn take_ship_stock(ships: Vec<&ShipType>) -> ShipType {
+pub fn take_ship_stock(ships: Vec<&ShipType>) -> ShipType {
     let (mut t1, mut t2, mut t3, mut t4, mut t5, mut t6) = (0., 0., 0., 0., 0., 0.);
+    let mut tc = 0.;
+    let mut tcap = 0.;
+    let mut tp = 0.;
     for ship in ships.iter() {
         match ship {
             ShipType::Ship1 => t1 += 1.,
@@ -1349,6 +3170,10 @@ fn take_ship_stock(ships: Vec<&ShipType>) -> ShipType {
             ShipType::Ship4 => t3 += 1.,
             ShipType::Ship5 => t4 += 1.,
             ShipType::Ship6 => t5 += 1.,
+            ShipType::Carrier => tc += 1.,
+            ShipType::Capital => tcap += 1.,
+            ShipType::Projector => tp += 1.,
+            ShipType::Drone | ShipType::Turret => (),
         };
     }
     let count = ships.len() as f32;
@@ -1358,12 +3183,18 @@ fn take_ship_stock(ships: Vec<&ShipType>) -> ShipType {
     t4 /= count;
     t5 /= count;
     t6 /= count;
+    tc /= count;
+    tcap /= count;
+    tp /= count;
     t1 -= 0.44;
     t2 -= 0.25;
     t3 -= 0.15;
     t4 -= 0.1;
     t5 -= 0.05;
     t6 -= 0.01;
+    tc -= 0.03;
+    tcap -= 0.01;
+    tp -= 0.03;
     let mut types = [
         (ShipType::Ship1, t1),
         (ShipType::Ship2, t2),
@@ -1371,16 +3202,51 @@ fn take_ship_stock(ships: Vec<&ShipType>) -> ShipType {
         (ShipType::Ship4, t4),
         (ShipType::Ship5, t5),
         (ShipType::Ship6, t6),
+        (ShipType::Carrier, tc),
+        (ShipType::Capital, tcap),
+        (ShipType::Projector, tp),
     ];
     types.sort_by(|(_, a), (_, b)| a.total_cmp(b));
     types[0].0
 }
 
-fn update_score(time: Res<Time>, mut score: ResMut<PlayerScore>) {
+/// Enemies this close to the player count towards the danger it's surviving
+pub const THREAT_RANGE: f32 = 2.;
+/// Survival points for an interval with no enemies around
+pub const SURVIVAL_BASE_POINTS: u32 = 1;
+/// Enemy point value kept nearby for a whole interval that earns one more survival point
+const THREAT_PER_POINT: f32 = 4.;
+
+/// Survival points for an interval of `interval` seconds, given the
+/// [`PlayerScore::threat_exposure`] built up over it
+pub fn survival_points(threat_exposure: f32, interval: f32) -> u32 {
+    SURVIVAL_BASE_POINTS + (threat_exposure / interval / THREAT_PER_POINT).round() as u32
+}
+
+pub fn update_score(
+    time: Res<Time>,
+    mut score: ResMut<PlayerScore>,
+    mut score_events: EventWriter<ScoreEvent>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    enemies: Query<&Spacecraft, (Without<PlayerMarker>, Without<Captured>)>,
+) {
+    if let Ok(player) = player.get_single() {
+        let threat = enemies
+            .iter()
+            .filter(|enemy| enemy.position.distance(player.position) < THREAT_RANGE)
+            .map(|enemy| points_for_ship(&enemy.ship_type))
+            .sum::<i32>();
+        score.threat_exposure += threat as f32 * time.delta_seconds();
+    }
     score.add_score_timer.tick(time.delta());
     score.survived_time.tick(time.delta());
     if score.add_score_timer.just_finished() {
-        score.score += 5;
+        let interval = score.add_score_timer.duration().as_secs_f32();
+        score_events.send(ScoreEvent {
+            source: ScoreSource::Survival,
+            points: survival_points(score.threat_exposure, interval),
+        });
+        score.threat_exposure = 0.;
         score.add_score_timer.reset();
     }
 }
@@ -1390,6 +3256,11 @@ pub struct PlayerScore {
     pub score: u32,
     pub add_score_timer: Timer,
     pub survived_time: Stopwatch,
+    /// Point value of nearby enemies multiplied by seconds spent near them, since the last survival
+    /// points were paid out
+    pub threat_exposure: f32,
+    /// Salvage picked up from wrecks this run
+    pub scrap: u32,
 }
 
 fn tick_bullet_immunity_time(time: Res<Time>, mut bullets: Query<&mut Bullet>) {
@@ -1397,3 +3268,774 @@ fn tick_bullet_immunity_time(time: Res<Time>, mut bullets: Query<&mut Bullet>) {
         b.immunity_time.tick(time.delta());
     })
 }
+
+/// Fixtures shared by the tests across the crate
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use crate::score::{ScoreBreakdown, ScoreMultipliers};
+    use bevy::{
+        input::{
+            gamepad::{GamepadAxis, GamepadButton, Gamepads},
+            Axis,
+        },
+        time::TimeUpdateStrategy,
+        MinimalPlugins,
+    };
+
+    /// An app stepping exactly one second of game time per update
+    pub fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(1)))
+            .insert_state(GameState::Regular)
+            .insert_resource(PlayerScore {
+                score: 0,
+                add_score_timer: Timer::new(Duration::from_secs(10), TimerMode::Repeating),
+                survived_time: Stopwatch::new(),
+                threat_exposure: 0.,
+                scrap: 0,
+            })
+            .insert_resource(RunStats::default())
+            .add_event::<ScoreEvent>()
+            .add_event::<ShipDestroyed>()
+            .add_event::<ShipCaptured>()
+            .add_event::<ShipSwapped>()
+            .add_event::<ShipDamaged>()
+            .add_event::<ShotFired>()
+            .add_event::<ShieldRechargeStarted>()
+            .add_event::<ShieldRechargeFinished>()
+            .add_event::<BossArrived>()
+            .add_event::<NearDeathEscape>()
+            .add_event::<HighScoreBeaten>()
+            .insert_resource(ScoreBreakdown::default())
+            .insert_resource(ScoreMultipliers::default())
+            .insert_resource(PausedWhatToDoImage(Handle::default()))
+            .init_resource::<PanelAssets>()
+            .init_resource::<RunUpgrades>()
+            .insert_resource(AllyTexture(Handle::default()))
+            .insert_resource(BulletTexture(Handle::default()))
+            .insert_resource(WarpPortalTexture(Handle::default()))
+            .insert_resource(DelayedPlayerLocation {
+                buffered_locations: vec![],
+                current_location: Vec2::ZERO,
+            })
+            .insert_resource(ShipTextures {
+                ship_one: Handle::default(),
+                ship_two: Handle::default(),
+                ship_three: Handle::default(),
+                ship_four: Handle::default(),
+                ship_five: Handle::default(),
+                ship_six: Handle::default(),
+            })
+            .init_resource::<CaptureQueue>()
+            .init_resource::<SpentBullets>()
+            .init_resource::<RunConfig>()
+            .init_resource::<Gamepads>()
+            .init_resource::<ButtonInput<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>();
+        app.world
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(10));
+        app
+    }
+
+    /// A bullet at `position` headed up the screen, fired by nobody in particular
+    pub fn bullet_at(position: Vec2) -> Bullet {
+        Bullet {
+            heading: 0.,
+            position,
+            velocity: BULLET_SPEED,
+            shooter: Entity::PLACEHOLDER,
+            immunity_time: Timer::new(Duration::ZERO, TimerMode::Once),
+            player_shot: false,
+            pierces_left: 0,
+            capture_chance: 0.,
+            damage_type: DamageType::Kinetic,
+            status: None,
+        }
+    }
+
+    /// A bullet from `shooter`, clear of it and out of pierces
+    pub fn stray_bullet(shooter: Entity) -> Bullet {
+        Bullet {
+            shooter,
+            ..bullet_at(Vec2::ZERO)
+        }
+    }
+
+    /// The score events sent so far, as what they were for and how much
+    pub fn drain_score(events: &mut Events<ScoreEvent>) -> Vec<(ScoreSource, u32)> {
+        events.drain().map(|e| (e.source, e.points)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::testing::{drain_score, test_app};
+    use crate::loadout::{Passive, WeaponPattern};
+    use crate::score::{apply_score_events, ScoreBreakdown};
+    use bevy::{
+        app::Update,
+        ecs::{schedule::State, system::RunSystemOnce},
+        hierarchy::BuildWorldChildren,
+    };
+
+    fn capture_app() -> App {
+        let mut app = test_app();
+        app.add_systems(
+            Update,
+            (
+                queue_capture_candidates,
+                slow_for_captured_ship,
+                check_for_usage_decision,
+                recover_from_capture_moment,
+            )
+                .chain(),
+        );
+        app
+    }
+
+    fn game_state(app: &App) -> GameState {
+        app.world.resource::<State<GameState>>().get().clone()
+    }
+
+    #[test]
+    fn ship_stock_starts_with_the_weakest_hull() {
+        assert!(matches!(take_ship_stock(vec![]), ShipType::Ship1));
+    }
+
+    #[test]
+    fn ship_stock_picks_the_most_underrepresented_tier() {
+        let all_ship_one = [ShipType::Ship1; 4];
+        assert!(matches!(
+            take_ship_stock(all_ship_one.iter().collect()),
+            ShipType::Ship2
+        ));
+        let mixed = [ShipType::Ship1, ShipType::Ship2, ShipType::Ship2];
+        assert!(matches!(
+            take_ship_stock(mixed.iter().collect()),
+            ShipType::Ship3
+        ));
+    }
+
+    #[test]
+    fn variants_tweak_their_base_hull() {
+        let base = ShipProfile::from_type(ShipType::Ship3);
+        let interceptor = ShipProfile::of(ShipType::Ship3, Some(ShipVariant::Interceptor));
+        assert!(interceptor.max_velocity > base.max_velocity);
+        assert!(interceptor.max_health >= 1 && interceptor.max_health < base.max_health);
+        let gunboat =
+            Spacecraft::from_variant(ShipType::Ship3, Some(ShipVariant::Gunboat), Vec2::ZERO);
+        assert_eq!(gunboat.health, base.max_health + 1);
+        assert_eq!(gunboat.profile().shots, 2);
+        for ship in [ShipType::Ship1, ShipType::Carrier, ShipType::Drone] {
+            assert!(ShipVariant::for_hull(ship).is_empty());
+        }
+    }
+
+    #[test]
+    fn shield_recharge_stalls_at_speed_and_cancels_on_throttle_up() {
+        let mut app = test_app();
+        app.insert_resource(ShieldRechargeTextures {
+            image: Handle::default(),
+            atlas: Handle::default(),
+        })
+        .add_systems(Update, recharge_shield);
+        let mut ship = Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO);
+        ship.health = 1;
+        ship.shield_recharge.reset();
+        let ship = app
+            .world
+            .spawn((ship, Transform::default(), RechargingShieldMarker))
+            .id();
+        app.update();
+        assert!(app.world.get::<ShieldRecharge>(ship).is_some());
+
+        app.world.get_mut::<Spacecraft>(ship).unwrap().velocity = MAX_VELOCITY;
+        app.update();
+        assert!(app.world.get::<ShieldRecharge>(ship).is_none());
+        let craft = app.world.get::<Spacecraft>(ship).unwrap();
+        assert!(craft.shield_recharge.finished());
+        assert_eq!(craft.health, 1);
+
+        let mut craft = app.world.get_mut::<Spacecraft>(ship).unwrap();
+        craft.velocity = 0.;
+        craft.shield_recharge.reset();
+        app.world.entity_mut(ship).insert(RechargingShieldMarker);
+        for _ in 0..6 {
+            app.update();
+        }
+        assert_eq!(app.world.get::<Spacecraft>(ship).unwrap().health, 2);
+        assert!(app.world.get::<ShieldRecharge>(ship).is_none());
+    }
+
+    #[test]
+    fn hit_during_shield_recharge_spends_the_attempt() {
+        let mut app = test_app();
+        app.insert_resource(ShieldRechargeTextures {
+            image: Handle::default(),
+            atlas: Handle::default(),
+        })
+        .add_systems(Update, recharge_shield);
+        let mut ship = Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO);
+        ship.health = 1;
+        ship.shield_recharge.reset();
+        let ship = app
+            .world
+            .spawn((ship, Transform::default(), RechargingShieldMarker))
+            .id();
+        for _ in 0..4 {
+            app.update();
+        }
+        let progress = app
+            .world
+            .get::<Spacecraft>(ship)
+            .unwrap()
+            .shield_recharge
+            .fraction();
+        assert!((LATE_INTERRUPT..1.).contains(&progress));
+
+        app.world.send_event(ShipDamaged {
+            ship,
+            attacker: None,
+            cause: DamageCause::Ram,
+            amount: 1,
+        });
+        app.update();
+        assert!(app.world.get::<ShieldRecharge>(ship).is_none());
+        let craft = app.world.get::<Spacecraft>(ship).unwrap();
+        assert_eq!(craft.health, 1);
+        assert!(craft.soaked_damage < 0.);
+        assert!(!craft.shield_recharge.finished());
+
+        // It cools down over the full recharge time, without giving back any hull
+        for _ in 0..4 {
+            app.update();
+        }
+        let craft = app.world.get::<Spacecraft>(ship).unwrap();
+        assert!(craft.shield_recharge.finished());
+        assert_eq!(craft.health, 1);
+    }
+
+    #[test]
+    fn fitted_player_ship_uses_loadout_stats() {
+        let fitting = Fitting {
+            weapon: WeaponPattern::Focused,
+            passive: Passive::ExtraHull,
+        };
+        let base = ShipProfile::from_type(ShipType::Ship2);
+        let ship = Spacecraft::from_parts(ShipType::Ship2, None, Some(fitting), Vec2::ZERO);
+        assert_eq!(ship.health, base.max_health + 1);
+        assert_eq!(ship.profile().shots, 1);
+        assert!(ship.weapon_cooldown.duration() < base.gun_reload_time);
+    }
+
+    #[test]
+    fn harder_enemies_react_sooner_and_sharper() {
+        let easy = AiReaction::for_difficulty(Difficulty::Easy);
+        let normal = AiReaction::for_difficulty(Difficulty::Normal);
+        let hard = AiReaction::for_difficulty(Difficulty::Hard);
+        assert!(easy.latency_secs > normal.latency_secs && normal.latency_secs > hard.latency_secs);
+        assert!(easy.turn_rate < normal.turn_rate && normal.turn_rate < hard.turn_rate);
+        assert!(easy.fire_chance < normal.fire_chance && normal.fire_chance < hard.fire_chance);
+    }
+
+    #[test]
+    fn ai_retargets_on_a_fixed_cadence() {
+        let mut logic = NPCLogic::new(Vec2::ZERO);
+        // Wherever the stagger started it, a full interval always brings it round once
+        let retargets = (0..5)
+            .filter(|_| logic.should_retarget(RETARGET_INTERVAL / 5, true))
+            .count();
+        assert_eq!(retargets, 1);
+        assert!(logic.should_retarget(Duration::ZERO, false));
+    }
+
+    #[test]
+    fn allies_are_pushed_out_of_the_players_line_of_fire() {
+        let shooter = Vec2::ZERO;
+        let ahead = line_of_fire_nudge(Vec2::new(0.05, 0.5), shooter, 0.);
+        assert!(ahead.x > 0. && ahead.y.abs() < 1e-5);
+        assert_ne!(
+            line_of_fire_nudge(Vec2::new(0., 0.5), shooter, 0.),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            line_of_fire_nudge(Vec2::new(0.5, 0.5), shooter, 0.),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            line_of_fire_nudge(Vec2::new(0., -0.5), shooter, 0.),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            line_of_fire_nudge(Vec2::new(0., 2.), shooter, 0.),
+            Vec2::ZERO
+        );
+    }
+
+    #[test]
+    fn border_warns_before_it_kills() {
+        assert_eq!(border_status(Vec2::new(3., 4.)), BorderStatus::Inside);
+        assert_eq!(border_status(Vec2::new(0., -10.)), BorderStatus::Warning);
+        assert_eq!(border_status(Vec2::new(12., 9.)), BorderStatus::Beyond);
+    }
+
+    #[test]
+    fn camera_view_accounts_for_zoom_and_margin() {
+        let window = Window {
+            resolution: (800., 800.).into(),
+            ..default()
+        };
+        // Sees x from 0 to 800 pixels, 1.25 units
+        let mut camera = Transform::from_xyz(400., 0., 0.);
+        let view = CameraView::new(&camera, &window);
+        assert!(view.contains(Vec2::new(1.2, 0.), 0.));
+        assert!(!view.contains(Vec2::new(1.3, 0.), 0.));
+        assert!(view.contains(Vec2::new(1.3, 0.), 50.));
+        camera.scale = Vec3::splat(2.);
+        let zoomed_out = CameraView::new(&camera, &window);
+        assert!(zoomed_out.contains(Vec2::new(1.8, 2.), 0.));
+
+        // A bigger window shows more of the arena rather than stretching it
+        let wide = Window {
+            resolution: (1600., 800.).into(),
+            ..default()
+        };
+        camera.scale = Vec3::ONE;
+        assert!(CameraView::new(&camera, &wide).contains(Vec2::new(1.8, 0.), 0.));
+    }
+
+    #[test]
+    fn player_shots_capture_instead_of_killing() {
+        let mut events = Events::<ScoreEvent>::default();
+        let mut ship = Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO);
+        assert!(!ship.collide(1, true, &mut events));
+        assert_eq!(ship.health, 1);
+        assert_eq!(drain_score(&mut events), [(ScoreSource::Kills, 5)]);
+        assert!(ship.collide(1, true, &mut events));
+        assert_eq!(ship.health, 1);
+        assert_eq!(drain_score(&mut events), [(ScoreSource::Captures, 15)]);
+    }
+
+    #[test]
+    fn command_ships_only_speed_up_their_own_side() {
+        let mut app = test_app();
+        app.add_systems(Update, command_nearby_allies);
+        let commander = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship6, Vec2::ZERO),
+                Captured,
+            ))
+            .id();
+        let ally = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship1, Vec2::new(0.5, 0.)),
+                PlayerMarker,
+            ))
+            .id();
+        let enemy = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship1,
+                Vec2::new(0., 0.5),
+            ))
+            .id();
+        app.update();
+        app.update();
+        let reload = |app: &App, entity| {
+            app.world
+                .get::<Spacecraft>(entity)
+                .unwrap()
+                .weapon_cooldown
+                .elapsed()
+        };
+        assert!(reload(&app, ally) > Duration::ZERO);
+        assert_eq!(reload(&app, enemy), Duration::ZERO);
+        assert_eq!(reload(&app, commander), Duration::ZERO);
+    }
+
+    #[test]
+    fn piercing_bullets_pass_through_one_ship() {
+        let mut bullet = Bullet {
+            heading: 0.,
+            position: Vec2::ZERO,
+            velocity: 0.,
+            shooter: Entity::PLACEHOLDER,
+            immunity_time: Timer::default(),
+            player_shot: true,
+            pierces_left: 1,
+            capture_chance: 0.,
+            damage_type: DamageType::Kinetic,
+            status: None,
+        };
+        assert!(bullet.pierce());
+        assert!(!bullet.pierce());
+        assert!(!bullet.lucky_shot());
+    }
+
+    #[test]
+    fn armour_soaks_or_amplifies_hits() {
+        let mut events = Events::<ScoreEvent>::default();
+        let mut heavy = Spacecraft::from_template(ShipType::Ship6, Vec2::ZERO);
+        let full = heavy.health;
+        assert!(!heavy.take_hit(1, DamageType::Kinetic, false, &mut events));
+        assert_eq!(heavy.health, full);
+        heavy.take_hit(1, DamageType::Kinetic, false, &mut events);
+        assert_eq!(heavy.health, full - 1);
+        heavy.take_hit(1, DamageType::Explosive, false, &mut events);
+        heavy.take_hit(1, DamageType::Explosive, false, &mut events);
+        assert_eq!(heavy.health, full - 4);
+        let mut light = Spacecraft::from_template(ShipType::Ship2, Vec2::ZERO);
+        light.take_hit(1, DamageType::Kinetic, false, &mut events);
+        assert_eq!(
+            light.health,
+            ShipProfile::from_type(ShipType::Ship2).max_health - 1
+        );
+    }
+
+    #[test]
+    fn shield_patch_on_armour_only_ever_soaks_hits() {
+        let mut events = Events::<ScoreEvent>::default();
+        let mut heavy = Spacecraft::from_template(ShipType::Ship6, Vec2::ZERO);
+        let full = heavy.health;
+        heavy.soaked_damage = -0.9;
+        // Half a hit each, so the patch takes two of them and another two make up the first damage
+        for _ in 0..3 {
+            heavy.take_hit(1, DamageType::Kinetic, false, &mut events);
+            assert_eq!(heavy.health, full);
+        }
+        heavy.take_hit(1, DamageType::Kinetic, false, &mut events);
+        assert_eq!(heavy.health, full - 1);
+    }
+
+    #[test]
+    fn enemy_shots_kill_without_scoring() {
+        let mut events = Events::<ScoreEvent>::default();
+        let mut ship = Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO);
+        assert!(!ship.collide(2, false, &mut events));
+        assert_eq!(ship.health, 0);
+        assert!(drain_score(&mut events).is_empty());
+    }
+
+    #[test]
+    fn wing_guns_converge_ahead_of_every_hull() {
+        for ship_type in [ShipType::Ship2, ShipType::Ship6, ShipType::Capital] {
+            let mut profile = ShipProfile::from_type(ship_type);
+            profile.shots = 3;
+            let guns = profile.gun_positions();
+            assert_eq!(guns.len(), 3);
+            assert_eq!(guns[0].x, -guns[2].x);
+            assert_eq!(toe_in(guns[1]), 0.);
+            for gun in [guns[0], guns[2]] {
+                // Following the toed-in heading from the gun reaches the centreline at the convergence point
+                let heading = toe_in(gun);
+                let travel = (GUN_CONVERGENCE - gun.y) / heading.cos();
+                let crossing = gun + Vec2::new(heading.sin(), heading.cos()) * travel;
+                assert!(
+                    crossing.x.abs() < 1e-4,
+                    "{ship_type:?} crosses at {crossing}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn swapped_ship_keeps_flying_while_controls_come_in() {
+        let mut app = test_app();
+        app.add_event::<ShipSwapped>()
+            .add_systems(Update, (swap_ships, tick_control_handoff).chain());
+        app.world.spawn((Camera2d, Transform::default()));
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        let mut craft = Spacecraft::from_template(ShipType::Ship3, Vec2::new(0.3, 0.));
+        craft.velocity = 0.02;
+        craft.heading = 1.;
+        let target = app
+            .world
+            .spawn((craft, Transform::default(), SwapToShipMarker))
+            .id();
+        app.update();
+        let handoff = app.world.get::<ControlHandoff>(target).unwrap();
+        assert!(handoff.authority() < 1.);
+        assert!(app.world.get::<PlayerMarker>(target).is_some());
+        assert!(app.world.contains_resource::<CameraTransition>());
+        let craft = app.world.get::<Spacecraft>(target).unwrap();
+        assert_eq!((craft.velocity, craft.heading), (0.02, 1.));
+
+        app.update();
+        assert!(app.world.get::<ControlHandoff>(target).is_none());
+    }
+
+    #[test]
+    fn only_the_same_ship_healing_up_is_a_near_death_escape() {
+        let mut app = test_app();
+        app.add_systems(Update, (swap_ships, watch_for_near_death_escapes).chain());
+        app.world.spawn((Camera2d, Transform::default()));
+        let mut wounded = Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO);
+        wounded.health = 1;
+        let player = app.world.spawn((wounded, PlayerMarker)).id();
+        app.update();
+        app.world.get_mut::<Spacecraft>(player).unwrap().health = 2;
+        app.update();
+        let escapes = app
+            .world
+            .resource_mut::<Events<NearDeathEscape>>()
+            .drain()
+            .count();
+        assert_eq!(escapes, 1);
+
+        app.world.get_mut::<Spacecraft>(player).unwrap().health = 1;
+        app.update();
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship3, Vec2::new(0.3, 0.)),
+            Transform::default(),
+            SwapToShipMarker,
+        ));
+        app.update();
+        app.update();
+        assert!(app.world.resource::<Events<NearDeathEscape>>().is_empty());
+    }
+
+    #[test]
+    fn survival_scores_every_ten_seconds() {
+        let mut app = test_app();
+        app.add_systems(Update, (update_score, apply_score_events).chain());
+        // The first update only establishes the starting instant
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(app.world.resource::<PlayerScore>().score, 0);
+        app.update();
+        let score = app.world.resource::<PlayerScore>();
+        assert_eq!(score.score, SURVIVAL_BASE_POINTS);
+        assert_eq!(score.survived_time.elapsed_secs(), 10.);
+        let breakdown = app.world.resource::<ScoreBreakdown>();
+        assert_eq!(breakdown.get(ScoreSource::Survival), SURVIVAL_BASE_POINTS);
+    }
+
+    #[test]
+    fn survival_scores_faster_in_the_thick_of_it() {
+        let mut app = test_app();
+        app.add_systems(Update, (update_score, apply_score_events).chain());
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        app.world.spawn(Spacecraft::from_template(
+            ShipType::Ship3,
+            Vec2::new(1., 0.),
+        ));
+        app.world.spawn(Spacecraft::from_template(
+            ShipType::Ship6,
+            Vec2::new(0., -1.5),
+        ));
+        // Too far off to count
+        app.world.spawn(Spacecraft::from_template(
+            ShipType::Ship6,
+            Vec2::new(8., 0.),
+        ));
+        for _ in 0..11 {
+            app.update();
+        }
+        let expected = survival_points(65. * 10., 10.);
+        assert_eq!(expected, SURVIVAL_BASE_POINTS + 16);
+        assert_eq!(app.world.resource::<PlayerScore>().score, expected);
+        assert_eq!(app.world.resource::<PlayerScore>().threat_exposure, 0.);
+    }
+
+    #[test]
+    fn dead_ships_announce_who_destroyed_them() {
+        let mut app = test_app();
+        app.insert_resource(Settings::default())
+            .add_systems(Update, kill_dead_ships);
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship2, Vec2::ZERO),
+                PlayerMarker,
+            ))
+            .id();
+        let mut wreck = Spacecraft::from_template(ShipType::Ship4, Vec2::new(0.5, 0.));
+        wreck.health = 0;
+        let enemy = app.world.spawn((wreck, LastHitBy(player))).id();
+        app.update();
+        let events = app.world.resource::<Events<ShipDestroyed>>();
+        let destroyed = events
+            .get_reader()
+            .read(events)
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(destroyed.len(), 1);
+        assert_eq!(destroyed[0].ship, enemy);
+        assert_eq!(destroyed[0].ship_type, ShipType::Ship4);
+        assert_eq!(destroyed[0].allegiance, Allegiance::Enemy);
+        assert_eq!(destroyed[0].killer, Some(player));
+        assert!(app.world.get_entity(enemy).is_none());
+    }
+
+    #[test]
+    fn capture_slows_time_until_a_decision_is_made() {
+        let mut app = capture_app();
+        let ship = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship3, Vec2::ZERO),
+                MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+            ))
+            .id();
+        let time_scale = |app: &App| app.world.resource::<Time<Virtual>>().relative_speed();
+        app.update();
+        app.update();
+        assert_eq!(time_scale(&app), CAPTURE_TIME_SCALE);
+        let mut menus = app
+            .world
+            .query_filtered::<Entity, With<ShipUsageImageMarker>>();
+        assert_eq!(menus.iter(&app.world).count(), 1);
+
+        // Nothing happens while the player is still deciding, but the game never stops
+        app.update();
+        assert_eq!(game_state(&app), GameState::Regular);
+        assert_eq!(time_scale(&app), CAPTURE_TIME_SCALE);
+
+        app.world.spawn(ShipUsageDecision::Keep);
+        app.update();
+        app.update();
+        assert_eq!(time_scale(&app), 1.);
+        assert!(app.world.get_resource::<CaptureMoment>().is_none());
+        assert!(app.world.get::<Captured>(ship).is_some());
+        assert!(app
+            .world
+            .get::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>(ship)
+            .is_none());
+        assert_eq!(menus.iter(&app.world).count(), 0);
+    }
+
+    #[test]
+    fn capture_transfer_marks_the_ship_for_swapping() {
+        let mut app = capture_app();
+        let ship = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship2, Vec2::ZERO),
+                MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+            ))
+            .id();
+        app.update();
+        app.update();
+        app.world.spawn(ShipUsageDecision::Transfer);
+        app.update();
+        assert!(app.world.get::<SwapToShipMarker>(ship).is_some());
+        assert!(app.world.get::<Captured>(ship).is_none());
+    }
+
+    #[test]
+    fn capture_destroy_scuttles_the_ship() {
+        let mut app = capture_app();
+        let ship = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship4, Vec2::ZERO),
+                MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+            ))
+            .id();
+        app.update();
+        app.update();
+        app.world.spawn(ShipUsageDecision::Destroy);
+        app.update();
+        assert!(app.world.get::<Spacecraft>(ship).unwrap().health <= 0);
+        assert_eq!(app.world.resource::<PlayerScore>().score, 0);
+    }
+
+    #[test]
+    fn abandoning_a_run_clears_it_but_keeps_what_came_before() {
+        let mut app = test_app();
+        let camera = app
+            .world
+            .spawn((
+                Camera2d,
+                Transform::from_rotation(Quat::from_rotation_z(1.)),
+            ))
+            .id();
+        let dialogue_box = app.world.spawn_empty().id();
+        app.world.run_system_once(remember_persistent_entities);
+        let ship = app
+            .world
+            .spawn(Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO))
+            .id();
+        let flag = app.world.spawn_empty().set_parent(ship).id();
+        app.world.run_system_once(despawn_run);
+
+        assert!(app.world.get_entity(ship).is_none());
+        assert!(app.world.get_entity(flag).is_none());
+        assert!(app.world.get_entity(dialogue_box).is_some());
+        let camera = app.world.get::<Transform>(camera).unwrap();
+        assert_eq!(camera.rotation, Quat::IDENTITY);
+    }
+
+    #[test]
+    fn ships_disabled_together_are_decided_one_at_a_time() {
+        let mut app = capture_app();
+        let marked = |app: &App, ship| {
+            app.world
+                .get::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>(
+                    ship,
+                )
+                .is_some()
+        };
+        let first = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship3, Vec2::ZERO),
+                MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+            ))
+            .id();
+        let second = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship4, Vec2::new(0.5, 0.)),
+                MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+            ))
+            .id();
+        app.update();
+        app.update();
+        let time_scale = |app: &App| app.world.resource::<Time<Virtual>>().relative_speed();
+        assert_eq!(time_scale(&app), CAPTURE_TIME_SCALE);
+        assert_eq!(app.world.resource::<CaptureQueue>().current(), Some(first));
+        let mut menus = app
+            .world
+            .query_filtered::<Entity, With<ShipUsageImageMarker>>();
+        assert_eq!(menus.iter(&app.world).count(), 1);
+
+        // The first choice only settles the first ship, and the second comes straight up
+        app.world.spawn(ShipUsageDecision::Keep);
+        app.update();
+        app.update();
+        assert!(app.world.get::<Captured>(first).is_some());
+        assert!(app.world.get::<Captured>(second).is_none());
+        assert!(marked(&app, second));
+        assert_eq!(app.world.resource::<CaptureQueue>().current(), Some(second));
+        assert!(matches!(
+            app.world.get_resource::<CaptureMoment>(),
+            Some(CaptureMoment::Deciding)
+        ));
+        assert_eq!(time_scale(&app), CAPTURE_TIME_SCALE);
+        assert_eq!(menus.iter(&app.world).count(), 1);
+
+        app.world.spawn(ShipUsageDecision::Destroy);
+        app.update();
+        app.update();
+        assert!(app.world.get::<Spacecraft>(second).unwrap().health <= 0);
+        assert!(!marked(&app, second));
+        assert!(app.world.get::<Captured>(second).is_none());
+        assert!(app.world.resource::<CaptureQueue>().0.is_empty());
+        assert_eq!(time_scale(&app), 1.);
+        assert_eq!(menus.iter(&app.world).count(), 0);
+    }
+}
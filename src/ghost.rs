@@ -0,0 +1,253 @@
+//! A translucent ghost flying the path of the best run so far, in step with the clock of the
+//! current one, so the player can race their own line. Its line only makes sense against the same
+//! enemies, so it only flies in runs started from the same seed, like a replay off the high scores.
+
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::Handle,
+    ecs::{
+        component::Component,
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    gizmos::gizmos::Gizmos,
+    math::{Quat, Vec2, Vec3},
+    prelude::{default, App},
+    render::{color::Color, texture::Image, view::Visibility},
+    sprite::{Sprite, SpriteBundle},
+    transform::components::Transform,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::{
+        GameState, GameplaySet, PlayerMarker, PlayerScore, ShipProfile, ShipTextures, ShipType,
+        Spacecraft, PIXELS_PER_UNIT,
+    },
+    seed::RunSeed,
+    storage, GameLifecycleState,
+};
+
+const GHOST_KEY: &str = "ghost.ron";
+/// How often the player's ship is sampled for the ghost, in survived time
+pub const GHOST_SAMPLE_TIME: Duration = Duration::from_millis(100);
+/// Samples of path drawn behind the ghost
+const TRAIL_SAMPLES: usize = 30;
+const GHOST_ALPHA: f32 = 0.3;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BestGhost::load())
+            .add_systems(OnEnter(GameLifecycleState::Game), spawn_ghost)
+            .add_systems(OnEnter(GameLifecycleState::EndScreen), keep_best_ghost)
+            .add_systems(
+                Update,
+                (
+                    record_ghost.in_set(GameplaySet::Cleanup),
+                    fly_ghost.in_set(GameplaySet::Presentation),
+                )
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GhostSample {
+    pub position: [f32; 2],
+    pub heading: f32,
+    pub ship_type: ShipType,
+}
+
+/// The path flown in the highest scoring run, one sample every [`GHOST_SAMPLE_TIME`]
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BestGhost {
+    pub score: u32,
+    /// The run's seed. Ghosts saved before this was kept have none, and never fly.
+    pub seed: Option<u32>,
+    pub samples: Vec<GhostSample>,
+}
+
+impl BestGhost {
+    pub fn load() -> Self {
+        storage::read(GHOST_KEY)
+            .and_then(|contents| ron::from_str::<BestGhost>(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(GHOST_KEY, &contents),
+            Err(e) => println!("Could not serialise ghost: {e}"),
+        }
+    }
+
+    /// Takes the run's path if it beat the best so far, returning whether it did
+    pub fn offer(&mut self, score: u32, seed: u32, samples: &[GhostSample]) -> bool {
+        if samples.is_empty() || score <= self.score {
+            return false;
+        }
+        self.score = score;
+        self.seed = Some(seed);
+        self.samples = samples.to_vec();
+        true
+    }
+
+    /// Whether the ghost's run started from `seed`, and so met the same enemies
+    pub fn flies_on(&self, seed: u32) -> bool {
+        self.seed == Some(seed)
+    }
+
+    /// Where the ghost was `secs` into its run, blended between samples. None once it's over.
+    pub fn at(&self, secs: f32) -> Option<(Vec2, f32, ShipType)> {
+        let step = secs / GHOST_SAMPLE_TIME.as_secs_f32();
+        let (from, to) = (
+            self.samples.get(step as usize)?,
+            self.samples.get(step as usize + 1)?,
+        );
+        let blend = step.fract();
+        let position = Vec2::from_array(from.position).lerp(Vec2::from_array(to.position), blend);
+        // The short way round, headings wrap
+        let turn = (to.heading - from.heading + PI).rem_euclid(2. * PI) - PI;
+        Some((position, from.heading + turn * blend, from.ship_type))
+    }
+}
+
+/// The current run's path so far
+#[derive(Resource, Default)]
+pub struct GhostRecorder {
+    pub samples: Vec<GhostSample>,
+}
+
+#[derive(Component)]
+pub struct GhostMarker {
+    ship_type: Option<ShipType>,
+}
+
+fn spawn_ghost(mut commands: Commands) {
+    commands.insert_resource(GhostRecorder::default());
+    commands.spawn((
+        SpriteBundle {
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        GhostMarker { ship_type: None },
+    ));
+}
+
+/// Samples are taken against survived time, so the ghost pauses and slows along with the run
+pub fn record_ghost(
+    score: Res<PlayerScore>,
+    mut recorder: ResMut<GhostRecorder>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+) {
+    if let Ok(ship) = player.get_single() {
+        let due = score.survived_time.elapsed_secs() / GHOST_SAMPLE_TIME.as_secs_f32();
+        while recorder.samples.len() as f32 <= due {
+            recorder.samples.push(GhostSample {
+                position: ship.position.to_array(),
+                heading: ship.heading,
+                ship_type: ship.ship_type,
+            });
+        }
+    }
+}
+
+fn keep_best_ghost(
+    score: Res<PlayerScore>,
+    seed: Res<RunSeed>,
+    recorder: Option<Res<GhostRecorder>>,
+    mut best: ResMut<BestGhost>,
+) {
+    if let Some(recorder) = recorder {
+        if best.offer(score.score, seed.seed, &recorder.samples) {
+            best.save();
+        }
+    }
+}
+
+fn fly_ghost(
+    mut gizmos: Gizmos,
+    score: Res<PlayerScore>,
+    seed: Res<RunSeed>,
+    best: Res<BestGhost>,
+    textures: Res<ShipTextures>,
+    mut ghosts: Query<(
+        &mut GhostMarker,
+        &mut Transform,
+        &mut Handle<Image>,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+) {
+    let secs = score.survived_time.elapsed_secs();
+    let on_course = best.flies_on(seed.seed);
+    for (mut ghost, mut transform, mut texture, mut sprite, mut visibility) in ghosts.iter_mut() {
+        if let Some((position, heading, ship_type)) = best.at(secs).filter(|_| on_course) {
+            if ghost.ship_type != Some(ship_type) {
+                ghost.ship_type = Some(ship_type);
+                *texture = textures.texture(ship_type);
+                sprite.color = ship_type.tint().with_a(GHOST_ALPHA);
+            }
+            let scale = ShipProfile::from_type(ship_type).relative_scale;
+            *transform = Transform {
+                translation: (position * PIXELS_PER_UNIT).extend(5.),
+                rotation: Quat::from_rotation_z(3. * PI / 2. - heading),
+                scale: Vec3::new(scale, scale, 1.),
+            };
+            *visibility = Visibility::Inherited;
+
+            let now = (secs / GHOST_SAMPLE_TIME.as_secs_f32()) as usize;
+            let trail = best.samples[now.saturating_sub(TRAIL_SAMPLES)..=now]
+                .iter()
+                .map(|sample| Vec2::from_array(sample.position) * PIXELS_PER_UNIT)
+                .chain([position * PIXELS_PER_UNIT]);
+            gizmos.linestrip_2d(trail, Color::WHITE.with_a(GHOST_ALPHA * 0.5));
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, update_score};
+
+    #[test]
+    fn ghost_keeps_the_best_run_and_replays_it_on_the_clock() {
+        let mut app = test_app();
+        app.insert_resource(GhostRecorder::default())
+            .add_systems(Update, (update_score, record_ghost).chain());
+        let mut ship = Spacecraft::from_template(ShipType::Ship1, Vec2::new(1., 2.));
+        ship.heading = PI / 2.;
+        app.world.spawn((ship, PlayerMarker));
+        for _ in 0..3 {
+            app.update();
+        }
+        let samples = app.world.resource::<GhostRecorder>().samples.clone();
+        // One sample per tenth of a second survived, with 2s gone
+        assert_eq!(samples.len(), 21);
+
+        let mut best = BestGhost::default();
+        assert!(!best.offer(0, 7, &samples));
+        assert!(best.offer(500, 7, &samples));
+        assert!(!best.offer(400, 8, &[]));
+        assert_eq!(best.score, 500);
+        // Only a run from the same seed meets the same enemies to race against
+        assert!(best.flies_on(7));
+        assert!(!best.flies_on(8));
+        assert!(!BestGhost::default().flies_on(0));
+        let (position, heading, ship_type) = best.at(0.05).unwrap();
+        assert_eq!(ship_type, ShipType::Ship1);
+        assert!((heading - PI / 2.).abs() < 1e-5);
+        assert_eq!(position, Vec2::new(1., 2.));
+        assert!(best.at(2.).is_none());
+    }
+}
@@ -0,0 +1,535 @@
+//! The player's own HUD layout. The weapon dial, throttle, shields and score each sit in a slot
+//! that can be moved to any corner and scaled, from an editor that opens with [H] over the pause
+//! menu. Pieces are dragged to the corner they should go in, or moved with the arrow keys. The
+//! layout is saved with the profile, and anything left alone stays where [`spawn_ui`] puts it.
+
+use std::collections::BTreeMap;
+
+use bevy::{
+    app::{Plugin, PreUpdate, Update},
+    asset::AssetServer,
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, OnEnter, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, Children, DespawnRecursiveExt},
+    input::{
+        keyboard::KeyCode,
+        mouse::{MouseButton, MouseWheel},
+        ButtonInput, InputSystem,
+    },
+    math::{Vec2, Vec3},
+    prelude::{default, App},
+    render::{color::Color, view::Visibility},
+    text::{Text, TextStyle},
+    transform::components::Transform,
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, BorderColor, Interaction, JustifyContent, Node, PositionType, Style, UiRect,
+        UiSystem, Val, ZIndex,
+    },
+    window::{PrimaryWindow, Window},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::BindingsScreen,
+    gameplay::GameState,
+    pause::PauseMenuMarker,
+    storage,
+    ui::{spawn_ui, HudAnchor},
+    upgrades::UpgradeScreen,
+    volume::AudioScreen,
+    GameLifecycleState,
+};
+
+const HUD_LAYOUT_KEY: &str = "hud_layout.ron";
+
+pub const MIN_HUD_SCALE: f32 = 0.5;
+pub const MAX_HUD_SCALE: f32 = 2.;
+/// How much one notch of the wheel or one press of [-]/[=] scales a piece by
+pub const HUD_SCALE_STEP: f32 = 0.1;
+
+pub struct HudLayoutPlugin;
+
+impl Plugin for HudLayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HudLayout::load())
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                arrange_hud.after(spawn_ui),
+            )
+            .add_systems(
+                Update,
+                (arrange_hud, fit_hud_slots)
+                    .chain()
+                    .run_if(in_state(GameLifecycleState::Game)),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    open_hud_editor,
+                    handle_hud_editor.run_if(resource_exists::<HudEditor>),
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .after(UiSystem::Focus)
+                    .run_if(not(resource_exists::<AudioScreen>))
+                    .run_if(not(resource_exists::<BindingsScreen>))
+                    .run_if(not(resource_exists::<UpgradeScreen>))
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::PauseMenu)),
+            )
+            .add_systems(OnExit(GameState::PauseMenu), close_hud_editor);
+    }
+}
+
+/// The parts of the HUD that can be moved and scaled
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum HudPiece {
+    WeaponDial,
+    Throttle,
+    Shields,
+    Score,
+}
+
+impl HudPiece {
+    pub const ALL: [HudPiece; 4] = [
+        HudPiece::WeaponDial,
+        HudPiece::Throttle,
+        HudPiece::Shields,
+        HudPiece::Score,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            HudPiece::WeaponDial => "Weapon dial",
+            HudPiece::Throttle => "Throttle",
+            HudPiece::Shields => "Shields",
+            HudPiece::Score => "Score",
+        }
+    }
+
+    /// Where [`spawn_ui`] puts it
+    pub fn home_corner(self) -> HudAnchor {
+        match self {
+            HudPiece::WeaponDial => HudAnchor::BottomLeft,
+            HudPiece::Throttle => HudAnchor::BottomRight,
+            HudPiece::Shields => HudAnchor::TopLeft,
+            HudPiece::Score => HudAnchor::TopRight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PiecePlacement {
+    pub corner: HudAnchor,
+    pub scale: f32,
+}
+
+/// Only pieces that have been moved or scaled are kept
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudLayout {
+    pieces: BTreeMap<HudPiece, PiecePlacement>,
+}
+
+impl HudLayout {
+    pub fn load() -> Self {
+        storage::read(HUD_LAYOUT_KEY)
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(HUD_LAYOUT_KEY, &contents),
+            Err(e) => println!("Could not serialise HUD layout: {e}"),
+        }
+    }
+
+    pub fn placement(&self, piece: HudPiece) -> PiecePlacement {
+        self.pieces.get(&piece).copied().unwrap_or(PiecePlacement {
+            corner: piece.home_corner(),
+            scale: 1.,
+        })
+    }
+
+    pub fn move_to(&mut self, piece: HudPiece, corner: HudAnchor) {
+        let placement = self.placement(piece);
+        self.pieces.insert(
+            piece,
+            PiecePlacement {
+                corner,
+                ..placement
+            },
+        );
+    }
+
+    /// Scales by whole steps, kept between [`MIN_HUD_SCALE`] and [`MAX_HUD_SCALE`]
+    pub fn rescale(&mut self, piece: HudPiece, steps: i32) {
+        let placement = self.placement(piece);
+        let scale = placement.scale + steps as f32 * HUD_SCALE_STEP;
+        let scale = ((scale * 10.).round() / 10.).clamp(MIN_HUD_SCALE, MAX_HUD_SCALE);
+        self.pieces
+            .insert(piece, PiecePlacement { scale, ..placement });
+    }
+}
+
+/// The corner of a window this size that a point, measured from the top left, falls in
+pub fn corner_at(point: Vec2, window: Vec2) -> HudAnchor {
+    match (point.x < window.x / 2., point.y < window.y / 2.) {
+        (true, true) => HudAnchor::TopLeft,
+        (false, true) => HudAnchor::TopRight,
+        (true, false) => HudAnchor::BottomLeft,
+        (false, false) => HudAnchor::BottomRight,
+    }
+}
+
+/// The corner across the screen from this one, side to side or top to bottom
+pub fn flip_corner(corner: HudAnchor, sideways: bool) -> HudAnchor {
+    match (corner, sideways) {
+        (HudAnchor::TopLeft, true) => HudAnchor::TopRight,
+        (HudAnchor::TopRight, true) => HudAnchor::TopLeft,
+        (HudAnchor::BottomLeft, true) => HudAnchor::BottomRight,
+        (HudAnchor::BottomRight, true) => HudAnchor::BottomLeft,
+        (HudAnchor::TopLeft, false) => HudAnchor::BottomLeft,
+        (HudAnchor::BottomLeft, false) => HudAnchor::TopLeft,
+        (HudAnchor::TopRight, false) => HudAnchor::BottomRight,
+        (HudAnchor::BottomRight, false) => HudAnchor::TopRight,
+    }
+}
+
+/// Holds one piece of the HUD, remembering the node and place among its children it was spawned
+/// at, so a piece moved back to its own corner goes back where it was
+#[derive(Component)]
+pub struct HudSlot {
+    pub piece: HudPiece,
+    home: Entity,
+    index: usize,
+    corner: HudAnchor,
+}
+
+/// A slot for a piece being spawned under `home`, as its `index`th child
+pub fn hud_slot(
+    piece: HudPiece,
+    home: Entity,
+    index: usize,
+) -> (NodeBundle, HudSlot, Interaction, Name) {
+    (
+        NodeBundle {
+            style: Style {
+                border: UiRect::all(Val::Px(1.)),
+                ..default()
+            },
+            border_color: Color::NONE.into(),
+            ..default()
+        },
+        HudSlot {
+            piece,
+            home,
+            index,
+            corner: piece.home_corner(),
+        },
+        Interaction::default(),
+        Name::new(piece.name()),
+    )
+}
+
+/// Moves every slot that isn't in the corner the layout has it in
+pub fn arrange_hud(
+    mut commands: Commands,
+    layout: Res<HudLayout>,
+    anchors: Query<(Entity, &HudAnchor)>,
+    children: Query<&Children>,
+    mut slots: Query<(Entity, &mut HudSlot)>,
+) {
+    for (entity, mut slot) in slots.iter_mut() {
+        let corner = layout.placement(slot.piece).corner;
+        if corner == slot.corner {
+            continue;
+        }
+        slot.corner = corner;
+        if corner == slot.piece.home_corner() {
+            let index = children
+                .get(slot.home)
+                .map_or(0, |c| c.len())
+                .min(slot.index);
+            commands.entity(slot.home).insert_children(index, &[entity]);
+        } else {
+            corner.attach(&mut commands, &anchors, entity);
+        }
+    }
+}
+
+/// Scaling doesn't change how much room a node takes up, so each slot's margin is grown or shrunk
+/// to match, keeping scaled pieces clear of each other and the edge of the screen
+pub fn fit_hud_slots(
+    layout: Res<HudLayout>,
+    mut slots: Query<(&HudSlot, &Node, &mut Style, &mut Transform)>,
+) {
+    for (slot, node, mut style, mut transform) in slots.iter_mut() {
+        let scale = layout.placement(slot.piece).scale;
+        if transform.scale.x != scale {
+            transform.scale = Vec3::splat(scale);
+        }
+        let extra = (scale - 1.) * node.size() / 2.;
+        let margin = UiRect::axes(Val::Px(extra.x), Val::Px(extra.y));
+        if style.margin != margin {
+            style.margin = margin;
+        }
+    }
+}
+
+/// The HUD editor is open over the pause menu, with this piece picked and maybe being dragged
+#[derive(Resource)]
+pub struct HudEditor {
+    selected: usize,
+    dragging: bool,
+}
+
+#[derive(Component)]
+pub struct HudEditorMarker;
+
+#[derive(Component)]
+pub struct HudEditorText;
+
+fn hud_editor_text(layout: &HudLayout, editor: &HudEditor) -> String {
+    let piece = HudPiece::ALL[editor.selected];
+    format!(
+        "HUD layout: {} at {:.0}%\n\
+        [Drag/Arrows] Move  [Scroll/-/=] Resize  [Tab] Next piece\n\
+        [Backspace] Defaults  [H/Esc] Done",
+        piece.name(),
+        layout.placement(piece).scale * 100.
+    )
+}
+
+fn open_hud_editor(
+    mut commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    editor: Option<Res<HudEditor>>,
+    layout: Res<HudLayout>,
+    asset_server: Res<AssetServer>,
+    mut pause_menu: Query<&mut Visibility, With<PauseMenuMarker>>,
+) {
+    if editor.is_some() || !inputs.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+    // Nothing under the editor should see the key that opened it
+    inputs.reset_all();
+    let editor = HudEditor {
+        selected: 0,
+        dragging: false,
+    };
+    // The pieces need to be seen to be moved
+    for mut visibility in pause_menu.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(HudEditorMarker)
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(12.)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                    ..TextBundle::from_section(
+                        hud_editor_text(&layout, &editor),
+                        TextStyle {
+                            font: asset_server.load("alphbeta.ttf"),
+                            font_size: 20.,
+                            color: Color::WHITE,
+                        },
+                    )
+                },
+                HudEditorText,
+            ));
+        });
+    commands.insert_resource(editor);
+}
+
+/// The editor takes every key while it's open, so the pause menu under it stays put
+#[allow(clippy::too_many_arguments)]
+fn handle_hud_editor(
+    commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut scroll: EventReader<MouseWheel>,
+    mut editor: ResMut<HudEditor>,
+    mut layout: ResMut<HudLayout>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    pressed: Query<(&HudSlot, &Interaction)>,
+    mut borders: Query<(&HudSlot, &mut BorderColor)>,
+    mut text: Query<&mut Text, With<HudEditorText>>,
+    editors: Query<Entity, With<HudEditorMarker>>,
+    pause_menu: Query<&mut Visibility, With<PauseMenuMarker>>,
+) {
+    if inputs.just_pressed(KeyCode::Escape) || inputs.just_pressed(KeyCode::KeyH) {
+        inputs.reset_all();
+        close_hud_editor(commands, editors, borders, pause_menu);
+        return;
+    }
+    let pieces = HudPiece::ALL.len();
+    let mut edited = layout.clone();
+    if mouse.just_pressed(MouseButton::Left) {
+        if let Some((slot, _)) = pressed
+            .iter()
+            .find(|(_, interaction)| **interaction == Interaction::Pressed)
+        {
+            editor.selected = HudPiece::ALL
+                .iter()
+                .position(|p| *p == slot.piece)
+                .unwrap_or(0);
+            editor.dragging = true;
+        }
+    }
+    let piece = HudPiece::ALL[editor.selected];
+    if editor.dragging && mouse.just_released(MouseButton::Left) {
+        editor.dragging = false;
+        if let Ok(window) = window.get_single() {
+            if let Some(cursor) = window.cursor_position() {
+                let size = Vec2::new(window.width(), window.height());
+                edited.move_to(piece, corner_at(cursor, size));
+            }
+        }
+    }
+    if inputs.just_pressed(KeyCode::Tab) {
+        editor.selected = (editor.selected + 1) % pieces;
+    }
+    let corner = edited.placement(piece).corner;
+    if inputs.any_just_pressed([KeyCode::ArrowLeft, KeyCode::ArrowRight]) {
+        edited.move_to(piece, flip_corner(corner, true));
+    }
+    if inputs.any_just_pressed([KeyCode::ArrowUp, KeyCode::ArrowDown]) {
+        edited.move_to(piece, flip_corner(corner, false));
+    }
+    let mut steps = scroll
+        .read()
+        .filter(|event| event.y != 0.)
+        .map(|event| event.y.signum() as i32)
+        .sum::<i32>();
+    if inputs.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        steps += 1;
+    }
+    if inputs.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        steps -= 1;
+    }
+    if steps != 0 {
+        edited.rescale(piece, steps);
+    }
+    if inputs.just_pressed(KeyCode::Backspace) {
+        edited = HudLayout::default();
+    }
+    if edited != *layout {
+        *layout = edited;
+        layout.save();
+    }
+    inputs.reset_all();
+
+    let selected = HudPiece::ALL[editor.selected];
+    for (slot, mut border) in borders.iter_mut() {
+        border.0 = match (slot.piece == selected, editor.dragging) {
+            (true, true) => Color::rgb(1., 0.8, 0.3),
+            (true, false) => Color::WHITE,
+            (false, _) => Color::rgba(1., 1., 1., 0.3),
+        };
+    }
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = hud_editor_text(&layout, &editor);
+    }
+}
+
+fn close_hud_editor(
+    mut commands: Commands,
+    editors: Query<Entity, With<HudEditorMarker>>,
+    mut borders: Query<(&HudSlot, &mut BorderColor)>,
+    mut pause_menu: Query<&mut Visibility, With<PauseMenuMarker>>,
+) {
+    commands.remove_resource::<HudEditor>();
+    for entity in editors.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (_, mut border) in borders.iter_mut() {
+        border.0 = Color::NONE;
+    }
+    for mut visibility in pause_menu.iter_mut() {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::testing::test_app;
+    use crate::ui::HudAnchor;
+    use bevy::hierarchy::{BuildWorldChildren, Parent};
+
+    #[test]
+    fn hud_pieces_move_between_corners_and_scale() {
+        let window = Vec2::new(1920., 1080.);
+        assert_eq!(
+            corner_at(Vec2::new(100., 1000.), window),
+            HudAnchor::BottomLeft
+        );
+        assert_eq!(
+            corner_at(Vec2::new(1800., 50.), window),
+            HudAnchor::TopRight
+        );
+        let mut layout = HudLayout::default();
+        layout.rescale(HudPiece::Score, 100);
+        assert_eq!(layout.placement(HudPiece::Score).scale, MAX_HUD_SCALE);
+        assert_eq!(
+            layout.placement(HudPiece::Score).corner,
+            HudAnchor::TopRight
+        );
+
+        let mut app = test_app();
+        app.init_resource::<HudLayout>()
+            .add_systems(Update, arrange_hud);
+        let top_left = app.world.spawn(HudAnchor::TopLeft).id();
+        let bottom_right = app.world.spawn(HudAnchor::BottomRight).id();
+        let slot = app
+            .world
+            .spawn(hud_slot(HudPiece::Shields, top_left, 0))
+            .id();
+        app.world.entity_mut(top_left).add_child(slot);
+        let parent = |app: &App| app.world.get::<Parent>(slot).unwrap().get();
+        app.update();
+        assert_eq!(parent(&app), top_left);
+
+        app.world
+            .resource_mut::<HudLayout>()
+            .move_to(HudPiece::Shields, HudAnchor::BottomRight);
+        app.update();
+        assert_eq!(parent(&app), bottom_right);
+        // Back into the node it was spawned in
+        app.world
+            .resource_mut::<HudLayout>()
+            .move_to(HudPiece::Shields, HudAnchor::TopLeft);
+        app.update();
+        assert_eq!(parent(&app), top_left);
+    }
+}
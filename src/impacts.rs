@@ -0,0 +1,200 @@
+//! Small sparks right where a bullet strikes, so hits in a busy volley can be told apart without
+//! the full explosion going off over the middle of the ship.
+
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::{
+    app::{Plugin, Update},
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::{Commands, Query, Res},
+    },
+    gizmos::gizmos::Gizmos,
+    math::{Quat, Vec2, Vec3},
+    prelude::{default, App},
+    render::color::Color,
+    sprite::{Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+    transform::components::{GlobalTransform, Transform},
+    window::Window,
+};
+use bevy_rapier2d::{pipeline::CollisionEvent, plugin::RapierContext};
+
+use crate::{
+    culling::on_screen,
+    damage::DamageType,
+    gameplay::{collide_bullets, Bullet, CameraView, GameplaySet, PIXELS_PER_UNIT},
+    GameLifecycleState,
+};
+
+pub const SPARK_TIME: Duration = Duration::from_millis(180);
+pub const SPARK_STREAKS: usize = 4;
+/// How wide the streaks fan out either side of straight back along the bullet's path
+const SPARK_SPREAD: f32 = PI / 3.;
+/// Length a streak reaches by the end of the spark, in pixels
+const STREAK_LENGTH: f32 = 14.;
+const FLASH_SIZE: f32 = 7.;
+
+pub struct ImpactEffectsPlugin;
+
+impl Plugin for ImpactEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_impact_sparks
+                    .before(collide_bullets)
+                    .in_set(GameplaySet::Collision),
+                update_impact_sparks.in_set(GameplaySet::Presentation),
+            )
+                .run_if(in_state(GameLifecycleState::Game)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct ImpactSpark {
+    timer: Timer,
+    color: Color,
+    streaks: [Vec2; SPARK_STREAKS],
+}
+
+fn spark_color(damage_type: DamageType) -> Color {
+    match damage_type {
+        DamageType::Kinetic => Color::rgb(1., 0.95, 0.7),
+        DamageType::Energy => Color::rgb(0.5, 0.9, 1.),
+        DamageType::Explosive => Color::rgb(1., 0.6, 0.25),
+    }
+}
+
+/// Directions for the streaks of a spark, fanned out around the way the bullet came from
+pub fn spark_directions(travel: Vec2) -> [Vec2; SPARK_STREAKS] {
+    let back = -travel.normalize_or_zero();
+    std::array::from_fn(|streak| {
+        let step = streak as f32 / (SPARK_STREAKS - 1) as f32;
+        Vec2::from_angle(SPARK_SPREAD * (2. * step - 1.)).rotate(back)
+    })
+}
+
+/// Where the two colliders touched, in world pixels. Rapier keeps the point in the first
+/// collider's own frame and in its own units.
+fn contact_point(
+    rapier: &RapierContext,
+    a: Entity,
+    b: Entity,
+    transforms: &Query<&GlobalTransform>,
+) -> Option<Vec2> {
+    let pair = rapier.contact_pair(a, b)?;
+    let local = pair
+        .manifolds()
+        .flat_map(|manifold| manifold.points().map(|point| point.local_p1()).last())
+        .next()?;
+    let (_, rotation, translation) = transforms
+        .get(pair.collider1())
+        .ok()?
+        .to_scale_rotation_translation();
+    let offset = rotation * (local * rapier.physics_scale()).extend(0.);
+    Some((translation + offset).truncate())
+}
+
+fn spawn_impact_sparks(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    rapier: Res<RapierContext>,
+    bullets: Query<&Bullet>,
+    transforms: Query<&GlobalTransform>,
+    camera: Query<&Transform, With<Camera2d>>,
+    window: Query<&Window>,
+) {
+    let view = CameraView::find(&camera, &window);
+    for event in collision_events.read() {
+        if let CollisionEvent::Started(a, b, _) = event {
+            let (bullet_entity, target, bullet) = match (bullets.get(*a), bullets.get(*b)) {
+                (Ok(bullet), Err(_)) => (*a, *b, bullet),
+                (Err(_), Ok(bullet)) => (*b, *a, bullet),
+                _ => continue,
+            };
+            // Still leaving the barrel of the ship that fired it
+            if bullet.shooter() == target && !bullet.immune_time_over() {
+                continue;
+            }
+            let point = contact_point(&rapier, bullet_entity, target, &transforms).or_else(|| {
+                transforms
+                    .get(bullet_entity)
+                    .ok()
+                    .map(|transform| transform.translation().truncate())
+            });
+            if let Some(point) =
+                point.filter(|point| on_screen(view.as_ref(), *point / PIXELS_PER_UNIT))
+            {
+                let heading = bullet.heading();
+                let travel = Vec2::new(heading.sin(), heading.cos()) * PIXELS_PER_UNIT;
+                let color = spark_color(bullet.damage_type());
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color,
+                            custom_size: Some(Vec2::splat(FLASH_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(point.extend(40.))
+                            .with_rotation(Quat::from_rotation_z(PI / 4.)),
+                        ..default()
+                    },
+                    ImpactSpark {
+                        timer: Timer::new(SPARK_TIME, TimerMode::Once),
+                        color,
+                        streaks: spark_directions(travel),
+                    },
+                ));
+            }
+        }
+    }
+}
+
+fn update_impact_sparks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+    mut sparks: Query<(Entity, &mut ImpactSpark, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut spark, mut transform, mut sprite) in sparks.iter_mut() {
+        if spark.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = spark.timer.fraction();
+        let fade = 1. - progress;
+        transform.scale = Vec3::splat(fade);
+        sprite.color = spark.color.with_a(fade);
+        let centre = transform.translation.truncate();
+        for direction in spark.streaks {
+            let start = centre + direction * STREAK_LENGTH * progress * 0.5;
+            let end = centre + direction * STREAK_LENGTH * progress;
+            gizmos.line_2d(start, end, spark.color.with_a(fade));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impact_sparks_fly_back_the_way_the_bullet_came() {
+        let travel = Vec2::new(0., -3.);
+        let streaks = spark_directions(travel);
+        for streak in streaks {
+            assert!((streak.length() - 1.).abs() < 1e-5);
+            assert!(streak.dot(travel) < 0., "{streak} follows the bullet on");
+        }
+        // Fanned out evenly either side of straight back
+        assert!((streaks[0].x + streaks[SPARK_STREAKS - 1].x).abs() < 1e-5);
+        assert!(streaks[0].x.abs() > streaks[1].x.abs());
+    }
+}
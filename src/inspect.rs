@@ -0,0 +1,201 @@
+//! A closer look at a disabled ship before deciding what to do with it. [I] brings up its full
+//! profile and how battered it is alongside the 1/2/3 choice.
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::{Commands, Query, Res},
+    },
+    hierarchy::DespawnRecursiveExt,
+    input::{keyboard::KeyCode, ButtonInput},
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{node_bundles::TextBundle, PositionType, Style, UiRect, Val, ZIndex},
+};
+
+use crate::{
+    gameplay::{
+        handle_inputs, CaptureMoment, CaptureQueue, Captured, GameState, GameplaySet,
+        MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker, PlayerMarker,
+        Spacecraft,
+    },
+    GameLifecycleState,
+};
+
+pub struct ShipInspectionPlugin;
+
+impl Plugin for ShipInspectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                inspect_captured_ship
+                    .after(handle_inputs)
+                    .in_set(GameplaySet::Input),
+                update_inspection.in_set(GameplaySet::Presentation),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct InspectionHintMarker;
+
+#[derive(Component)]
+pub struct InspectionPanelMarker;
+
+/// Everything worth knowing about a ship before taking it on, given how many allies there are
+pub fn inspection_report(ship: &Spacecraft, allies: usize) -> String {
+    let profile = ship.profile();
+    let name = match ship.variant {
+        Some(variant) => format!("{:?} {}", ship.ship_type, variant.name()),
+        None => format!("{:?}", ship.ship_type),
+    };
+    let mut lines = vec![
+        name,
+        format!("Hull: {}/{}", ship.health.max(0), profile.max_health),
+        format!("Armour: {:?}", profile.armor),
+        format!(
+            "Guns: {} x {:?}, reloading in {:.1}s",
+            profile.shots,
+            profile.damage_type,
+            profile.gun_reload_time.as_secs_f32()
+        ),
+        format!(
+            "Top speed: {:.0}, turning x{:.1}",
+            profile.max_velocity * 1000.,
+            profile.turn_rate
+        ),
+        format!(
+            "Shield recharge: {:.1}s",
+            profile.shield_recharge_time.as_secs_f32()
+        ),
+    ];
+    if let Some(hull_trait) = profile.hull_trait {
+        lines.push(format!("Trait: {hull_trait:?}"));
+    }
+    if let Some(status) = profile.on_hit {
+        lines.push(format!("Shots inflict: {status:?}"));
+    }
+    // There's no cap on the fleet, so keeping it never costs another ally
+    lines.push(format!("\nKeeping it makes {} allies", allies + 1));
+    lines.join("\n")
+}
+
+fn text_bundle(asset_server: &AssetServer, value: String, style: Style) -> TextBundle {
+    TextBundle {
+        style,
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..TextBundle::from_section(
+            value,
+            TextStyle {
+                font: asset_server.load("alphbeta.ttf"),
+                font_size: 18.,
+                color: Color::WHITE,
+            },
+        )
+    }
+}
+
+/// [I] opens and closes the panel, for as long as the choice is on screen
+fn inspect_captured_ship(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    inputs: Res<ButtonInput<KeyCode>>,
+    capture: Option<Res<CaptureMoment>>,
+    hint: Query<Entity, With<InspectionHintMarker>>,
+    panel: Query<Entity, With<InspectionPanelMarker>>,
+) {
+    let deciding = matches!(capture.as_deref(), Some(CaptureMoment::Deciding));
+    if !deciding {
+        for entity in hint.iter().chain(panel.iter()) {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    if hint.is_empty() {
+        commands.spawn((
+            text_bundle(
+                &asset_server,
+                "[I] Inspect ship".to_string(),
+                Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(25.),
+                    left: Val::Percent(45.),
+                    ..default()
+                },
+            ),
+            InspectionHintMarker,
+        ));
+    }
+    if inputs.just_pressed(KeyCode::KeyI) {
+        if let Ok(panel) = panel.get_single() {
+            commands.entity(panel).despawn_recursive();
+        } else {
+            commands.spawn((
+                text_bundle(
+                    &asset_server,
+                    String::new(),
+                    Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Percent(30.),
+                        right: Val::Percent(3.),
+                        padding: UiRect::all(Val::Px(10.)),
+                        ..default()
+                    },
+                ),
+                InspectionPanelMarker,
+            ));
+        }
+    }
+}
+
+/// Only the ship the choice is currently for, when more than one went down at once
+fn update_inspection(
+    queue: Res<CaptureQueue>,
+    candidate: Query<
+        &Spacecraft,
+        With<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
+    >,
+    allies: Query<(), (With<Captured>, Without<PlayerMarker>)>,
+    mut panel: Query<&mut Text, With<InspectionPanelMarker>>,
+) {
+    let ship = queue
+        .current()
+        .and_then(|entity| candidate.get(entity).ok());
+    if let (Some(ship), Ok(mut text)) = (ship, panel.get_single_mut()) {
+        text.sections[0].value = inspection_report(ship, allies.iter().count());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{ShipType, ShipVariant};
+    use bevy::math::Vec2;
+
+    #[test]
+    fn inspecting_a_capture_shows_its_damage_and_the_fleet_it_joins() {
+        let mut ship = Spacecraft::from_template(ShipType::Ship4, Vec2::ZERO);
+        ship.variant = Some(ShipVariant::Gunboat);
+        ship.health = 0;
+        let report = inspection_report(&ship, 2);
+        let max_health = ship.profile().max_health;
+        assert!(report.starts_with("Ship4 Gunboat\n"), "{report}");
+        assert!(
+            report.contains(&format!("Hull: 0/{max_health}")),
+            "{report}"
+        );
+        assert!(report.contains(&format!("Armour: {:?}", ship.profile().armor)));
+        assert!(report.ends_with("Keeping it makes 3 allies"), "{report}");
+    }
+}
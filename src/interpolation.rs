@@ -0,0 +1,128 @@
+//! Keeps rendering apart from the simulation, so sprites can be drawn between two simulation steps.
+//! Ships and bullets move in `FixedUpdate`, [`SIMULATION_HZ`] times a second whatever the frame
+//! rate. Movement systems record each new position with [`Interpolated::step`], and
+//! [`interpolate_transforms`] places the sprite [`RenderAlpha`] of the way from the last step to it,
+//! so it has to run after everything that moves.
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        component::Component,
+        schedule::IntoSystemConfigs,
+        system::{Query, Res, ResMut, Resource},
+    },
+    math::Vec2,
+    prelude::App,
+    time::{Fixed, Time, Virtual},
+    transform::components::Transform,
+};
+
+use crate::gameplay::PIXELS_PER_UNIT;
+
+/// Simulation steps a second. Speeds and turn rates are all tuned per step at this rate.
+pub const SIMULATION_HZ: f64 = 60.;
+
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .insert_resource(RenderAlpha(1.))
+            .add_systems(Update, update_render_alpha.before(interpolate_transforms));
+    }
+}
+
+/// How far between the previous and latest simulation step to draw things
+#[derive(Resource)]
+pub struct RenderAlpha(pub f32);
+
+/// How many simulation steps this frame lasted, for what pilots and the AI do once a frame, like
+/// turning and throttling. Goes by the unscaled clock, as slow motion only ever slowed movement.
+pub fn frame_steps(time: &Time<Virtual>) -> f32 {
+    time.delta_seconds() / time.relative_speed() * SIMULATION_HZ as f32
+}
+
+fn update_render_alpha(time: Res<Time<Fixed>>, mut alpha: ResMut<RenderAlpha>) {
+    alpha.0 = time.overstep_fraction();
+}
+
+#[derive(Component)]
+pub struct Interpolated {
+    previous: Vec2,
+    current: Vec2,
+    depth: f32,
+}
+
+impl Interpolated {
+    pub fn new(position: Vec2, depth: f32) -> Self {
+        Self {
+            previous: position,
+            current: position,
+            depth,
+        }
+    }
+
+    pub fn step(&mut self, position: Vec2) {
+        self.previous = self.current;
+        self.current = position;
+    }
+
+    pub fn at(&self, alpha: f32) -> Vec2 {
+        self.previous.lerp(self.current, alpha)
+    }
+}
+
+pub fn interpolate_transforms(
+    alpha: Res<RenderAlpha>,
+    mut entities: Query<(&Interpolated, &mut Transform)>,
+) {
+    for (interpolated, mut transform) in entities.iter_mut() {
+        transform.translation =
+            (interpolated.at(alpha.0) * PIXELS_PER_UNIT).extend(interpolated.depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{move_spaceships, ShipType, Spacecraft, MAX_VELOCITY};
+    use bevy::{
+        app::FixedUpdate,
+        time::{Fixed, TimeUpdateStrategy},
+        MinimalPlugins,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn ships_cover_the_same_ground_at_any_frame_rate() {
+        let fly_for_a_second = |frame: Duration| {
+            let mut app = App::new();
+            app.add_plugins(MinimalPlugins)
+                .insert_resource(TimeUpdateStrategy::ManualDuration(frame))
+                .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+                .add_systems(FixedUpdate, move_spaceships);
+            let mut ship = Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO);
+            ship.velocity = MAX_VELOCITY;
+            let ship = app
+                .world
+                .spawn((ship, Interpolated::new(Vec2::ZERO, 10.)))
+                .id();
+            while app.world.resource::<Time<Virtual>>().elapsed() < Duration::from_secs(1) {
+                app.update();
+            }
+            app.world.get::<Spacecraft>(ship).unwrap().position
+        };
+        let at_50hz = fly_for_a_second(Duration::from_millis(20));
+        let at_200hz = fly_for_a_second(Duration::from_millis(5));
+        assert!(at_50hz.y > 50. * MAX_VELOCITY);
+        assert!((at_50hz - at_200hz).length() < 1e-5);
+
+        // Turning goes by the frame, at full speed even in slow motion
+        let mut time = Time::<Virtual>::default();
+        time.advance_by(Duration::from_millis(20));
+        assert!((frame_steps(&time) - 1.2).abs() < 1e-5);
+        time.set_relative_speed(0.1);
+        time.advance_by(Duration::from_millis(2));
+        assert!((frame_steps(&time) - 1.2).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,208 @@
+//! Fixed landmarks scattered around the arena, so there's something to steer by and somewhere for
+//! objectives to send the player. Each one shows as a pip on the origin dial.
+
+use std::f32::consts::TAU;
+
+use bevy::{
+    app::{Plugin, Update},
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res},
+    },
+    gizmos::gizmos::Gizmos,
+    hierarchy::BuildChildren,
+    math::{Quat, Vec2},
+    prelude::{default, App},
+    render::color::Color,
+    time::Time,
+    transform::components::Transform,
+    ui::{node_bundles::NodeBundle, PositionType, Style, Val},
+};
+
+use crate::{
+    gameplay::{GameplaySet, PlayerMarker, Spacecraft, PIXELS_PER_UNIT},
+    ui::{spawn_ui, update_instruments, HudElement, OriginDial, ORIGIN_DIAL_SIZE},
+    GameLifecycleState,
+};
+
+/// Landmarks this far from the player sit on the rim of the origin dial
+pub const DIAL_RANGE: f32 = 8.;
+const PIP_SIZE: f32 = 0.8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LandmarkKind {
+    WreckField,
+    Beacon,
+    Station,
+}
+
+impl LandmarkKind {
+    pub fn color(self) -> Color {
+        match self {
+            LandmarkKind::WreckField => Color::rgb(0.75, 0.55, 0.4),
+            LandmarkKind::Beacon => Color::rgb(1., 0.35, 0.35),
+            LandmarkKind::Station => Color::rgb(0.6, 0.9, 0.6),
+        }
+    }
+}
+
+pub struct Landmark {
+    pub name: &'static str,
+    pub kind: LandmarkKind,
+    pub position: Vec2,
+}
+
+/// Kept inside the border warning radius, so none of them is a trip into the wall
+pub const LANDMARKS: [Landmark; 4] = [
+    Landmark {
+        name: "Hesper Beacon",
+        kind: LandmarkKind::Beacon,
+        position: Vec2::new(0., 6.),
+    },
+    Landmark {
+        name: "the Kessler wrecks",
+        kind: LandmarkKind::WreckField,
+        position: Vec2::new(-6.5, -2.),
+    },
+    Landmark {
+        name: "Vigil Station",
+        kind: LandmarkKind::Station,
+        position: Vec2::new(5., -5.),
+    },
+    Landmark {
+        name: "Lucent Beacon",
+        kind: LandmarkKind::Beacon,
+        position: Vec2::new(7., 3.5),
+    },
+];
+
+/// Bits of hull in a wreck field, as offsets from its middle and a size, all in world units
+const WRECKAGE: [(Vec2, f32); 7] = [
+    (Vec2::new(0., 0.), 0.12),
+    (Vec2::new(0.35, 0.2), 0.07),
+    (Vec2::new(-0.3, 0.3), 0.09),
+    (Vec2::new(-0.45, -0.15), 0.05),
+    (Vec2::new(0.15, -0.4), 0.1),
+    (Vec2::new(0.5, -0.25), 0.04),
+    (Vec2::new(-0.1, 0.55), 0.06),
+];
+
+pub struct LandmarkPlugin;
+
+impl Plugin for LandmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameLifecycleState::Game),
+            spawn_landmark_pips.after(spawn_ui),
+        )
+        .add_systems(
+            Update,
+            (
+                draw_landmarks,
+                update_landmark_pips.after(update_instruments),
+            )
+                .in_set(GameplaySet::Presentation)
+                .run_if(in_state(GameLifecycleState::Game)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct LandmarkPip(usize);
+
+/// Where the pip for a landmark `offset` from the player sits on the origin dial, from -1 to 1
+/// across it, seen through a camera turned by `view`. The pip points the way the landmark looks on
+/// the stretched screen. Nearby landmarks sit towards the middle, and anything past [`DIAL_RANGE`]
+/// is pinned to the rim.
+pub fn dial_position(offset: Vec2, view: Quat) -> Vec2 {
+    let on_screen = (view.inverse() * (offset * PIXELS_PER_UNIT).extend(0.)).truncate();
+    on_screen.normalize_or_zero() * (offset.length() / DIAL_RANGE).min(1.)
+}
+
+fn spawn_landmark_pips(mut commands: Commands, dials: Query<Entity, With<OriginDial>>) {
+    if let Ok(dial) = dials.get_single() {
+        commands.entity(dial).with_children(|parent| {
+            for (index, landmark) in LANDMARKS.iter().enumerate() {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::VMin(PIP_SIZE),
+                            height: Val::VMin(PIP_SIZE),
+                            ..default()
+                        },
+                        background_color: landmark.kind.color().into(),
+                        ..default()
+                    },
+                    LandmarkPip(index),
+                    HudElement,
+                ));
+            }
+        });
+    }
+}
+
+fn update_landmark_pips(
+    ship: Query<&Spacecraft, With<PlayerMarker>>,
+    camera: Query<&Transform, With<Camera2d>>,
+    mut pips: Query<(&LandmarkPip, &mut Style), Without<OriginDial>>,
+) {
+    if let (Ok(ship), Ok(camera)) = (ship.get_single(), camera.get_single()) {
+        let radius = (ORIGIN_DIAL_SIZE - PIP_SIZE) / 2.;
+        for (pip, mut style) in pips.iter_mut() {
+            let offset = LANDMARKS[pip.0].position - ship.position;
+            let spot = dial_position(offset, camera.rotation);
+            style.left = Val::VMin(radius * (1. + spot.x));
+            style.top = Val::VMin(radius * (1. - spot.y));
+        }
+    }
+}
+
+fn draw_landmarks(mut gizmos: Gizmos, time: Res<Time>) {
+    let pulse = (time.elapsed_seconds() * 2.).sin() * 0.5 + 0.5;
+    for (index, landmark) in LANDMARKS.iter().enumerate() {
+        let centre = landmark.position * PIXELS_PER_UNIT;
+        let color = landmark.kind.color();
+        match landmark.kind {
+            LandmarkKind::Beacon => {
+                gizmos.circle_2d(centre, 0.05 * PIXELS_PER_UNIT.y, color);
+                gizmos
+                    .circle_2d(
+                        centre,
+                        (0.1 + 0.25 * pulse) * PIXELS_PER_UNIT.y,
+                        color.with_a(1. - pulse),
+                    )
+                    .segments(64);
+            }
+            LandmarkKind::WreckField => {
+                for (piece, (offset, size)) in WRECKAGE.iter().enumerate() {
+                    // Each piece tumbles at its own rate
+                    let spin = time.elapsed_seconds() * 0.1 * (piece as f32 + 1.) + index as f32;
+                    gizmos.rect_2d(
+                        centre + *offset * PIXELS_PER_UNIT,
+                        spin % TAU,
+                        Vec2::new(2., 1.) * *size * PIXELS_PER_UNIT.y,
+                        color,
+                    );
+                }
+            }
+            LandmarkKind::Station => {
+                let spin = time.elapsed_seconds() * 0.05;
+                gizmos.rect_2d(centre, spin, Vec2::splat(0.3 * PIXELS_PER_UNIT.y), color);
+                gizmos.rect_2d(
+                    centre,
+                    spin + TAU / 8.,
+                    Vec2::splat(0.2 * PIXELS_PER_UNIT.y),
+                    color,
+                );
+                gizmos
+                    .circle_2d(centre, 0.45 * PIXELS_PER_UNIT.y, color.with_a(0.4))
+                    .segments(64);
+            }
+        }
+    }
+}
@@ -0,0 +1,539 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Has, With},
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, NextState, OnEnter, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    input::{gamepad::GamepadButtonType, keyboard::KeyCode, ButtonInput},
+    prelude::{default, App},
+    reflect::Reflect,
+    render::color::Color,
+    text::{Text, TextSection, TextStyle},
+    time::{Time, Timer, TimerMode},
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, FlexDirection, JustifyContent, Style, UiRect, Val,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controls::Pads,
+    gameplay::{GameState, GameplaySet, PlayerMarker, ShipProfile, ShipType, Spacecraft},
+    pause::RestartRun,
+    records::MetaProgress,
+    seed::{settle_run_config, RunConfig},
+    storage, GameLifecycleState,
+};
+
+const LOADOUT_KEY: &str = "loadout.ron";
+
+/// How long an overdrive keeps the guns cycling at double speed
+pub const OVERDRIVE_TIME: Duration = Duration::from_secs(6);
+
+pub struct LoadoutPlugin;
+
+impl Plugin for LoadoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Loadout::load())
+            .add_systems(
+                OnEnter(GameLifecycleState::Loadout),
+                spawn_loadout_screen.run_if(not(resource_exists::<RestartRun>)),
+            )
+            .add_systems(
+                Update,
+                handle_loadout_inputs
+                    .run_if(in_state(GameLifecycleState::Loadout))
+                    .run_if(not(resource_exists::<RestartRun>)),
+            )
+            .add_systems(OnExit(GameLifecycleState::Loadout), despawn_loadout_screen)
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                arm_consumable.after(settle_run_config),
+            )
+            .add_systems(
+                Update,
+                (
+                    use_consumable.in_set(GameplaySet::Input),
+                    (tick_overdrive, run_repair_drones).in_set(GameplaySet::Simulation),
+                )
+                    .run_if(in_state(GameState::Regular))
+                    .run_if(in_state(GameLifecycleState::Game)),
+            );
+    }
+}
+
+/// What the player takes into a run, picked on the loadout screen before it starts
+#[derive(Resource, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Loadout {
+    pub hull: ShipType,
+    pub weapon: WeaponPattern,
+    pub passive: Passive,
+    pub consumable: Consumable,
+    /// An ally to start the run with, out of the presets bought with salvage credits
+    pub escort: Option<ShipType>,
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Self {
+            hull: ShipType::Ship2,
+            weapon: WeaponPattern::Standard,
+            passive: Passive::ExtraHull,
+            consumable: Consumable::RepairKit,
+            escort: None,
+        }
+    }
+}
+
+/// Hulls the player is allowed to start a run in
+pub const STARTING_HULLS: [ShipType; 2] = [ShipType::Ship1, ShipType::Ship2];
+/// Low tier hulls that can be bought once with salvage credits to start every run alongside, already
+/// captured, and what each costs
+pub const ESCORT_PRESETS: [(ShipType, u32); 3] = [
+    (ShipType::Ship1, 100),
+    (ShipType::Ship2, 250),
+    (ShipType::Ship3, 500),
+];
+
+pub fn escort_cost(hull: ShipType) -> Option<u32> {
+    ESCORT_PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == hull)
+        .map(|(_, cost)| *cost)
+}
+
+impl Loadout {
+    pub fn load() -> Self {
+        let mut loadout = storage::read(LOADOUT_KEY)
+            .and_then(|contents| ron::from_str::<Loadout>(&contents).ok())
+            .unwrap_or_default();
+        if !STARTING_HULLS.contains(&loadout.hull) {
+            loadout.hull = Loadout::default().hull;
+        }
+        if loadout
+            .escort
+            .is_some_and(|escort| escort_cost(escort).is_none())
+        {
+            loadout.escort = None;
+        }
+        loadout
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(LOADOUT_KEY, &contents),
+            Err(e) => println!("Could not serialise loadout: {e}"),
+        }
+    }
+
+    pub fn fitting(&self) -> Fitting {
+        Fitting {
+            weapon: self.weapon,
+            passive: self.passive,
+        }
+    }
+
+    fn next_hull(&mut self) {
+        let index = STARTING_HULLS
+            .iter()
+            .position(|hull| *hull == self.hull)
+            .unwrap_or(0);
+        self.hull = STARTING_HULLS[(index + 1) % STARTING_HULLS.len()];
+    }
+
+    /// Goes through the presets, with no escort after the last of them
+    pub fn next_escort(&mut self) {
+        let next = match self.escort {
+            None => 0,
+            Some(escort) => match ESCORT_PRESETS.iter().position(|(hull, _)| *hull == escort) {
+                Some(index) => index + 1,
+                None => ESCORT_PRESETS.len(),
+            },
+        };
+        self.escort = ESCORT_PRESETS.get(next).map(|(hull, _)| *hull);
+    }
+}
+
+/// The parts of a loadout that change the ship's stats
+#[derive(Clone, Copy, Debug, Reflect, Serialize, Deserialize)]
+pub struct Fitting {
+    pub weapon: WeaponPattern,
+    pub passive: Passive,
+}
+
+impl Fitting {
+    pub fn apply(&self, profile: &mut ShipProfile) {
+        match self.weapon {
+            WeaponPattern::Standard => {}
+            WeaponPattern::Spread => {
+                profile.shots = 3;
+                profile.spread *= 2.;
+            }
+            WeaponPattern::Focused => {
+                profile.shots = 1;
+                profile.base_bullet_velocity *= 1.4;
+                profile.gun_reload_time = profile.gun_reload_time.mul_f32(0.8);
+            }
+        }
+        match self.passive {
+            Passive::ExtraHull => profile.max_health += 1,
+            Passive::FasterTurn => profile.turn_rate *= 1.25,
+            Passive::CheaperShield => {
+                profile.shield_recharge_time = profile.shield_recharge_time.mul_f32(0.7)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum WeaponPattern {
+    /// Whatever guns the hull came with
+    Standard,
+    /// Three guns fanned out wide
+    Spread,
+    /// A single fast shot on a quicker reload
+    Focused,
+}
+
+impl WeaponPattern {
+    fn next(self) -> Self {
+        match self {
+            WeaponPattern::Standard => WeaponPattern::Spread,
+            WeaponPattern::Spread => WeaponPattern::Focused,
+            WeaponPattern::Focused => WeaponPattern::Standard,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum Passive {
+    ExtraHull,
+    FasterTurn,
+    CheaperShield,
+}
+
+impl Passive {
+    fn next(self) -> Self {
+        match self {
+            Passive::ExtraHull => Passive::FasterTurn,
+            Passive::FasterTurn => Passive::CheaperShield,
+            Passive::CheaperShield => Passive::ExtraHull,
+        }
+    }
+}
+
+/// Limited use items, triggered with [R] during a run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Consumable {
+    /// Restores the current ship to full health
+    RepairKit,
+    /// Doubles the rate of fire for [`OVERDRIVE_TIME`]
+    Overdrive,
+    /// Patches up one hull point every [`REPAIR_DRONE_PULSE`] for [`REPAIR_DRONE_TIME`],
+    /// without having to slow down like a shield recharge does
+    RepairDrone,
+}
+
+impl Consumable {
+    fn next(self) -> Self {
+        match self {
+            Consumable::RepairKit => Consumable::Overdrive,
+            Consumable::Overdrive => Consumable::RepairDrone,
+            Consumable::RepairDrone => Consumable::RepairKit,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Consumable::RepairKit => "Repair Kit",
+            Consumable::Overdrive => "Overdrive",
+            Consumable::RepairDrone => "Repair Drone",
+        }
+    }
+
+    pub fn starting_charges(self) -> u32 {
+        match self {
+            Consumable::RepairDrone => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// The consumable taken on this run, and how many uses of it are left
+#[derive(Resource)]
+pub struct ConsumableCharge {
+    pub consumable: Consumable,
+    pub charges: u32,
+}
+
+impl ConsumableCharge {
+    /// Tops up the charges, for pickups and the like
+    pub fn add(&mut self, charges: u32) {
+        self.charges += charges;
+    }
+}
+
+#[derive(Component)]
+pub struct Overdrive(Timer);
+
+pub const REPAIR_DRONE_TIME: Duration = Duration::from_secs(4);
+pub const REPAIR_DRONE_PULSE: Duration = Duration::from_secs(1);
+
+#[derive(Component)]
+pub struct RepairDrone {
+    duration: Timer,
+    pulse: Timer,
+}
+
+fn arm_consumable(mut commands: Commands, run: Res<RunConfig>) {
+    commands.insert_resource(ConsumableCharge {
+        consumable: run.loadout.consumable,
+        charges: run.loadout.consumable.starting_charges(),
+    });
+}
+
+fn use_consumable(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut charge: ResMut<ConsumableCharge>,
+    mut player: Query<(Entity, &mut Spacecraft, Has<RepairDrone>), With<PlayerMarker>>,
+) {
+    if !inputs.just_pressed(KeyCode::KeyR) || charge.charges == 0 {
+        return;
+    }
+    if let Ok((entity, mut craft, repairing)) = player.get_single_mut() {
+        match charge.consumable {
+            Consumable::RepairKit => craft.health = craft.profile().max_health,
+            Consumable::Overdrive => {
+                commands
+                    .entity(entity)
+                    .insert(Overdrive(Timer::new(OVERDRIVE_TIME, TimerMode::Once)));
+            }
+            Consumable::RepairDrone if repairing => return,
+            Consumable::RepairDrone => {
+                commands.entity(entity).insert(RepairDrone {
+                    duration: Timer::new(REPAIR_DRONE_TIME, TimerMode::Once),
+                    pulse: Timer::new(REPAIR_DRONE_PULSE, TimerMode::Repeating),
+                });
+            }
+        }
+        charge.charges -= 1;
+    }
+}
+
+fn run_repair_drones(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ships: Query<(Entity, &mut Spacecraft, &mut RepairDrone)>,
+) {
+    for (entity, mut craft, mut drone) in ships.iter_mut() {
+        if drone.pulse.tick(time.delta()).just_finished() {
+            craft.health = (craft.health + 1).min(craft.profile().max_health);
+        }
+        if drone.duration.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<RepairDrone>();
+        }
+    }
+}
+
+fn tick_overdrive(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ships: Query<(Entity, &mut Spacecraft, &mut Overdrive)>,
+) {
+    for (entity, mut craft, mut overdrive) in ships.iter_mut() {
+        craft.weapon_cooldown.tick(time.delta());
+        if overdrive.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Overdrive>();
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct LoadoutScreenMarker;
+
+#[derive(Component)]
+pub struct LoadoutTextMarker;
+
+fn loadout_text(loadout: &Loadout, progress: &MetaProgress) -> String {
+    let escort = match loadout.escort {
+        None => "None".to_string(),
+        Some(hull) if progress.escorts.contains(&hull) => format!("{hull:?}"),
+        Some(hull) => format!(
+            "{hull:?} (locked: [6] Unlock for {} of {} credits)",
+            escort_cost(hull).unwrap_or_default(),
+            progress.credits
+        ),
+    };
+    format!(
+        "[1] Hull: {:?}\n[2] Weapon: {:?}\n[3] Passive: {:?}\n[4] Consumable: {:?}\n[5] Escort: {}\n\n[Enter] Launch",
+        loadout.hull, loadout.weapon, loadout.passive, loadout.consumable, escort
+    )
+}
+
+fn spawn_loadout_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    loadout: Res<Loadout>,
+    progress: Res<MetaProgress>,
+) {
+    let alphbeta = asset_server.load("alphbeta.ttf");
+    let jupitercrash = asset_server.load("jupiterc.ttf");
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::BLACK.into(),
+            ..default()
+        })
+        .insert(LoadoutScreenMarker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Percent(4.)),
+                    ..default()
+                },
+                text: Text {
+                    sections: vec![TextSection {
+                        value: "Loadout".to_string(),
+                        style: TextStyle {
+                            font: jupitercrash,
+                            font_size: 56.,
+                            color: Color::WHITE,
+                        },
+                    }],
+                    ..default()
+                },
+                ..default()
+            });
+            parent
+                .spawn(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: loadout_text(&loadout, &progress),
+                            style: TextStyle {
+                                font: alphbeta,
+                                font_size: 24.,
+                                color: Color::WHITE,
+                            },
+                        }],
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(LoadoutTextMarker);
+        });
+}
+
+/// On a gamepad the d-pad cycles the hull, weapon, passive and consumable, clockwise from the left,
+/// with the escort on [West] and unlocking it on [North]
+fn handle_loadout_inputs(
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    mut loadout: ResMut<Loadout>,
+    mut progress: ResMut<MetaProgress>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+    mut text: Query<&mut Text, With<LoadoutTextMarker>>,
+) {
+    if inputs.just_pressed(KeyCode::Digit1) || pads.just_pressed(GamepadButtonType::DPadLeft) {
+        loadout.next_hull();
+    }
+    if inputs.just_pressed(KeyCode::Digit2) || pads.just_pressed(GamepadButtonType::DPadUp) {
+        loadout.weapon = loadout.weapon.next();
+    }
+    if inputs.just_pressed(KeyCode::Digit3) || pads.just_pressed(GamepadButtonType::DPadRight) {
+        loadout.passive = loadout.passive.next();
+    }
+    if inputs.just_pressed(KeyCode::Digit4) || pads.just_pressed(GamepadButtonType::DPadDown) {
+        loadout.consumable = loadout.consumable.next();
+    }
+    if inputs.just_pressed(KeyCode::Digit5) || pads.just_pressed(GamepadButtonType::West) {
+        loadout.next_escort();
+    }
+    if inputs.just_pressed(KeyCode::Digit6) || pads.just_pressed(GamepadButtonType::North) {
+        if let Some(escort) = loadout.escort {
+            if !progress.escorts.contains(&escort) && progress.unlock_escort(escort) {
+                progress.save();
+            }
+        }
+    }
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = loadout_text(&loadout, &progress);
+    }
+    if inputs.just_pressed(KeyCode::Enter) || pads.just_pressed(GamepadButtonType::South) {
+        loadout.save();
+        state.set(GameLifecycleState::Game);
+    }
+}
+
+fn despawn_loadout_screen(
+    mut commands: Commands,
+    screen: Query<Entity, With<LoadoutScreenMarker>>,
+) {
+    for entity in screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escort_presets_are_bought_once_and_start_every_run() {
+        let mut loadout = Loadout::default();
+        let mut picked = vec![];
+        for _ in 0..=ESCORT_PRESETS.len() {
+            loadout.next_escort();
+            picked.push(loadout.escort);
+        }
+        assert_eq!(
+            picked,
+            [
+                Some(ShipType::Ship1),
+                Some(ShipType::Ship2),
+                Some(ShipType::Ship3),
+                None
+            ]
+        );
+
+        let cost = escort_cost(ShipType::Ship2).unwrap();
+        let mut progress = MetaProgress {
+            credits: cost - 1,
+            ..default()
+        };
+        loadout.escort = Some(ShipType::Ship2);
+        assert!(!progress.unlock_escort(ShipType::Ship2));
+        assert_eq!(progress.starting_escort(&loadout), None);
+        // Only the cheap hulls are on offer
+        progress.credits = 10_000;
+        assert!(!progress.unlock_escort(ShipType::Ship6));
+
+        progress.credits = cost + 5;
+        assert!(progress.unlock_escort(ShipType::Ship2));
+        assert_eq!(progress.credits, 5);
+        assert_eq!(progress.banked(), cost + 5);
+        assert_eq!(progress.starting_escort(&loadout), Some(ShipType::Ship2));
+        // Already owned, so it's free from here on
+        assert!(progress.unlock_escort(ShipType::Ship2));
+        assert_eq!(progress.credits, 5);
+    }
+}
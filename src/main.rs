@@ -1,10 +1,13 @@
+use audio::GameAudioPlugin;
 use bevy::{
     app::{App, PluginGroup, Update},
     asset::{AssetMetaCheck, AssetServer, Handle},
-    core_pipeline::core_2d::Camera2dBundle,
+    core_pipeline::core_2d::{Camera2d, Camera2dBundle},
     ecs::{
+        change_detection::DetectChanges,
         component::Component,
         entity::Entity,
+        event::EventReader,
         query::With,
         schedule::{
             common_conditions::in_state, IntoSystemConfigs, NextState, OnEnter, OnExit, States,
@@ -12,7 +15,7 @@ use bevy::{
         system::{Commands, Query, Res, ResMut, Resource},
     },
     hierarchy::{BuildChildren, DespawnRecursiveExt},
-    input::{keyboard::KeyCode, ButtonInput},
+    input::{gamepad::GamepadButtonType, keyboard::KeyCode, ButtonInput},
     prelude::default,
     render::{
         color::Color,
@@ -24,22 +27,103 @@ use bevy::{
         AlignItems, FlexDirection, JustifyContent, PositionType, Style, UiImage, UiRect, Val,
         ZIndex,
     },
-    window::Window,
+    window::{Window, WindowResized},
     DefaultPlugins,
 };
+use bindings::{Action, InputBindings, InputBindingsPlugin};
+use controls::{ControlScheme, ControlsPlugin, Pads};
 use dialogue::Dialogue;
 use gameplay::{GameplayPlugin, PlayerScore};
+use hud::HudLayoutPlugin;
+use pause::RestartRun;
+use practice::CapturePractice;
+use profile::ProfilePlugin;
+use records::{bank_run, BankedPlace, HighScores, MetaProgress, RecordsPlugin, RunRetired};
+use score::ScoreBreakdown;
+use seed::{run_config_text, RunConfig, RunSeed, RunSeedPlugin};
+use settings::{Settings, SettingsPlugin};
+use stats::RunStats;
+use unlocks::UnlockCards;
+use volume::VolumeSettingsPlugin;
 
+pub mod ace;
+pub mod assist;
+pub mod audio;
+pub mod aura;
+pub mod barks;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod bindings;
+pub mod border;
+pub mod boss;
+pub mod capital;
+pub mod carrier;
+#[cfg(feature = "dev_cheats")]
+pub mod cheats;
+pub mod checkpoint;
+pub mod controls;
+pub mod convoy;
+pub mod crew;
+pub mod culling;
+pub mod damage;
 pub mod dialogue;
+pub mod engines;
+pub mod events;
+pub mod fleet;
 pub mod gameplay;
+pub mod ghost;
+pub mod hud;
+pub mod impacts;
+pub mod inspect;
+pub mod interpolation;
+pub mod landmarks;
+pub mod loadout;
+pub mod morale;
+pub mod nebula;
+pub mod objectives;
+pub mod pacing;
+pub mod pause;
+pub mod photo;
+pub mod practice;
+pub mod profile;
+pub mod range;
+pub mod recoil;
+pub mod records;
+pub mod salvage;
+pub mod score;
+pub mod seed;
+pub mod settings;
+pub mod spectate;
+pub mod stats;
+pub mod status;
+pub mod storage;
+pub mod telemetry;
+pub mod turret;
 pub mod ui;
+pub mod unlocks;
+pub mod upgrades;
+pub mod volume;
+pub mod waves;
+pub mod weather;
+pub mod wrecks;
 
 fn main() {
     App::new()
         .insert_resource(AssetMetaCheck::Never)
         .insert_state(GameLifecycleState::MainMenu)
         .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
-        .add_plugins(GameplayPlugin)
+        .add_plugins((
+            ProfilePlugin,
+            SettingsPlugin,
+            RecordsPlugin,
+            GameplayPlugin,
+            GameAudioPlugin,
+            VolumeSettingsPlugin,
+            ControlsPlugin,
+            InputBindingsPlugin,
+            HudLayoutPlugin,
+            RunSeedPlugin,
+        ))
         .add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_main_menu)
         .add_systems(
             Update,
@@ -51,8 +135,18 @@ fn main() {
             handle_inputs_tutorial.run_if(in_state(GameLifecycleState::Tutorial)),
         )
         .add_systems(OnExit(GameLifecycleState::Tutorial), despawn_tutorial)
-        .add_systems(OnEnter(GameLifecycleState::EndScreen), spawn_end_screen)
+        .add_systems(
+            OnEnter(GameLifecycleState::EndScreen),
+            spawn_end_screen.after(bank_run),
+        )
+        .add_systems(
+            Update,
+            (handle_end_screen_inputs, update_end_screen_prompt)
+                .run_if(in_state(GameLifecycleState::EndScreen)),
+        )
+        .add_systems(OnExit(GameLifecycleState::EndScreen), despawn_end_screen)
         .add_systems(OnExit(GameLifecycleState::MainMenu), kill_main_menu)
+        .add_systems(Update, fit_backgrounds_to_window)
         .run();
 }
 
@@ -60,15 +154,24 @@ fn main() {
 pub enum GameLifecycleState {
     MainMenu,
     Tutorial,
+    Loadout,
     Game,
     EndScreen,
 }
 
-fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, window: Query<&Window>) {
+fn spawn_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    window: Query<&Window>,
+    cameras: Query<(), With<Camera2d>>,
+    settings: Res<Settings>,
+) {
     commands.insert_resource(BackgroundPNG(asset_server.load("background.png")));
-    let window = window.single();
-    let side_len = f32::max(window.width(), window.height());
-    commands.spawn(Camera2dBundle::default());
+    let side_len = FullscreenBackground(1.).side_len(window.single());
+    // Coming back from a run, the camera is still around
+    if cameras.is_empty() {
+        commands.spawn(Camera2dBundle::default());
+    }
     let background = asset_server.load("main_menu.png");
     commands
         .spawn(ImageBundle {
@@ -80,18 +183,56 @@ fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, windo
             image: UiImage::new(background),
             ..default()
         })
-        .insert(MainMenuMarker);
+        .insert((MainMenuMarker, FullscreenBackground(1.)));
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(15.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection {
+                    value: difficulty_text(&settings),
+                    style: TextStyle {
+                        font: asset_server.load("alphbeta.ttf"),
+                        font_size: 20.,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..default()
+            },
+            ..default()
+        })
+        .insert((MainMenuMarker, DifficultyTextMarker));
+}
+
+#[derive(Component)]
+pub struct DifficultyTextMarker;
+
+fn difficulty_text(settings: &Settings) -> String {
+    format!("[D] Difficulty: {:?}", settings.difficulty)
 }
 
 fn handle_inputs(
     inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
     mut state: ResMut<NextState<GameLifecycleState>>,
+    mut settings: ResMut<Settings>,
+    mut difficulty_label: Query<&mut Text, With<DifficultyTextMarker>>,
 ) {
-    if inputs.pressed(KeyCode::Space) {
+    if inputs.just_pressed(KeyCode::KeyD) {
+        settings.difficulty = settings.difficulty.next();
+        if let Ok(mut label) = difficulty_label.get_single_mut() {
+            label.sections[0].value = difficulty_text(&settings);
+        }
+    }
+    if inputs.pressed(KeyCode::Space) || pads.pressed(GamepadButtonType::South) {
         state.set(GameLifecycleState::Tutorial);
     }
-    if inputs.pressed(KeyCode::KeyT) {
-        state.set(GameLifecycleState::Game);
+    if inputs.pressed(KeyCode::KeyT) || pads.pressed(GamepadButtonType::Start) {
+        state.set(GameLifecycleState::Loadout);
     }
 }
 
@@ -104,11 +245,25 @@ fn kill_main_menu(mut commands: Commands, to_kill: Query<Entity, With<MainMenuMa
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_end_screen(
     mut commands: Commands,
     score: Res<PlayerScore>,
+    breakdown: Res<ScoreBreakdown>,
+    stats: Res<RunStats>,
+    progress: Res<MetaProgress>,
+    retired: Option<Res<RunRetired>>,
     asset_server: Res<AssetServer>,
+    scheme: Res<ControlScheme>,
+    seed: Res<RunSeed>,
+    run: Res<RunConfig>,
+    place: Res<BankedPlace>,
+    high_scores: Res<HighScores>,
 ) {
+    let title = match retired {
+        Some(_) => "Run Retired",
+        None => "Game Over",
+    };
     let alphbeta = asset_server.load("alphbeta.ttf");
     let jupitercrash = asset_server.load("jupiterc.ttf");
     commands
@@ -124,6 +279,7 @@ fn spawn_end_screen(
             background_color: Color::BLACK.into(),
             ..default()
         })
+        .insert(EndScreenMarker)
         .with_children(|parent| {
             parent.spawn(TextBundle {
                 style: Style {
@@ -132,7 +288,7 @@ fn spawn_end_screen(
                 },
                 text: Text {
                     sections: vec![TextSection {
-                        value: "Game Over".to_string(),
+                        value: title.to_string(),
                         style: TextStyle {
                             font: jupitercrash,
                             font_size: 72.,
@@ -143,14 +299,35 @@ fn spawn_end_screen(
                 },
                 ..default()
             });
+            if let Some(headline) = place.headline(high_scores.entries.len()) {
+                parent.spawn(TextBundle {
+                    style: Style {
+                        padding: UiRect::bottom(Val::Px(20.)),
+                        ..default()
+                    },
+                    text: Text::from_section(
+                        headline,
+                        TextStyle {
+                            font: alphbeta.clone(),
+                            font_size: 32.,
+                            color: Color::GOLD,
+                        },
+                    ),
+                    ..default()
+                });
+            }
 
             parent.spawn(TextBundle {
                 text: Text {
                     sections: vec![TextSection {
                         value: format!(
-                            "Score: {}\nTime Alive: {:?}",
+                            "Score: {}\nTime Alive: {:?}\nScrap salvaged: {}\nSalvage credits: +{} ({} total)\n{}",
                             score.score,
-                            score.survived_time.elapsed()
+                            score.survived_time.elapsed(),
+                            score.scrap,
+                            progress.last_reward,
+                            progress.credits,
+                            run_config_text(seed.seed, run.difficulty, &run.loadout)
                         ),
                         style: TextStyle {
                             font: alphbeta.clone(),
@@ -162,6 +339,63 @@ fn spawn_end_screen(
                 },
                 ..default()
             });
+            parent.spawn(TextBundle {
+                style: Style {
+                    padding: UiRect::top(Val::Px(20.)),
+                    ..default()
+                },
+                text: Text {
+                    sections: vec![TextSection {
+                        value: breakdown.summary(),
+                        style: TextStyle {
+                            font: alphbeta.clone(),
+                            font_size: 18.,
+                            color: Color::GRAY,
+                        },
+                    }],
+                    ..default()
+                },
+                ..default()
+            });
+            parent.spawn(TextBundle {
+                style: Style {
+                    padding: UiRect::top(Val::Px(20.)),
+                    ..default()
+                },
+                text: Text {
+                    sections: vec![TextSection {
+                        value: stats.combat_report(),
+                        style: TextStyle {
+                            font: alphbeta.clone(),
+                            font_size: 18.,
+                            color: Color::GRAY,
+                        },
+                    }],
+                    ..default()
+                },
+                ..default()
+            });
+            parent.spawn((
+                TextBundle {
+                    style: Style {
+                        padding: UiRect::top(Val::Px(30.)),
+                        ..default()
+                    },
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: end_screen_prompt(*scheme),
+                            style: TextStyle {
+                                font: alphbeta.clone(),
+                                font_size: 24.,
+                                color: Color::WHITE,
+                            },
+                        }],
+                        ..default()
+                    },
+                    ..default()
+                },
+                EndScreenPromptMarker,
+            ));
             parent.spawn(TextBundle {
                 style: Style {
                     position_type: PositionType::Absolute,
@@ -190,6 +424,56 @@ fn spawn_end_screen(
         });
 }
 
+#[derive(Component)]
+pub struct EndScreenMarker;
+
+#[derive(Component)]
+pub struct EndScreenPromptMarker;
+
+fn end_screen_prompt(scheme: ControlScheme) -> String {
+    format!(
+        "[{}] New run    [{}] Main menu",
+        scheme.prompt("R", "Y"),
+        scheme.prompt("M", "Select")
+    )
+}
+
+fn update_end_screen_prompt(
+    scheme: Res<ControlScheme>,
+    mut prompt: Query<&mut Text, With<EndScreenPromptMarker>>,
+) {
+    if scheme.is_changed() {
+        for mut prompt in prompt.iter_mut() {
+            prompt.sections[0].value = end_screen_prompt(*scheme);
+        }
+    }
+}
+
+/// Waits until any unlock cards have been read, so the keys aren't pressed through them
+fn handle_end_screen_inputs(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    cards: Option<Res<UnlockCards>>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+) {
+    if cards.is_some_and(|cards| !cards.0.is_empty()) {
+        return;
+    }
+    if inputs.just_pressed(KeyCode::KeyR) || pads.just_pressed(GamepadButtonType::North) {
+        commands.insert_resource(RestartRun);
+        state.set(GameLifecycleState::Loadout);
+    } else if inputs.just_pressed(KeyCode::KeyM) || pads.just_pressed(GamepadButtonType::Select) {
+        state.set(GameLifecycleState::MainMenu);
+    }
+}
+
+fn despawn_end_screen(mut commands: Commands, end_screen: Query<Entity, With<EndScreenMarker>>) {
+    for entity in end_screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 #[derive(Resource)]
 struct TutorialDialogue {
     dialogue: Vec<String>,
@@ -207,30 +491,53 @@ fn init_tutorial(mut commands: Commands, background: Res<BackgroundPNG>, window:
         "By pressing [1], you will switch perspective to that ship, controlling it yourself.",
         "By pressing [2], you'll turn the ship into an ally, fighting for us, but without you controlling it.",
         "By pressing [3], you'll scuttle the ship where is flies, destroying it.",
-        "Hopefully that might give us a chance against the bigger ships out there.",
-        "There's one final thing, captain. If you press [S], we'll begin to recharge shields.",
-        "But be careful! We can't move while they're charging; we're sitting ducks.",
-        "Good luck, and may the stars guide us"
+        "Let's give it a try. We've parked a practice target just outside."
     ];
     let diague_string = dialogues.iter().map(|d| d.to_string()).collect::<Vec<_>>();
     commands.insert_resource(TutorialDialogue {
         dialogue: diague_string,
         index: 0,
     });
-    let window = window.single();
-    let side_len = f32::max(window.width(), window.height());
+    let side_len = FullscreenBackground(3.).side_len(window.single());
     commands
         .spawn(ImageBundle {
             style: Style {
-                width: Val::Px(side_len * 3.),
-                height: Val::Px(side_len * 3.),
+                width: Val::Px(side_len),
+                height: Val::Px(side_len),
                 ..default()
             },
             image: UiImage::new(background.0.clone()),
             z_index: ZIndex::Global(-1),
             ..default()
         })
-        .insert(TutorialBackgroundMarker);
+        .insert((TutorialBackgroundMarker, FullscreenBackground(3.)));
+}
+
+/// A square UI image covering the whole window, this many times the window's longer side
+#[derive(Component)]
+pub struct FullscreenBackground(f32);
+
+impl FullscreenBackground {
+    fn side_len(&self, window: &Window) -> f32 {
+        f32::max(window.width(), window.height()) * self.0
+    }
+}
+
+fn fit_backgrounds_to_window(
+    mut resized: EventReader<WindowResized>,
+    window: Query<&Window>,
+    mut backgrounds: Query<(&mut Style, &FullscreenBackground)>,
+) {
+    if resized.read().last().is_none() {
+        return;
+    }
+    if let Ok(window) = window.get_single() {
+        for (mut style, background) in backgrounds.iter_mut() {
+            let side_len = background.side_len(window);
+            style.width = Val::Px(side_len);
+            style.height = Val::Px(side_len);
+        }
+    }
 }
 
 #[derive(Component)]
@@ -240,14 +547,21 @@ pub struct TutorialBackgroundMarker;
 pub struct BackgroundPNG(pub Handle<Image>);
 
 fn handle_inputs_tutorial(
+    mut commands: Commands,
     inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    bindings: Res<InputBindings>,
     mut dialogue: ResMut<Dialogue>,
     mut tutorial_words: ResMut<TutorialDialogue>,
     mut state: ResMut<NextState<GameLifecycleState>>,
 ) {
-    if inputs.just_released(KeyCode::Enter) {
+    // Nothing else is going on, so the pad keeps [A] rather than the in-flight dialogue button
+    if inputs.just_released(bindings.key(Action::AdvanceDialogue))
+        || pads.just_released(GamepadButtonType::South)
+    {
         tutorial_words.index += 1;
         if tutorial_words.index >= tutorial_words.dialogue.len() {
+            commands.insert_resource(CapturePractice::default());
             state.set(GameLifecycleState::Game);
             return;
         }
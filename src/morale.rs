@@ -0,0 +1,311 @@
+//! The fleet's morale. A run of captures and kills lifts it, and every ally lost knocks it back,
+//! while a lull lets it settle towards steady again. In high spirits allies reload a little faster
+//! and call in over the radio; when it's low they break off long before they would otherwise.
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::{AssetServer, Assets},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Local, Query, Res, ResMut, Resource},
+    },
+    hierarchy::BuildChildren,
+    prelude::{default, App},
+    render::color::Color,
+    text::TextStyle,
+    time::Time,
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        BackgroundColor, FlexDirection, Style, UiRect, Val,
+    },
+};
+use rand::seq::SliceRandom;
+
+use crate::{
+    crew::CrewScript,
+    dialogue::{Dialogue, DialogueScript},
+    events::{Allegiance, ShipCaptured, ShipDestroyed},
+    fleet::AllyStance,
+    gameplay::{handle_npc_logic, Captured, GameState, GameplaySet, PlayerMarker, Spacecraft},
+    ui::{spawn_ui, HudAnchor, HudElement},
+    GameLifecycleState,
+};
+
+/// Where morale starts, and settles back to
+pub const STEADY_MORALE: f32 = 0.5;
+pub const HIGH_MORALE: f32 = 0.75;
+pub const LOW_MORALE: f32 = 0.25;
+pub const CAPTURE_MORALE: f32 = 0.15;
+pub const KILL_MORALE: f32 = 0.03;
+pub const ALLY_LOST_MORALE: f32 = 0.2;
+/// Roughly how long, in seconds, morale takes to settle once the fighting lets up
+pub const MORALE_SETTLE_TIME: f32 = 45.;
+/// Extra reload progress for allies in high spirits, as a fraction of real time
+pub const HIGH_MORALE_RELOAD_BONUS: f32 = 0.08;
+/// How much more of its hull an ally keeps back before breaking off when morale is low
+pub const LOW_MORALE_RETREAT: f32 = 0.2;
+
+pub struct FleetMoralePlugin;
+
+impl Plugin for FleetMoralePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameLifecycleState::Game),
+            (reset_morale, spawn_morale_meter.after(spawn_ui)),
+        )
+        .add_systems(
+            Update,
+            (
+                rally_allies
+                    .after(handle_npc_logic)
+                    .in_set(GameplaySet::Input),
+                track_morale.in_set(GameplaySet::Cleanup),
+                (call_in_high_spirits, update_morale_meter).in_set(GameplaySet::Presentation),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoraleLevel {
+    Low,
+    Steady,
+    High,
+}
+
+impl MoraleLevel {
+    fn color(self) -> Color {
+        match self {
+            MoraleLevel::Low => Color::rgb(0.8, 0.3, 0.25),
+            MoraleLevel::Steady => Color::rgb(0.85, 0.75, 0.35),
+            MoraleLevel::High => Color::rgb(0.35, 0.85, 0.45),
+        }
+    }
+}
+
+/// From 0 to 1, with [`STEADY_MORALE`] in the middle
+#[derive(Resource)]
+pub struct FleetMorale(pub f32);
+
+impl Default for FleetMorale {
+    fn default() -> Self {
+        Self(STEADY_MORALE)
+    }
+}
+
+impl FleetMorale {
+    pub fn level(&self) -> MoraleLevel {
+        match self.0 {
+            x if x >= HIGH_MORALE => MoraleLevel::High,
+            x if x <= LOW_MORALE => MoraleLevel::Low,
+            _ => MoraleLevel::Steady,
+        }
+    }
+
+    pub fn lift(&mut self, amount: f32) {
+        self.0 = (self.0 + amount).clamp(0., 1.);
+    }
+
+    /// Eases back the same amount over the same time, whatever the frame rate
+    pub fn settle(&mut self, delta_secs: f32) {
+        let eased = 1. - (-delta_secs / MORALE_SETTLE_TIME).exp();
+        self.0 += (STEADY_MORALE - self.0) * eased;
+    }
+
+    /// Below this fraction of its hull, an ally with this stance breaks off
+    pub fn retreat_health(&self, stance: AllyStance) -> f32 {
+        match self.level() {
+            MoraleLevel::Low => (stance.retreat_health() + LOW_MORALE_RETREAT).min(1.),
+            _ => stance.retreat_health(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MoraleMeterMarker;
+
+fn reset_morale(mut commands: Commands) {
+    commands.insert_resource(FleetMorale::default());
+}
+
+/// Kills only count if someone fired the shot, so enemies lost to the border don't lift anyone
+pub fn track_morale(
+    time: Res<Time>,
+    mut morale: ResMut<FleetMorale>,
+    mut captured: EventReader<ShipCaptured>,
+    mut destroyed: EventReader<ShipDestroyed>,
+) {
+    morale.settle(time.delta_seconds());
+    for _ in captured.read() {
+        morale.lift(CAPTURE_MORALE);
+    }
+    for loss in destroyed.read() {
+        match loss.allegiance {
+            Allegiance::Enemy if loss.killer.is_some() => morale.lift(KILL_MORALE),
+            Allegiance::Ally => morale.lift(-ALLY_LOST_MORALE),
+            _ => (),
+        }
+    }
+}
+
+pub fn rally_allies(
+    time: Res<Time>,
+    morale: Res<FleetMorale>,
+    mut allies: Query<&mut Spacecraft, (With<Captured>, Without<PlayerMarker>)>,
+) {
+    if morale.level() != MoraleLevel::High {
+        return;
+    }
+    for mut ally in allies.iter_mut() {
+        let bonus = time.delta().mul_f32(HIGH_MORALE_RELOAD_BONUS);
+        ally.weapon_cooldown.tick(bonus);
+    }
+}
+
+/// Once each time morale climbs into high spirits, if there's anyone left to say it
+fn call_in_high_spirits(
+    morale: Res<FleetMorale>,
+    mut was_high: Local<bool>,
+    crew_script: Res<CrewScript>,
+    scripts: Res<Assets<DialogueScript>>,
+    mut dialogue: ResMut<Dialogue>,
+    allies: Query<(), (With<Captured>, Without<PlayerMarker>)>,
+) {
+    let high = morale.level() == MoraleLevel::High;
+    if high && !*was_high && !allies.is_empty() {
+        let line = scripts
+            .get(&crew_script.0)
+            .and_then(|script| script.rallying.choose(&mut rand::thread_rng()));
+        if let Some(line) = line {
+            dialogue.chatter(line.clone());
+        }
+    }
+    *was_high = high;
+}
+
+fn spawn_morale_meter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    anchors: Query<(Entity, &HudAnchor)>,
+) {
+    let meter = commands
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::top(Val::Px(8.)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "Morale",
+                    TextStyle {
+                        font: asset_server.load("alphbeta.ttf"),
+                        font_size: 14.,
+                        color: Color::GRAY,
+                    },
+                ))
+                .insert(HudElement);
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(120.),
+                        height: Val::Px(4.),
+                        margin: UiRect::top(Val::Px(2.)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                    ..default()
+                })
+                .insert(HudElement)
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Percent(STEADY_MORALE * 100.),
+                                height: Val::Percent(100.),
+                                ..default()
+                            },
+                            background_color: MoraleLevel::Steady.color().into(),
+                            ..default()
+                        })
+                        .insert((MoraleMeterMarker, HudElement));
+                });
+        })
+        .id();
+    HudAnchor::TopLeft.attach(&mut commands, &anchors, meter);
+}
+
+/// Keeps whatever alpha the HUD's fade has given the fill
+fn update_morale_meter(
+    morale: Res<FleetMorale>,
+    mut meter: Query<(&mut Style, &mut BackgroundColor), With<MoraleMeterMarker>>,
+) {
+    if let Ok((mut style, mut color)) = meter.get_single_mut() {
+        style.width = Val::Percent(morale.0 * 100.);
+        color.0 = morale.level().color().with_a(color.0.a());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, ShipType};
+    use bevy::math::Vec2;
+
+    #[test]
+    fn fleet_morale_lifts_with_a_capture_streak_and_sours_with_losses() {
+        let mut app = test_app();
+        app.init_resource::<FleetMorale>()
+            .add_systems(Update, track_morale);
+        app.update();
+        let ship = app.world.spawn_empty().id();
+        for _ in 0..2 {
+            app.world.send_event(ShipCaptured {
+                ship,
+                ship_type: ShipType::Ship2,
+            });
+        }
+        app.update();
+        assert_eq!(
+            app.world.resource::<FleetMorale>().level(),
+            MoraleLevel::High
+        );
+        // A kill nobody made doesn't count, losing allies does
+        app.world.insert_resource(FleetMorale::default());
+        let loss = |allegiance, killer| ShipDestroyed {
+            ship,
+            ship_type: ShipType::Ship1,
+            allegiance,
+            position: Vec2::ZERO,
+            killer,
+        };
+        app.world.send_event(loss(Allegiance::Enemy, None));
+        app.update();
+        let steady = app.world.resource::<FleetMorale>().0;
+        assert!((steady - 0.5).abs() < 1e-5);
+        app.world.send_event(loss(Allegiance::Ally, Some(ship)));
+        app.world.send_event(loss(Allegiance::Ally, Some(ship)));
+        app.update();
+        let morale = app.world.resource::<FleetMorale>();
+        assert_eq!(morale.level(), MoraleLevel::Low);
+        let stance = AllyStance::Balanced;
+        assert!(
+            (morale.retreat_health(stance) - stance.retreat_health() - LOW_MORALE_RETREAT).abs()
+                < 1e-5
+        );
+
+        // Left alone, it settles back towards steady
+        let mut morale = FleetMorale(1.);
+        morale.settle(60.);
+        assert_eq!(morale.level(), MoraleLevel::Steady);
+    }
+}
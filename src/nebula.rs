@@ -0,0 +1,242 @@
+//! The arena drifts towards a red nebula as the spawn budget ramps up. The backdrop warms, more
+//! stars come out and a dim red light settles over everything, so how dangerous the run has got
+//! can be read off the surroundings without looking at the clock.
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::Assets,
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        component::Component,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, OnEnter,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    gizmos::gizmos::Gizmos,
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    sprite::Sprite,
+    time::Time,
+    transform::components::Transform,
+    ui::{node_bundles::NodeBundle, BackgroundColor, PositionType, Style, Val, ZIndex},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    gameplay::{
+        focus_position, remember_persistent_entities, GameState, GameplaySet, PlayerMarker,
+        PlayerScore, Spacecraft, PIXELS_PER_UNIT,
+    },
+    pacing::{SpawnCurve, SpawnPacing, SpawnPacingHandle},
+    range::TargetRange,
+    seed::RunConfig,
+    spectate::Spectating,
+    GameLifecycleState,
+};
+
+/// Spawn points per update at which the nebula is as thick as it gets
+pub const FULL_DANGER_POINTS: f32 = 12.;
+/// Roughly how long, in seconds, the nebula takes to catch up with the spawn budget
+pub const DANGER_DRIFT_TIME: f32 = 20.;
+/// Width and height, in world units, of the patch of stars that repeats across the arena
+const STAR_TILE: f32 = 2.;
+const CALM_STARS: usize = 8;
+const MAX_STARS: usize = 40;
+const NEBULA_TINT: Color = Color::rgb(1., 0.55, 0.5);
+const NEBULA_LIGHT: Color = Color::rgb(0.55, 0.05, 0.1);
+/// How strong the red light is at full danger
+const MAX_LIGHT_ALPHA: f32 = 0.18;
+/// The same sky every run, so it reads as the same place getting worse
+const STAR_SEED: u64 = 0x5A1_7A6E;
+
+pub struct NebulaPlugin;
+
+impl Plugin for NebulaPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Starfield::generate())
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                spawn_nebula_light.after(remember_persistent_entities),
+            )
+            .add_systems(
+                Update,
+                drift_danger
+                    .in_set(GameplaySet::Simulation)
+                    .run_if(not(resource_exists::<TargetRange>))
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            )
+            .add_systems(
+                Update,
+                (shade_nebula, draw_stars)
+                    .in_set(GameplaySet::Presentation)
+                    .run_if(in_state(GameLifecycleState::Game)),
+            );
+    }
+}
+
+/// How far into the nebula the run is, from 0 at the start up to 1
+#[derive(Resource, Default)]
+pub struct Danger(pub f32);
+
+/// The backdrop sprite the run is played over
+#[derive(Component)]
+pub struct ArenaBackground;
+
+#[derive(Component)]
+pub struct NebulaLightMarker;
+
+/// Star offsets within a [`STAR_TILE`], in the order they come out
+#[derive(Resource)]
+pub struct Starfield(pub Vec<Vec2>);
+
+impl Starfield {
+    fn generate() -> Self {
+        let mut rng = StdRng::seed_from_u64(STAR_SEED);
+        Self(
+            (0..MAX_STARS)
+                .map(|_| Vec2::new(rng.gen_range(0. ..STAR_TILE), rng.gen_range(0. ..STAR_TILE)))
+                .collect(),
+        )
+    }
+}
+
+/// Where the spawn budget puts the nebula, before it has had time to drift there
+pub fn danger_level(points: i32) -> f32 {
+    (points as f32 / FULL_DANGER_POINTS).clamp(0., 1.)
+}
+
+/// Eases towards the target the same amount over the same time, whatever the frame rate
+pub fn drift_towards(current: f32, target: f32, delta_secs: f32) -> f32 {
+    current + (target - current) * (1. - (-delta_secs / DANGER_DRIFT_TIME).exp())
+}
+
+pub fn nebula_tint(danger: f32) -> Color {
+    let calm = Color::WHITE;
+    Color::rgb(
+        calm.r() + (NEBULA_TINT.r() - calm.r()) * danger,
+        calm.g() + (NEBULA_TINT.g() - calm.g()) * danger,
+        calm.b() + (NEBULA_TINT.b() - calm.b()) * danger,
+    )
+}
+
+pub fn star_count(danger: f32) -> usize {
+    CALM_STARS + ((MAX_STARS - CALM_STARS) as f32 * danger).round() as usize
+}
+
+fn spawn_nebula_light(mut commands: Commands) {
+    commands.insert_resource(Danger::default());
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            background_color: NEBULA_LIGHT.with_a(0.).into(),
+            // Under the HUD, but over the arena
+            z_index: ZIndex::Global(-1),
+            ..default()
+        },
+        NebulaLightMarker,
+    ));
+}
+
+/// Follows the same budget [`crate::gameplay::spawn_ships`] works from
+#[allow(clippy::too_many_arguments)]
+fn drift_danger(
+    time: Res<Time>,
+    mut danger: ResMut<Danger>,
+    score: Res<PlayerScore>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    spectating: Option<Res<Spectating>>,
+    run: Res<RunConfig>,
+    pacing: Res<SpawnPacingHandle>,
+    pacings: Res<Assets<SpawnPacing>>,
+) {
+    let default_curve = SpawnCurve::default();
+    let curve = pacings
+        .get(&pacing.0)
+        .map(|p| p.curve(run.difficulty))
+        .unwrap_or(&default_curve);
+    if let Some(focus) = focus_position(&player, &spectating) {
+        let points = curve.points_earned(
+            focus.length(),
+            score.survived_time.elapsed().as_secs_f32(),
+            score.score,
+        );
+        danger.0 = drift_towards(danger.0, danger_level(points), time.delta_seconds());
+    }
+}
+
+fn shade_nebula(
+    danger: Option<Res<Danger>>,
+    mut backgrounds: Query<&mut Sprite, With<ArenaBackground>>,
+    mut lights: Query<&mut BackgroundColor, With<NebulaLightMarker>>,
+) {
+    let danger = danger.map_or(0., |danger| danger.0);
+    for mut background in backgrounds.iter_mut() {
+        background.color = nebula_tint(danger);
+    }
+    for mut light in lights.iter_mut() {
+        light.0 = NEBULA_LIGHT.with_a(MAX_LIGHT_ALPHA * danger);
+    }
+}
+
+/// Tiles the starfield over the nine patches around the camera, which is more than it ever sees
+fn draw_stars(
+    mut gizmos: Gizmos,
+    danger: Option<Res<Danger>>,
+    starfield: Res<Starfield>,
+    camera: Query<&Transform, With<Camera2d>>,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    let danger = danger.map_or(0., |danger| danger.0);
+    let centre = camera.translation.truncate() / PIXELS_PER_UNIT;
+    let tile = (centre / STAR_TILE).floor();
+    let color = nebula_tint(danger).with_a(0.6);
+    for x in -1..=1 {
+        for y in -1..=1 {
+            let origin = (tile + Vec2::new(x as f32, y as f32)) * STAR_TILE;
+            for star in starfield.0.iter().take(star_count(danger)) {
+                gizmos
+                    .circle_2d((origin + *star) * PIXELS_PER_UNIT, 1.5, color)
+                    .segments(6);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_nebula_thickens_slowly_as_the_spawn_budget_ramps() {
+        let curve = SpawnCurve::default();
+        let early = danger_level(curve.points_earned(0., 30., 0));
+        let late = danger_level(curve.points_earned(0., 900., 400));
+        assert!(early < 0.2);
+        assert!(late > early);
+        assert_eq!(danger_level(1000), 1.);
+        assert!(star_count(late) > star_count(early));
+        assert!(nebula_tint(1.).g() < nebula_tint(0.).g());
+        assert_eq!(nebula_tint(0.), Color::WHITE);
+
+        // One long frame or many short ones end up in the same place, and neither gets there at once
+        let mut stepped = 0.;
+        for _ in 0..100 {
+            stepped = drift_towards(stepped, 1., 0.01);
+        }
+        let jumped = drift_towards(0., 1., 1.);
+        assert!((stepped - jumped).abs() < 1e-4);
+        assert!(jumped < 0.1);
+    }
+}
@@ -0,0 +1,435 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{Added, With},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::BuildChildren,
+    math::Vec3,
+    prelude::{default, App},
+    render::{color::Color, view::Visibility},
+    sprite::{Sprite, SpriteBundle},
+    text::{Text, TextStyle},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        FlexDirection, Style, UiRect, Val,
+    },
+};
+
+use crate::{
+    dialogue::{Dialogue, DialogueLine},
+    gameplay::{Bullet, GameState, PlayerMarker, Spacecraft, PIXELS_PER_UNIT},
+    landmarks::LANDMARKS,
+    score::{ScoreEvent, ScoreMultipliers, ScoreSource},
+    ui::{spawn_ui, HudAnchor, HudElement, HudOpacity},
+    GameLifecycleState,
+};
+
+const FIRST_OBJECTIVE_DELAY: Duration = Duration::from_secs(30);
+/// Quiet time between one objective ending and the next being offered
+const OBJECTIVE_COOLDOWN: Duration = Duration::from_secs(45);
+/// Score multiplier granted for completing an objective
+pub const OBJECTIVE_MULTIPLIER: f32 = 2.;
+pub const OBJECTIVE_BONUS_TIME: Duration = Duration::from_secs(30);
+const MARKER_RADIUS: f32 = 0.4;
+
+/// Offered in this order, then round again. Each time round the marker moves on to the next
+/// landmark.
+const ROTATION: [ObjectiveKind; 3] = [
+    ObjectiveKind::CaptureShips { count: 2 },
+    ObjectiveKind::HoldFire,
+    ObjectiveKind::VisitMarker { landmark: 0 },
+];
+
+/// The objective offered `next`th in a run
+pub fn rotation(next: usize) -> ObjectiveKind {
+    match ROTATION[next % ROTATION.len()] {
+        ObjectiveKind::VisitMarker { .. } => ObjectiveKind::VisitMarker {
+            landmark: (next / ROTATION.len()) % LANDMARKS.len(),
+        },
+        kind => kind,
+    }
+}
+
+pub struct ObjectivesPlugin;
+
+impl Plugin for ObjectivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameLifecycleState::Game),
+            (reset_objectives, spawn_objective_ui.after(spawn_ui)),
+        )
+        .add_systems(
+            Update,
+            (run_objectives, tick_objective_bonus)
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        )
+        .add_systems(
+            Update,
+            (update_objective_panel, update_objective_marker)
+                .run_if(in_state(GameLifecycleState::Game)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObjectiveKind {
+    CaptureShips {
+        count: u32,
+    },
+    HoldFire,
+    /// Reach one of the [`LANDMARKS`], by index
+    VisitMarker {
+        landmark: usize,
+    },
+}
+
+impl ObjectiveKind {
+    fn time_limit(self) -> Duration {
+        match self {
+            ObjectiveKind::CaptureShips { .. } => Duration::from_secs(60),
+            ObjectiveKind::HoldFire => Duration::from_secs(30),
+            ObjectiveKind::VisitMarker { .. } => Duration::from_secs(90),
+        }
+    }
+
+    fn announcement(self) -> String {
+        match self {
+            ObjectiveKind::CaptureShips { count } => format!(
+                "Captain, command wants {count} ships captured in the next {} seconds.",
+                self.time_limit().as_secs()
+            ),
+            ObjectiveKind::HoldFire => format!(
+                "Captain, hold fire for {} seconds. Let's see if they lose interest.",
+                self.time_limit().as_secs()
+            ),
+            ObjectiveKind::VisitMarker { landmark } => format!(
+                "Captain, there's a signal coming from {}. Take us to the marker.",
+                LANDMARKS[landmark].name
+            ),
+        }
+    }
+}
+
+pub struct Objective {
+    pub kind: ObjectiveKind,
+    pub timer: Timer,
+    pub progress: u32,
+}
+
+impl Objective {
+    fn new(kind: ObjectiveKind) -> Self {
+        Self {
+            kind,
+            timer: Timer::new(kind.time_limit(), TimerMode::Once),
+            progress: 0,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        let goal = match self.kind {
+            ObjectiveKind::CaptureShips { count } => {
+                format!("Capture {}/{count} ships", self.progress)
+            }
+            ObjectiveKind::HoldFire => "Hold fire".to_string(),
+            ObjectiveKind::VisitMarker { landmark } => {
+                format!("Reach {}", LANDMARKS[landmark].name)
+            }
+        };
+        format!(
+            "{goal}  {}/{}",
+            clock(self.timer.elapsed()),
+            clock(self.timer.duration())
+        )
+    }
+}
+
+/// Formats as minutes and seconds, e.g. 2:14
+fn clock(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[derive(Resource)]
+pub struct Objectives {
+    pub active: Option<Objective>,
+    next: usize,
+    cooldown: Timer,
+    bonus: Timer,
+}
+
+impl Default for Objectives {
+    fn default() -> Self {
+        let mut bonus = Timer::new(OBJECTIVE_BONUS_TIME, TimerMode::Once);
+        bonus.tick(OBJECTIVE_BONUS_TIME);
+        Self {
+            active: None,
+            next: 0,
+            cooldown: Timer::new(FIRST_OBJECTIVE_DELAY, TimerMode::Once),
+            bonus,
+        }
+    }
+}
+
+impl Objectives {
+    /// One line per goal or bonus currently in play, for the tracker panel
+    pub fn tracker_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        match &self.active {
+            Some(objective) => lines.push(objective.description()),
+            None => lines.push(format!(
+                "Next objective in {}",
+                clock(self.cooldown.remaining() + Duration::from_millis(999))
+            )),
+        }
+        if !self.bonus.finished() {
+            lines.push(format!(
+                "Bonus score x{OBJECTIVE_MULTIPLIER}  {}",
+                clock(self.bonus.remaining() + Duration::from_millis(999))
+            ));
+        }
+        lines
+    }
+}
+
+fn reset_objectives(mut commands: Commands) {
+    commands.insert_resource(Objectives::default());
+    commands.insert_resource(ScoreMultipliers::default());
+}
+
+fn run_objectives(
+    time: Res<Time>,
+    mut objectives: ResMut<Objectives>,
+    mut multipliers: ResMut<ScoreMultipliers>,
+    mut dialogue: ResMut<Dialogue>,
+    mut score_events: EventReader<ScoreEvent>,
+    fired: Query<&Bullet, Added<Bullet>>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+) {
+    let captures = score_events
+        .read()
+        .filter(|e| e.source == ScoreSource::Captures)
+        .count() as u32;
+    if let Ok(player) = player.get_single() {
+        let objectives = &mut *objectives;
+        match objectives.active.as_mut() {
+            None => {
+                if objectives.cooldown.tick(time.delta()).finished() {
+                    let kind = rotation(objectives.next);
+                    objectives.next += 1;
+                    dialogue.queue_lines([DialogueLine::from(kind.announcement())]);
+                    objectives.active = Some(Objective::new(kind));
+                }
+            }
+            Some(objective) => {
+                objective.timer.tick(time.delta());
+                let completed = match objective.kind {
+                    ObjectiveKind::CaptureShips { count } => {
+                        objective.progress += captures;
+                        objective.progress >= count
+                    }
+                    ObjectiveKind::HoldFire => {
+                        if fired.iter().any(|bullet| bullet.player_shot) {
+                            // Firing runs the clock out, failing the objective
+                            let remaining = objective.timer.remaining();
+                            objective.timer.tick(remaining);
+                            false
+                        } else {
+                            objective.timer.finished()
+                        }
+                    }
+                    ObjectiveKind::VisitMarker { landmark } => {
+                        player.position.distance(LANDMARKS[landmark].position) < MARKER_RADIUS
+                    }
+                };
+                if completed {
+                    dialogue.queue_lines([DialogueLine::from(format!(
+                        "Objective complete! Score x{OBJECTIVE_MULTIPLIER} for {} seconds.",
+                        OBJECTIVE_BONUS_TIME.as_secs()
+                    ))]);
+                    multipliers.objective = OBJECTIVE_MULTIPLIER;
+                    objectives.bonus.reset();
+                } else if objective.timer.finished() {
+                    dialogue.queue_lines([DialogueLine::from(
+                        "Objective failed. Maybe next time, captain.".to_string(),
+                    )]);
+                }
+                if completed || objective.timer.finished() {
+                    objectives.active = None;
+                    objectives.cooldown = Timer::new(OBJECTIVE_COOLDOWN, TimerMode::Once);
+                }
+            }
+        }
+    }
+}
+
+fn tick_objective_bonus(
+    time: Res<Time>,
+    mut objectives: ResMut<Objectives>,
+    mut multipliers: ResMut<ScoreMultipliers>,
+) {
+    if objectives.bonus.tick(time.delta()).just_finished() {
+        multipliers.objective = 1.;
+    }
+}
+
+#[derive(Component)]
+pub struct ObjectivePanelMarker;
+
+#[derive(Component)]
+pub struct ObjectiveTextMarker;
+
+#[derive(Component)]
+pub struct ObjectiveMarker;
+
+fn spawn_objective_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    anchors: Query<(Entity, &HudAnchor)>,
+) {
+    let alpha_beta = asset_server.load("alphbeta.ttf");
+    // Sits under the shield pips
+    let panel = commands
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::top(Val::Px(8.)),
+                padding: UiRect::all(Val::Px(8.)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.4).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(ObjectivePanelMarker)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "Objectives",
+                    TextStyle {
+                        font: alpha_beta.clone(),
+                        font_size: 22.,
+                        color: Color::GOLD,
+                    },
+                ))
+                .insert(HudElement);
+            parent
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: alpha_beta,
+                        font_size: 18.,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert((ObjectiveTextMarker, HudElement));
+        })
+        .id();
+    HudAnchor::TopLeft.attach(&mut commands, &anchors, panel);
+    commands
+        .spawn(SpriteBundle {
+            texture: asset_server.load("warp_portal.png"),
+            sprite: Sprite {
+                color: Color::LIME_GREEN,
+                ..default()
+            },
+            transform: Transform::from_scale(Vec3::splat(3.)),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(ObjectiveMarker);
+}
+
+fn update_objective_panel(
+    objectives: Res<Objectives>,
+    opacity: Res<HudOpacity>,
+    mut panel: Query<&mut Visibility, With<ObjectivePanelMarker>>,
+    mut text: Query<&mut Text, With<ObjectiveTextMarker>>,
+) {
+    let lines = objectives.tracker_lines();
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+    if let Ok(mut visibility) = panel.get_single_mut() {
+        *visibility = match lines.is_empty() || opacity.0 == 0. {
+            true => Visibility::Hidden,
+            false => Visibility::Inherited,
+        };
+    }
+}
+
+fn update_objective_marker(
+    objectives: Res<Objectives>,
+    mut marker: Query<(&mut Transform, &mut Visibility), With<ObjectiveMarker>>,
+) {
+    if let Ok((mut transform, mut visibility)) = marker.get_single_mut() {
+        match objectives.active.as_ref().map(|o| o.kind) {
+            Some(ObjectiveKind::VisitMarker { landmark }) => {
+                *visibility = Visibility::Inherited;
+                transform.translation = (LANDMARKS[landmark].position * PIXELS_PER_UNIT).extend(5.);
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::BORDER_WARNING_RADIUS;
+    use crate::landmarks::{dial_position, DIAL_RANGE};
+    use bevy::math::{Quat, Vec2};
+
+    #[test]
+    fn objective_tracker_shows_progress_and_time() {
+        let mut objectives = Objectives::default();
+        assert_eq!(objectives.tracker_lines(), ["Next objective in 0:30"]);
+        let mut timer = Timer::new(Duration::from_secs(60), TimerMode::Once);
+        timer.tick(Duration::from_secs(14));
+        objectives.active = Some(Objective {
+            kind: ObjectiveKind::CaptureShips { count: 2 },
+            timer,
+            progress: 1,
+        });
+        assert_eq!(objectives.tracker_lines(), ["Capture 1/2 ships  0:14/1:00"]);
+    }
+
+    #[test]
+    fn landmark_objectives_tour_the_arena() {
+        let visits = (0..3 * LANDMARKS.len())
+            .filter_map(|next| match rotation(next) {
+                ObjectiveKind::VisitMarker { landmark } => Some(landmark),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(visits, (0..LANDMARKS.len()).collect::<Vec<_>>());
+        for landmark in LANDMARKS.iter() {
+            assert!(landmark.position.length() < BORDER_WARNING_RADIUS);
+        }
+
+        // Pips move out from the middle of the dial and stop at the rim
+        assert_eq!(dial_position(Vec2::ZERO, Quat::IDENTITY), Vec2::ZERO);
+        assert_eq!(
+            dial_position(Vec2::new(DIAL_RANGE / 2., 0.), Quat::IDENTITY),
+            Vec2::new(0.5, 0.)
+        );
+        assert_eq!(
+            dial_position(Vec2::new(0., -3. * DIAL_RANGE), Quat::IDENTITY),
+            Vec2::new(0., -1.)
+        );
+        // The screen is wider than it's tall, so a landmark off on the diagonal looks flatter
+        let diagonal = dial_position(Vec2::splat(DIAL_RANGE), Quat::IDENTITY);
+        assert!(diagonal.x > diagonal.y);
+        assert!((diagonal.length() - 1.).abs() < 1e-5);
+    }
+}
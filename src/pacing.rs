@@ -0,0 +1,181 @@
+use std::fmt;
+
+use bevy::{
+    app::{Plugin, Startup},
+    asset::{
+        io::Reader, Asset, AssetApp, AssetLoader, AssetServer, AsyncReadExt, Handle, LoadContext,
+    },
+    ecs::system::{Commands, Res, Resource},
+    prelude::App,
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::settings::Difficulty;
+
+pub struct SpawnPacingPlugin;
+
+impl Plugin for SpawnPacingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<SpawnPacing>()
+            .register_asset_loader(SpawnPacingLoader)
+            .add_systems(Startup, load_spawn_pacing);
+    }
+}
+
+#[derive(Resource)]
+pub struct SpawnPacingHandle(pub Handle<SpawnPacing>);
+
+fn load_spawn_pacing(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SpawnPacingHandle(
+        asset_server.load("pacing/spawn.pacing.ron"),
+    ));
+}
+
+/// Spawn budget curves for each difficulty, authored in `assets/pacing/*.pacing.ron`
+#[derive(Asset, TypePath, Deserialize)]
+pub struct SpawnPacing {
+    pub easy: SpawnCurve,
+    pub normal: SpawnCurve,
+    pub hard: SpawnCurve,
+}
+
+impl SpawnPacing {
+    pub fn curve(&self, difficulty: Difficulty) -> &SpawnCurve {
+        match difficulty {
+            Difficulty::Easy => &self.easy,
+            Difficulty::Normal => &self.normal,
+            Difficulty::Hard => &self.hard,
+        }
+    }
+}
+
+/// How many spawn points each update grants, before subtracting what's already deployed
+#[derive(Clone, Deserialize)]
+pub struct SpawnCurve {
+    /// `(seconds survived, points)` keyframes, linearly interpolated and extended past the last one
+    pub over_time: Vec<(f32, f32)>,
+    /// Points per unit of distance from the origin
+    pub distance_weight: f32,
+    /// Points per point of score
+    pub score_weight: f32,
+}
+
+/// Matches the normal curve in the shipped pacing file, used until it has loaded
+impl Default for SpawnCurve {
+    fn default() -> Self {
+        Self {
+            over_time: vec![(0., 0.), (800., 10.)],
+            distance_weight: 0.1,
+            score_weight: 0.005,
+        }
+    }
+}
+
+impl SpawnCurve {
+    pub fn points_earned(&self, distance_from_origin: f32, survived_secs: f32, score: u32) -> i32 {
+        (self.sample(survived_secs)
+            + self.distance_weight * distance_from_origin
+            + self.score_weight * score as f32)
+            .ceil() as i32
+    }
+
+    fn sample(&self, secs: f32) -> f32 {
+        match self.over_time.as_slice() {
+            [] => 0.,
+            [(_, points)] => *points,
+            keys => {
+                let segment = keys
+                    .windows(2)
+                    .find(|w| secs <= w[1].0)
+                    .unwrap_or(&keys[keys.len() - 2..]);
+                let ((t0, p0), (t1, p1)) = (segment[0], segment[1]);
+                if secs <= t0 || t1 <= t0 {
+                    return p0;
+                }
+                p0 + (p1 - p0) * (secs - t0) / (t1 - t0)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SpawnPacingError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for SpawnPacingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpawnPacingError::Io(e) => write!(f, "Could not read spawn pacing: {e}"),
+            SpawnPacingError::Parse(e) => write!(f, "Could not parse spawn pacing: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SpawnPacingError {}
+
+#[derive(Default)]
+pub struct SpawnPacingLoader;
+
+impl AssetLoader for SpawnPacingLoader {
+    type Asset = SpawnPacing;
+    type Settings = ();
+    type Error = SpawnPacingError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(SpawnPacingError::Io)?;
+            let mut pacing: SpawnPacing =
+                ron::de::from_bytes(&bytes).map_err(SpawnPacingError::Parse)?;
+            for curve in [&mut pacing.easy, &mut pacing.normal, &mut pacing.hard] {
+                curve.over_time.sort_by(|a, b| a.0.total_cmp(&b.0));
+            }
+            Ok(pacing)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pacing.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_budget_grows_with_distance_time_and_score() {
+        let curve = SpawnCurve::default();
+        assert_eq!(curve.points_earned(0., 0., 0), 0);
+        assert_eq!(curve.points_earned(0., 20., 0), 1);
+        assert!(curve.points_earned(20., 0., 0) > curve.points_earned(2., 0., 0));
+        assert!(curve.points_earned(0., 600., 0) > curve.points_earned(0., 60., 0));
+        assert!(curve.points_earned(0., 0., 500) > curve.points_earned(0., 0., 50));
+        assert!(curve.points_earned(0., 3000., 0) > curve.points_earned(0., 800., 0));
+    }
+
+    #[test]
+    fn easy_pacing_ramps_slower_than_hard() {
+        let pacing: SpawnPacing =
+            ron::from_str(include_str!("../assets/pacing/spawn.pacing.ron")).unwrap();
+        for secs in [120., 600., 1800.] {
+            let easy = pacing.easy.points_earned(0., secs, 0);
+            let normal = pacing.normal.points_earned(0., secs, 0);
+            let hard = pacing.hard.points_earned(0., secs, 0);
+            assert!(easy <= normal && normal <= hard, "at {secs}s");
+        }
+        assert!(pacing.easy.points_earned(0., 600., 0) < pacing.hard.points_earned(0., 600., 0));
+    }
+}
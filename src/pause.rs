@@ -0,0 +1,246 @@
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, NextState, OnEnter, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    input::{gamepad::GamepadButtonType, keyboard::KeyCode, ButtonInput},
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, FlexDirection, JustifyContent, Style, UiRect, Val, ZIndex,
+    },
+};
+
+use crate::{
+    bindings::BindingsScreen,
+    controls::{ControlScheme, Pads},
+    gameplay::{DeathSequence, GameState, GameplaySet},
+    hud::HudEditor,
+    range::TargetRange,
+    records::RunRetired,
+    spectate::{SpectateOffer, Spectating},
+    ui::{panel, panel_slice, PanelAssets},
+    upgrades::UpgradeScreen,
+    volume::AudioScreen,
+    GameLifecycleState,
+};
+
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameLifecycleState::Game), clear_retired)
+            .add_systems(
+                Update,
+                open_pause_menu
+                    .in_set(GameplaySet::Input)
+                    .run_if(in_state(GameState::Regular))
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(not(resource_exists::<DeathSequence>))
+                    .run_if(not(resource_exists::<SpectateOffer>))
+                    .run_if(not(resource_exists::<Spectating>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    // The screens opened over the menu only clear the keyboard behind them
+                    handle_pause_menu
+                        .run_if(not(resource_exists::<AudioScreen>))
+                        .run_if(not(resource_exists::<BindingsScreen>))
+                        .run_if(not(resource_exists::<HudEditor>))
+                        .run_if(not(resource_exists::<UpgradeScreen>)),
+                    update_pause_menu_options,
+                )
+                    .run_if(in_state(GameState::PauseMenu))
+                    .run_if(in_state(GameLifecycleState::Game)),
+            )
+            .add_systems(OnEnter(GameState::PauseMenu), spawn_pause_menu)
+            .add_systems(OnExit(GameState::PauseMenu), despawn_pause_menu)
+            .add_systems(OnExit(GameLifecycleState::Game), leave_pause_menu)
+            .add_systems(
+                Update,
+                relaunch_run
+                    .run_if(in_state(GameLifecycleState::Loadout))
+                    .run_if(resource_exists::<RestartRun>),
+            );
+    }
+}
+
+/// Goes straight back into a new run with the same loadout, passing over the loadout screen
+#[derive(Resource)]
+pub struct RestartRun;
+
+#[derive(Component)]
+pub struct PauseMenuMarker;
+
+fn clear_retired(mut commands: Commands) {
+    commands.remove_resource::<RunRetired>();
+}
+
+fn open_pause_menu(
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if inputs.just_pressed(KeyCode::Escape) || pads.just_pressed(GamepadButtonType::Start) {
+        state.set(GameState::PauseMenu);
+    }
+}
+
+fn handle_pause_menu(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    range: Option<Res<TargetRange>>,
+    mut state: ResMut<NextState<GameState>>,
+    mut lifecycle: ResMut<NextState<GameLifecycleState>>,
+) {
+    if inputs.just_pressed(KeyCode::Escape)
+        || pads.just_pressed(GamepadButtonType::Start)
+        || pads.just_pressed(GamepadButtonType::East)
+    {
+        state.set(GameState::Regular);
+    } else if inputs.just_pressed(KeyCode::KeyR) || pads.just_pressed(GamepadButtonType::North) {
+        commands.insert_resource(RestartRun);
+        lifecycle.set(GameLifecycleState::Loadout);
+    } else if inputs.just_pressed(KeyCode::KeyM) || pads.just_pressed(GamepadButtonType::Select) {
+        lifecycle.set(GameLifecycleState::MainMenu);
+    } else if range.is_none()
+        && (inputs.just_pressed(KeyCode::KeyQ) || pads.just_pressed(GamepadButtonType::West))
+    {
+        commands.insert_resource(RunRetired);
+        lifecycle.set(GameLifecycleState::EndScreen);
+    }
+}
+
+#[derive(Component)]
+pub struct PauseMenuOptionsMarker;
+
+/// The audio, controls, HUD layout and upgrade screens open from the keyboard only, so they're left off for the
+/// gamepad. The target range isn't a real run, so there's nothing to retire and bank.
+fn pause_menu_options(scheme: ControlScheme, can_retire: bool) -> String {
+    let mut options = format!(
+        "[{}] Resume\n[{}] Restart run\n[{}] Return to main menu",
+        scheme.prompt("Esc", "Start"),
+        scheme.prompt("R", "Y"),
+        scheme.prompt("M", "Select"),
+    );
+    if can_retire {
+        options.push_str(&format!(
+            "\n[{}] Retire and bank the run",
+            scheme.prompt("Q", "X")
+        ));
+    }
+    if scheme == ControlScheme::Keyboard {
+        options.push_str("\n[A] Audio settings\n[K] Controls\n[H] HUD layout\n[U] Upgrades");
+    }
+    options
+}
+
+fn update_pause_menu_options(
+    scheme: Res<ControlScheme>,
+    range: Option<Res<TargetRange>>,
+    mut options: Query<&mut Text, With<PauseMenuOptionsMarker>>,
+) {
+    if scheme.is_changed() {
+        for mut options in options.iter_mut() {
+            options.sections[0].value = pause_menu_options(*scheme, range.is_none());
+        }
+    }
+}
+
+fn spawn_pause_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    scheme: Res<ControlScheme>,
+    range: Option<Res<TargetRange>>,
+    panel_assets: Res<PanelAssets>,
+) {
+    let alphbeta = asset_server.load("alphbeta.ttf");
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.6).into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(PauseMenuMarker)
+        .with_children(|parent| {
+            parent
+                .spawn(panel(
+                    Style {
+                        align_items: AlignItems::Center,
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Val::Px(30.)),
+                        ..default()
+                    },
+                    panel_assets.frame.clone(),
+                    panel_slice(),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle {
+                        style: Style {
+                            padding: UiRect::bottom(Val::Px(30.)),
+                            ..default()
+                        },
+                        text: Text::from_section(
+                            "Paused",
+                            TextStyle {
+                                font: panel_assets.font.clone(),
+                                font_size: 56.,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        ..default()
+                    });
+                    parent.spawn((
+                        TextBundle {
+                            text: Text::from_section(
+                                pause_menu_options(*scheme, range.is_none()),
+                                TextStyle {
+                                    font: alphbeta,
+                                    font_size: 24.,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            ..default()
+                        },
+                        PauseMenuOptionsMarker,
+                    ));
+                });
+        });
+}
+
+fn despawn_pause_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenuMarker>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn leave_pause_menu(mut state: ResMut<NextState<GameState>>) {
+    state.set(GameState::Regular);
+}
+
+fn relaunch_run(mut commands: Commands, mut lifecycle: ResMut<NextState<GameLifecycleState>>) {
+    commands.remove_resource::<RestartRun>();
+    lifecycle.set(GameLifecycleState::Game);
+}
@@ -0,0 +1,187 @@
+use bevy::{
+    app::{Plugin, Update},
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        entity::Entity,
+        event::EventReader,
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, NextState, OnEnter, OnExit},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{
+        keyboard::KeyCode,
+        mouse::{MouseScrollUnit, MouseWheel},
+        ButtonInput,
+    },
+    math::Vec3,
+    prelude::App,
+    render::view::screenshot::ScreenshotManager,
+    time::{Real, Time},
+    transform::components::Transform,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    dialogue::Dialogue,
+    gameplay::{GameState, GameplaySet},
+    settings::{MAX_CAMERA_ZOOM, MIN_CAMERA_ZOOM},
+    ui::HudOpacity,
+    GameLifecycleState,
+};
+
+/// World units per second the photo camera pans at a zoom of 1
+const PHOTO_PAN_SPEED: f32 = 600.;
+const PHOTO_ROTATE_SPEED: f32 = 1.5;
+
+pub struct PhotoModePlugin;
+
+impl Plugin for PhotoModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            enter_photo_mode
+                .in_set(GameplaySet::Input)
+                .run_if(in_state(GameState::Regular))
+                .run_if(in_state(GameLifecycleState::Game)),
+        )
+        .add_systems(
+            Update,
+            (move_photo_camera, take_screenshot, exit_photo_mode)
+                .run_if(in_state(GameState::Photo))
+                .run_if(in_state(GameLifecycleState::Game)),
+        )
+        .add_systems(OnEnter(GameState::Photo), hide_overlays)
+        .add_systems(OnExit(GameState::Photo), restore_overlays)
+        .add_systems(OnExit(GameLifecycleState::Game), leave_photo_mode);
+    }
+}
+
+/// Where the gameplay camera was before photo mode took it over
+#[derive(Resource)]
+pub struct PhotoCameraReturn(Transform);
+
+fn enter_photo_mode(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    camera: Query<&Transform, With<Camera2d>>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if inputs.just_pressed(KeyCode::KeyP) {
+        if let Ok(camera) = camera.get_single() {
+            commands.insert_resource(PhotoCameraReturn(*camera));
+            state.set(GameState::Photo);
+        }
+    }
+}
+
+fn exit_photo_mode(inputs: Res<ButtonInput<KeyCode>>, mut state: ResMut<NextState<GameState>>) {
+    if inputs.any_just_pressed([KeyCode::KeyP, KeyCode::Escape]) {
+        state.set(GameState::Regular);
+    }
+}
+
+fn hide_overlays(mut hud_opacity: ResMut<HudOpacity>, mut dialogue: ResMut<Dialogue>) {
+    hud_opacity.0 = 0.;
+    dialogue.set_suppressed(true);
+}
+
+fn restore_overlays(
+    mut commands: Commands,
+    mut hud_opacity: ResMut<HudOpacity>,
+    mut dialogue: ResMut<Dialogue>,
+    camera_return: Option<Res<PhotoCameraReturn>>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+) {
+    hud_opacity.0 = 1.;
+    dialogue.set_suppressed(false);
+    if let (Some(camera_return), Ok(mut camera)) = (camera_return, camera.get_single_mut()) {
+        *camera = camera_return.0;
+    }
+    commands.remove_resource::<PhotoCameraReturn>();
+}
+
+fn leave_photo_mode(mut state: ResMut<NextState<GameState>>) {
+    state.set(GameState::Regular);
+}
+
+fn move_photo_camera(
+    time: Res<Time<Real>>,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut scroll: EventReader<MouseWheel>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+) {
+    if let Ok(mut camera) = camera.get_single_mut() {
+        let delta = time.delta_seconds();
+        let mut pan = Vec3::ZERO;
+        if inputs.any_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+            pan.y += 1.;
+        }
+        if inputs.any_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+            pan.y -= 1.;
+        }
+        if inputs.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+            pan.x -= 1.;
+        }
+        if inputs.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+            pan.x += 1.;
+        }
+        // Pan relative to the way the camera is facing, faster when zoomed out
+        let pan = camera.rotation * pan.normalize_or_zero() * PHOTO_PAN_SPEED * camera.scale.x;
+        camera.translation += pan * delta;
+        if inputs.pressed(KeyCode::KeyQ) {
+            camera.rotate_z(PHOTO_ROTATE_SPEED * delta);
+        }
+        if inputs.pressed(KeyCode::KeyE) {
+            camera.rotate_z(-PHOTO_ROTATE_SPEED * delta);
+        }
+
+        let mut zoom = camera.scale.x;
+        for event in scroll.read() {
+            zoom -= match event.unit {
+                MouseScrollUnit::Line => event.y * 0.1,
+                MouseScrollUnit::Pixel => event.y * 0.002,
+            };
+        }
+        if inputs.any_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+            zoom -= delta;
+        }
+        if inputs.any_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+            zoom += delta;
+        }
+        let zoom = zoom.clamp(MIN_CAMERA_ZOOM * 0.5, MAX_CAMERA_ZOOM * 2.);
+        camera.scale.x = zoom;
+        camera.scale.y = zoom;
+    }
+}
+
+fn screenshot_name() -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        format!("quantum_salvage_{secs}.png")
+    }
+    // The browser picks a unique name for the download itself
+    #[cfg(target_arch = "wasm32")]
+    {
+        "quantum_salvage.png".to_string()
+    }
+}
+
+fn take_screenshot(
+    inputs: Res<ButtonInput<KeyCode>>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+) {
+    if inputs.any_just_pressed([KeyCode::Enter, KeyCode::F12]) {
+        if let Ok(window) = window.get_single() {
+            let name = screenshot_name();
+            match screenshots.save_screenshot_to_disk(window, &name) {
+                Ok(_) => println!("Saved screenshot {name}"),
+                Err(e) => println!("Could not take screenshot: {e}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! The last step of the tutorial: a harmless target parked in front of the player, so the
+//! shoot, capture and 1/2/3 choice is tried once before any real enemies warp in.
+
+use bevy::{
+    app::{Plugin, Update},
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Has, With},
+        schedule::{
+            common_conditions::{in_state, resource_exists},
+            IntoSystemConfigs, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    math::Vec2,
+    prelude::App,
+    render::color::Color,
+};
+
+use crate::{
+    dialogue::{Dialogue, DialogueLine},
+    gameplay::{
+        handle_npc_logic, CaptureMoment, Captured, EnemySpacecraftBundle, GameState, GameplaySet,
+        PlayerMarker, ShipTextures, ShipType, Spacecraft,
+    },
+    GameLifecycleState,
+};
+
+/// How far in front of the player the target is parked
+const TARGET_DISTANCE: f32 = 0.6;
+
+const SHOOT_LINE: &str =
+    "There's a practice target ahead, captain. Shoot it with [SPACE] until it's disabled.";
+const DECIDE_LINE: &str =
+    "Got it! Now choose: [1] to fly it, [2] to make it an ally or [3] to scuttle it.";
+const CLOSING_LINES: [&str; 4] = [
+    "Well done. Hopefully that might give us a chance against the bigger ships out there.",
+    "There's one final thing, captain. If you press [S], we'll begin to recharge shields.",
+    "But be careful! They only charge while we crawl along, and one hit knocks the charge out.",
+    "Good luck, and may the stars guide us",
+];
+
+pub struct CapturePracticePlugin;
+
+impl Plugin for CapturePracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(GameLifecycleState::Game), end_practice)
+            .add_systems(
+                Update,
+                (
+                    hold_target
+                        .after(handle_npc_logic)
+                        .in_set(GameplaySet::Input),
+                    run_practice.in_set(GameplaySet::Simulation),
+                )
+                    .run_if(resource_exists::<CapturePractice>)
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+/// Present from the end of the tutorial until the practice target has been dealt with. Enemies
+/// hold off until then.
+#[derive(Resource, Default)]
+pub struct CapturePractice {
+    target: Option<Entity>,
+}
+
+#[derive(Component)]
+pub struct PracticeTarget;
+
+/// The target shares the enemy pilot logic, so it's kept still and its guns cold here
+fn hold_target(mut targets: Query<&mut Spacecraft, With<PracticeTarget>>) {
+    for mut target in targets.iter_mut() {
+        target.velocity = 0.;
+        target.weapon_cooldown.reset();
+    }
+}
+
+fn run_practice(
+    mut commands: Commands,
+    mut practice: ResMut<CapturePractice>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    targets: Query<(Has<Captured>, Has<PlayerMarker>), With<PracticeTarget>>,
+    capture: Option<Res<CaptureMoment>>,
+    textures: Res<ShipTextures>,
+    mut dialogue: ResMut<Dialogue>,
+) {
+    let target = match practice.target {
+        Some(target) => target,
+        None => {
+            if let Ok(player) = player.get_single() {
+                let ahead = Vec2::new(player.heading.sin(), player.heading.cos());
+                let position = player.position + ahead * TARGET_DISTANCE;
+                let target = commands
+                    .spawn(
+                        EnemySpacecraftBundle::create_ship(ShipType::Ship1, position, &textures)
+                            .with_tint(Color::GRAY),
+                    )
+                    .insert((Name::new("Practice Target"), PracticeTarget))
+                    .id();
+                practice.target = Some(target);
+            }
+            return;
+        }
+    };
+    let dealt_with = match targets.get(target) {
+        Ok((captured, piloted)) => captured || piloted,
+        // Scuttled
+        Err(_) => true,
+    };
+    if dealt_with {
+        if let Some(mut target) = commands.get_entity(target) {
+            target.remove::<PracticeTarget>();
+        }
+        commands.remove_resource::<CapturePractice>();
+        dialogue.hide();
+        dialogue.queue_lines(CLOSING_LINES.map(|line| DialogueLine::from(line.to_string())));
+        return;
+    }
+    let line = match capture {
+        Some(_) => DECIDE_LINE,
+        None => SHOOT_LINE,
+    };
+    dialogue.set_text(line.to_string());
+    dialogue.show();
+}
+
+fn end_practice(mut commands: Commands) {
+    commands.remove_resource::<CapturePractice>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::testing::test_app;
+
+    #[test]
+    fn practice_holds_enemies_until_the_target_is_dealt_with() {
+        let mut app = test_app();
+        app.insert_state(GameLifecycleState::Game)
+            .insert_resource(Dialogue::init())
+            .insert_resource(CapturePractice::default())
+            .add_plugins(CapturePracticePlugin);
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        app.update();
+        let mut targets = app
+            .world
+            .query_filtered::<(Entity, &Spacecraft), With<PracticeTarget>>();
+        let (target, craft) = targets.single(&app.world);
+        assert!(craft.position.y > 0.);
+
+        for _ in 0..3 {
+            app.update();
+        }
+        let craft = app.world.get::<Spacecraft>(target).unwrap();
+        assert_eq!(craft.velocity, 0.);
+        assert!(!craft.weapon_cooldown.finished());
+        assert!(app.world.contains_resource::<CapturePractice>());
+
+        app.world.entity_mut(target).insert(Captured);
+        app.update();
+        assert!(!app.world.contains_resource::<CapturePractice>());
+        assert!(app.world.get::<PracticeTarget>(target).is_none());
+    }
+}
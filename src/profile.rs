@@ -0,0 +1,218 @@
+//! Separate save data for everyone playing on the same machine. Each profile keeps its own
+//! settings, loadout, HUD layout, high scores and salvage credits, picked from the main menu.
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ace::AceRoster,
+    bindings::InputBindings,
+    difficulty_text,
+    ghost::BestGhost,
+    hud::HudLayout,
+    loadout::Loadout,
+    records::{HighScores, MetaProgress},
+    settings::Settings,
+    storage,
+    telemetry::Telemetry,
+    volume::AudioSettings,
+    DifficultyTextMarker, GameLifecycleState, MainMenuMarker,
+};
+
+const PROFILES_KEY: &str = "profiles.ron";
+
+pub const MAX_PROFILES: usize = 4;
+
+/// Must be added before any plugin that loads save data, so that data comes from the right profile
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        let profiles = Profiles::load();
+        storage::set_profile_dir(Profiles::dir(profiles.active));
+        app.insert_resource(profiles)
+            .add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_profile_text)
+            .add_systems(
+                Update,
+                switch_profile.run_if(in_state(GameLifecycleState::MainMenu)),
+            );
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profiles {
+    pub names: Vec<String>,
+    pub active: usize,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            names: vec!["Pilot 1".to_string()],
+            active: 0,
+        }
+    }
+}
+
+impl Profiles {
+    pub fn load() -> Self {
+        let mut profiles = storage::read_shared(PROFILES_KEY)
+            .and_then(|contents| ron::from_str::<Profiles>(&contents).ok())
+            .unwrap_or_default();
+        if profiles.names.is_empty() {
+            profiles = Profiles::default();
+        }
+        profiles.active = profiles.active.min(profiles.names.len() - 1);
+        profiles
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write_shared(PROFILES_KEY, &contents),
+            Err(e) => println!("Could not serialise profiles: {e}"),
+        }
+    }
+
+    /// The first profile uses the save data from before there were profiles
+    pub fn dir(index: usize) -> Option<String> {
+        (index > 0).then(|| format!("profile_{}", index + 1))
+    }
+
+    pub fn active_name(&self) -> &str {
+        &self.names[self.active]
+    }
+
+    /// Adds a fresh profile and switches to it, unless there are already [`MAX_PROFILES`]
+    pub fn create(&mut self) -> bool {
+        if self.names.len() >= MAX_PROFILES {
+            return false;
+        }
+        self.names.push(format!("Pilot {}", self.names.len() + 1));
+        self.active = self.names.len() - 1;
+        true
+    }
+
+    pub fn cycle(&mut self) -> bool {
+        self.active = (self.active + 1) % self.names.len();
+        self.names.len() > 1
+    }
+}
+
+#[derive(Component)]
+pub struct ProfileTextMarker;
+
+fn profile_text(profiles: &Profiles) -> String {
+    let mut text = format!(
+        "[P] Profile: {} ({}/{})",
+        profiles.active_name(),
+        profiles.active + 1,
+        profiles.names.len()
+    );
+    if profiles.names.len() < MAX_PROFILES {
+        text.push_str("\n[N] New profile");
+    }
+    text
+}
+
+fn spawn_profile_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    profiles: Res<Profiles>,
+) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(15.),
+                left: Val::Px(15.),
+                ..default()
+            },
+            text: Text::from_section(
+                profile_text(&profiles),
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            ),
+            ..default()
+        })
+        .insert((MainMenuMarker, ProfileTextMarker));
+}
+
+fn switch_profile(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut profiles: ResMut<Profiles>,
+    mut profile_label: Query<&mut Text, With<ProfileTextMarker>>,
+    mut difficulty_label: Query<
+        &mut Text,
+        (With<DifficultyTextMarker>, Without<ProfileTextMarker>),
+    >,
+) {
+    let switched = if inputs.just_pressed(KeyCode::KeyN) {
+        profiles.create()
+    } else if inputs.just_pressed(KeyCode::KeyP) {
+        profiles.cycle()
+    } else {
+        false
+    };
+    if !switched {
+        return;
+    }
+    profiles.save();
+    storage::set_profile_dir(Profiles::dir(profiles.active));
+    let settings = Settings::load();
+    if let Ok(mut label) = difficulty_label.get_single_mut() {
+        label.sections[0].value = difficulty_text(&settings);
+    }
+    if let Ok(mut label) = profile_label.get_single_mut() {
+        label.sections[0].value = profile_text(&profiles);
+    }
+    commands.insert_resource(settings);
+    commands.insert_resource(Loadout::load());
+    commands.insert_resource(HighScores::load());
+    commands.insert_resource(MetaProgress::load());
+    commands.insert_resource(AceRoster::load());
+    commands.insert_resource(Telemetry::load());
+    commands.insert_resource(BestGhost::load());
+    commands.insert_resource(AudioSettings::load());
+    commands.insert_resource(InputBindings::load());
+    commands.insert_resource(HudLayout::load());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_keep_separate_save_folders() {
+        let mut profiles = Profiles::default();
+        assert_eq!(Profiles::dir(profiles.active), None);
+        assert!(!profiles.cycle());
+        while profiles.create() {}
+        assert_eq!(profiles.names.len(), MAX_PROFILES);
+        assert_eq!(profiles.active_name(), format!("Pilot {MAX_PROFILES}"));
+        assert_eq!(
+            Profiles::dir(profiles.active),
+            Some(format!("profile_{MAX_PROFILES}"))
+        );
+        assert!(profiles.cycle());
+        assert_eq!(profiles.active, 0);
+    }
+}
@@ -0,0 +1,396 @@
+//! A target range, opened with [R] on the main menu. Three targets are parked ahead, one for each
+//! weight of armour, and never go down. A panel keeps count of damage per second and accuracy,
+//! and [Tab] jumps straight to the next hull so their firepower can be compared. Nothing warps in,
+//! and the run can't be retired, so none of it ends up in the records.
+
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{Added, With, Without},
+        schedule::{
+            common_conditions::{in_state, resource_exists},
+            IntoSystemConfigs, NextState, OnEnter,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::DespawnRecursiveExt,
+    input::{gamepad::GamepadButtonType, keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    time::Time,
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+};
+use bevy_rapier2d::pipeline::CollisionEvent;
+
+use crate::{
+    controls::Pads,
+    events::{DamageCause, ShipDamaged},
+    gameplay::{
+        handle_npc_logic, Bullet, EnemySpacecraftBundle, GameState, GameplaySet,
+        MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker, PlayerBundle,
+        PlayerMarker, ShipProfile, ShipTextures, ShipType, Spacecraft,
+    },
+    GameLifecycleState, MainMenuMarker,
+};
+
+/// Every hull that can be tried out, in the order [Tab] goes through them
+pub const RANGE_HULLS: [ShipType; 11] = [
+    ShipType::Ship1,
+    ShipType::Ship2,
+    ShipType::Ship3,
+    ShipType::Ship4,
+    ShipType::Ship5,
+    ShipType::Ship6,
+    ShipType::Carrier,
+    ShipType::Drone,
+    ShipType::Turret,
+    ShipType::Capital,
+    ShipType::Projector,
+];
+/// Light, medium and heavy armour, and where each is parked
+const TARGETS: [(ShipType, Vec2); 3] = [
+    (ShipType::Ship1, Vec2::new(-0.4, 0.8)),
+    (ShipType::Ship4, Vec2::new(0., 0.9)),
+    (ShipType::Ship6, Vec2::new(0.4, 0.8)),
+];
+/// Far more hull than any weapon can get through, topped up every frame besides
+const TARGET_HULL: i32 = 1_000_000;
+/// How far back damage per second looks
+pub const DPS_WINDOW: Duration = Duration::from_secs(5);
+
+pub struct TargetRangePlugin;
+
+impl Plugin for TargetRangePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameLifecycleState::MainMenu),
+            (leave_range, spawn_range_hint),
+        )
+        .add_systems(
+            Update,
+            open_range.run_if(in_state(GameLifecycleState::MainMenu)),
+        )
+        .add_systems(
+            OnEnter(GameLifecycleState::Game),
+            reset_range.run_if(resource_exists::<TargetRange>),
+        )
+        .add_systems(
+            Update,
+            (
+                (swap_range_hull, hold_range.after(handle_npc_logic)).in_set(GameplaySet::Input),
+                set_up_range.in_set(GameplaySet::Simulation),
+                count_range_hits.in_set(GameplaySet::Collision),
+                (count_range_shots, record_range_damage).in_set(GameplaySet::Cleanup),
+                update_range_panel.in_set(GameplaySet::Presentation),
+            )
+                .run_if(resource_exists::<TargetRange>)
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+/// Present while the run is a trip to the target range, including restarts from the pause menu
+#[derive(Resource, Default)]
+pub struct TargetRange {
+    set_up: bool,
+    hull: Option<usize>,
+    pub readout: RangeReadout,
+}
+
+/// What the panel shows, counted from the last hull swap
+#[derive(Default)]
+pub struct RangeReadout {
+    pub shots: u32,
+    pub hits: u32,
+    /// When each hit landed, in seconds, and how much damage it did
+    pub damage: VecDeque<(f32, i32)>,
+}
+
+impl RangeReadout {
+    pub fn record_damage(&mut self, now: f32, amount: i32) {
+        self.damage.push_back((now, amount));
+        let window = DPS_WINDOW.as_secs_f32();
+        while self
+            .damage
+            .front()
+            .is_some_and(|(at, _)| *at <= now - window)
+        {
+            self.damage.pop_front();
+        }
+    }
+
+    /// Spread over the whole window, so a single burst doesn't read as a huge number
+    pub fn dps(&self, now: f32) -> f32 {
+        let window = DPS_WINDOW.as_secs_f32();
+        let dealt: i32 = self
+            .damage
+            .iter()
+            .filter(|(at, _)| *at > now - window)
+            .map(|(_, amount)| amount)
+            .sum();
+        dealt as f32 / window
+    }
+
+    pub fn accuracy(&self) -> Option<f32> {
+        match self.shots {
+            0 => None,
+            shots => Some(self.hits.min(shots) as f32 / shots as f32),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct RangeTarget;
+
+#[derive(Component)]
+pub struct RangePanelMarker;
+
+fn spawn_range_hint(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(105.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                "[R] Target range",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert(MainMenuMarker);
+}
+
+fn open_range(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+) {
+    if inputs.just_pressed(KeyCode::KeyR) {
+        commands.insert_resource(TargetRange::default());
+        state.set(GameLifecycleState::Game);
+    }
+}
+
+fn leave_range(mut commands: Commands) {
+    commands.remove_resource::<TargetRange>();
+}
+
+/// A restart starts the range over from the loadout's hull
+fn reset_range(mut commands: Commands) {
+    commands.insert_resource(TargetRange::default());
+}
+
+fn set_up_range(
+    mut commands: Commands,
+    mut range: ResMut<TargetRange>,
+    textures: Res<ShipTextures>,
+    asset_server: Res<AssetServer>,
+) {
+    if range.set_up {
+        return;
+    }
+    for (ship_type, position) in TARGETS {
+        commands.spawn((
+            EnemySpacecraftBundle::create_ship(ship_type, position, &textures)
+                .with_tint(Color::GRAY)
+                .with_health(TARGET_HULL),
+            Name::new(format!(
+                "{:?} Target",
+                ShipProfile::from_type(ship_type).armor
+            )),
+            RangeTarget,
+        ));
+    }
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(80.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        },
+        RangePanelMarker,
+    ));
+    range.set_up = true;
+}
+
+/// Targets share the enemy pilot logic, so they're kept still and their guns cold here. A lucky
+/// shot can still disable one, which is shrugged off before the capture choice comes up. The
+/// player's hull is kept whole too, so the range never ends in a death.
+fn hold_range(
+    mut commands: Commands,
+    mut targets: Query<(Entity, &mut Spacecraft), With<RangeTarget>>,
+    mut player: Query<&mut Spacecraft, (With<PlayerMarker>, Without<RangeTarget>)>,
+) {
+    for (entity, mut target) in targets.iter_mut() {
+        target.velocity = 0.;
+        target.weapon_cooldown.reset();
+        target.health = TARGET_HULL;
+        commands
+            .entity(entity)
+            .remove::<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>();
+    }
+    for mut player in player.iter_mut() {
+        player.health = player.profile().max_health;
+    }
+}
+
+/// Swaps in a fresh ship rather than changing the current one, so nothing a hull fits onto
+/// itself, like a capital ship's subsystems, carries over to the next
+fn swap_range_hull(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    mut range: ResMut<TargetRange>,
+    player: Query<(Entity, &Spacecraft), With<PlayerMarker>>,
+    textures: Res<ShipTextures>,
+) {
+    if !inputs.just_pressed(KeyCode::Tab) && !pads.just_pressed(GamepadButtonType::RightTrigger) {
+        return;
+    }
+    if let Ok((entity, ship)) = player.get_single() {
+        let current = range
+            .hull
+            .or_else(|| RANGE_HULLS.iter().position(|hull| *hull == ship.ship_type))
+            .unwrap_or_default();
+        let next = (current + 1) % RANGE_HULLS.len();
+        commands.entity(entity).despawn_recursive();
+        commands
+            .spawn(PlayerBundle::create_ship(
+                RANGE_HULLS[next],
+                Vec2::ZERO,
+                &textures,
+            ))
+            .insert(Name::new("Player"));
+        range.hull = Some(next);
+        range.readout = RangeReadout::default();
+    }
+}
+
+fn count_range_shots(mut range: ResMut<TargetRange>, bullets: Query<&Bullet, Added<Bullet>>) {
+    let shots = bullets.iter().filter(|bullet| bullet.player_shot).count();
+    range.readout.shots += shots as u32;
+}
+
+/// Counted from the collisions themselves, as armour can soak a hit without any damage showing
+fn count_range_hits(
+    mut range: ResMut<TargetRange>,
+    mut collision_events: EventReader<CollisionEvent>,
+    bullets: Query<&Bullet>,
+    targets: Query<(), With<RangeTarget>>,
+) {
+    for event in collision_events.read() {
+        if let CollisionEvent::Started(a, b, _) = event {
+            for (bullet, target) in [(*a, *b), (*b, *a)] {
+                if bullets.get(bullet).is_ok_and(|bullet| bullet.player_shot)
+                    && targets.contains(target)
+                {
+                    range.readout.hits += 1;
+                }
+            }
+        }
+    }
+}
+
+fn record_range_damage(
+    time: Res<Time>,
+    mut range: ResMut<TargetRange>,
+    mut damaged: EventReader<ShipDamaged>,
+    targets: Query<(), With<RangeTarget>>,
+    player: Query<Entity, With<PlayerMarker>>,
+) {
+    let player = player.get_single().ok();
+    for hit in damaged.read() {
+        // A lucky shot takes the whole hull at once, which says nothing about firepower
+        if targets.contains(hit.ship)
+            && hit.amount < TARGET_HULL / 2
+            && hit.attacker.is_some()
+            && hit.attacker == player
+            && matches!(hit.cause, DamageCause::Weapon(_))
+        {
+            range
+                .readout
+                .record_damage(time.elapsed_seconds(), hit.amount);
+        }
+    }
+}
+
+fn update_range_panel(
+    time: Res<Time>,
+    range: Res<TargetRange>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    mut panel: Query<&mut Text, With<RangePanelMarker>>,
+) {
+    if let (Ok(player), Ok(mut panel)) = (player.get_single(), panel.get_single_mut()) {
+        let readout = &range.readout;
+        let accuracy = match readout.accuracy() {
+            Some(accuracy) => format!("{:.0}%", accuracy * 100.),
+            None => "-".to_string(),
+        };
+        panel.sections[0].value = format!(
+            "Target range: {:?}\nDamage per second: {:.1}\nAccuracy: {accuracy} ({}/{})\n[Tab] Next hull",
+            player.ship_type,
+            readout.dps(time.elapsed_seconds()),
+            readout.hits,
+            readout.shots,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_range_reads_out_dps_and_accuracy() {
+        let mut readout = RangeReadout::default();
+        assert_eq!(readout.accuracy(), None);
+        assert_eq!(readout.dps(0.), 0.);
+
+        let window = DPS_WINDOW.as_secs_f32();
+        readout.record_damage(1., 50);
+        readout.record_damage(2., 50);
+        assert_eq!(readout.dps(2.), 100. / window);
+        // The first hit drops out once the window has moved past it
+        readout.record_damage(1. + window, 20);
+        assert_eq!(readout.damage.len(), 2);
+        assert_eq!(readout.dps(1. + window), 70. / window);
+        assert_eq!(readout.dps(10. * window), 0.);
+
+        readout.shots = 8;
+        readout.hits = 6;
+        assert_eq!(readout.accuracy(), Some(0.75));
+
+        for hull in RANGE_HULLS {
+            assert_eq!(
+                RANGE_HULLS.iter().filter(|other| **other == hull).count(),
+                1
+            );
+        }
+    }
+}
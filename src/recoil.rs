@@ -0,0 +1,253 @@
+//! Guns that push back. Every volley knocks the firing ship back along its heading, harder the
+//! more guns it has and the faster they shoot, and the player's volleys also kick the camera for a
+//! moment. [W] on the main menu turns both off.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        event::EventReader,
+        query::{Has, With},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+};
+
+use crate::{
+    events::ShotFired,
+    gameplay::{camera_follow, GameState, GameplaySet, PlayerMarker, ShipProfile, Spacecraft},
+    settings::Settings,
+    GameLifecycleState, MainMenuMarker,
+};
+
+/// Speed lost per gun for each unit of bullet speed, enough that Ship6's triple volley is felt
+pub const RECOIL_PER_SHOT: f32 = 0.02;
+/// How far the camera is thrown back, in pixels, per gun for each unit of bullet speed
+pub const CAMERA_KICK_PER_SHOT: f32 = 60.;
+pub const CAMERA_KICK_TIME: Duration = Duration::from_millis(120);
+
+pub struct RecoilPlugin;
+
+impl Plugin for RecoilPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameLifecycleState::MainMenu),
+            spawn_weapon_kick_text,
+        )
+        .add_systems(
+            Update,
+            toggle_weapon_kick.run_if(in_state(GameLifecycleState::MainMenu)),
+        )
+        .add_systems(OnEnter(GameLifecycleState::Game), reset_camera_kick)
+        .add_systems(
+            Update,
+            (
+                recoil_firing_ships.in_set(GameplaySet::Simulation),
+                kick_camera
+                    .after(camera_follow)
+                    .in_set(GameplaySet::Presentation),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+/// The camera's throw from the player's last volley, easing out over [`CAMERA_KICK_TIME`]
+#[derive(Resource)]
+pub struct CameraKick {
+    pub offset: Vec2,
+    pub timer: Timer,
+}
+
+impl Default for CameraKick {
+    fn default() -> Self {
+        let mut timer = Timer::new(CAMERA_KICK_TIME, TimerMode::Once);
+        timer.tick(CAMERA_KICK_TIME);
+        Self {
+            offset: Vec2::ZERO,
+            timer,
+        }
+    }
+}
+
+impl CameraKick {
+    pub fn current(&self) -> Vec2 {
+        self.offset * (1. - self.timer.fraction())
+    }
+}
+
+/// Speed lost to one volley. Ships that can't move, like turrets, shrug it off.
+pub fn recoil(profile: &ShipProfile) -> f32 {
+    match profile.max_velocity > 0. {
+        true => RECOIL_PER_SHOT * profile.shots as f32 * profile.base_bullet_velocity,
+        false => 0.,
+    }
+}
+
+#[derive(Component)]
+pub struct WeaponKickTextMarker;
+
+fn weapon_kick_text(settings: &Settings) -> String {
+    let state = match settings.weapon_kick {
+        true => "On",
+        false => "Off",
+    };
+    format!("[W] Weapon kick: {state}")
+}
+
+fn spawn_weapon_kick_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(165.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                weapon_kick_text(&settings),
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert((MainMenuMarker, WeaponKickTextMarker));
+}
+
+/// Also catches the settings being swapped out by a profile switch
+fn toggle_weapon_kick(
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut label: Query<&mut Text, With<WeaponKickTextMarker>>,
+) {
+    if inputs.just_pressed(KeyCode::KeyW) {
+        settings.weapon_kick = !settings.weapon_kick;
+    }
+    if settings.is_changed() {
+        if let Ok(mut label) = label.get_single_mut() {
+            label.sections[0].value = weapon_kick_text(&settings);
+        }
+    }
+}
+
+fn reset_camera_kick(mut commands: Commands) {
+    commands.insert_resource(CameraKick::default());
+}
+
+pub fn recoil_firing_ships(
+    settings: Res<Settings>,
+    mut fired: EventReader<ShotFired>,
+    mut ships: Query<(&mut Spacecraft, Has<PlayerMarker>)>,
+    mut kick: ResMut<CameraKick>,
+) {
+    if !settings.weapon_kick {
+        fired.clear();
+        return;
+    }
+    for volley in fired.read() {
+        if let Ok((mut ship, piloted)) = ships.get_mut(volley.ship) {
+            let profile = ship.profile();
+            let max_velocity = profile.max_velocity;
+            ship.velocity =
+                (ship.velocity - recoil(&profile)).clamp(-0.3 * max_velocity, max_velocity);
+            if piloted {
+                let backward = -Vec2::new(ship.heading.sin(), ship.heading.cos());
+                kick.offset = backward
+                    * CAMERA_KICK_PER_SHOT
+                    * profile.shots as f32
+                    * profile.base_bullet_velocity;
+                kick.timer.reset();
+            }
+        }
+    }
+}
+
+/// Added on top of wherever the camera was put on the player's ship this frame, so it never drifts
+/// away. Without a ship nothing puts it back, so there's no kick either.
+fn kick_camera(
+    time: Res<Time>,
+    mut kick: ResMut<CameraKick>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+    player: Query<(), With<PlayerMarker>>,
+) {
+    kick.timer.tick(time.delta());
+    if player.is_empty() {
+        return;
+    }
+    if let Ok(mut camera) = camera.get_single_mut() {
+        let offset = kick.current();
+        camera.translation.x += offset.x;
+        camera.translation.y += offset.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, ShipType};
+    use bevy::ecs::entity::Entity;
+
+    #[test]
+    fn volleys_push_the_firing_ship_back_unless_turned_off() {
+        let mut app = test_app();
+        app.insert_resource(Settings::default())
+            .init_resource::<CameraKick>()
+            .add_systems(Update, recoil_firing_ships);
+        let mut heavy = Spacecraft::from_template(ShipType::Ship6, Vec2::ZERO);
+        heavy.velocity = heavy.profile().max_velocity;
+        let player = app.world.spawn((heavy, PlayerMarker)).id();
+        let light = app
+            .world
+            .spawn(Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO))
+            .id();
+        for ship in [player, light] {
+            app.world.send_event(ShotFired {
+                ship,
+                player_shot: ship == player,
+            });
+        }
+        app.update();
+
+        let profile = ShipProfile::from_type(ShipType::Ship6);
+        let velocity =
+            |app: &App, ship: Entity| app.world.get::<Spacecraft>(ship).unwrap().velocity;
+        assert!((velocity(&app, player) - (profile.max_velocity - recoil(&profile))).abs() < 1e-6);
+        assert!(recoil(&profile) > 2. * recoil(&ShipProfile::from_type(ShipType::Ship1)));
+        // A ship sitting still drifts backwards, but never faster than it can reverse
+        assert!(velocity(&app, light) < 0.);
+        assert!(
+            velocity(&app, light) >= -0.3 * ShipProfile::from_type(ShipType::Ship1).max_velocity
+        );
+        assert!(app.world.resource::<CameraKick>().current().y < 0.);
+        assert_eq!(recoil(&ShipProfile::from_type(ShipType::Turret)), 0.);
+
+        app.world.resource_mut::<Settings>().weapon_kick = false;
+        let before = velocity(&app, player);
+        app.world.send_event(ShotFired {
+            ship: player,
+            player_shot: true,
+        });
+        app.update();
+        assert_eq!(velocity(&app, player), before);
+    }
+}
@@ -0,0 +1,377 @@
+//! What carries over between runs: the best scores, and the salvage credits banked at the end of each run.
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        event::EventWriter,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Res, ResMut, Resource},
+    },
+    prelude::App,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::HighScoreBeaten,
+    gameplay::{GameplaySet, PlayerScore, ShipType},
+    loadout::{escort_cost, Loadout},
+    seed::{settle_run_config, RunConfig, RunSeed},
+    settings::Difficulty,
+    storage, GameLifecycleState,
+};
+
+const HIGH_SCORES_KEY: &str = "highscores.ron";
+const PROGRESS_KEY: &str = "progress.ron";
+
+/// How many runs the high score table keeps
+pub const MAX_HIGH_SCORES: usize = 10;
+/// Score it takes to earn one salvage credit
+pub const SCORE_PER_CREDIT: u32 = 10;
+
+pub struct RecordsPlugin;
+
+impl Plugin for RecordsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .insert_resource(MetaProgress::load())
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                set_best_to_beat.after(settle_run_config),
+            )
+            .add_systems(
+                Update,
+                watch_for_new_best
+                    .in_set(GameplaySet::Cleanup)
+                    .run_if(in_state(GameLifecycleState::Game)),
+            )
+            .add_systems(OnEnter(GameLifecycleState::EndScreen), bank_run);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub score: u32,
+    pub survived_secs: f32,
+    /// Whether the run was retired from the pause menu rather than ending in death
+    #[serde(default)]
+    pub retired: bool,
+    /// What the run was flown at, so it can be replayed. Older entries have none of these.
+    #[serde(default)]
+    pub seed: Option<u32>,
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    #[serde(default)]
+    pub loadout: Option<Loadout>,
+    /// When the run was banked, in seconds since the Unix epoch
+    #[serde(default)]
+    pub date: Option<u64>,
+    /// Whether aim assist was pulling the nose round. These runs are kept off the table.
+    #[serde(default)]
+    pub aim_assisted: bool,
+}
+
+impl ScoreEntry {
+    /// As a UTC calendar date, like 2024-03-09
+    pub fn date_text(&self) -> Option<String> {
+        self.date.map(date_text)
+    }
+}
+
+pub fn date_text(unix_secs: u64) -> String {
+    // Days to a civil date, after Howard Hinnant's days_from_civil in reverse
+    let days = (unix_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_unix_secs() -> Option<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|since| since.as_secs())
+}
+
+/// There's no system clock to ask on the web, so the browser's is used instead
+#[cfg(target_arch = "wasm32")]
+fn now_unix_secs() -> Option<u64> {
+    Some((js_sys::Date::now() / 1000.) as u64)
+}
+
+/// Best runs first
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<ScoreEntry>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        storage::read(HIGH_SCORES_KEY)
+            .and_then(|contents| ron::from_str::<HighScores>(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(HIGH_SCORES_KEY, &contents),
+            Err(e) => println!("Could not serialise high scores: {e}"),
+        }
+    }
+
+    /// Returns the entry's place in the table, if it made it in
+    pub fn submit(&mut self, entry: ScoreEntry) -> Option<usize> {
+        if entry.aim_assisted {
+            return None;
+        }
+        let place = self
+            .entries
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(self.entries.len());
+        if place >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entries.insert(place, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(place)
+    }
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetaProgress {
+    pub credits: u32,
+    /// Credits the last run to finish added
+    pub last_reward: u32,
+    /// Credits spent on unlocks, so milestones still go by everything ever banked
+    pub spent: u32,
+    /// Escort hulls bought to start runs alongside, see [`crate::loadout::ESCORT_PRESETS`]
+    pub escorts: Vec<ShipType>,
+}
+
+impl MetaProgress {
+    pub fn load() -> Self {
+        storage::read(PROGRESS_KEY)
+            .and_then(|contents| ron::from_str::<MetaProgress>(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(PROGRESS_KEY, &contents),
+            Err(e) => println!("Could not serialise progress: {e}"),
+        }
+    }
+
+    /// Every credit ever banked, spent or not
+    pub fn banked(&self) -> u32 {
+        self.credits + self.spent
+    }
+
+    /// Buys an escort preset, if it's one and there are the credits for it. Returns whether it's
+    /// unlocked afterwards.
+    pub fn unlock_escort(&mut self, hull: ShipType) -> bool {
+        if self.escorts.contains(&hull) {
+            return true;
+        }
+        match escort_cost(hull) {
+            Some(cost) if cost <= self.credits => {
+                self.credits -= cost;
+                self.spent += cost;
+                self.escorts.push(hull);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The escort the loadout asks for, as long as it's been bought
+    pub fn starting_escort(&self, loadout: &Loadout) -> Option<ShipType> {
+        loadout.escort.filter(|hull| self.escorts.contains(hull))
+    }
+}
+
+pub fn credits_for_score(score: u32) -> u32 {
+    score / SCORE_PER_CREDIT
+}
+
+/// Where the run that just finished landed in the high score table, if it made it in
+#[derive(Resource)]
+pub struct BankedPlace(pub Option<usize>);
+
+impl BankedPlace {
+    /// What the end screen says about it. Topping a table with nothing in it yet doesn't count.
+    pub fn headline(&self, table_size: usize) -> Option<String> {
+        match self.0? {
+            0 if table_size > 1 => Some("New High Score!".to_string()),
+            0 => None,
+            place => Some(format!("#{} on the high score list", place + 1)),
+        }
+    }
+}
+
+/// Set when the player chose to end the run, so it can be told apart from a death
+#[derive(Resource)]
+pub struct RunRetired;
+
+/// The profile's best score going into the run, until the run beats it. An aim assisted run can't
+/// make the table, so it has nothing to beat.
+#[derive(Resource, Default)]
+pub struct BestToBeat(pub Option<u32>);
+
+pub fn set_best_to_beat(mut commands: Commands, high_scores: Res<HighScores>, run: Res<RunConfig>) {
+    let best = match run.aim_assist {
+        true => None,
+        false => high_scores.entries.first().map(|entry| entry.score),
+    };
+    commands.insert_resource(BestToBeat(best));
+}
+
+/// A first run has nothing to beat, so it never counts
+pub fn watch_for_new_best(
+    score: Res<PlayerScore>,
+    mut best: ResMut<BestToBeat>,
+    mut beaten: EventWriter<HighScoreBeaten>,
+) {
+    if let Some(previous_best) = best.0 {
+        if score.score > previous_best {
+            beaten.send(HighScoreBeaten { previous_best });
+            best.0 = None;
+        }
+    }
+}
+
+pub fn bank_run(
+    mut commands: Commands,
+    score: Res<PlayerScore>,
+    retired: Option<Res<RunRetired>>,
+    seed: Res<RunSeed>,
+    run: Res<RunConfig>,
+    mut high_scores: ResMut<HighScores>,
+    mut progress: ResMut<MetaProgress>,
+) {
+    let place = high_scores.submit(ScoreEntry {
+        score: score.score,
+        survived_secs: score.survived_time.elapsed_secs(),
+        retired: retired.is_some(),
+        seed: Some(seed.seed),
+        difficulty: Some(run.difficulty),
+        loadout: Some(run.loadout),
+        date: now_unix_secs(),
+        aim_assisted: run.aim_assist,
+    });
+    commands.insert_resource(BankedPlace(place));
+    high_scores.save();
+    progress.last_reward = credits_for_score(score.score);
+    progress.credits += progress.last_reward;
+    progress.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed::ReplayConfig;
+    use crate::settings::Settings;
+    use bevy::prelude::default;
+
+    #[test]
+    fn high_scores_are_dated_and_a_new_best_is_called_out() {
+        assert_eq!(date_text(0), "1970-01-01");
+        assert_eq!(date_text(951_782_400), "2000-02-29");
+        assert_eq!(date_text(1_709_942_399), "2024-03-08");
+        assert_eq!(date_text(1_709_942_400), "2024-03-09");
+
+        let old: HighScores =
+            ron::from_str("(entries: [(score: 40, survived_secs: 12.0, retired: false)])").unwrap();
+        assert_eq!(old.entries[0].date_text(), None);
+
+        let entry = |score| ScoreEntry {
+            score,
+            survived_secs: 30.,
+            retired: false,
+            seed: None,
+            difficulty: None,
+            loadout: None,
+            date: Some(1_709_942_400),
+            aim_assisted: false,
+        };
+        let mut high_scores = HighScores::default();
+        let first = high_scores.submit(entry(50));
+        // Nothing to beat on the first run
+        assert_eq!(BankedPlace(first).headline(high_scores.entries.len()), None);
+        let best = high_scores.submit(entry(80));
+        assert_eq!(
+            BankedPlace(best).headline(high_scores.entries.len()),
+            Some("New High Score!".to_string())
+        );
+        let second = high_scores.submit(entry(60));
+        assert_eq!(
+            BankedPlace(second).headline(high_scores.entries.len()),
+            Some("#2 on the high score list".to_string())
+        );
+        assert_eq!(BankedPlace(None).headline(high_scores.entries.len()), None);
+        // However well an aim assisted run went, it's kept off the table
+        let assisted = high_scores.submit(ScoreEntry {
+            aim_assisted: true,
+            ..entry(500)
+        });
+        assert_eq!(assisted, None);
+        assert_eq!(high_scores.entries.len(), 3);
+        assert_eq!(
+            high_scores.entries[0].date_text(),
+            Some("2024-03-09".to_string())
+        );
+    }
+
+    #[test]
+    fn an_aim_assisted_run_stays_assisted_when_resumed_with_the_assist_off() {
+        let mut high_scores = HighScores::default();
+        high_scores.submit(ScoreEntry {
+            score: 90,
+            survived_secs: 30.,
+            retired: false,
+            seed: None,
+            difficulty: None,
+            loadout: None,
+            date: None,
+            aim_assisted: false,
+        });
+        let mut app = App::new();
+        app.insert_resource(Settings {
+            aim_assist: true,
+            ..default()
+        })
+        .insert_resource(Loadout::default())
+        .insert_resource(high_scores)
+        .init_resource::<RunConfig>()
+        .add_systems(Update, (settle_run_config, set_best_to_beat).chain());
+        app.update();
+        let flown = *app.world.resource::<RunConfig>();
+        assert!(flown.aim_assist);
+        assert_eq!(app.world.resource::<BestToBeat>().0, None);
+
+        // Turned off on the menu, then the run's checkpoint picked back up
+        app.world.resource_mut::<Settings>().aim_assist = false;
+        app.insert_resource(ReplayConfig(flown));
+        app.update();
+        assert!(app.world.resource::<RunConfig>().aim_assist);
+        assert_eq!(app.world.resource::<BestToBeat>().0, None);
+
+        // A new run goes without
+        app.update();
+        assert!(!app.world.resource::<RunConfig>().aim_assist);
+        assert_eq!(app.world.resource::<BestToBeat>().0, Some(90));
+    }
+}
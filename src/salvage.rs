@@ -0,0 +1,512 @@
+//! Wrecks scatter salvage. Every enemy that goes down, shot down or scuttled, throws off a few
+//! crates that drift away from it and slow to a stop, until the player flies through them or they
+//! float off for good. Each crate is worth some points and a little scrap, the run's salvage kept
+//! in [`PlayerScore::scrap`]. Now and then a big ship leaves a gun pod as well, which bolts onto
+//! whatever the player is flying for an extra stream of fire until it wears out, and the odd wreck
+//! leaves a repair kit that patches up the player's hull, or a spare charge for the consumable the
+//! run was started with. Anything the player flies near enough to is drawn in the rest of the way.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::{Quat, Vec2},
+    prelude::{default, App},
+    render::{color::Color, view::Visibility},
+    sprite::{Sprite, SpriteBundle},
+    text::{Text, TextStyle},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+    ui::{node_bundles::TextBundle, Style, UiRect, Val},
+};
+use rand::Rng;
+
+use crate::{
+    events::{Allegiance, ShipDestroyed},
+    gameplay::{
+        GameState, GameplaySet, PlayerMarker, PlayerScore, ShipType, Spacecraft, PIXELS_PER_UNIT,
+    },
+    loadout::ConsumableCharge,
+    score::{ScoreEvent, ScoreSource},
+    ui::{spawn_ui, HudAnchor, HudElement},
+    GameLifecycleState,
+};
+
+/// How close the player has to fly to a crate to pick it up
+pub const SALVAGE_PICKUP_RADIUS: f32 = 0.15;
+/// Points for every crate picked up
+pub const SALVAGE_POINTS: u32 = 3;
+/// How long a crate floats about before it's lost
+pub const SALVAGE_LIFETIME: Duration = Duration::from_secs(20);
+/// How long a gun pod keeps firing once it's bolted on
+pub const GUN_POD_TIME: Duration = Duration::from_secs(30);
+/// Chance a wreck of at least [`GUN_POD_TIER`] leaves a gun pod
+pub const GUN_POD_DROP_CHANCE: f64 = 0.3;
+pub const GUN_POD_TIER: u8 = 5;
+/// Chance any wreck leaves a repair kit
+pub const REPAIR_KIT_DROP_CHANCE: f64 = 0.15;
+/// Chance any wreck leaves a spare consumable charge
+pub const SPARE_CHARGE_DROP_CHANCE: f64 = 0.05;
+/// Crates within this of the player are pulled towards it
+pub const SALVAGE_MAGNET_RADIUS: f32 = 0.5;
+/// How fast a crate in magnet range closes in, in world units per second
+const SALVAGE_MAGNET_SPEED: f32 = 0.6;
+/// Fraction of a crate's speed it loses every second
+const SALVAGE_DRAG: f32 = 0.7;
+/// Fastest a crate leaves the wreck, in world units per second
+const SALVAGE_SPEED: f32 = 0.3;
+const SALVAGE_SIZE: f32 = 8.;
+const SALVAGE_COLOR: Color = Color::rgb(0.85, 0.7, 0.35);
+const GUN_POD_COLOR: Color = Color::rgb(1., 0.35, 0.3);
+const REPAIR_KIT_COLOR: Color = Color::rgb(0.4, 1., 0.5);
+const SPARE_CHARGE_COLOR: Color = Color::rgb(0.45, 0.75, 1.);
+
+pub struct SalvagePlugin;
+
+impl Plugin for SalvagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (drift_salvage, wear_gun_pods).in_set(GameplaySet::Simulation),
+                collect_salvage.in_set(GameplaySet::Collision),
+                drop_salvage.in_set(GameplaySet::Cleanup),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        )
+        .add_systems(
+            OnEnter(GameLifecycleState::Game),
+            spawn_gun_pod_timer.after(spawn_ui),
+        )
+        .add_systems(
+            Update,
+            update_gun_pod_timer
+                .in_set(GameplaySet::Presentation)
+                .run_if(in_state(GameLifecycleState::Game)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SalvageContents {
+    Scrap(u32),
+    GunPod,
+    /// Hull points it patches back on
+    RepairKit(i32),
+    /// One more use of the run's consumable
+    SpareCharge,
+}
+
+impl SalvageContents {
+    fn color(self) -> Color {
+        match self {
+            SalvageContents::Scrap(_) => SALVAGE_COLOR,
+            SalvageContents::GunPod => GUN_POD_COLOR,
+            SalvageContents::RepairKit(_) => REPAIR_KIT_COLOR,
+            SalvageContents::SpareCharge => SPARE_CHARGE_COLOR,
+        }
+    }
+}
+
+/// A crate thrown off a wreck
+#[derive(Component)]
+pub struct Salvage {
+    pub contents: SalvageContents,
+    pub position: Vec2,
+    /// World units per second
+    pub velocity: Vec2,
+    lifetime: Timer,
+}
+
+impl Salvage {
+    pub fn new(contents: SalvageContents, position: Vec2, velocity: Vec2) -> Self {
+        Self {
+            contents,
+            position,
+            velocity,
+            lifetime: Timer::new(SALVAGE_LIFETIME, TimerMode::Once),
+        }
+    }
+}
+
+/// How many crates of scrap a wreck leaves, and the scrap in each. Bigger hulls leave more of both.
+pub fn salvage_for(ship_type: ShipType) -> (usize, u32) {
+    let tier = ship_type.tier() as u32;
+    (1 + tier as usize / 2, tier.max(1))
+}
+
+/// Whether a wreck leaves a gun pod, given a roll from 0 to 1
+pub fn drops_gun_pod(ship_type: ShipType, roll: f64) -> bool {
+    ship_type.tier() >= GUN_POD_TIER && roll < GUN_POD_DROP_CHANCE
+}
+
+pub fn drop_salvage(mut commands: Commands, mut destroyed: EventReader<ShipDestroyed>) {
+    let mut rand = rand::thread_rng();
+    for wreck in destroyed.read() {
+        if wreck.allegiance != Allegiance::Enemy {
+            continue;
+        }
+        let (crates, scrap) = salvage_for(wreck.ship_type);
+        let mut contents = vec![SalvageContents::Scrap(scrap); crates];
+        if drops_gun_pod(wreck.ship_type, rand.gen()) {
+            contents.push(SalvageContents::GunPod);
+        }
+        if rand.gen_bool(REPAIR_KIT_DROP_CHANCE) {
+            contents.push(SalvageContents::RepairKit(rand.gen_range(1..=2)));
+        }
+        if rand.gen_bool(SPARE_CHARGE_DROP_CHANCE) {
+            contents.push(SalvageContents::SpareCharge);
+        }
+        for contents in contents {
+            let heading = rand.gen_range(0. ..std::f32::consts::TAU);
+            let speed = rand.gen_range(0.3..1.) * SALVAGE_SPEED;
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: contents.color(),
+                        custom_size: Some(Vec2::splat(SALVAGE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        (wreck.position * PIXELS_PER_UNIT).extend(5.),
+                    )
+                    .with_rotation(Quat::from_rotation_z(heading)),
+                    ..default()
+                },
+                Salvage::new(contents, wreck.position, Vec2::from_angle(heading) * speed),
+            ));
+        }
+    }
+}
+
+/// Crates coast to a stop, tumbling as they go, and fade out over their last few seconds. Any
+/// within [`SALVAGE_MAGNET_RADIUS`] of the player home in on it.
+pub fn drift_salvage(
+    mut commands: Commands,
+    time: Res<Time>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    mut crates: Query<(Entity, &mut Salvage, &mut Transform, &mut Sprite)>,
+) {
+    let delta = time.delta_seconds();
+    let player = player.get_single().ok().map(|player| player.position);
+    for (entity, mut salvage, mut transform, mut sprite) in crates.iter_mut() {
+        if salvage.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let velocity = salvage.velocity;
+        salvage.position += velocity * delta;
+        salvage.velocity *= (1. - SALVAGE_DRAG * delta).max(0.);
+        if let Some(player) = player {
+            let to_player = player - salvage.position;
+            if to_player.length() < SALVAGE_MAGNET_RADIUS {
+                let pull = (SALVAGE_MAGNET_SPEED * delta).min(to_player.length());
+                salvage.position += to_player.normalize_or_zero() * pull;
+            }
+        }
+        transform.translation = (salvage.position * PIXELS_PER_UNIT).extend(5.);
+        transform.rotate_z(velocity.length() * 10. * delta);
+        let alpha = (salvage.lifetime.remaining_secs() / 3.).min(1.);
+        sprite.color = salvage.contents.color().with_a(alpha);
+    }
+}
+
+pub fn collect_salvage(
+    mut commands: Commands,
+    mut score: ResMut<PlayerScore>,
+    mut score_events: EventWriter<ScoreEvent>,
+    mut player: Query<&mut Spacecraft, With<PlayerMarker>>,
+    crates: Query<(Entity, &Salvage)>,
+    mut charge: Option<ResMut<ConsumableCharge>>,
+) {
+    let Ok(mut player) = player.get_single_mut() else {
+        return;
+    };
+    for (entity, salvage) in crates.iter() {
+        if salvage.position.distance(player.position) >= SALVAGE_PICKUP_RADIUS {
+            continue;
+        }
+        commands.entity(entity).despawn();
+        match salvage.contents {
+            SalvageContents::Scrap(scrap) => {
+                score.scrap += scrap;
+                score_events.send(ScoreEvent {
+                    source: ScoreSource::Pickups,
+                    points: SALVAGE_POINTS,
+                });
+            }
+            // A second pod only tops the first back up
+            SalvageContents::GunPod => {
+                player.gun_pod = Some(Timer::new(GUN_POD_TIME, TimerMode::Once));
+            }
+            SalvageContents::RepairKit(repair) => {
+                player.health = (player.health + repair).min(player.profile().max_health);
+            }
+            SalvageContents::SpareCharge => {
+                if let Some(charge) = charge.as_mut() {
+                    charge.add(1);
+                }
+            }
+        }
+    }
+}
+
+/// Pods stay with the hull they're bolted to, so one left on a ship the player swapped out of
+/// keeps going for whoever flies it now
+pub fn wear_gun_pods(time: Res<Time>, mut ships: Query<&mut Spacecraft>) {
+    for mut ship in ships.iter_mut() {
+        let worn_out = ship
+            .gun_pod
+            .as_mut()
+            .is_some_and(|pod| pod.tick(time.delta()).finished());
+        if worn_out {
+            ship.gun_pod = None;
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct GunPodTimerMarker;
+
+fn spawn_gun_pod_timer(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    anchors: Query<(Entity, &HudAnchor)>,
+) {
+    let timer = commands
+        .spawn(TextBundle {
+            style: Style {
+                margin: UiRect::top(Val::Px(8.)),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 16.,
+                    color: GUN_POD_COLOR,
+                },
+            )
+        })
+        .insert((GunPodTimerMarker, HudElement))
+        .id();
+    HudAnchor::BottomLeft.attach(&mut commands, &anchors, timer);
+}
+
+fn update_gun_pod_timer(
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    mut timer: Query<(&mut Text, &mut Visibility), With<GunPodTimerMarker>>,
+) {
+    let Ok((mut text, mut visibility)) = timer.get_single_mut() else {
+        return;
+    };
+    let pod = player
+        .get_single()
+        .ok()
+        .and_then(|ship| ship.gun_pod.as_ref());
+    match pod {
+        Some(pod) => {
+            text.sections[0].value = format!("Gun pod: {:.0}s", pod.remaining_secs().ceil());
+            *visibility = Visibility::Inherited;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{
+        testing::{drain_score, test_app},
+        ShipProfile,
+    };
+    use crate::loadout::{Consumable, ConsumableCharge};
+    use bevy::ecs::event::Events;
+
+    #[test]
+    fn wrecks_drop_salvage_the_player_can_pick_up() {
+        let mut app = test_app();
+        app.add_systems(Update, (drop_salvage, collect_salvage).chain());
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::new(5., 0.)),
+            PlayerMarker,
+        ));
+        let wreck = |allegiance| ShipDestroyed {
+            ship: Entity::PLACEHOLDER,
+            ship_type: ShipType::Ship4,
+            allegiance,
+            position: Vec2::ZERO,
+            killer: None,
+        };
+        // Lost allies are the fleet's loss, not salvage
+        app.world.send_event(wreck(Allegiance::Ally));
+        app.world.send_event(wreck(Allegiance::Enemy));
+        app.update();
+        // Repair kits and spare charges turn up by chance, and have tests of their own
+        let kits = app
+            .world
+            .query::<(Entity, &Salvage)>()
+            .iter(&app.world)
+            .filter(|(_, salvage)| {
+                matches!(
+                    salvage.contents,
+                    SalvageContents::RepairKit(_) | SalvageContents::SpareCharge
+                )
+            })
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for kit in kits {
+            app.world.despawn(kit);
+        }
+        let (crates, scrap) = salvage_for(ShipType::Ship4);
+        let mut salvage = app.world.query::<&Salvage>();
+        assert_eq!(salvage.iter(&app.world).count(), crates);
+        assert!(salvage.iter(&app.world).all(|salvage| {
+            salvage.contents == SalvageContents::Scrap(scrap) && salvage.position == Vec2::ZERO
+        }));
+
+        app.world
+            .query_filtered::<&mut Spacecraft, With<PlayerMarker>>()
+            .single_mut(&mut app.world)
+            .position = Vec2::new(0.05, 0.);
+        app.update();
+        assert_eq!(salvage.iter(&app.world).count(), 0);
+        assert_eq!(
+            app.world.resource::<PlayerScore>().scrap,
+            scrap * crates as u32
+        );
+        let mut events = app.world.resource_mut::<Events<ScoreEvent>>();
+        assert_eq!(
+            drain_score(&mut events),
+            vec![(ScoreSource::Pickups, SALVAGE_POINTS); crates]
+        );
+    }
+
+    #[test]
+    fn gun_pods_bolt_on_a_temporary_mount() {
+        assert!(drops_gun_pod(ShipType::Carrier, 0.));
+        assert!(!drops_gun_pod(ShipType::Carrier, 0.99));
+        assert!(!drops_gun_pod(ShipType::Ship1, 0.));
+
+        let mut app = test_app();
+        app.add_systems(Update, (collect_salvage, wear_gun_pods).chain());
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+                PlayerMarker,
+            ))
+            .id();
+        app.world.spawn(Salvage::new(
+            SalvageContents::GunPod,
+            Vec2::ZERO,
+            Vec2::ZERO,
+        ));
+        app.update();
+        assert!(app
+            .world
+            .get::<Spacecraft>(player)
+            .unwrap()
+            .gun_pod
+            .is_some());
+        // Pods pay no points or scrap, just the gun
+        assert_eq!(app.world.resource::<PlayerScore>().scrap, 0);
+
+        app.world
+            .get_mut::<Spacecraft>(player)
+            .unwrap()
+            .gun_pod
+            .as_mut()
+            .unwrap()
+            .set_elapsed(GUN_POD_TIME);
+        app.update();
+        assert!(app
+            .world
+            .get::<Spacecraft>(player)
+            .unwrap()
+            .gun_pod
+            .is_none());
+    }
+
+    #[test]
+    fn repair_kits_are_drawn_in_and_patch_the_hull() {
+        let mut app = test_app();
+        app.add_systems(Update, (drift_salvage, collect_salvage).chain());
+        let max_health = ShipProfile::from_type(ShipType::Ship1).max_health;
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+                PlayerMarker,
+            ))
+            .id();
+        app.world.get_mut::<Spacecraft>(player).unwrap().health = max_health - 3;
+        let kit = |position| {
+            (
+                Salvage::new(SalvageContents::RepairKit(2), position, Vec2::ZERO),
+                Transform::default(),
+                Sprite::default(),
+            )
+        };
+        app.world.spawn(kit(Vec2::X * SALVAGE_MAGNET_RADIUS * 0.8));
+        let stray = app
+            .world
+            .spawn(kit(Vec2::X * SALVAGE_MAGNET_RADIUS * 2.))
+            .id();
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world.get::<Spacecraft>(player).unwrap().health,
+            max_health - 1
+        );
+        assert_eq!(
+            app.world.get::<Salvage>(stray).unwrap().position,
+            Vec2::X * SALVAGE_MAGNET_RADIUS * 2.
+        );
+
+        // Never past the hull's full health
+        app.world.spawn(kit(Vec2::ZERO));
+        app.update();
+        assert_eq!(
+            app.world.get::<Spacecraft>(player).unwrap().health,
+            max_health
+        );
+    }
+
+    #[test]
+    fn spare_charges_top_up_the_runs_consumable() {
+        let mut app = test_app();
+        app.insert_resource(ConsumableCharge {
+            consumable: Consumable::Overdrive,
+            charges: 0,
+        })
+        .add_systems(Update, collect_salvage);
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        for _ in 0..2 {
+            app.world.spawn(Salvage::new(
+                SalvageContents::SpareCharge,
+                Vec2::ZERO,
+                Vec2::ZERO,
+            ));
+        }
+        app.update();
+        let charge = app.world.resource::<ConsumableCharge>();
+        assert_eq!(charge.consumable, Consumable::Overdrive);
+        assert_eq!(charge.charges, 2);
+        assert_eq!(app.world.resource::<PlayerScore>().scrap, 0);
+    }
+}
@@ -0,0 +1,445 @@
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::{default, App},
+    render::{color::Color, view::Visibility},
+    text::{Text, TextSection, TextStyle},
+    ui::{node_bundles::TextBundle, Style, UiRect, Val},
+};
+use serde::Serialize;
+
+use crate::{
+    barks::{nearest_wingmate, Bark},
+    dialogue::BarkKind,
+    events::{Allegiance, ShipCaptured, ShipDestroyed},
+    gameplay::{
+        points_for_ship, Captured, GameplaySet, PlayerMarker, PlayerScore, ShipType, Spacecraft,
+        BORDER_KILL_RADIUS,
+    },
+    ui::{spawn_ui, HudAnchor, HudElement},
+    GameLifecycleState,
+};
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScoreEvent>()
+            .insert_resource(ScoreBreakdown::default())
+            .insert_resource(ScoreMultipliers::default())
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                (
+                    reset_score_breakdown,
+                    spawn_breakdown_overlay.after(spawn_ui),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_danger_multiplier,
+                    reward_underdog_takedowns,
+                    apply_score_events,
+                    show_score_breakdown,
+                )
+                    .chain()
+                    .in_set(GameplaySet::Cleanup)
+                    .run_if(in_state(GameLifecycleState::Game)),
+            );
+    }
+}
+
+/// Every change to the player's score goes through one of these
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ScoreEvent {
+    pub source: ScoreSource,
+    pub points: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ScoreSource {
+    Survival,
+    Kills,
+    Captures,
+    Objectives,
+    Pickups,
+    /// Extra points for scoring far out from the origin
+    Danger,
+    /// Kills and captures made in a hull well below the victim's, see [`underdog_bonus`]
+    Underdog,
+    Cheats,
+}
+
+impl ScoreSource {
+    pub const ALL: [ScoreSource; 8] = [
+        ScoreSource::Survival,
+        ScoreSource::Kills,
+        ScoreSource::Captures,
+        ScoreSource::Objectives,
+        ScoreSource::Pickups,
+        ScoreSource::Danger,
+        ScoreSource::Underdog,
+        ScoreSource::Cheats,
+    ];
+}
+
+/// Applied to every score event as it comes in
+#[derive(Resource)]
+pub struct ScoreMultipliers {
+    pub objective: f32,
+    /// Grows with the player's distance from the origin, see [`danger_multiplier`]
+    pub danger: f32,
+}
+
+impl Default for ScoreMultipliers {
+    fn default() -> Self {
+        Self {
+            objective: 1.,
+            danger: 1.,
+        }
+    }
+}
+
+/// Multiplier at the kill border, scaling linearly from x1 at the origin
+pub const MAX_DANGER_MULTIPLIER: f32 = 2.5;
+
+/// The same distance that drives spawn pressure and the border, rounded to a tenth for the HUD
+pub fn danger_multiplier(distance_from_origin: f32) -> f32 {
+    let danger = (distance_from_origin / BORDER_KILL_RADIUS).clamp(0., 1.);
+    ((1. + (MAX_DANGER_MULTIPLIER - 1.) * danger) * 10.).round() / 10.
+}
+
+fn update_danger_multiplier(
+    mut multipliers: ResMut<ScoreMultipliers>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+) {
+    let danger = match player.get_single() {
+        Ok(player) => danger_multiplier(player.position.distance(Vec2::ZERO)),
+        Err(_) => 1.,
+    };
+    if multipliers.danger != danger {
+        multipliers.danger = danger;
+    }
+}
+
+/// How many tiers the victim has to be above the player's hull for an underdog bonus
+pub const UNDERDOG_TIER_GAP: u8 = 2;
+/// Underdog bonus for every tier the victim has on the player's hull, as a multiple of what the
+/// victim costs to spawn
+pub const UNDERDOG_MULTIPLIER_PER_TIER: f32 = 1.5;
+
+/// Extra points for taking down `victim` while flying `pilot`, if it outclasses the pilot's hull by
+/// at least [`UNDERDOG_TIER_GAP`] tiers. The wider the gap, the bigger the bonus.
+pub fn underdog_bonus(pilot: ShipType, victim: ShipType) -> Option<u32> {
+    let gap = victim
+        .tier()
+        .checked_sub(pilot.tier())
+        .filter(|gap| *gap >= UNDERDOG_TIER_GAP)?;
+    let multiplier = UNDERDOG_MULTIPLIER_PER_TIER * gap as f32;
+    Some((points_for_ship(&victim) as f32 * multiplier).round() as u32)
+}
+
+/// Pays out the underdog bonus for the player's kills and captures, and has the enemy side take it
+/// badly: the captured ship itself, or the nearest wingmate of one that was shot down. A capture
+/// only pays once the player has chosen to keep the ship.
+#[allow(clippy::type_complexity)]
+pub fn reward_underdog_takedowns(
+    mut destroyed: EventReader<ShipDestroyed>,
+    mut captured: EventReader<ShipCaptured>,
+    mut score_events: EventWriter<ScoreEvent>,
+    mut barks: EventWriter<Bark>,
+    player: Query<(Entity, &Spacecraft), With<PlayerMarker>>,
+    enemies: Query<(Entity, &Spacecraft), (Without<Captured>, Without<PlayerMarker>)>,
+) {
+    let Ok((pilot, flown)) = player.get_single() else {
+        destroyed.clear();
+        captured.clear();
+        return;
+    };
+    let mut reward = |victim: ShipType| {
+        let bonus = underdog_bonus(flown.ship_type, victim);
+        if let Some(points) = bonus {
+            score_events.send(ScoreEvent {
+                source: ScoreSource::Underdog,
+                points,
+            });
+        }
+        bonus.is_some()
+    };
+    for capture in captured.read() {
+        if reward(capture.ship_type) {
+            barks.send(Bark {
+                ship: capture.ship,
+                kind: BarkKind::Outclassed,
+            });
+        }
+    }
+    for kill in destroyed.read() {
+        if kill.allegiance != Allegiance::Enemy
+            || kill.killer != Some(pilot)
+            || !reward(kill.ship_type)
+        {
+            continue;
+        }
+        let others = enemies.iter().map(|(ship, enemy)| (ship, enemy.position));
+        if let Some(ship) = nearest_wingmate(kill.ship, kill.position, others) {
+            barks.send(Bark {
+                ship,
+                kind: BarkKind::Outclassed,
+            });
+        }
+    }
+}
+
+/// Points earned this run, split by where they came from
+#[derive(Resource, Default)]
+pub struct ScoreBreakdown([u32; ScoreSource::ALL.len()]);
+
+impl ScoreBreakdown {
+    pub fn get(&self, source: ScoreSource) -> u32 {
+        self.0[source as usize]
+    }
+
+    pub fn add(&mut self, source: ScoreSource, points: u32) {
+        self.0[source as usize] += points;
+    }
+
+    /// One line per source, leaving out cheats unless they were used
+    pub fn summary(&self) -> String {
+        ScoreSource::ALL
+            .iter()
+            .filter(|source| **source != ScoreSource::Cheats || self.get(**source) > 0)
+            .map(|source| format!("{:?}: {}", source, self.get(*source)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn reset_score_breakdown(mut commands: Commands) {
+    commands.insert_resource(ScoreBreakdown::default());
+}
+
+pub fn apply_score_events(
+    mut events: EventReader<ScoreEvent>,
+    mut score: ResMut<PlayerScore>,
+    mut breakdown: ResMut<ScoreBreakdown>,
+    multipliers: Res<ScoreMultipliers>,
+) {
+    for event in events.read() {
+        let with_objective = (event.points as f32 * multipliers.objective).round() as u32;
+        let points = (with_objective as f32 * multipliers.danger).round() as u32;
+        score.score += points;
+        breakdown.add(event.source, event.points);
+        // Anything on top of the base points is thanks to the multipliers
+        breakdown.add(
+            ScoreSource::Objectives,
+            with_objective.saturating_sub(event.points),
+        );
+        breakdown.add(ScoreSource::Danger, points.saturating_sub(with_objective));
+    }
+}
+
+#[derive(Component)]
+pub struct ScoreBreakdownMarker;
+
+fn spawn_breakdown_overlay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    anchors: Query<(Entity, &HudAnchor)>,
+) {
+    let overlay = commands
+        .spawn(TextBundle {
+            style: Style {
+                margin: UiRect::top(Val::Px(8.)),
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection {
+                    value: String::new(),
+                    style: TextStyle {
+                        font: asset_server.load("alphbeta.ttf"),
+                        font_size: 18.,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert((ScoreBreakdownMarker, HudElement))
+        .id();
+    HudAnchor::TopRight.attach(&mut commands, &anchors, overlay);
+}
+
+/// Holding [Tab] shows where the score so far has come from
+fn show_score_breakdown(
+    inputs: Res<ButtonInput<KeyCode>>,
+    breakdown: Res<ScoreBreakdown>,
+    mut overlay: Query<(&mut Text, &mut Visibility), With<ScoreBreakdownMarker>>,
+) {
+    if let Ok((mut text, mut visibility)) = overlay.get_single_mut() {
+        *visibility = match inputs.pressed(KeyCode::Tab) {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        if inputs.pressed(KeyCode::Tab) {
+            text.sections[0].value = breakdown.summary();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{
+        testing::{drain_score, test_app},
+        MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+        BORDER_WARNING_RADIUS,
+    };
+    use bevy::ecs::event::Events;
+
+    #[test]
+    fn underdog_takedowns_pay_a_bonus_and_get_a_bark() {
+        assert_eq!(underdog_bonus(ShipType::Ship1, ShipType::Ship2), None);
+        assert_eq!(underdog_bonus(ShipType::Ship1, ShipType::Ship3), Some(45));
+        // Twice the gap, twice the multiplier
+        assert_eq!(underdog_bonus(ShipType::Ship1, ShipType::Ship5), Some(186));
+        assert_eq!(
+            underdog_bonus(ShipType::Ship4, ShipType::Capital),
+            Some(405)
+        );
+
+        let mut app = test_app();
+        app.add_event::<Bark>()
+            .add_systems(Update, reward_underdog_takedowns);
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+                PlayerMarker,
+            ))
+            .id();
+        let disabled = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship3, Vec2::new(3., 0.)),
+                MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+            ))
+            .id();
+        let wingmate = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship2,
+                Vec2::new(0.5, 0.),
+            ))
+            .id();
+        let kill = |ship_type, killer| ShipDestroyed {
+            ship: Entity::PLACEHOLDER,
+            ship_type,
+            allegiance: Allegiance::Enemy,
+            position: Vec2::new(0.6, 0.),
+            killer,
+        };
+        app.world.send_event(kill(ShipType::Ship4, Some(player)));
+        // Someone else's kill, and one that's no bigger than the player's own hull
+        app.world.send_event(kill(ShipType::Ship6, None));
+        app.world.send_event(kill(ShipType::Ship2, Some(player)));
+        app.update();
+        // Disabled isn't captured yet, so only the kill pays
+        let mut events = app.world.resource_mut::<Events<ScoreEvent>>();
+        assert_eq!(drain_score(&mut events), [(ScoreSource::Underdog, 95)]);
+
+        app.world.send_event(ShipCaptured {
+            ship: disabled,
+            ship_type: ShipType::Ship3,
+        });
+        app.update();
+        let mut events = app.world.resource_mut::<Events<ScoreEvent>>();
+        assert_eq!(drain_score(&mut events), [(ScoreSource::Underdog, 45)]);
+        let barks = app
+            .world
+            .resource_mut::<Events<Bark>>()
+            .drain()
+            .map(|bark| (bark.ship, bark.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            barks,
+            [
+                (wingmate, BarkKind::Outclassed),
+                (disabled, BarkKind::Outclassed)
+            ]
+        );
+    }
+
+    #[test]
+    fn score_events_are_tallied_by_source() {
+        let mut app = test_app();
+        app.add_systems(Update, apply_score_events);
+        for (source, points) in [
+            (ScoreSource::Kills, 5),
+            (ScoreSource::Captures, 15),
+            (ScoreSource::Kills, 5),
+        ] {
+            app.world.send_event(ScoreEvent { source, points });
+        }
+        app.update();
+        let breakdown = app.world.resource::<ScoreBreakdown>();
+        assert_eq!(breakdown.get(ScoreSource::Kills), 10);
+        assert_eq!(breakdown.get(ScoreSource::Captures), 15);
+        assert_eq!(breakdown.get(ScoreSource::Survival), 0);
+        assert_eq!(app.world.resource::<PlayerScore>().score, 25);
+    }
+
+    #[test]
+    fn objective_bonus_multiplies_new_score() {
+        let mut app = test_app();
+        app.add_systems(Update, apply_score_events);
+        app.world.resource_mut::<ScoreMultipliers>().objective = 2.;
+        app.world.send_event(ScoreEvent {
+            source: ScoreSource::Kills,
+            points: 5,
+        });
+        app.update();
+        let breakdown = app.world.resource::<ScoreBreakdown>();
+        assert_eq!(breakdown.get(ScoreSource::Kills), 5);
+        assert_eq!(breakdown.get(ScoreSource::Objectives), 5);
+        assert_eq!(app.world.resource::<PlayerScore>().score, 10);
+    }
+
+    #[test]
+    fn danger_multiplier_grows_towards_the_border() {
+        assert_eq!(danger_multiplier(0.), 1.);
+        assert!(danger_multiplier(5.) > 1.);
+        assert!(danger_multiplier(BORDER_WARNING_RADIUS) > danger_multiplier(5.));
+        assert_eq!(danger_multiplier(BORDER_KILL_RADIUS), MAX_DANGER_MULTIPLIER);
+        assert_eq!(danger_multiplier(100.), MAX_DANGER_MULTIPLIER);
+    }
+
+    #[test]
+    fn danger_bonus_is_tallied_separately() {
+        let mut app = test_app();
+        app.add_systems(Update, apply_score_events);
+        app.world.resource_mut::<ScoreMultipliers>().danger = 1.5;
+        app.world.send_event(ScoreEvent {
+            source: ScoreSource::Survival,
+            points: 10,
+        });
+        app.update();
+        let breakdown = app.world.resource::<ScoreBreakdown>();
+        assert_eq!(breakdown.get(ScoreSource::Survival), 10);
+        assert_eq!(breakdown.get(ScoreSource::Danger), 5);
+        assert_eq!(breakdown.get(ScoreSource::Objectives), 0);
+        assert_eq!(app.world.resource::<PlayerScore>().score, 15);
+    }
+}
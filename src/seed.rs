@@ -0,0 +1,389 @@
+//! Every run rolls a seed that decides where and what enemies warp in as, which is kept with its
+//! score along with the difficulty and loadout it was flown at. The high score list, opened with
+//! [H] on the main menu, can start any of those runs over again at a single key.
+
+use bevy::{
+    app::{Plugin, PreUpdate},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, NextState, OnEnter, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    input::{keyboard::KeyCode, ButtonInput, InputSystem},
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, FlexDirection, JustifyContent, PositionType, Style, UiRect, Val, ZIndex,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    assist::aim_assist_on,
+    bindings::BindingsScreen,
+    loadout::Loadout,
+    records::{HighScores, ScoreEntry},
+    settings::{Difficulty, Settings},
+    volume::AudioScreen,
+    GameLifecycleState, MainMenuMarker,
+};
+
+/// The keys that replay each place on the list, in order
+const PLACE_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+pub struct RunSeedPlugin;
+
+impl Plugin for RunSeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RunSeed::new(0))
+            .init_resource::<RunConfig>()
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                (roll_run_seed, settle_run_config),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    open_high_score_screen,
+                    handle_high_score_screen.run_if(resource_exists::<HighScoreScreen>),
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .run_if(not(resource_exists::<AudioScreen>))
+                    .run_if(not(resource_exists::<BindingsScreen>))
+                    .run_if(in_state(GameLifecycleState::MainMenu)),
+            )
+            .add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_high_score_hint)
+            .add_systems(
+                OnExit(GameLifecycleState::MainMenu),
+                close_high_score_screen,
+            );
+    }
+}
+
+/// Where the current run's random spawns come from
+#[derive(Resource)]
+pub struct RunSeed {
+    pub seed: u32,
+    rng: StdRng,
+}
+
+impl RunSeed {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed as u64),
+        }
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
+
+/// The seed the next run starts from, picked from the high score list
+#[derive(Resource)]
+pub struct ReplaySeed(pub u32);
+
+/// A restart from the pause menu or end screen is a new run, so it gets a new seed
+pub fn roll_run_seed(mut commands: Commands, replay: Option<Res<ReplaySeed>>) {
+    let seed = match replay {
+        Some(replay) => {
+            commands.remove_resource::<ReplaySeed>();
+            replay.0
+        }
+        None => rand::thread_rng().gen(),
+    };
+    commands.insert_resource(RunSeed::new(seed));
+}
+
+/// The difficulty and loadout the run in progress is flown at. Normally the player's own picks, but
+/// a replay or a resumed checkpoint is flown at the ones it first was, and leaves the player's own
+/// picks as they were for the run after.
+#[derive(Resource, Default, Clone, Copy)]
+pub struct RunConfig {
+    pub difficulty: Difficulty,
+    pub loadout: Loadout,
+    /// Whether pad aim assist flies with the run, settled once at the start so turning it off
+    /// before resuming doesn't clear an assisted run for the high score table
+    pub aim_assist: bool,
+}
+
+/// Set to fly the next run at something other than the player's own difficulty and loadout
+#[derive(Resource)]
+pub struct ReplayConfig(pub RunConfig);
+
+/// Anything setting up the run from its difficulty or loadout goes after this
+pub fn settle_run_config(
+    mut commands: Commands,
+    replay: Option<Res<ReplayConfig>>,
+    settings: Res<Settings>,
+    loadout: Res<Loadout>,
+    mut run: ResMut<RunConfig>,
+) {
+    *run = match replay {
+        Some(replay) => {
+            commands.remove_resource::<ReplayConfig>();
+            replay.0
+        }
+        None => RunConfig {
+            difficulty: settings.difficulty,
+            loadout: *loadout,
+            aim_assist: aim_assist_on(&settings, settings.difficulty),
+        },
+    };
+}
+
+/// The seed, difficulty and loadout a run was flown at, on one line
+pub fn run_config_text(seed: u32, difficulty: Difficulty, loadout: &Loadout) -> String {
+    format!(
+        "Seed {seed:08X} // {difficulty:?} // {:?}, {:?}, {:?}, {:?}",
+        loadout.hull, loadout.weapon, loadout.passive, loadout.consumable
+    )
+}
+
+/// Entries from before seeds and dates were kept have nothing to replay and no date to show
+fn entry_text(place: usize, entry: &ScoreEntry) -> String {
+    let key = (place + 1) % PLACE_KEYS.len();
+    let mut text = format!("[{key}] {} in {:.0}s", entry.score, entry.survived_secs);
+    if let Some(date) = entry.date_text() {
+        text.push_str(&format!(" on {date}"));
+    }
+    if let (Some(seed), Some(difficulty), Some(loadout)) =
+        (entry.seed, entry.difficulty, entry.loadout)
+    {
+        text.push_str(&format!(
+            "\n      {}",
+            run_config_text(seed, difficulty, &loadout)
+        ));
+    }
+    text
+}
+
+fn high_score_screen_text(high_scores: &HighScores) -> String {
+    let entries = match high_scores.entries.is_empty() {
+        true => "No runs banked yet".to_string(),
+        false => high_scores
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(place, entry)| entry_text(place, entry))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    format!("{entries}\n\n[1-0] Replay that run  [H/Esc] Back")
+}
+
+/// The high score screen is open
+#[derive(Resource)]
+pub struct HighScoreScreen;
+
+#[derive(Component)]
+pub struct HighScoreScreenMarker;
+
+fn spawn_high_score_hint(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(195.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                "[H] High scores",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert(MainMenuMarker);
+}
+
+fn open_high_score_screen(
+    mut commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    screen: Option<Res<HighScoreScreen>>,
+    high_scores: Res<HighScores>,
+    asset_server: Res<AssetServer>,
+) {
+    if screen.is_some() || !inputs.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+    // Nothing under the screen should see the key that opened it
+    inputs.reset_all();
+    commands.insert_resource(HighScoreScreen);
+    let alphbeta = asset_server.load("alphbeta.ttf");
+    let jupitercrash = asset_server.load("jupiterc.ttf");
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.8).into(),
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(HighScoreScreenMarker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                style: Style {
+                    padding: UiRect::bottom(Val::Px(30.)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    "High Scores",
+                    TextStyle {
+                        font: jupitercrash,
+                        font_size: 56.,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+            parent.spawn(TextBundle::from_section(
+                high_score_screen_text(&high_scores),
+                TextStyle {
+                    font: alphbeta,
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+/// Replaying flies the run at its difficulty and loadout, and goes straight into it
+fn handle_high_score_screen(
+    mut commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    high_scores: Res<HighScores>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+    screens: Query<Entity, With<HighScoreScreenMarker>>,
+) {
+    let picked = PLACE_KEYS
+        .iter()
+        .position(|key| inputs.just_pressed(*key))
+        .and_then(|place| high_scores.entries.get(place));
+    if let Some(ScoreEntry {
+        seed: Some(seed),
+        difficulty: Some(difficulty),
+        loadout: Some(replayed),
+        ..
+    }) = picked
+    {
+        // Only runs flown without aim assist make the list
+        commands.insert_resource(ReplayConfig(RunConfig {
+            difficulty: *difficulty,
+            loadout: *replayed,
+            aim_assist: false,
+        }));
+        commands.insert_resource(ReplaySeed(*seed));
+        state.set(GameLifecycleState::Game);
+    } else if inputs.just_pressed(KeyCode::Escape) || inputs.just_pressed(KeyCode::KeyH) {
+        close_high_score_screen(commands, screens);
+    }
+    inputs.reset_all();
+}
+
+fn close_high_score_screen(
+    mut commands: Commands,
+    screens: Query<Entity, With<HighScoreScreenMarker>>,
+) {
+    commands.remove_resource::<HighScoreScreen>();
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{ShipType, ShipVariant};
+    use crate::loadout::Loadout;
+    use crate::records::HighScores;
+    use bevy::app::Update;
+
+    #[test]
+    fn a_seed_replays_the_same_spawns_and_is_kept_with_the_score() {
+        let rolls = |seed: u32| {
+            let mut seed = RunSeed::new(seed);
+            (0..50)
+                .map(|_| ShipVariant::roll(ShipType::Ship4, seed.rng()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(7), rolls(7));
+        assert_ne!(rolls(7), rolls(8));
+
+        // Scores banked before seeds were kept still load, with nothing to replay
+        let old: HighScores =
+            ron::from_str("(entries: [(score: 40, survived_secs: 12.0, retired: false)])").unwrap();
+        assert_eq!(old.entries[0].score, 40);
+        assert!(old.entries[0].seed.is_none() && old.entries[0].loadout.is_none());
+
+        let text = run_config_text(0xBEEF, Difficulty::Hard, &Loadout::default());
+        assert!(text.starts_with("Seed 0000BEEF // Hard // Ship2"));
+    }
+
+    #[test]
+    fn a_replay_is_flown_at_its_own_config_without_changing_the_players() {
+        let mut app = App::new();
+        app.insert_resource(Settings::default())
+            .insert_resource(Loadout::default())
+            .init_resource::<RunConfig>()
+            .insert_resource(ReplayConfig(RunConfig {
+                difficulty: Difficulty::Hard,
+                loadout: Loadout {
+                    hull: ShipType::Ship4,
+                    ..default()
+                },
+                aim_assist: false,
+            }))
+            .add_systems(Update, settle_run_config);
+        app.update();
+        let run = app.world.resource::<RunConfig>();
+        assert_eq!(run.difficulty, Difficulty::Hard);
+        assert_eq!(run.loadout.hull, ShipType::Ship4);
+        assert_eq!(
+            app.world.resource::<Settings>().difficulty,
+            Difficulty::default()
+        );
+        assert_eq!(
+            app.world.resource::<Loadout>().hull,
+            Loadout::default().hull
+        );
+
+        // The run after is back to the player's own picks
+        app.update();
+        let run = app.world.resource::<RunConfig>();
+        assert_eq!(run.difficulty, Difficulty::default());
+        assert_eq!(run.loadout.hull, Loadout::default().hull);
+    }
+}
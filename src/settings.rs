@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        change_detection::DetectChanges,
+        system::{Local, Res, Resource},
+    },
+    prelude::App,
+    time::{Time, Timer, TimerMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const SETTINGS_KEY: &str = "settings.ron";
+/// Bumped whenever a field changes meaning, so [`Settings::migrate`] can bring old files forward
+pub const SETTINGS_VERSION: u32 = 1;
+
+pub const MIN_CAMERA_ZOOM: f32 = 0.8;
+pub const MAX_CAMERA_ZOOM: f32 = 2.6;
+pub const MAX_HUD_MARGIN: f32 = 10.;
+/// Strongest aim assist pull, in degrees a second
+pub const MAX_AIM_ASSIST_STRENGTH: f32 = 20.;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .add_systems(Update, persist_settings);
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Files written before settings were versioned have none, and read as 0
+    #[serde(default)]
+    pub version: u32,
+    /// Camera scale during a run, larger values show more of the battlefield
+    pub camera_zoom: f32,
+    pub difficulty: Difficulty,
+    /// Space kept clear around the HUD, as a percentage of the shorter side of the window
+    pub hud_margin: f32,
+    /// Whether to keep a local record of deaths, captures and kills for balancing
+    pub telemetry: bool,
+    /// Whether volleys push the firing ship back and kick the camera
+    pub weapon_kick: bool,
+    /// Whether the guns fire by themselves at enemies lined up ahead
+    pub auto_fire: bool,
+    /// Whether the capture choice is made by holding one key rather than pressing one of three
+    pub hold_to_capture: bool,
+    /// Whether turns towards a ship or the border close by are eased off, outside of Hard
+    pub flight_assist: bool,
+    /// Whether enemies come in numbered waves with a breather between them, rather than a steady stream
+    pub wave_mode: bool,
+    /// Whether the nose is drawn towards an enemy just off it while firing on a gamepad, outside of Hard
+    pub aim_assist: bool,
+    /// How hard aim assist pulls, in degrees a second
+    pub aim_assist_strength: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            camera_zoom: 1.4,
+            difficulty: Difficulty::Normal,
+            hud_margin: 1.5,
+            telemetry: false,
+            weapon_kick: true,
+            auto_fire: false,
+            hold_to_capture: false,
+            flight_assist: false,
+            wave_mode: false,
+            aim_assist: false,
+            aim_assist_strength: 6.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::read(SETTINGS_KEY)
+            .map(|contents| Settings::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    /// Missing fields take their defaults. If the file doesn't parse as a whole, every field that
+    /// still reads correctly is kept rather than starting from scratch.
+    pub fn parse(contents: &str) -> Self {
+        let mut settings = match ron::from_str::<Settings>(contents) {
+            Ok(settings) => settings,
+            Err(e) => {
+                println!("Settings file is damaged ({e}), recovering what we can");
+                Settings::recover(contents)
+            }
+        };
+        settings.migrate();
+        settings.validate();
+        settings
+    }
+
+    fn recover(contents: &str) -> Self {
+        let mut settings = Settings::default();
+        if let Ok(ron::Value::Map(fields)) = ron::from_str::<ron::Value>(contents) {
+            for (key, value) in fields.iter() {
+                if let ron::Value::String(key) = key {
+                    let value = value.clone();
+                    let read = match key.as_str() {
+                        "version" => value.into_rust().map(|v| settings.version = v),
+                        "camera_zoom" => value.into_rust().map(|v| settings.camera_zoom = v),
+                        "difficulty" => value.into_rust().map(|v| settings.difficulty = v),
+                        "hud_margin" => value.into_rust().map(|v| settings.hud_margin = v),
+                        "telemetry" => value.into_rust().map(|v| settings.telemetry = v),
+                        "weapon_kick" => value.into_rust().map(|v| settings.weapon_kick = v),
+                        "auto_fire" => value.into_rust().map(|v| settings.auto_fire = v),
+                        "hold_to_capture" => {
+                            value.into_rust().map(|v| settings.hold_to_capture = v)
+                        }
+                        "flight_assist" => value.into_rust().map(|v| settings.flight_assist = v),
+                        "wave_mode" => value.into_rust().map(|v| settings.wave_mode = v),
+                        "aim_assist" => value.into_rust().map(|v| settings.aim_assist = v),
+                        "aim_assist_strength" => {
+                            value.into_rust().map(|v| settings.aim_assist_strength = v)
+                        }
+                        _ => Ok(()),
+                    };
+                    if let Err(e) = read {
+                        println!("Resetting setting {key}: {e}");
+                    }
+                }
+            }
+        }
+        settings
+    }
+
+    fn migrate(&mut self) {
+        if self.version > SETTINGS_VERSION {
+            println!(
+                "Settings are from a newer version ({}), unknown fields will be dropped",
+                self.version
+            );
+        }
+        // Unversioned files only lack fields added since, which have already taken their defaults
+        self.version = SETTINGS_VERSION;
+    }
+
+    /// Puts anything out of range, hand edited or not, back where the game can use it
+    fn validate(&mut self) {
+        let defaults = Settings::default();
+        if !self.camera_zoom.is_finite() {
+            self.camera_zoom = defaults.camera_zoom;
+        }
+        if !self.hud_margin.is_finite() {
+            self.hud_margin = defaults.hud_margin;
+        }
+        if !self.aim_assist_strength.is_finite() {
+            self.aim_assist_strength = defaults.aim_assist_strength;
+        }
+        self.camera_zoom = self.camera_zoom.clamp(MIN_CAMERA_ZOOM, MAX_CAMERA_ZOOM);
+        self.hud_margin = self.hud_margin.clamp(0., MAX_HUD_MARGIN);
+        self.aim_assist_strength = self.aim_assist_strength.clamp(0., MAX_AIM_ASSIST_STRENGTH);
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(SETTINGS_KEY, &contents),
+            Err(e) => println!("Could not serialise settings: {e}"),
+        }
+    }
+}
+
+/// Writes settings out shortly after they stop changing, so dragging a value doesn't hammer the disk
+fn persist_settings(time: Res<Time>, settings: Res<Settings>, mut pending: Local<Option<Timer>>) {
+    if settings.is_changed() && !settings.is_added() {
+        *pending = Some(Timer::new(Duration::from_millis(500), TimerMode::Once));
+    }
+    if let Some(timer) = pending.as_mut() {
+        if timer.tick(time.delta()).finished() {
+            settings.save();
+            *pending = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::default;
+
+    #[test]
+    fn settings_keep_what_still_reads_across_versions() {
+        let saved = Settings {
+            camera_zoom: 2.,
+            difficulty: Difficulty::Hard,
+            ..default()
+        };
+        let contents = ron::ser::to_string_pretty(&saved, ron::ser::PrettyConfig::default());
+        let loaded = Settings::parse(&contents.unwrap());
+        assert_eq!(loaded.camera_zoom, 2.);
+        assert_eq!(loaded.difficulty, Difficulty::Hard);
+
+        // From before the version field and the HUD margin existed
+        let legacy = Settings::parse("(camera_zoom: 9.0, difficulty: Easy)");
+        assert_eq!(legacy.version, SETTINGS_VERSION);
+        assert_eq!(legacy.camera_zoom, MAX_CAMERA_ZOOM);
+        assert_eq!(legacy.difficulty, Difficulty::Easy);
+        assert_eq!(legacy.hud_margin, Settings::default().hud_margin);
+
+        let damaged = Settings::parse("(version: 1, camera_zoom: \"far\", hud_margin: 3.0)");
+        assert_eq!(damaged.camera_zoom, Settings::default().camera_zoom);
+        assert_eq!(damaged.hud_margin, 3.);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::{
+    app::{Plugin, Update},
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventWriter,
+        query::{With, Without},
+        schedule::{
+            common_conditions::{in_state, resource_exists},
+            IntoSystemConfigs, NextState, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{gamepad::GamepadButtonType, keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::App,
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+};
+
+use crate::{
+    controls::{ControlScheme, Pads},
+    dialogue::Dialogue,
+    gameplay::{kill_dead_ships, Captured, GameplaySet, LastHitBy, PlayerMarker, Spacecraft},
+    score::{ScoreEvent, ScoreSource},
+    GameLifecycleState,
+};
+
+/// How long after the player's death their allies' kills still count towards the score
+pub const SPECTATE_BONUS_TIME: Duration = Duration::from_secs(30);
+pub const ALLY_KILL_SCORE: u32 = 10;
+
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                answer_spectate_offer
+                    .run_if(resource_exists::<SpectateOffer>)
+                    .in_set(GameplaySet::Input),
+                follow_spectated_ally
+                    .run_if(resource_exists::<Spectating>)
+                    .in_set(GameplaySet::Presentation),
+                score_ally_kills
+                    .run_if(resource_exists::<Spectating>)
+                    .in_set(GameplaySet::Cleanup)
+                    .before(kill_dead_ships),
+            )
+                .run_if(in_state(GameLifecycleState::Game)),
+        )
+        .add_systems(OnExit(GameLifecycleState::Game), stop_spectating);
+    }
+}
+
+/// The player died with allies still alive, and is being asked whether to watch them
+#[derive(Resource)]
+pub struct SpectateOffer;
+
+#[derive(Resource)]
+pub struct Spectating {
+    bonus_time: Timer,
+    target: Option<Entity>,
+    pub position: Vec2,
+}
+
+/// Ally kills that have already been paid out
+#[derive(Component)]
+pub struct KillCredited;
+
+fn answer_spectate_offer(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    scheme: Res<ControlScheme>,
+    mut dialogue: ResMut<Dialogue>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+) {
+    dialogue.warn(format!(
+        "Captain! Our allies are still fighting. [{}] to watch over them, [{}] to end the run.",
+        scheme.prompt("Enter", "A"),
+        scheme.prompt("Escape", "B"),
+    ));
+    if inputs.just_pressed(KeyCode::Enter) || pads.just_pressed(GamepadButtonType::South) {
+        dialogue.clear_warning();
+        commands.remove_resource::<SpectateOffer>();
+        commands.insert_resource(Spectating {
+            bonus_time: Timer::new(SPECTATE_BONUS_TIME, TimerMode::Once),
+            target: None,
+            position: Vec2::ZERO,
+        });
+    } else if inputs.just_pressed(KeyCode::Escape) || pads.just_pressed(GamepadButtonType::East) {
+        state.set(GameLifecycleState::EndScreen);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn follow_spectated_ally(
+    time: Res<Time>,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut spectating: ResMut<Spectating>,
+    allies: Query<(Entity, &Spacecraft, &Transform), (With<Captured>, Without<Camera2d>)>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+    mut state: ResMut<NextState<GameLifecycleState>>,
+) {
+    spectating.bonus_time.tick(time.delta());
+    let target = spectating
+        .target
+        .and_then(|entity| allies.get(entity).ok())
+        .or_else(|| allies.iter().next());
+    match target {
+        Some((entity, ally, transform)) => {
+            spectating.target = Some(entity);
+            spectating.position = ally.position;
+            if let Ok(mut cam_transform) = camera.get_single_mut() {
+                cam_transform.translation.x = transform.translation.x;
+                cam_transform.translation.y = transform.translation.y;
+                cam_transform.rotation = transform.rotation;
+                cam_transform.rotate_z(-3. * PI / 2.);
+            }
+        }
+        None => state.set(GameLifecycleState::EndScreen),
+    }
+    if inputs.just_pressed(KeyCode::Escape) {
+        state.set(GameLifecycleState::EndScreen);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn score_ally_kills(
+    mut commands: Commands,
+    spectating: Res<Spectating>,
+    mut score_events: EventWriter<ScoreEvent>,
+    victims: Query<
+        (Entity, &Spacecraft, &LastHitBy),
+        (
+            Without<Captured>,
+            Without<PlayerMarker>,
+            Without<KillCredited>,
+        ),
+    >,
+    allies: Query<(), With<Captured>>,
+) {
+    if spectating.bonus_time.finished() {
+        return;
+    }
+    for (entity, victim, hit_by) in victims.iter() {
+        if victim.health <= 0 && allies.contains(hit_by.0) {
+            score_events.send(ScoreEvent {
+                source: ScoreSource::Kills,
+                points: ALLY_KILL_SCORE,
+            });
+            commands.entity(entity).insert(KillCredited);
+        }
+    }
+}
+
+fn stop_spectating(mut commands: Commands) {
+    commands.remove_resource::<SpectateOffer>();
+    commands.remove_resource::<Spectating>();
+}
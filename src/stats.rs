@@ -0,0 +1,340 @@
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{Has, With},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter, OnExit},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::DespawnRecursiveExt,
+    input::{keyboard::KeyCode, ButtonInput},
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextSection, TextStyle},
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+};
+use serde::Serialize;
+
+use crate::{
+    events::{Allegiance, DamageCause, ShipDamaged},
+    gameplay::{
+        kill_dead_ships, Captured, GameplaySet, PlayerMarker, PlayerScore, ShipType, Spacecraft,
+    },
+    GameLifecycleState,
+};
+
+/// How many of the ships that hurt the player most the combat report names
+pub const REPORTED_ATTACKERS: usize = 3;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RunStats::default())
+            .add_systems(OnEnter(GameLifecycleState::Game), reset_run_stats)
+            .add_systems(OnEnter(GameLifecycleState::EndScreen), spawn_export_hint)
+            .add_systems(OnExit(GameLifecycleState::EndScreen), despawn_export_hint)
+            .add_systems(
+                Update,
+                // Before the dead are cleared away, so the ships involved can still be looked up
+                log_damage
+                    .before(kill_dead_ships)
+                    .in_set(GameplaySet::Cleanup)
+                    .run_if(in_state(GameLifecycleState::Game)),
+            )
+            .add_systems(
+                Update,
+                export_timeline.run_if(in_state(GameLifecycleState::EndScreen)),
+            );
+    }
+}
+
+/// Everything notable that happened during the current (or last) run
+#[derive(Resource, Default, Serialize)]
+pub struct RunStats {
+    pub timeline: Vec<TimelineEntry>,
+    pub damage: Vec<DamageEntry>,
+}
+
+#[derive(Serialize)]
+pub struct TimelineEntry {
+    /// Seconds survived when the event happened
+    pub at_secs: f32,
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(tag = "event")]
+pub enum TimelineEvent {
+    EnemySpawned { ship: ShipType },
+    EnemyDestroyed { ship: ShipType },
+    AllyDestroyed { ship: ShipType },
+    Captured { ship: ShipType },
+    Scuttled { ship: ShipType },
+    SwappedInto { ship: ShipType },
+    PlayerDied { ship: ShipType },
+}
+
+/// A ship on either end of a [`DamageEntry`]
+#[derive(Clone, Copy, Serialize)]
+pub struct Combatant {
+    /// Tells apart ships of the same type
+    pub id: u64,
+    pub ship: ShipType,
+    pub allegiance: Allegiance,
+}
+
+impl Combatant {
+    fn name(&self) -> String {
+        format!("{:?} {:?}", self.allegiance, self.ship)
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct DamageEntry {
+    pub at_secs: f32,
+    pub target: Combatant,
+    /// Left out for damage no ship dealt, or from a ship that was gone by the time it landed
+    pub attacker: Option<Combatant>,
+    pub cause: DamageCause,
+    pub amount: i32,
+}
+
+impl RunStats {
+    pub fn record(&mut self, score: &PlayerScore, event: TimelineEvent) {
+        self.timeline.push(TimelineEntry {
+            at_secs: score.survived_time.elapsed_secs(),
+            event,
+        });
+    }
+
+    /// What hurt the player this run, worst first, and what finished them off if something did
+    pub fn combat_report(&self) -> String {
+        let taken = self
+            .damage
+            .iter()
+            .filter(|entry| entry.target.allegiance == Allegiance::Player)
+            .collect::<Vec<_>>();
+        if taken.is_empty() {
+            return "No hull damage taken".to_string();
+        }
+        let total = taken.iter().map(|entry| entry.amount).sum::<i32>();
+        let mut lines = vec![format!("Hull damage taken: {total}")];
+        let died = self
+            .timeline
+            .iter()
+            .any(|entry| matches!(entry.event, TimelineEvent::PlayerDied { .. }));
+        if let (true, Some(last)) = (died, taken.last()) {
+            lines.push(match last.attacker {
+                Some(attacker) => format!(
+                    "Final blow: {} from {}",
+                    last.cause.describe(),
+                    attacker.name()
+                ),
+                None => format!("Final blow: {}", last.cause.describe()),
+            });
+        }
+        let mut sources: Vec<(String, Option<u64>, i32)> = vec![];
+        for entry in taken.iter() {
+            let (name, id) = match entry.attacker {
+                Some(attacker) => (attacker.name(), Some(attacker.id)),
+                None => (entry.cause.describe().to_string(), None),
+            };
+            match sources
+                .iter_mut()
+                .find(|(source, source_id, _)| *source == name && *source_id == id)
+            {
+                Some((_, _, amount)) => *amount += entry.amount,
+                None => sources.push((name, id, entry.amount)),
+            }
+        }
+        sources.sort_by(|(_, _, a), (_, _, b)| b.cmp(a));
+        for (name, _, amount) in sources.iter().take(REPORTED_ATTACKERS) {
+            lines.push(format!("{name}: {amount}"));
+        }
+        lines.join("\n")
+    }
+}
+
+fn reset_run_stats(mut commands: Commands) {
+    commands.insert_resource(RunStats::default());
+}
+
+fn log_damage(
+    mut damaged: EventReader<ShipDamaged>,
+    ships: Query<(&Spacecraft, Has<PlayerMarker>, Has<Captured>)>,
+    score: Res<PlayerScore>,
+    mut stats: ResMut<RunStats>,
+) {
+    let combatant = |entity: Entity| {
+        ships
+            .get(entity)
+            .ok()
+            .map(|(ship, player, captured)| Combatant {
+                id: entity.to_bits(),
+                ship: ship.ship_type,
+                allegiance: match (player, captured) {
+                    (true, _) => Allegiance::Player,
+                    (false, true) => Allegiance::Ally,
+                    (false, false) => Allegiance::Enemy,
+                },
+            })
+    };
+    for event in damaged.read() {
+        if let Some(target) = combatant(event.ship) {
+            stats.damage.push(DamageEntry {
+                at_secs: score.survived_time.elapsed_secs(),
+                target,
+                attacker: event.attacker.and_then(combatant),
+                cause: event.cause,
+                amount: event.amount,
+            });
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TimelineExport<'a> {
+    score: u32,
+    survived_secs: f32,
+    timeline: &'a [TimelineEntry],
+    damage: &'a [DamageEntry],
+}
+
+#[derive(Component)]
+pub struct ExportHintMarker;
+
+fn spawn_export_hint(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(15.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection {
+                    value: "[E] Export run timeline".to_string(),
+                    style: TextStyle {
+                        font: asset_server.load("alphbeta.ttf"),
+                        font_size: 16.,
+                        color: Color::GRAY,
+                    },
+                }],
+                ..default()
+            },
+            ..default()
+        })
+        .insert(ExportHintMarker);
+}
+
+fn despawn_export_hint(mut commands: Commands, hint: Query<Entity, With<ExportHintMarker>>) {
+    for entity in hint.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn export_timeline(
+    inputs: Res<ButtonInput<KeyCode>>,
+    stats: Res<RunStats>,
+    score: Res<PlayerScore>,
+    mut hint: Query<&mut Text, With<ExportHintMarker>>,
+) {
+    if !inputs.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    let export = TimelineExport {
+        score: score.score,
+        survived_secs: score.survived_time.elapsed_secs(),
+        timeline: &stats.timeline,
+        damage: &stats.damage,
+    };
+    let message = match serde_json::to_string_pretty(&export) {
+        Ok(json) => write_export(&json),
+        Err(e) => format!("Could not export timeline: {e}"),
+    };
+    if let Ok(mut hint) = hint.get_single_mut() {
+        hint.sections[0].value = message;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_export(json: &str) -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let name = format!("quantum_salvage_run_{secs}.json");
+    match std::fs::write(&name, json) {
+        Ok(_) => format!("Timeline saved to {name}"),
+        Err(e) => format!("Could not export timeline: {e}"),
+    }
+}
+
+/// There's no file system on the web, so the timeline goes to the browser console instead
+#[cfg(target_arch = "wasm32")]
+fn write_export(json: &str) -> String {
+    bevy::log::info!("{json}");
+    "Timeline written to the browser console".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::damage::DamageType;
+    use bevy::time::{Stopwatch, Timer};
+
+    #[test]
+    fn combat_report_names_what_hurt_the_player_most() {
+        let player = Combatant {
+            id: 1,
+            ship: ShipType::Ship1,
+            allegiance: Allegiance::Player,
+        };
+        let gunner = Combatant {
+            id: 2,
+            ship: ShipType::Ship3,
+            allegiance: Allegiance::Enemy,
+        };
+        let hit = |attacker, cause, amount| DamageEntry {
+            at_secs: 0.,
+            target: player,
+            attacker,
+            cause,
+            amount,
+        };
+        let mut stats = RunStats::default();
+        assert_eq!(stats.combat_report(), "No hull damage taken");
+
+        let energy = DamageCause::Weapon(DamageType::Energy);
+        stats.damage = vec![
+            hit(Some(gunner), energy, 1),
+            hit(None, DamageCause::Burning, 1),
+            hit(Some(gunner), energy, 2),
+        ];
+        assert_eq!(
+            stats.combat_report(),
+            "Hull damage taken: 4\nEnemy Ship3: 3\nburning: 1"
+        );
+        let score = PlayerScore {
+            score: 0,
+            add_score_timer: Timer::default(),
+            survived_time: Stopwatch::new(),
+            threat_exposure: 0.,
+            scrap: 0,
+        };
+        stats.record(
+            &score,
+            TimelineEvent::PlayerDied {
+                ship: ShipType::Ship1,
+            },
+        );
+        let report = stats.combat_report();
+        assert!(report.contains("Final blow: energy fire from Enemy Ship3"));
+    }
+}
@@ -0,0 +1,324 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter, Events},
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs},
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::Vec2,
+    prelude::{default, App},
+    render::{color::Color, view::Visibility},
+    sprite::{Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+};
+
+use crate::{
+    events::{report_damage, DamageCause, ShipDamaged},
+    gameplay::{handle_inputs, handle_npc_logic, GameState, GameplaySet, Spacecraft},
+    score::ScoreEvent,
+    GameLifecycleState,
+};
+
+/// How often a burning ship loses hull, one point per stack
+pub const BURN_TICK: Duration = Duration::from_secs(1);
+pub const MAX_BURN_STACKS: u32 = 3;
+/// Fraction of top speed a slowed ship can still reach
+pub const SLOW_FACTOR: f32 = 0.5;
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyStatus>().add_systems(
+            Update,
+            (
+                (
+                    equip_status_effects,
+                    apply_status_events,
+                    tick_status_effects,
+                )
+                    .chain()
+                    .in_set(GameplaySet::Simulation),
+                enforce_status_effects
+                    .after(handle_inputs)
+                    .after(handle_npc_logic)
+                    .in_set(GameplaySet::Input),
+                update_status_indicators.in_set(GameplaySet::Presentation),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatusKind {
+    /// Loses hull every [`BURN_TICK`]
+    Burning,
+    /// Top speed is cut to [`SLOW_FACTOR`]
+    Slowed,
+    /// Can't steer, throttle or fire
+    Stunned,
+    /// Guns won't reload
+    WeaponJammed,
+}
+
+/// What happens when an effect is applied to a ship that already has it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stacking {
+    /// Adds a stack, up to the cap, and restarts the clock
+    Intensity(u32),
+    /// Restarts the clock
+    Refresh,
+    /// Adds another full duration on top of what's left
+    Extend,
+    /// Does nothing until the effect wears off, so it can't be chained
+    Ignore,
+}
+
+impl StatusKind {
+    pub fn duration(self) -> Duration {
+        match self {
+            StatusKind::Burning => Duration::from_secs(4),
+            StatusKind::Slowed => Duration::from_secs(3),
+            StatusKind::Stunned => Duration::from_millis(750),
+            StatusKind::WeaponJammed => Duration::from_secs(2),
+        }
+    }
+
+    pub fn stacking(self) -> Stacking {
+        match self {
+            StatusKind::Burning => Stacking::Intensity(MAX_BURN_STACKS),
+            StatusKind::Slowed => Stacking::Refresh,
+            StatusKind::Stunned => Stacking::Ignore,
+            StatusKind::WeaponJammed => Stacking::Extend,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            StatusKind::Burning => Color::ORANGE_RED,
+            StatusKind::Slowed => Color::CYAN,
+            StatusKind::Stunned => Color::YELLOW,
+            StatusKind::WeaponJammed => Color::GRAY,
+        }
+    }
+
+    fn indicator_offset(self) -> f32 {
+        match self {
+            StatusKind::Burning => -9.,
+            StatusKind::Slowed => -3.,
+            StatusKind::Stunned => 3.,
+            StatusKind::WeaponJammed => 9.,
+        }
+    }
+}
+
+/// Sent by weapons and hazards to put an effect on a ship
+#[derive(Event)]
+pub struct ApplyStatus {
+    pub target: Entity,
+    pub kind: StatusKind,
+}
+
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub stacks: u32,
+    timer: Timer,
+    pulse: Timer,
+}
+
+#[derive(Component, Default)]
+pub struct StatusEffects(Vec<StatusEffect>);
+
+impl StatusEffects {
+    /// Returns whether the ship didn't already have this effect
+    pub fn apply(&mut self, kind: StatusKind) -> bool {
+        let duration = kind.duration();
+        if let Some(effect) = self.0.iter_mut().find(|effect| effect.kind == kind) {
+            match kind.stacking() {
+                Stacking::Intensity(max) => {
+                    effect.stacks = (effect.stacks + 1).min(max);
+                    effect.timer.reset();
+                }
+                Stacking::Refresh => effect.timer.reset(),
+                Stacking::Extend => {
+                    let remaining = effect.timer.remaining();
+                    effect.timer = Timer::new(remaining + duration, TimerMode::Once);
+                }
+                Stacking::Ignore => {}
+            }
+            return false;
+        }
+        self.0.push(StatusEffect {
+            kind,
+            stacks: 1,
+            timer: Timer::new(duration, TimerMode::Once),
+            pulse: Timer::new(BURN_TICK, TimerMode::Repeating),
+        });
+        true
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn stacks(&self, kind: StatusKind) -> u32 {
+        self.0
+            .iter()
+            .find(|effect| effect.kind == kind)
+            .map_or(0, |effect| effect.stacks)
+    }
+
+    /// Runs the clocks down, returning how much burn damage is due
+    pub fn tick(&mut self, delta: Duration) -> i32 {
+        let mut burn = 0;
+        for effect in self.0.iter_mut() {
+            effect.timer.tick(delta);
+            if effect.kind == StatusKind::Burning {
+                effect.pulse.tick(delta);
+                burn += (effect.pulse.times_finished_this_tick() * effect.stacks) as i32;
+            }
+        }
+        self.0.retain(|effect| !effect.timer.finished());
+        burn
+    }
+}
+
+/// A coloured pip that follows an affected ship around
+#[derive(Component)]
+pub struct StatusIndicator {
+    ship: Entity,
+    kind: StatusKind,
+}
+
+fn equip_status_effects(
+    mut commands: Commands,
+    ships: Query<Entity, (With<Spacecraft>, Without<StatusEffects>)>,
+) {
+    for entity in ships.iter() {
+        commands.entity(entity).insert(StatusEffects::default());
+    }
+}
+
+fn apply_status_events(
+    mut commands: Commands,
+    mut events: EventReader<ApplyStatus>,
+    mut ships: Query<&mut StatusEffects>,
+) {
+    for event in events.read() {
+        if let Ok(mut effects) = ships.get_mut(event.target) {
+            if effects.apply(event.kind) {
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: event.kind.color(),
+                            custom_size: Some(Vec2::splat(4.)),
+                            ..default()
+                        },
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    StatusIndicator {
+                        ship: event.target,
+                        kind: event.kind,
+                    },
+                ));
+            }
+        }
+    }
+}
+
+fn tick_status_effects(
+    time: Res<Time>,
+    mut score_events: ResMut<Events<ScoreEvent>>,
+    mut damaged: EventWriter<ShipDamaged>,
+    mut ships: Query<(Entity, &mut Spacecraft, &mut StatusEffects)>,
+) {
+    for (entity, mut ship, mut effects) in ships.iter_mut() {
+        let burn = effects.tick(time.delta());
+        if burn > 0 {
+            let before = ship.health;
+            ship.collide(burn, false, &mut score_events);
+            report_damage(
+                &mut damaged,
+                entity,
+                None,
+                DamageCause::Burning,
+                before,
+                ship.health,
+            );
+        }
+    }
+}
+
+/// Undoes whatever the pilot or AI asked of a ship this frame that its effects don't allow
+fn enforce_status_effects(mut ships: Query<(&mut Spacecraft, &StatusEffects)>) {
+    for (mut ship, effects) in ships.iter_mut() {
+        if effects.has(StatusKind::Slowed) {
+            let slow = ship.profile().max_velocity * SLOW_FACTOR;
+            ship.velocity = ship.velocity.min(slow);
+        }
+        if effects.has(StatusKind::Stunned) {
+            ship.heading += ship.delta_rotation;
+            ship.delta_rotation = 0.;
+            ship.velocity = 0.;
+        }
+        if effects.has(StatusKind::Stunned) || effects.has(StatusKind::WeaponJammed) {
+            ship.weapon_cooldown.reset();
+        }
+    }
+}
+
+fn update_status_indicators(
+    mut commands: Commands,
+    mut indicators: Query<
+        (Entity, &StatusIndicator, &mut Transform, &mut Visibility),
+        Without<StatusEffects>,
+    >,
+    ships: Query<(&StatusEffects, &Transform)>,
+) {
+    for (entity, indicator, mut transform, mut visibility) in indicators.iter_mut() {
+        match ships.get(indicator.ship) {
+            Ok((effects, ship)) if effects.has(indicator.kind) => {
+                let offset = Vec2::new(indicator.kind.indicator_offset(), -22.) * ship.scale.x;
+                transform.translation = (ship.translation.truncate() + offset).extend(80.);
+                *visibility = Visibility::Visible;
+            }
+            _ => commands.entity(entity).despawn(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_effects_follow_their_stacking_rules() {
+        let mut effects = StatusEffects::default();
+        assert!(effects.apply(StatusKind::Burning));
+        for _ in 0..5 {
+            assert!(!effects.apply(StatusKind::Burning));
+        }
+        assert_eq!(effects.stacks(StatusKind::Burning), MAX_BURN_STACKS);
+        assert_eq!(effects.tick(BURN_TICK), MAX_BURN_STACKS as i32);
+
+        effects.apply(StatusKind::Stunned);
+        effects.tick(StatusKind::Stunned.duration() / 2);
+        effects.apply(StatusKind::Stunned);
+        effects.tick(StatusKind::Stunned.duration() / 2);
+        assert!(!effects.has(StatusKind::Stunned));
+
+        effects.apply(StatusKind::WeaponJammed);
+        effects.apply(StatusKind::WeaponJammed);
+        effects.tick(StatusKind::WeaponJammed.duration() * 3 / 2);
+        assert!(effects.has(StatusKind::WeaponJammed));
+    }
+}
@@ -0,0 +1,85 @@
+//! Small key/value persistence used for settings and save data.
+//! Native builds write files into the user's data directory, the web build uses localStorage.
+//!
+//! [`read`] and [`write`] go to the active profile's save data, [`read_shared`] and
+//! [`write_shared`] to data every profile shares.
+
+use std::sync::RwLock;
+
+/// Folder of the active profile's save data, or `None` for the first profile, which keeps
+/// its data where saves went before there were profiles
+static PROFILE_DIR: RwLock<Option<String>> = RwLock::new(None);
+
+/// Points [`read`] and [`write`] at another profile's save data
+pub fn set_profile_dir(dir: Option<String>) {
+    *PROFILE_DIR.write().unwrap() = dir;
+}
+
+fn profile_key(key: &str) -> String {
+    match PROFILE_DIR.read().unwrap().as_deref() {
+        Some(dir) => format!("{dir}/{key}"),
+        None => key.to_string(),
+    }
+}
+
+pub fn read(key: &str) -> Option<String> {
+    read_shared(&profile_key(key))
+}
+
+pub fn write(key: &str, contents: &str) {
+    write_shared(&profile_key(key), contents)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn data_dir() -> Option<std::path::PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(std::path::PathBuf::from))
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|home| std::path::PathBuf::from(home).join(".local").join("share"))
+        })?;
+    Some(base.join("quantum_salvage"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_shared(key: &str) -> Option<String> {
+    std::fs::read_to_string(data_dir()?.join(key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_shared(key: &str, contents: &str) {
+    if let Some(dir) = data_dir() {
+        let path = dir.join(key);
+        let folder = path.parent().unwrap_or(&dir);
+        if let Err(e) =
+            std::fs::create_dir_all(folder).and_then(|_| std::fs::write(&path, contents))
+        {
+            println!("Could not save {key}: {e}");
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_shared(key: &str) -> Option<String> {
+    local_storage()?
+        .get_item(&format!("quantum_salvage/{key}"))
+        .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_shared(key: &str, contents: &str) {
+    if let Some(storage) = local_storage() {
+        if storage
+            .set_item(&format!("quantum_salvage/{key}"), contents)
+            .is_err()
+        {
+            println!("Could not save {key}");
+        }
+    }
+}
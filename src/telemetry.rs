@@ -0,0 +1,354 @@
+//! Opt-in balance telemetry: where the player dies, captures and gets kills, kept locally across
+//! runs. Builds with `dev_cheats` can lay the totals over the arena as a heat map with [H], to help
+//! tune spawn distances and how hard the border pushes.
+
+use std::collections::HashMap;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter, OnExit},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{Allegiance, ShipCaptured, ShipDestroyed, ShipSwapped},
+    gameplay::{GameplaySet, PlayerMarker, Spacecraft},
+    settings::Settings,
+    storage, GameLifecycleState, MainMenuMarker,
+};
+
+const TELEMETRY_KEY: &str = "telemetry.ron";
+/// Oldest points are dropped past this, so the file doesn't grow forever
+pub const MAX_TELEMETRY_POINTS: usize = 5000;
+/// Side of a heat map cell, in world units
+pub const HEAT_CELL: f32 = 0.5;
+
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Telemetry::load())
+            .add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_telemetry_text)
+            .add_systems(
+                Update,
+                toggle_telemetry.run_if(in_state(GameLifecycleState::MainMenu)),
+            )
+            .add_systems(OnExit(GameLifecycleState::Game), save_telemetry)
+            .add_systems(
+                Update,
+                record_telemetry
+                    .in_set(GameplaySet::Cleanup)
+                    .run_if(in_state(GameLifecycleState::Game)),
+            );
+        #[cfg(feature = "dev_cheats")]
+        app.insert_resource(HeatMapOverlay(false)).add_systems(
+            Update,
+            (toggle_heat_map, draw_heat_map)
+                .chain()
+                .in_set(GameplaySet::Presentation)
+                .run_if(in_state(GameLifecycleState::Game)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TelemetryKind {
+    Death,
+    Capture,
+    Kill,
+}
+
+impl TelemetryKind {
+    pub fn color(self) -> Color {
+        match self {
+            TelemetryKind::Death => Color::RED,
+            TelemetryKind::Capture => Color::LIME_GREEN,
+            TelemetryKind::Kill => Color::ORANGE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryPoint {
+    pub kind: TelemetryKind,
+    pub position: [f32; 2],
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Telemetry {
+    pub points: Vec<TelemetryPoint>,
+}
+
+impl Telemetry {
+    pub fn load() -> Self {
+        storage::read(TELEMETRY_KEY)
+            .and_then(|contents| ron::from_str::<Telemetry>(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(TELEMETRY_KEY, &contents),
+            Err(e) => println!("Could not serialise telemetry: {e}"),
+        }
+    }
+
+    pub fn record(&mut self, kind: TelemetryKind, position: Vec2) {
+        self.points.push(TelemetryPoint {
+            kind,
+            position: position.to_array(),
+        });
+        if self.points.len() > MAX_TELEMETRY_POINTS {
+            let excess = self.points.len() - MAX_TELEMETRY_POINTS;
+            self.points.drain(..excess);
+        }
+    }
+
+    /// How many points of a kind landed in each [`HEAT_CELL`], keyed by cell
+    pub fn heat_map(&self, kind: TelemetryKind) -> HashMap<(i32, i32), u32> {
+        let mut cells = HashMap::new();
+        for point in self.points.iter().filter(|point| point.kind == kind) {
+            let cell = (Vec2::from_array(point.position) / HEAT_CELL).floor();
+            *cells.entry((cell.x as i32, cell.y as i32)).or_default() += 1;
+        }
+        cells
+    }
+}
+
+#[derive(Component)]
+pub struct TelemetryTextMarker;
+
+fn telemetry_text(settings: &Settings) -> String {
+    let state = match settings.telemetry {
+        true => "On",
+        false => "Off",
+    };
+    format!("[L] Balance telemetry: {state}")
+}
+
+fn spawn_telemetry_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(45.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                telemetry_text(&settings),
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert((MainMenuMarker, TelemetryTextMarker));
+}
+
+/// Also catches the settings being swapped out by a profile switch
+fn toggle_telemetry(
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut label: Query<&mut Text, With<TelemetryTextMarker>>,
+) {
+    if inputs.just_pressed(KeyCode::KeyL) {
+        settings.telemetry = !settings.telemetry;
+    }
+    if settings.is_changed() {
+        if let Ok(mut label) = label.get_single_mut() {
+            label.sections[0].value = telemetry_text(&settings);
+        }
+    }
+}
+
+pub fn record_telemetry(
+    settings: Res<Settings>,
+    mut telemetry: ResMut<Telemetry>,
+    mut destroyed: EventReader<ShipDestroyed>,
+    mut captured: EventReader<ShipCaptured>,
+    mut swapped: EventReader<ShipSwapped>,
+    ships: Query<&Spacecraft>,
+    player: Query<Entity, With<PlayerMarker>>,
+) {
+    if !settings.telemetry {
+        destroyed.clear();
+        captured.clear();
+        swapped.clear();
+        return;
+    }
+    let player = player.get_single().ok();
+    for loss in destroyed.read() {
+        let kind = match loss.allegiance {
+            Allegiance::Player => TelemetryKind::Death,
+            Allegiance::Enemy if loss.killer.is_some() && loss.killer == player => {
+                TelemetryKind::Kill
+            }
+            _ => continue,
+        };
+        telemetry.record(kind, loss.position);
+    }
+    // Swapping into a disabled ship is capturing it for the player's own
+    let taken = captured
+        .read()
+        .map(|capture| capture.ship)
+        .chain(swapped.read().map(|swap| swap.to));
+    for taken in taken {
+        if let Ok(ship) = ships.get(taken) {
+            telemetry.record(TelemetryKind::Capture, ship.position);
+        }
+    }
+}
+
+fn save_telemetry(settings: Res<Settings>, telemetry: Res<Telemetry>) {
+    if settings.telemetry {
+        telemetry.save();
+    }
+}
+
+#[cfg(feature = "dev_cheats")]
+#[derive(Resource)]
+pub struct HeatMapOverlay(bool);
+
+#[cfg(feature = "dev_cheats")]
+fn toggle_heat_map(inputs: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<HeatMapOverlay>) {
+    if inputs.just_pressed(KeyCode::KeyH) {
+        overlay.0 = !overlay.0;
+    }
+}
+
+#[cfg(feature = "dev_cheats")]
+fn draw_heat_map(
+    mut gizmos: bevy::gizmos::gizmos::Gizmos,
+    overlay: Res<HeatMapOverlay>,
+    telemetry: Res<Telemetry>,
+) {
+    use crate::gameplay::PIXELS_PER_UNIT;
+
+    if !overlay.0 {
+        return;
+    }
+    for kind in [
+        TelemetryKind::Death,
+        TelemetryKind::Capture,
+        TelemetryKind::Kill,
+    ] {
+        let cells = telemetry.heat_map(kind);
+        let hottest = cells.values().copied().max().unwrap_or(1) as f32;
+        for ((x, y), count) in cells {
+            let centre = (Vec2::new(x as f32, y as f32) + 0.5) * HEAT_CELL * PIXELS_PER_UNIT;
+            // Busier cells are drawn both brighter and bigger
+            let heat = count as f32 / hottest;
+            gizmos.rect_2d(
+                centre,
+                0.,
+                Vec2::splat(HEAT_CELL * (0.3 + 0.6 * heat)) * PIXELS_PER_UNIT,
+                kind.color().with_a(0.2 + 0.8 * heat),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, ShipType};
+
+    #[test]
+    fn telemetry_only_records_once_opted_in() {
+        let mut app = test_app();
+        app.insert_resource(Settings::default())
+            .insert_resource(Telemetry::default())
+            .add_systems(Update, record_telemetry);
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+                PlayerMarker,
+            ))
+            .id();
+        let prize = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship3,
+                Vec2::new(1.1, 1.1),
+            ))
+            .id();
+        let taken_over = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship4,
+                Vec2::new(1.1, 1.1),
+            ))
+            .id();
+        let send = |app: &mut App| {
+            for (allegiance, killer) in [
+                (Allegiance::Player, None),
+                (Allegiance::Enemy, Some(player)),
+                (Allegiance::Enemy, Some(prize)),
+            ] {
+                app.world.send_event(ShipDestroyed {
+                    ship: Entity::PLACEHOLDER,
+                    ship_type: ShipType::Ship2,
+                    allegiance,
+                    position: Vec2::new(0.1, -0.1),
+                    killer,
+                });
+            }
+            app.world.send_event(ShipCaptured {
+                ship: prize,
+                ship_type: ShipType::Ship3,
+            });
+            app.world.send_event(ShipSwapped {
+                from: player,
+                to: taken_over,
+                ship_type: ShipType::Ship4,
+            });
+        };
+        send(&mut app);
+        app.update();
+        assert!(app.world.resource::<Telemetry>().points.is_empty());
+
+        app.world.resource_mut::<Settings>().telemetry = true;
+        send(&mut app);
+        app.update();
+        let telemetry = app.world.resource::<Telemetry>();
+        let kinds: Vec<_> = telemetry.points.iter().map(|point| point.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TelemetryKind::Death,
+                TelemetryKind::Kill,
+                TelemetryKind::Capture,
+                TelemetryKind::Capture
+            ]
+        );
+        // Only kills the player made count, and each lands in the cell it happened in
+        let kills = telemetry.heat_map(TelemetryKind::Kill);
+        assert_eq!(kills.len(), 1);
+        assert_eq!(kills[&(0, -1)], 1);
+        let cell = (1.1 / HEAT_CELL).floor() as i32;
+        assert_eq!(telemetry.heat_map(TelemetryKind::Capture)[&(cell, cell)], 2);
+    }
+}
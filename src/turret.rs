@@ -0,0 +1,100 @@
+//! Stationary guns the player can drop with [F]. A turret is an allied [`ShipType::Turret`] that can't
+//! move, so it picks its targets through the same captured-ship logic as every other ally.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::DespawnRecursiveExt,
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec2,
+    prelude::App,
+    time::{Time, Timer, TimerMode},
+};
+
+use crate::{
+    gameplay::{
+        make_ally, AllyTexture, EnemySpacecraftBundle, GameState, GameplaySet, PlayerMarker,
+        ShipTextures, ShipType, Spacecraft,
+    },
+    GameLifecycleState,
+};
+
+pub const TURRET_STOCK: u32 = 2;
+/// How long a turret holds its position before packing up
+pub const TURRET_LIFETIME: Duration = Duration::from_secs(25);
+
+pub struct TurretPlugin;
+
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameLifecycleState::Game), reset_turret_stock)
+            .add_systems(
+                Update,
+                (
+                    deploy_turret.in_set(GameplaySet::Input),
+                    expire_turrets.in_set(GameplaySet::Cleanup),
+                )
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+/// Turrets left to deploy this run
+#[derive(Resource)]
+pub struct TurretStock(pub u32);
+
+#[derive(Component)]
+pub struct Turret(Timer);
+
+fn reset_turret_stock(mut commands: Commands) {
+    commands.insert_resource(TurretStock(TURRET_STOCK));
+}
+
+fn deploy_turret(
+    mut commands: Commands,
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut stock: ResMut<TurretStock>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    textures: Res<ShipTextures>,
+    ally_texture: Res<AllyTexture>,
+) {
+    if !inputs.just_pressed(KeyCode::KeyF) || stock.0 == 0 {
+        return;
+    }
+    if let Ok(player) = player.get_single() {
+        let behind = -Vec2::new(player.heading.sin(), player.heading.cos()) * 0.15;
+        let mut turret = commands.spawn(EnemySpacecraftBundle::create_ship(
+            ShipType::Turret,
+            player.position + behind,
+            &textures,
+        ));
+        turret.insert((
+            Name::new("Turret"),
+            Turret(Timer::new(TURRET_LIFETIME, TimerMode::Once)),
+        ));
+        make_ally(&mut turret, &ally_texture);
+        stock.0 -= 1;
+    }
+}
+
+fn expire_turrets(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut turrets: Query<(Entity, &mut Turret)>,
+) {
+    for (entity, mut turret) in turrets.iter_mut() {
+        if turret.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,38 +1,244 @@
+use std::{collections::HashSet, f32::consts::TAU};
+
 use bevy::{
     asset::{AssetServer, Assets, Handle},
     core::Name,
+    core_pipeline::core_2d::Camera2d,
     ecs::{
+        change_detection::DetectChanges,
         component::Component,
         entity::Entity,
-        query::With,
-        system::{Commands, Query, Res, ResMut, Resource},
+        query::{Has, With, Without},
+        system::{Commands, EntityCommands, Query, Res, ResMut, Resource},
     },
-    hierarchy::{BuildChildren, Children},
-    math::Vec2,
+    hierarchy::{BuildChildren, ChildBuilder, DespawnRecursiveExt},
+    math::{Quat, Vec2},
     prelude::default,
     render::{color::Color, texture::Image},
-    sprite::{TextureAtlas, TextureAtlasLayout},
-    text::{Text, TextSection, TextStyle},
+    sprite::{
+        BorderRect, ImageScaleMode, SliceScaleMode, TextureAtlas, TextureAtlasLayout, TextureSlicer,
+    },
+    text::{Font, Text, TextSection, TextStyle},
+    time::Time,
+    transform::components::Transform,
     ui::{
         node_bundles::{AtlasImageBundle, ImageBundle, NodeBundle, TextBundle},
-        AlignItems, FlexDirection, JustifyContent, PositionType, Style, UiImage, UiRect, Val,
+        AlignItems, BackgroundColor, BorderColor, Display, FlexDirection, FlexWrap, JustifyContent,
+        Overflow, PositionType, Style, UiImage, UiRect, Val, ZIndex,
     },
 };
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::gameplay::{PlayerMarker, PlayerScore, ShipProfile, Spacecraft, MAX_VELOCITY};
+use crate::border::wall_intensity;
+use crate::gameplay::{
+    points_for_ship, take_ship_stock, Captured, CarryoverEnemyPoints, PlayerMarker, PlayerScore,
+    Spacecraft, BORDER_WARNING_RADIUS, MAX_VELOCITY, PIXELS_PER_UNIT,
+};
+use crate::hud::{hud_slot, HudPiece};
+use crate::loadout::ConsumableCharge;
+use crate::score::ScoreMultipliers;
+use crate::settings::Settings;
+use crate::turret::TurretStock;
+use crate::upgrades::RunUpgrades;
+use crate::waves::WaveDirector;
 
 #[derive(Component)]
 pub struct WeaponRechargeMarker;
 #[derive(Component)]
 pub struct ThrottleMarker;
 #[derive(Component)]
+pub struct HeadingTapeMarker;
+#[derive(Component)]
+pub struct SpeedReadoutMarker;
+/// The dot on the instrument dial that points back to the middle of the arena
+#[derive(Component)]
+pub struct OriginMarker;
+/// The instrument dial's rim, which glows like the border wall as the player gets near it
+#[derive(Component)]
+pub struct OriginDial;
+#[derive(Component)]
 pub struct ShieldMarker;
 #[derive(Component)]
 pub struct ScoreMarker;
+#[derive(Component)]
+pub struct EnemiesRemainingMarker;
+/// Fill of the bar showing how close the next enemy is to arriving
+#[derive(Component)]
+pub struct IncomingBarMarker;
+
+/// The radar, which blips for every other ship are kept inside
+#[derive(Component)]
+pub struct Radar;
+/// A ship's spot on the radar
+#[derive(Component)]
+pub struct RadarBlip(pub Entity);
+/// One of the dots tracing the border on the radar, by its place around the ring
+#[derive(Component)]
+pub struct RadarBorderDot(usize);
+
+#[derive(Component)]
+pub struct ConsumableMarker;
+/// HUD elements that fade out with [`HudOpacity`]
+#[derive(Component)]
+pub struct HudElement;
+
+#[derive(Resource)]
+pub struct HudOpacity(pub f32);
+
+/// Extra dimming on top of [`HudOpacity`] while the HUD is flickering
+#[derive(Resource)]
+pub struct HudFlicker(pub f32);
+
+/// Present while the player's ship is down to its last point of hull
+#[derive(Resource)]
+pub struct LowHealth;
+
+/// One of the overlapping red borders that make up the low health vignette
+#[derive(Component)]
+pub struct LowHealthVignette;
+
+/// Corners of the safe area that HUD elements are laid out in, top to bottom within each
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HudAnchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl HudAnchor {
+    fn name(self) -> &'static str {
+        match self {
+            HudAnchor::TopLeft => "Top Left",
+            HudAnchor::TopRight => "Top Right",
+            HudAnchor::BottomLeft => "Bottom Left",
+            HudAnchor::BottomRight => "Bottom Right",
+        }
+    }
+
+    fn style(self) -> Style {
+        let align_items = match self {
+            HudAnchor::TopLeft | HudAnchor::BottomLeft => AlignItems::Start,
+            HudAnchor::TopRight | HudAnchor::BottomRight => AlignItems::End,
+        };
+        Style {
+            flex_direction: FlexDirection::Column,
+            align_items,
+            ..default()
+        }
+    }
+
+    /// Attaches a node spawned by another plugin to this corner. Those plugins need to spawn their
+    /// UI after [`spawn_ui`] for the corner to exist yet.
+    pub fn attach(
+        self,
+        commands: &mut Commands,
+        anchors: &Query<(Entity, &HudAnchor)>,
+        node: Entity,
+    ) {
+        if let Some((anchor, _)) = anchors.iter().find(|(_, anchor)| **anchor == self) {
+            commands.entity(anchor).add_child(node);
+        }
+    }
+}
+
+/// The art panels are drawn with: the frame, the crew portrait and the font they talk in
+#[derive(Resource, Clone, Default)]
+pub struct PanelAssets {
+    pub frame: Handle<Image>,
+    pub portrait: Handle<Image>,
+    pub font: Handle<Font>,
+}
+
+impl PanelAssets {
+    pub fn load(asset_server: &AssetServer) -> Self {
+        Self {
+            frame: asset_server.load("dialogue_box.png"),
+            portrait: asset_server.load("char_spin.png"),
+            font: asset_server.load("jupiterc.ttf"),
+        }
+    }
+}
+
+/// How the frame is cut up, so its corners keep their size however big the panel gets
+pub fn panel_slice() -> TextureSlicer {
+    TextureSlicer {
+        border: BorderRect {
+            left: 3.,
+            right: 3.,
+            top: 3.,
+            bottom: 3.,
+        },
+        center_scale_mode: SliceScaleMode::Stretch,
+        sides_scale_mode: SliceScaleMode::Stretch,
+        max_corner_scale: 24.,
+    }
+}
+
+/// A framed panel laid out by `style`, with anything spawned under it drawn inside the frame
+pub fn panel(
+    style: Style,
+    frame: Handle<Image>,
+    slice: TextureSlicer,
+) -> (ImageBundle, ImageScaleMode) {
+    (
+        ImageBundle {
+            style,
+            image: UiImage::new(frame),
+            ..default()
+        },
+        ImageScaleMode::Sliced(slice),
+    )
+}
+
+/// The crew portrait with a line of text beside it, filling a panel laid out as a row. Returns the
+/// text, for whoever needs to change what it says.
+pub fn portrait_text_row<'a>(
+    parent: &'a mut ChildBuilder,
+    assets: &PanelAssets,
+    text: impl Into<String>,
+    font_size: f32,
+) -> EntityCommands<'a> {
+    parent.spawn(ImageBundle {
+        style: Style {
+            width: Val::Percent(17.),
+            height: Val::Percent(95.),
+            ..default()
+        },
+        image: UiImage::new(assets.portrait.clone()),
+        ..default()
+    });
+    parent.spawn(TextBundle::from_section(
+        text,
+        TextStyle {
+            font: assets.font.clone(),
+            font_size,
+            color: Color::WHITE,
+        },
+    ))
+}
+
+/// Compass points shown along the heading tape, clockwise from north
+const COMPASS_POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+/// Diameter of the dial the origin marker moves around
+pub const ORIGIN_DIAL_SIZE: f32 = 8.;
+/// Width and height of the radar, in percent of the shorter side of the window
+pub const RADAR_SIZE: f32 = 16.;
+/// How far from the player, in world units, the edge of the radar reaches
+pub const RADAR_RANGE: f32 = 6.;
+const RADAR_BLIP_SIZE: f32 = 0.8;
+const RADAR_BORDER_DOTS: usize = 96;
+
+/// Pulses per second of the low health vignette
+pub const LOW_HEALTH_PULSE_RATE: f32 = 1.2;
+/// Chance each frame that the HUD flickers while on low health
+pub const HUD_FLICKER_CHANCE: f64 = 0.04;
 
 pub fn spawn_ui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
 ) {
     let weapon_reload_image = asset_server.load("weapon_reloading_atlas.png");
@@ -48,6 +254,8 @@ pub fn spawn_ui(
 
     let alpha_beta = asset_server.load("alphbeta.ttf");
 
+    commands.insert_resource(HudOpacity(1.));
+    commands.insert_resource(HudFlicker(1.));
     commands.insert_resource(ShieldImages {
         full: shield_full,
         empty: shield_empty,
@@ -57,85 +265,446 @@ pub fn spawn_ui(
         .spawn(NodeBundle {
             style: Style {
                 position_type: PositionType::Absolute,
-                left: Val::Percent(2.),
-                top: Val::Percent(2.),
-                flex_direction: FlexDirection::Row,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
                 ..default()
             },
+            z_index: ZIndex::Global(5),
             ..default()
         })
-        .insert(Name::new("Shields"))
-        .insert(ShieldMarker);
+        .insert(Name::new("Low Health Vignette"))
+        .with_children(|parent| {
+            // Nested borders overlap at the screen edge, so it fades in towards the middle
+            for width in [16., 32., 48., 64.] {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            border: UiRect::all(Val::Px(width)),
+                            ..default()
+                        },
+                        border_color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .insert(LowHealthVignette);
+            }
+        });
+
+    let text = |value: &str, font_size: f32, color: Color| TextBundle {
+        text: Text {
+            sections: vec![TextSection {
+                value: value.to_string(),
+                style: TextStyle {
+                    font: alpha_beta.clone(),
+                    font_size,
+                    color,
+                },
+            }],
+            ..default()
+        },
+        ..default()
+    };
 
+    // Everything sits in one of the four corners of the safe area, sized against the shorter side
+    // of the window so the layout keeps its proportions from 720p up to ultrawide
     commands
         .spawn(NodeBundle {
             style: Style {
                 width: Val::Percent(100.),
                 height: Val::Percent(100.),
-                flex_direction: FlexDirection::Row,
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::VMin(settings.hud_margin)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceBetween,
                 ..default()
             },
             ..default()
         })
         .insert(Name::new("UI"))
         .with_children(|parent| {
-            parent
-                .spawn(AtlasImageBundle {
-                    style: Style {
-                        width: Val::Px(176.),
-                        height: Val::Px(176.),
-                        position_type: PositionType::Absolute,
-                        left: Val::Px(10.),
-                        bottom: Val::Px(10.),
+            for (row, align_items) in [
+                ([HudAnchor::TopLeft, HudAnchor::TopRight], AlignItems::Start),
+                (
+                    [HudAnchor::BottomLeft, HudAnchor::BottomRight],
+                    AlignItems::End,
+                ),
+            ] {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items,
+                            ..default()
+                        },
                         ..default()
-                    },
-                    texture_atlas: weapon_reload_atlas_handle.into(),
-                    image: UiImage::new(weapon_reload_image),
-                    ..default()
-                })
-                .insert(WeaponRechargeMarker);
+                    })
+                    .with_children(|parent| {
+                        for anchor in row {
+                            parent
+                                .spawn(NodeBundle {
+                                    style: anchor.style(),
+                                    ..default()
+                                })
+                                .insert((Name::new(anchor.name()), anchor))
+                                .with_children(|parent| match anchor {
+                                    HudAnchor::TopLeft => {
+                                        let home = parent.parent_entity();
+                                        parent
+                                            .spawn(hud_slot(HudPiece::Shields, home, 0))
+                                            .with_children(|parent| {
+                                                parent
+                                                    .spawn(NodeBundle::default())
+                                                    .insert(Name::new("Shields"))
+                                                    .insert(ShieldMarker);
+                                            });
+                                    }
+                                    HudAnchor::TopRight => {
+                                        let home = parent.parent_entity();
+                                        parent
+                                            .spawn(hud_slot(HudPiece::Score, home, 0))
+                                            .with_children(|parent| {
+                                                parent
+                                                    .spawn(text("Score: XX", 24., Color::WHITE))
+                                                    .insert((ScoreMarker, HudElement));
+                                            });
+                                        parent
+                                            .spawn(text("Enemies: XX", 16., Color::GRAY))
+                                            .insert((EnemiesRemainingMarker, HudElement));
+                                        parent
+                                            .spawn(NodeBundle {
+                                                style: Style {
+                                                    width: Val::Px(120.),
+                                                    height: Val::Px(4.),
+                                                    margin: UiRect::top(Val::Px(6.)),
+                                                    ..default()
+                                                },
+                                                background_color: Color::rgb(0.15, 0.15, 0.15)
+                                                    .into(),
+                                                ..default()
+                                            })
+                                            .insert((Name::new("Incoming"), HudElement))
+                                            .with_children(|parent| {
+                                                parent
+                                                    .spawn(NodeBundle {
+                                                        style: Style {
+                                                            width: Val::Percent(0.),
+                                                            height: Val::Percent(100.),
+                                                            ..default()
+                                                        },
+                                                        background_color: Color::rgb(
+                                                            0.7, 0.25, 0.2,
+                                                        )
+                                                        .into(),
+                                                        ..default()
+                                                    })
+                                                    .insert((IncomingBarMarker, HudElement));
+                                            });
+                                        spawn_radar(parent);
+                                    }
+                                    HudAnchor::BottomLeft => {
+                                        parent
+                                            .spawn(text("", 16., Color::WHITE))
+                                            .insert((ConsumableMarker, HudElement));
+                                        let home = parent.parent_entity();
+                                        parent
+                                            .spawn(hud_slot(HudPiece::WeaponDial, home, 1))
+                                            .with_children(|parent| {
+                                                parent
+                                                    .spawn(AtlasImageBundle {
+                                                        style: Style {
+                                                            width: Val::VMin(24.4),
+                                                            height: Val::VMin(24.4),
+                                                            margin: UiRect::top(Val::Px(4.)),
+                                                            ..default()
+                                                        },
+                                                        texture_atlas: weapon_reload_atlas_handle
+                                                            .clone()
+                                                            .into(),
+                                                        image: UiImage::new(
+                                                            weapon_reload_image.clone(),
+                                                        ),
+                                                        ..default()
+                                                    })
+                                                    .insert((WeaponRechargeMarker, HudElement));
+                                            });
+                                    }
+                                    HudAnchor::BottomRight => {
+                                        parent
+                                            .spawn(NodeBundle {
+                                                style: Style {
+                                                    align_items: AlignItems::End,
+                                                    ..default()
+                                                },
+                                                ..default()
+                                            })
+                                            .with_children(|parent| {
+                                                spawn_instruments(parent, &text);
+                                                let home = parent.parent_entity();
+                                                parent
+                                                    .spawn(hud_slot(HudPiece::Throttle, home, 1))
+                                                    .with_children(|parent| {
+                                                        parent
+                                                            .spawn(AtlasImageBundle {
+                                                                style: Style {
+                                                                    width: Val::VMin(11.25),
+                                                                    height: Val::VMin(47.5),
+                                                                    ..default()
+                                                                },
+                                                                texture_atlas:
+                                                                    throttle_atlas_handle
+                                                                        .clone()
+                                                                        .into(),
+                                                                image: UiImage::new(
+                                                                    throttle_image.clone(),
+                                                                ),
+                                                                ..default()
+                                                            })
+                                                            .insert((ThrottleMarker, HudElement));
+                                                    });
+                                            });
+                                    }
+                                });
+                        }
+                    });
+            }
+        });
+}
+
+/// The origin dial, heading tape and speed readout, stacked to the left of the throttle
+fn spawn_instruments(parent: &mut ChildBuilder, text: &impl Fn(&str, f32, Color) -> TextBundle) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::End,
+                margin: UiRect::right(Val::VMin(1.5)),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Name::new("Instruments"))
+        .with_children(|parent| {
             parent
-                .spawn(AtlasImageBundle {
+                .spawn(NodeBundle {
                     style: Style {
-                        width: Val::Px(81.),
-                        height: Val::Px(342.),
-                        position_type: PositionType::Absolute,
-                        right: Val::Px(10.),
-                        bottom: Val::Px(10.),
+                        width: Val::VMin(ORIGIN_DIAL_SIZE),
+                        height: Val::VMin(ORIGIN_DIAL_SIZE),
+                        border: UiRect::all(Val::Px(1.)),
+                        margin: UiRect::bottom(Val::Px(6.)),
                         ..default()
                     },
-                    texture_atlas: throttle_atlas_handle.into(),
-                    image: UiImage::new(throttle_image),
+                    border_color: Color::GRAY.into(),
                     ..default()
                 })
-                .insert(ThrottleMarker);
-            parent
-                .spawn(TextBundle {
-                    style: Style {
-                        position_type: PositionType::Absolute,
-                        right: Val::Px(15.),
-                        top: Val::Px(15.),
-                        ..default()
-                    },
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: "Score: XX".to_string(),
-                            style: TextStyle {
-                                font: alpha_beta,
-                                font_size: 24.,
-                                color: Color::WHITE,
+                .insert((Name::new("Origin Dial"), OriginDial))
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                width: Val::VMin(1.),
+                                height: Val::VMin(1.),
+                                ..default()
                             },
-                        }],
-                        ..default()
-                    },
-                    ..default()
-                })
-                .insert(ScoreMarker);
+                            background_color: Color::rgb(0.3, 0.8, 1.).into(),
+                            ..default()
+                        })
+                        .insert((OriginMarker, HudElement));
+                });
+            parent
+                .spawn(text("N", 16., Color::WHITE))
+                .insert((HeadingTapeMarker, HudElement));
+            parent
+                .spawn(text("SPD 0", 16., Color::GRAY))
+                .insert((SpeedReadoutMarker, HudElement));
+        });
+}
+
+fn radar_dot(size: f32, color: Color) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::VMin(size),
+            height: Val::VMin(size),
+            ..default()
+        },
+        background_color: color.into(),
+        ..default()
+    }
+}
+
+/// The player sits still in the middle, with everything else moved around them each frame
+fn spawn_radar(parent: &mut ChildBuilder) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::VMin(RADAR_SIZE),
+                height: Val::VMin(RADAR_SIZE),
+                margin: UiRect::top(Val::Px(6.)),
+                border: UiRect::all(Val::Px(1.)),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.35).into(),
+            border_color: Color::GRAY.into(),
+            ..default()
+        })
+        .insert((Name::new("Radar"), Radar))
+        .with_children(|parent| {
+            for index in 0..RADAR_BORDER_DOTS {
+                parent.spawn((
+                    radar_dot(RADAR_BLIP_SIZE / 2., Color::rgba(1., 0.7, 0.2, 0.8)),
+                    RadarBorderDot(index),
+                ));
+            }
+            let mut player = radar_dot(RADAR_BLIP_SIZE, Color::WHITE);
+            let middle = (RADAR_SIZE - RADAR_BLIP_SIZE) / 2.;
+            player.style.left = Val::VMin(middle);
+            player.style.top = Val::VMin(middle);
+            parent.spawn(player);
         });
 }
 
+/// Where something this far from the player shows on the radar, from -1 to 1 across it, turned to
+/// match the camera. Anything out of range is pinned to the rim, so it can still be found.
+pub fn radar_position(offset: Vec2, camera_rotation: Quat) -> Vec2 {
+    let on_screen = (camera_rotation.inverse() * offset.extend(0.)).truncate() / RADAR_RANGE;
+    let reach = on_screen.abs().max_element();
+    match reach > 1. {
+        true => on_screen / reach,
+        false => on_screen,
+    }
+}
+
+/// Puts a radar dot of this size at a point from -1 to 1 across the radar
+fn place_on_radar(style: &mut Style, spot: Vec2, size: f32) {
+    let radius = (RADAR_SIZE - size) / 2.;
+    style.left = Val::VMin(radius * (1. + spot.x));
+    style.top = Val::VMin(radius * (1. - spot.y));
+}
+
+/// Blips come and go with the ships they follow. The border only shows where it's in range.
+pub fn update_radar(
+    mut commands: Commands,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    ships: Query<(Entity, &Spacecraft, Has<Captured>), Without<PlayerMarker>>,
+    camera: Query<&Transform, With<Camera2d>>,
+    radar: Query<Entity, With<Radar>>,
+    mut blips: Query<
+        (Entity, &RadarBlip, &mut Style, &mut BackgroundColor),
+        Without<RadarBorderDot>,
+    >,
+    mut border: Query<(&RadarBorderDot, &mut Style), Without<RadarBlip>>,
+) {
+    let (Ok(player), Ok(radar)) = (player.get_single(), radar.get_single()) else {
+        return;
+    };
+    let rotation = camera
+        .get_single()
+        .map_or(Quat::IDENTITY, |camera| camera.rotation);
+    let mut shown = HashSet::new();
+    for (entity, blip, mut style, mut color) in blips.iter_mut() {
+        match ships.get(blip.0) {
+            Ok((_, ship, captured)) => {
+                place_on_radar(
+                    &mut style,
+                    radar_position(ship.position - player.position, rotation),
+                    RADAR_BLIP_SIZE,
+                );
+                color.0 = radar_color(captured);
+                shown.insert(blip.0);
+            }
+            Err(_) => commands.entity(entity).despawn_recursive(),
+        }
+    }
+    for (entity, ship, captured) in ships.iter() {
+        if !shown.contains(&entity) {
+            let mut dot = radar_dot(RADAR_BLIP_SIZE, radar_color(captured));
+            place_on_radar(
+                &mut dot.style,
+                radar_position(ship.position - player.position, rotation),
+                RADAR_BLIP_SIZE,
+            );
+            let blip = commands.spawn((dot, RadarBlip(entity))).id();
+            commands.entity(radar).add_child(blip);
+        }
+    }
+    for (dot, mut style) in border.iter_mut() {
+        let angle = TAU * dot.0 as f32 / RADAR_BORDER_DOTS as f32;
+        let point = Vec2::new(angle.sin(), angle.cos()) * BORDER_WARNING_RADIUS;
+        let offset = point - player.position;
+        if offset.length() > RADAR_RANGE * 1.5 {
+            style.display = Display::None;
+            continue;
+        }
+        style.display = Display::Flex;
+        place_on_radar(
+            &mut style,
+            (rotation.inverse() * offset.extend(0.)).truncate() / RADAR_RANGE,
+            RADAR_BLIP_SIZE / 2.,
+        );
+    }
+}
+
+fn radar_color(captured: bool) -> Color {
+    match captured {
+        true => Color::rgb(0.4, 1., 0.5),
+        false => Color::rgb(1., 0.3, 0.25),
+    }
+}
+
+/// Compass bearing of a heading in whole degrees, clockwise from the world's +y
+pub fn compass_bearing(heading: f32) -> u32 {
+    (heading.to_degrees().round() as i32).rem_euclid(360) as u32
+}
+
+/// The compass points either side of a bearing, with the closest one bracketed
+pub fn heading_tape(bearing: u32) -> String {
+    let nearest = ((bearing as f32 / 45.).round() as usize) % COMPASS_POINTS.len();
+    let point = |offset: usize| COMPASS_POINTS[(nearest + offset) % COMPASS_POINTS.len()];
+    format!(
+        "{}  [{}]  {}\nHDG {bearing:03}",
+        point(7),
+        point(0),
+        point(1)
+    )
+}
+
+/// The camera turns with the ship, so the heading and the way home are easy to lose
+pub fn update_instruments(
+    ship: Query<&Spacecraft, With<PlayerMarker>>,
+    camera: Query<&Transform, With<Camera2d>>,
+    mut tape: Query<&mut Text, (With<HeadingTapeMarker>, Without<SpeedReadoutMarker>)>,
+    mut speed: Query<&mut Text, With<SpeedReadoutMarker>>,
+    mut marker: Query<&mut Style, With<OriginMarker>>,
+    mut dial: Query<&mut BorderColor, With<OriginDial>>,
+) {
+    if let Ok(ship) = ship.get_single() {
+        if let Ok(mut tape) = tape.get_single_mut() {
+            tape.sections[0].value = heading_tape(compass_bearing(ship.heading));
+        }
+        if let Ok(mut speed) = speed.get_single_mut() {
+            speed.sections[0].value = format!("SPD {:.0}", ship.velocity * 100.);
+        }
+        if let (Ok(camera), Ok(mut marker)) = (camera.get_single(), marker.get_single_mut()) {
+            // Pointing the way home looks on screen, which is stretched wider than it's tall
+            let home = (camera.rotation.inverse() * (-ship.position * PIXELS_PER_UNIT).extend(0.))
+                .truncate()
+                .normalize_or_zero();
+            let radius = (ORIGIN_DIAL_SIZE - 1.) / 2.;
+            marker.left = Val::VMin(radius * (1. + home.x));
+            marker.top = Val::VMin(radius * (1. - home.y));
+        }
+        if let Ok(mut rim) = dial.get_single_mut() {
+            let glow = wall_intensity(ship.position.length());
+            rim.0 = Color::rgb(0.5 - 0.1 * glow, 0.5 + 0.3 * glow, 0.5 + 0.5 * glow);
+        }
+    }
+}
+
 pub fn update_weapon_ui(
     mut image: Query<&mut TextureAtlas, With<WeaponRechargeMarker>>,
     ship: Query<&Spacecraft, With<PlayerMarker>>,
@@ -179,6 +748,8 @@ pub struct ShieldImages {
 pub fn update_shield_ui(
     mut commands: Commands,
     images: Res<ShieldImages>,
+    opacity: Res<HudOpacity>,
+    flicker: Res<HudFlicker>,
     ship: Query<&Spacecraft, With<PlayerMarker>>,
     shield_ui: Query<Entity, With<ShieldMarker>>,
 ) {
@@ -186,16 +757,12 @@ pub fn update_shield_ui(
         if let Ok(ship) = ship.get_single() {
             commands
                 .entity(entity)
-                .remove::<Children>()
+                .despawn_descendants()
                 .insert(NodeBundle {
                     style: Style {
-                        width: Val::Percent(100.),
-                        height: Val::Percent(100.),
-                        position_type: PositionType::Absolute,
-                        left: Val::Px(5.),
-                        top: Val::Px(5.),
-                        padding: UiRect::all(Val::Px(3.)),
+                        max_width: Val::Vw(60.),
                         flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
                         justify_content: JustifyContent::Start,
                         align_items: AlignItems::Start,
                         ..default()
@@ -203,31 +770,208 @@ pub fn update_shield_ui(
                     ..default()
                 })
                 .with_children(|parent| {
-                    for i in 0..ShipProfile::from_type(ship.ship_type).max_health {
+                    for i in 0..ship.profile().max_health {
                         let image = match i < ship.health {
                             true => images.full.clone(),
                             false => images.empty.clone(),
                         };
                         parent.spawn(ImageBundle {
                             style: Style {
-                                padding: UiRect::all(Val::Px(12.)),
-                                width: Val::Px(54.),
-                                height: Val::Px(60.),
+                                padding: UiRect::all(Val::VMin(1.7)),
+                                width: Val::VMin(7.5),
+                                height: Val::VMin(8.3),
                                 ..default()
                             },
                             image: UiImage::new(image),
+                            background_color: Color::WHITE.with_a(opacity.0 * flicker.0).into(),
                             ..default()
                         });
                     }
                 });
         } else {
-            commands.entity(entity).despawn();
+            commands.entity(entity).despawn_recursive();
         }
     }
 }
 
-pub fn update_score_text(mut text: Query<&mut Text, With<ScoreMarker>>, score: Res<PlayerScore>) {
+pub fn update_score_text(
+    mut text: Query<&mut Text, With<ScoreMarker>>,
+    score: Res<PlayerScore>,
+    multipliers: Res<ScoreMultipliers>,
+    upgrades: Res<RunUpgrades>,
+) {
     if let Ok(mut text) = text.get_single_mut() {
-        text.sections[0].value = format!("Score: {}", score.score)
+        let mut value = match multipliers.danger > 1. {
+            true => format!("Score: {} (x{:.1})", score.score, multipliers.danger),
+            false => format!("Score: {}", score.score),
+        };
+        if score.scrap > 0 {
+            value.push_str(&format!("\nScrap: {}", upgrades.scrap_left(&score)));
+        }
+        text.sections[0].value = value;
+    }
+}
+
+/// Leads with the wave number while wave mode is on
+pub fn update_incoming_ui(
+    mut text: Query<&mut Text, With<EnemiesRemainingMarker>>,
+    mut bar: Query<&mut Style, With<IncomingBarMarker>>,
+    enemies: Query<&Spacecraft, (Without<PlayerMarker>, Without<Captured>)>,
+    spawn_points: Res<CarryoverEnemyPoints>,
+    waves: Option<Res<WaveDirector>>,
+) {
+    if let Ok(mut text) = text.get_single_mut() {
+        let enemies = format!("Enemies: {}", enemies.iter().len());
+        text.sections[0].value = match waves {
+            Some(waves) => format!("Wave {}  {enemies}", waves.wave),
+            None => enemies,
+        };
+    }
+    if let Ok(mut bar) = bar.get_single_mut() {
+        let next_ship = take_ship_stock(enemies.iter().map(|s| &s.ship_type).collect::<Vec<_>>());
+        let fraction = spawn_points.0 as f32 / points_for_ship(&next_ship) as f32;
+        bar.width = Val::Percent(fraction.clamp(0., 1.) * 100.);
+    }
+}
+
+pub fn update_consumable_ui(
+    mut text: Query<&mut Text, With<ConsumableMarker>>,
+    charge: Option<Res<ConsumableCharge>>,
+    turrets: Option<Res<TurretStock>>,
+) {
+    if let (Ok(mut text), Some(charge), Some(turrets)) = (text.get_single_mut(), charge, turrets) {
+        text.sections[0].value = format!(
+            "[R] {} x{}\n[F] Turret x{}",
+            charge.consumable.name(),
+            charge.charges,
+            turrets.0
+        );
+    }
+}
+
+/// Pulses the vignette and flickers the HUD while the player is on their last point of hull
+pub fn update_low_health_ui(
+    mut commands: Commands,
+    time: Res<Time>,
+    opacity: Res<HudOpacity>,
+    mut flicker: ResMut<HudFlicker>,
+    low_health: Option<Res<LowHealth>>,
+    ship: Query<&Spacecraft, With<PlayerMarker>>,
+    mut vignette: Query<&mut BorderColor, With<LowHealthVignette>>,
+) {
+    let on_last_hit = ship.get_single().is_ok_and(|ship| ship.health <= 1);
+    match (on_last_hit, low_health.is_some()) {
+        (true, false) => commands.insert_resource(LowHealth),
+        (false, true) => commands.remove_resource::<LowHealth>(),
+        _ => {}
+    }
+
+    let alpha = match on_last_hit {
+        true => {
+            let pulse = (time.elapsed_seconds() * LOW_HEALTH_PULSE_RATE * TAU).sin() * 0.5 + 0.5;
+            (0.1 + 0.15 * pulse) * opacity.0
+        }
+        false => 0.,
+    };
+    for mut color in vignette.iter_mut() {
+        color.0 = Color::RED.with_a(alpha);
+    }
+
+    let dimming = match on_last_hit && rand::thread_rng().gen_bool(HUD_FLICKER_CHANCE) {
+        true => 0.5,
+        false => 1.,
+    };
+    if flicker.0 != dimming {
+        flicker.0 = dimming;
+    }
+}
+
+pub fn apply_hud_opacity(
+    opacity: Res<HudOpacity>,
+    flicker: Res<HudFlicker>,
+    mut images: Query<&mut BackgroundColor, With<HudElement>>,
+    mut texts: Query<&mut Text, With<HudElement>>,
+) {
+    if opacity.is_changed() || flicker.is_changed() {
+        let alpha = opacity.0 * flicker.0;
+        for mut color in images.iter_mut() {
+            color.0.set_a(alpha);
+        }
+        for mut text in texts.iter_mut() {
+            for section in text.sections.iter_mut() {
+                section.style.color.set_a(alpha);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, ShipType};
+    use bevy::app::{App, Update};
+    use std::f32::consts::PI;
+
+    #[test]
+    fn the_radar_follows_every_other_ship_and_pins_far_ones_to_the_rim() {
+        let ahead = radar_position(Vec2::new(0., RADAR_RANGE / 2.), Quat::IDENTITY);
+        assert_eq!(ahead, Vec2::new(0., 0.5));
+        let far = radar_position(Vec2::new(RADAR_RANGE * 4., RADAR_RANGE), Quat::IDENTITY);
+        assert_eq!(far, Vec2::new(1., 0.25));
+        // The radar turns with the camera, so what's ahead of the ship is always up
+        let turned = radar_position(Vec2::new(1., 0.), Quat::from_rotation_z(-PI / 2.));
+        assert!((turned - Vec2::new(0., 1. / RADAR_RANGE)).length() < 1e-5);
+
+        let mut app = test_app();
+        app.add_systems(Update, update_radar);
+        app.world.spawn(Radar);
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        let enemy = app
+            .world
+            .spawn(Spacecraft::from_template(
+                ShipType::Ship2,
+                Vec2::new(0., 20.),
+            ))
+            .id();
+        let ally = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship3, Vec2::new(1., 0.)),
+                Captured,
+            ))
+            .id();
+        app.update();
+        let followed = |app: &mut App| {
+            let mut followed = app
+                .world
+                .query::<&RadarBlip>()
+                .iter(&app.world)
+                .map(|blip| blip.0)
+                .collect::<Vec<_>>();
+            followed.sort();
+            followed
+        };
+        let mut both = vec![enemy, ally];
+        both.sort();
+        assert_eq!(followed(&mut app), both);
+
+        app.world.despawn(enemy);
+        app.update();
+        app.update();
+        assert_eq!(followed(&mut app), vec![ally]);
+    }
+
+    #[test]
+    fn heading_tape_reads_clockwise_from_north() {
+        assert_eq!(compass_bearing(0.), 0);
+        assert_eq!(compass_bearing(PI / 2.), 90);
+        assert_eq!(compass_bearing(-PI / 2.), 270);
+        assert_eq!(compass_bearing(5. * PI), 180);
+        assert_eq!(heading_tape(0), "NW  [N]  NE\nHDG 000");
+        assert_eq!(heading_tape(100), "NE  [E]  SE\nHDG 100");
+        assert_eq!(heading_tape(350), "NW  [N]  NE\nHDG 350");
     }
 }
@@ -0,0 +1,331 @@
+//! Cards shown over the end screen for whatever the run changed in the profile, so progress between
+//! runs is seen as it happens rather than only turning up later in the menus.
+
+use std::collections::VecDeque;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter, OnExit},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    input::{gamepad::GamepadButtonType, keyboard::KeyCode, ButtonInput},
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, FlexDirection, JustifyContent, Style, UiRect, Val, ZIndex,
+    },
+};
+
+use crate::{
+    ace::AceRoster,
+    controls::Pads,
+    records::{bank_run, HighScores, MetaProgress},
+    GameLifecycleState,
+};
+
+/// Banked salvage totals that get a card the first time they're passed
+pub const CREDIT_MILESTONES: [u32; 5] = [50, 200, 500, 1000, 5000];
+
+pub struct UnlockCardsPlugin;
+
+impl Plugin for UnlockCardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameLifecycleState::Game), take_profile_snapshot)
+            .add_systems(
+                OnEnter(GameLifecycleState::EndScreen),
+                queue_unlock_cards.after(bank_run),
+            )
+            .add_systems(
+                Update,
+                show_unlock_cards.run_if(in_state(GameLifecycleState::EndScreen)),
+            )
+            .add_systems(OnExit(GameLifecycleState::EndScreen), clear_unlock_cards);
+    }
+}
+
+/// The parts of the profile a run can move on
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct ProfileSnapshot {
+    pub best_score: Option<u32>,
+    pub credits: u32,
+    /// Each remembered ace, with how many times they've got away
+    pub rivals: Vec<(String, u32)>,
+}
+
+impl ProfileSnapshot {
+    pub fn take(high_scores: &HighScores, progress: &MetaProgress, aces: &AceRoster) -> Self {
+        Self {
+            best_score: high_scores.entries.first().map(|entry| entry.score),
+            credits: progress.banked(),
+            rivals: aces
+                .rivals
+                .iter()
+                .map(|ace| (ace.name.clone(), ace.escapes))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnlockCard {
+    pub title: String,
+    pub detail: String,
+}
+
+impl UnlockCard {
+    fn new(title: &str, detail: String) -> Self {
+        Self {
+            title: title.to_string(),
+            detail,
+        }
+    }
+}
+
+/// One card for each thing that changed between the two snapshots, best news first
+pub fn unlocks_between(before: &ProfileSnapshot, after: &ProfileSnapshot) -> Vec<UnlockCard> {
+    let mut cards = vec![];
+    if let Some(best) = after.best_score {
+        if before.best_score.is_none_or(|old| best > old) {
+            cards.push(UnlockCard::new(
+                "New Personal Best",
+                format!("{best} points, the highest this profile has scored"),
+            ));
+        }
+    }
+    for milestone in CREDIT_MILESTONES {
+        if before.credits < milestone && after.credits >= milestone {
+            cards.push(UnlockCard::new(
+                "Salvage Milestone",
+                format!("{milestone} salvage credits banked in all"),
+            ));
+        }
+    }
+    for (name, escapes) in after.rivals.iter() {
+        match before.rivals.iter().find(|(old, _)| old == name) {
+            None => cards.push(UnlockCard::new(
+                "New Rival",
+                format!("{name} got away, and will be looking for you"),
+            )),
+            Some((_, old_escapes)) if escapes > old_escapes => cards.push(UnlockCard::new(
+                "Rival Returns",
+                format!("{name} slipped away again, tougher for it"),
+            )),
+            _ => {}
+        }
+    }
+    for (name, _) in before.rivals.iter() {
+        if !after.rivals.iter().any(|(new, _)| new == name) {
+            cards.push(UnlockCard::new(
+                "Rivalry Settled",
+                format!("You won't be seeing {name} again"),
+            ));
+        }
+    }
+    cards
+}
+
+/// Cards still to be shown, front first
+#[derive(Resource, Default)]
+pub struct UnlockCards(pub VecDeque<UnlockCard>);
+
+#[derive(Component)]
+pub struct UnlockCardMarker;
+
+fn take_profile_snapshot(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+    progress: Res<MetaProgress>,
+    aces: Res<AceRoster>,
+) {
+    commands.insert_resource(ProfileSnapshot::take(&high_scores, &progress, &aces));
+}
+
+fn queue_unlock_cards(
+    mut commands: Commands,
+    snapshot: Option<Res<ProfileSnapshot>>,
+    high_scores: Res<HighScores>,
+    progress: Res<MetaProgress>,
+    aces: Res<AceRoster>,
+) {
+    let after = ProfileSnapshot::take(&high_scores, &progress, &aces);
+    let cards = match snapshot {
+        Some(before) => unlocks_between(&before, &after),
+        None => vec![],
+    };
+    commands.insert_resource(UnlockCards(cards.into()));
+}
+
+/// Shows the front card over the end screen, [Enter] moving on to the next
+fn show_unlock_cards(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    inputs: Res<ButtonInput<KeyCode>>,
+    pads: Pads,
+    cards: Option<ResMut<UnlockCards>>,
+    shown: Query<Entity, With<UnlockCardMarker>>,
+) {
+    if let Some(mut cards) = cards {
+        if let Ok(card) = shown.get_single() {
+            if inputs.just_pressed(KeyCode::Enter) || pads.just_pressed(GamepadButtonType::South) {
+                commands.entity(card).despawn_recursive();
+                cards.0.pop_front();
+            }
+            return;
+        }
+        if let Some(card) = cards.0.front() {
+            spawn_card(&mut commands, &asset_server, card, cards.0.len());
+        }
+    }
+}
+
+fn clear_unlock_cards(mut commands: Commands, shown: Query<Entity, With<UnlockCardMarker>>) {
+    for entity in shown.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<UnlockCards>();
+}
+
+fn spawn_card(commands: &mut Commands, asset_server: &AssetServer, card: &UnlockCard, left: usize) {
+    let alphbeta = asset_server.load("alphbeta.ttf");
+    let jupitercrash = asset_server.load("jupiterc.ttf");
+    let more = match left {
+        1 => "[Enter] Continue".to_string(),
+        _ => format!("[Enter] Next ({} more)", left - 1),
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.7).into(),
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(UnlockCardMarker)
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(30.)),
+                        border: UiRect::all(Val::Px(2.)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.08, 0.1, 0.16).into(),
+                    border_color: Color::rgb(0.6, 0.9, 1.).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (value, font, size, color, gap) in [
+                        (card.title.clone(), jupitercrash, 40., Color::WHITE, 16.),
+                        (
+                            card.detail.clone(),
+                            alphbeta.clone(),
+                            22.,
+                            Color::WHITE,
+                            24.,
+                        ),
+                        (more, alphbeta, 16., Color::GRAY, 0.),
+                    ] {
+                        parent.spawn(TextBundle {
+                            style: Style {
+                                margin: UiRect::bottom(Val::Px(gap)),
+                                ..default()
+                            },
+                            text: Text::from_section(
+                                value,
+                                TextStyle {
+                                    font,
+                                    font_size: size,
+                                    color,
+                                },
+                            ),
+                            ..default()
+                        });
+                    }
+                });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::testing::test_app;
+    use crate::handle_end_screen_inputs;
+    use crate::pause::RestartRun;
+    use bevy::ecs::{schedule::NextState, system::RunSystemOnce};
+
+    #[test]
+    fn end_of_run_cards_cover_what_changed_in_the_profile() {
+        let before = ProfileSnapshot {
+            best_score: Some(900),
+            credits: 40,
+            rivals: vec![("Vex".to_string(), 1), ("Moth".to_string(), 1)],
+        };
+        assert!(unlocks_between(&before, &before).is_empty());
+
+        let after = ProfileSnapshot {
+            best_score: Some(1200),
+            credits: 250,
+            rivals: vec![("Vex".to_string(), 2), ("Kestrel".to_string(), 1)],
+        };
+        let titles: Vec<_> = unlocks_between(&before, &after)
+            .into_iter()
+            .map(|card| card.title)
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                "New Personal Best",
+                "Salvage Milestone",
+                "Salvage Milestone",
+                "Rival Returns",
+                "New Rival",
+                "Rivalry Settled"
+            ]
+        );
+    }
+
+    #[test]
+    fn end_screen_restarts_or_leaves_once_the_cards_are_read() {
+        let mut app = test_app();
+        app.insert_state(GameLifecycleState::EndScreen);
+        let mut inputs = ButtonInput::<KeyCode>::default();
+        inputs.press(KeyCode::KeyR);
+        app.insert_resource(inputs);
+        app.insert_resource(UnlockCards(
+            [UnlockCard {
+                title: "New Personal Best".to_string(),
+                detail: String::new(),
+            }]
+            .into(),
+        ));
+        app.world.run_system_once(handle_end_screen_inputs);
+        assert!(app.world.get_resource::<RestartRun>().is_none());
+        assert_eq!(
+            app.world.resource::<NextState<GameLifecycleState>>().0,
+            None
+        );
+
+        app.world.resource_mut::<UnlockCards>().0.clear();
+        app.world.run_system_once(handle_end_screen_inputs);
+        assert!(app.world.get_resource::<RestartRun>().is_some());
+        assert_eq!(
+            app.world.resource::<NextState<GameLifecycleState>>().0,
+            Some(GameLifecycleState::Loadout)
+        );
+    }
+}
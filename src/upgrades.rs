@@ -0,0 +1,506 @@
+//! Scrap salvaged during a run buys upgrades that last until it ends, whatever hull the player is
+//! flying. The upgrade screen opens with [U] over the pause menu, and pops up by itself every
+//! [`UPGRADE_OFFER_EVERY`] points while there's scrap enough for something. Bought upgrades are
+//! kept in [`RunUpgrades`], which the player's ship carries into [`Spacecraft::profile`].
+
+use bevy::{
+    app::{Plugin, PreUpdate, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Has, With},
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, NextState, OnEnter, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    input::{
+        gamepad::{GamepadButton, GamepadButtonType},
+        keyboard::KeyCode,
+        ButtonInput, InputSystem,
+    },
+    prelude::{default, App},
+    reflect::Reflect,
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, FlexDirection, JustifyContent, PositionType, Style, UiRect, Val, ZIndex,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::BindingsScreen,
+    checkpoint::resume_checkpoint,
+    gameplay::{
+        DeathSequence, GameState, GameplaySet, PlayerMarker, PlayerScore, ShipProfile, Spacecraft,
+    },
+    hud::HudEditor,
+    spectate::Spectating,
+    volume::AudioScreen,
+    GameLifecycleState,
+};
+
+/// Scrap the first level of any upgrade costs, with each level after costing that much more again
+pub const UPGRADE_BASE_COST: u32 = 10;
+pub const MAX_UPGRADE_LEVEL: u8 = 3;
+/// Points between the upgrade screen offering itself
+pub const UPGRADE_OFFER_EVERY: u32 = 250;
+
+pub struct UpgradesPlugin;
+
+impl Plugin for UpgradesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunUpgrades>()
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                reset_run_upgrades.before(resume_checkpoint),
+            )
+            .add_systems(
+                Update,
+                (
+                    fit_run_upgrades.in_set(GameplaySet::Simulation),
+                    offer_upgrades
+                        .in_set(GameplaySet::Cleanup)
+                        .run_if(in_state(GameState::Regular))
+                        .run_if(not(resource_exists::<DeathSequence>))
+                        .run_if(not(resource_exists::<Spectating>)),
+                )
+                    .run_if(in_state(GameLifecycleState::Game)),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    open_upgrade_screen,
+                    handle_upgrade_screen.run_if(resource_exists::<UpgradeScreen>),
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .run_if(not(resource_exists::<AudioScreen>))
+                    .run_if(not(resource_exists::<BindingsScreen>))
+                    .run_if(not(resource_exists::<HudEditor>))
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::PauseMenu)),
+            )
+            .add_systems(OnExit(GameState::PauseMenu), close_upgrade_screen);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Upgrade {
+    Reload,
+    Hull,
+    Spread,
+    Shields,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 4] = [
+        Upgrade::Reload,
+        Upgrade::Hull,
+        Upgrade::Spread,
+        Upgrade::Shields,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Upgrade::Reload => "Faster reload",
+            Upgrade::Hull => "Extra hull plating",
+            Upgrade::Spread => "Wider gun spread",
+            Upgrade::Shields => "Faster shield recharge",
+        }
+    }
+}
+
+/// Levels bought this run, and the scrap they took
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunUpgrades {
+    pub reload: u8,
+    pub hull: u8,
+    pub spread: u8,
+    pub shields: u8,
+    pub spent: u32,
+}
+
+impl RunUpgrades {
+    pub fn level(&self, upgrade: Upgrade) -> u8 {
+        match upgrade {
+            Upgrade::Reload => self.reload,
+            Upgrade::Hull => self.hull,
+            Upgrade::Spread => self.spread,
+            Upgrade::Shields => self.shields,
+        }
+    }
+
+    /// Scrap the next level costs, or `None` once it's maxed out
+    pub fn cost(&self, upgrade: Upgrade) -> Option<u32> {
+        let level = self.level(upgrade);
+        (level < MAX_UPGRADE_LEVEL).then(|| UPGRADE_BASE_COST * (level as u32 + 1))
+    }
+
+    /// What's left of the scrap salvaged so far
+    pub fn scrap_left(&self, score: &PlayerScore) -> u32 {
+        score.scrap.saturating_sub(self.spent)
+    }
+
+    pub fn affordable(&self, score: &PlayerScore) -> bool {
+        Upgrade::ALL
+            .iter()
+            .filter_map(|upgrade| self.cost(*upgrade))
+            .any(|cost| cost <= self.scrap_left(score))
+    }
+
+    /// Buys the next level if there's scrap enough and it isn't maxed out
+    pub fn buy(&mut self, upgrade: Upgrade, score: &PlayerScore) -> bool {
+        let Some(cost) = self.cost(upgrade) else {
+            return false;
+        };
+        if cost > self.scrap_left(score) {
+            return false;
+        }
+        self.spent += cost;
+        match upgrade {
+            Upgrade::Reload => self.reload += 1,
+            Upgrade::Hull => self.hull += 1,
+            Upgrade::Spread => self.spread += 1,
+            Upgrade::Shields => self.shields += 1,
+        }
+        true
+    }
+
+    pub fn apply(&self, profile: &mut ShipProfile) {
+        profile.gun_reload_time = profile
+            .gun_reload_time
+            .mul_f32(1. - 0.15 * self.reload as f32);
+        profile.max_health += self.hull as i32;
+        profile.spread *= 1. + 0.3 * self.spread as f32;
+        profile.shield_recharge_time = profile
+            .shield_recharge_time
+            .mul_f32(1. - 0.15 * self.shields as f32);
+    }
+
+    fn row_text(&self, upgrade: Upgrade) -> String {
+        let level = self.level(upgrade);
+        let pips = format!(
+            "{}{}",
+            "#".repeat(level as usize),
+            "-".repeat((MAX_UPGRADE_LEVEL - level) as usize)
+        );
+        match self.cost(upgrade) {
+            Some(cost) => format!("{} [{pips}] {cost} scrap", upgrade.label()),
+            None => format!("{} [{pips}] Maxed", upgrade.label()),
+        }
+    }
+}
+
+fn reset_run_upgrades(mut commands: Commands, mut upgrades: ResMut<RunUpgrades>) {
+    *upgrades = RunUpgrades::default();
+    commands.remove_resource::<NextUpgradeOffer>();
+    commands.remove_resource::<UpgradesOffered>();
+}
+
+/// Keeps the upgrades on whichever ship the player is flying, and off any they've left. Plating
+/// bought comes bolted on, adding to the hull as well as its maximum, but a hull the player moves
+/// into only gets the room for it.
+pub fn fit_run_upgrades(
+    upgrades: Res<RunUpgrades>,
+    mut ships: Query<(&mut Spacecraft, Has<PlayerMarker>)>,
+) {
+    for (mut ship, player) in ships.iter_mut() {
+        let fitted = player.then_some(*upgrades);
+        if ship.upgrades == fitted {
+            continue;
+        }
+        let bought = match (ship.upgrades, fitted) {
+            (Some(old), Some(new)) => new.hull.saturating_sub(old.hull) as i32,
+            _ => 0,
+        };
+        ship.upgrades = fitted;
+        let profile = ship.profile();
+        ship.health = (ship.health + bought).min(profile.max_health);
+        ship.weapon_cooldown.set_duration(profile.gun_reload_time);
+        ship.shield_recharge
+            .set_duration(profile.shield_recharge_time);
+    }
+}
+
+/// Score the upgrade screen next offers itself at, worked out from the score on the first frame so
+/// a resumed run doesn't get one straight away
+#[derive(Resource)]
+pub struct NextUpgradeOffer(pub u32);
+
+/// Pauses the run for the upgrade screen to open over, once the pause menu is up
+#[derive(Resource)]
+pub struct UpgradesOffered;
+
+pub fn offer_upgrades(
+    mut commands: Commands,
+    score: Res<PlayerScore>,
+    upgrades: Res<RunUpgrades>,
+    next: Option<ResMut<NextUpgradeOffer>>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    let due = (score.score / UPGRADE_OFFER_EVERY + 1) * UPGRADE_OFFER_EVERY;
+    let Some(mut next) = next else {
+        commands.insert_resource(NextUpgradeOffer(due));
+        return;
+    };
+    if score.score < next.0 {
+        return;
+    }
+    next.0 = due;
+    if upgrades.affordable(&score) {
+        commands.insert_resource(UpgradesOffered);
+        state.set(GameState::PauseMenu);
+    }
+}
+
+/// The upgrade screen is open with this row picked. Closing one that offered itself carries on
+/// with the run, rather than leaving the player in the pause menu.
+#[derive(Resource)]
+pub struct UpgradeScreen {
+    selected: usize,
+    offered: bool,
+}
+
+#[derive(Component)]
+pub struct UpgradeScreenMarker;
+
+#[derive(Component)]
+pub struct UpgradesText;
+
+fn upgrade_screen_text(upgrades: &RunUpgrades, score: &PlayerScore, selected: usize) -> String {
+    let rows = Upgrade::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, upgrade)| {
+            let cursor = match i == selected {
+                true => "> ",
+                false => "  ",
+            };
+            format!("{cursor}{}", upgrades.row_text(*upgrade))
+        })
+        .collect::<Vec<_>>();
+    format!(
+        "Scrap: {}\n\n{}\n\n[Up/Down] Choose  [Enter] Buy\n[U/Esc] Back",
+        upgrades.scrap_left(score),
+        rows.join("\n")
+    )
+}
+
+fn spawn_upgrade_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    upgrades: &RunUpgrades,
+    score: &PlayerScore,
+    offered: bool,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.5).into(),
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(UpgradeScreenMarker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                style: Style {
+                    padding: UiRect::bottom(Val::Px(30.)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Upgrades",
+                    TextStyle {
+                        font: asset_server.load("jupiterc.ttf"),
+                        font_size: 56.,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        upgrade_screen_text(upgrades, score, 0),
+                        TextStyle {
+                            font: asset_server.load("alphbeta.ttf"),
+                            font_size: 24.,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    ..default()
+                },
+                UpgradesText,
+            ));
+        });
+    commands.insert_resource(UpgradeScreen {
+        selected: 0,
+        offered,
+    });
+}
+
+fn open_upgrade_screen(
+    mut commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    screen: Option<Res<UpgradeScreen>>,
+    offered: Option<Res<UpgradesOffered>>,
+    upgrades: Res<RunUpgrades>,
+    score: Res<PlayerScore>,
+    asset_server: Res<AssetServer>,
+) {
+    if screen.is_some() || (offered.is_none() && !inputs.just_pressed(KeyCode::KeyU)) {
+        return;
+    }
+    // Nothing under the screen should see the key that opened it
+    inputs.reset_all();
+    commands.remove_resource::<UpgradesOffered>();
+    spawn_upgrade_screen(
+        &mut commands,
+        &asset_server,
+        &upgrades,
+        &score,
+        offered.is_some(),
+    );
+}
+
+/// The screen takes every key and button while it's open, so the pause menu under it stays put.
+/// It answers to the d-pad and face buttons too, as it can open by itself mid-run.
+#[allow(clippy::too_many_arguments)]
+fn handle_upgrade_screen(
+    commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    mut buttons: ResMut<ButtonInput<GamepadButton>>,
+    mut screen: ResMut<UpgradeScreen>,
+    mut upgrades: ResMut<RunUpgrades>,
+    score: Res<PlayerScore>,
+    mut state: ResMut<NextState<GameState>>,
+    mut text: Query<&mut Text, With<UpgradesText>>,
+    screens: Query<Entity, With<UpgradeScreenMarker>>,
+) {
+    let rows = Upgrade::ALL.len();
+    let pressed = |button| buttons.get_just_pressed().any(|b| b.button_type == button);
+    if inputs.just_pressed(KeyCode::Escape)
+        || inputs.just_pressed(KeyCode::KeyU)
+        || pressed(GamepadButtonType::East)
+    {
+        if screen.offered {
+            state.set(GameState::Regular);
+        }
+        inputs.reset_all();
+        buttons.reset_all();
+        close_upgrade_screen(commands, screens);
+        return;
+    }
+    if inputs.just_pressed(KeyCode::ArrowUp) || pressed(GamepadButtonType::DPadUp) {
+        screen.selected = (screen.selected + rows - 1) % rows;
+    }
+    if inputs.just_pressed(KeyCode::ArrowDown) || pressed(GamepadButtonType::DPadDown) {
+        screen.selected = (screen.selected + 1) % rows;
+    }
+    if inputs.just_pressed(KeyCode::Enter) || pressed(GamepadButtonType::South) {
+        upgrades.buy(Upgrade::ALL[screen.selected], &score);
+    }
+    inputs.reset_all();
+    buttons.reset_all();
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = upgrade_screen_text(&upgrades, &score, screen.selected);
+    }
+}
+
+fn close_upgrade_screen(mut commands: Commands, screens: Query<Entity, With<UpgradeScreenMarker>>) {
+    commands.remove_resource::<UpgradeScreen>();
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, ShipType};
+    use bevy::{
+        math::Vec2,
+        time::{Stopwatch, Timer},
+    };
+
+    #[test]
+    fn scrap_buys_upgrades_for_whatever_the_player_flies() {
+        let score = PlayerScore {
+            score: 0,
+            add_score_timer: Timer::default(),
+            survived_time: Stopwatch::new(),
+            threat_exposure: 0.,
+            scrap: UPGRADE_BASE_COST * 2,
+        };
+        let mut upgrades = RunUpgrades::default();
+        assert!(upgrades.buy(Upgrade::Hull, &score));
+        // The second level costs twice the first, more than is left
+        assert!(!upgrades.buy(Upgrade::Hull, &score));
+        assert_eq!(upgrades.scrap_left(&score), UPGRADE_BASE_COST);
+        assert!(upgrades.buy(Upgrade::Reload, &score));
+        assert!(!upgrades.affordable(&score));
+
+        let mut app = test_app();
+        app.insert_resource(upgrades)
+            .insert_resource(score)
+            .add_systems(Update, (fit_run_upgrades, offer_upgrades));
+        let player = app
+            .world
+            .spawn((
+                Spacecraft::from_template(ShipType::Ship2, Vec2::ZERO),
+                PlayerMarker,
+            ))
+            .id();
+        let ally = app
+            .world
+            .spawn(Spacecraft::from_template(ShipType::Ship2, Vec2::ZERO))
+            .id();
+        app.update();
+        let base = ShipProfile::from_type(ShipType::Ship2);
+        let craft = app.world.get::<Spacecraft>(player).unwrap();
+        assert_eq!(craft.profile().max_health, base.max_health + 1);
+        assert!(craft.weapon_cooldown.duration() < base.gun_reload_time);
+        assert!(app
+            .world
+            .get::<Spacecraft>(ally)
+            .unwrap()
+            .upgrades
+            .is_none());
+
+        // Plating bought mid-run adds a point of hull as well as the room for it. The first fit
+        // only made room, so the ship stays a point short of its new maximum.
+        app.world.resource_mut::<RunUpgrades>().hull += 1;
+        app.update();
+        let craft = app.world.get::<Spacecraft>(player).unwrap();
+        assert_eq!(craft.profile().max_health, base.max_health + 2);
+        assert_eq!(craft.health, base.max_health + 1);
+
+        // The screen only offers itself with scrap to spend
+        app.world.resource_mut::<PlayerScore>().score = UPGRADE_OFFER_EVERY;
+        app.update();
+        assert!(!app.world.contains_resource::<UpgradesOffered>());
+        let mut score = app.world.resource_mut::<PlayerScore>();
+        score.scrap += UPGRADE_BASE_COST * 3;
+        score.score = UPGRADE_OFFER_EVERY * 2;
+        app.update();
+        assert!(app.world.contains_resource::<UpgradesOffered>());
+        assert_eq!(
+            app.world.resource::<NextState<GameState>>().0,
+            Some(GameState::PauseMenu)
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Master, music and effects volume, and a mute switch, set from a screen that opens with [A]
+//! over the main menu or the pause menu. The sound itself is turned up or down in
+//! [`crate::audio`], every frame, so a change is heard straight away.
+
+use bevy::{
+    app::{Plugin, PreUpdate},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            Condition, IntoSystemConfigs, OnEnter, OnExit, State,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::{BuildChildren, DespawnRecursiveExt},
+    input::{keyboard::KeyCode, ButtonInput, InputSystem},
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{
+        node_bundles::{NodeBundle, TextBundle},
+        AlignItems, FlexDirection, JustifyContent, PositionType, Style, UiRect, Val, ZIndex,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::BindingsScreen, gameplay::GameState, hud::HudEditor, seed::HighScoreScreen, storage,
+    upgrades::UpgradeScreen, GameLifecycleState, MainMenuMarker,
+};
+
+const AUDIO_SETTINGS_KEY: &str = "audio.ron";
+/// How far one press of left or right moves a volume
+pub const VOLUME_STEP: f32 = 0.1;
+const SLIDER_WIDTH: usize = 10;
+
+pub struct VolumeSettingsPlugin;
+
+impl Plugin for VolumeSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AudioSettings::load())
+            .add_systems(
+                PreUpdate,
+                (
+                    open_audio_screen,
+                    handle_audio_screen.run_if(resource_exists::<AudioScreen>),
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .run_if(not(resource_exists::<BindingsScreen>))
+                    .run_if(not(resource_exists::<HudEditor>))
+                    .run_if(not(resource_exists::<UpgradeScreen>))
+                    .run_if(not(resource_exists::<HighScoreScreen>))
+                    .run_if(in_state(GameLifecycleState::MainMenu).or_else(
+                        in_state(GameLifecycleState::Game).and_then(in_state(GameState::PauseMenu)),
+                    )),
+            )
+            .add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_audio_hint)
+            .add_systems(OnExit(GameLifecycleState::MainMenu), close_audio_screen)
+            .add_systems(OnExit(GameState::PauseMenu), close_audio_screen);
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.,
+            music: 1.,
+            effects: 1.,
+            muted: false,
+        }
+    }
+}
+
+/// A row on the audio screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioSetting {
+    Master,
+    Music,
+    Effects,
+    Mute,
+}
+
+impl AudioSetting {
+    pub const ALL: [AudioSetting; 4] = [
+        AudioSetting::Master,
+        AudioSetting::Music,
+        AudioSetting::Effects,
+        AudioSetting::Mute,
+    ];
+}
+
+impl AudioSettings {
+    pub fn load() -> Self {
+        let mut settings = storage::read(AUDIO_SETTINGS_KEY)
+            .and_then(|contents| ron::from_str::<AudioSettings>(&contents).ok())
+            .unwrap_or_default();
+        for volume in [
+            &mut settings.master,
+            &mut settings.music,
+            &mut settings.effects,
+        ] {
+            *volume = match volume.is_finite() {
+                true => volume.clamp(0., 1.),
+                false => 1.,
+            };
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => storage::write(AUDIO_SETTINGS_KEY, &contents),
+            Err(e) => println!("Could not serialise audio settings: {e}"),
+        }
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        self.volume(self.music)
+    }
+
+    pub fn effects_volume(&self) -> f32 {
+        self.volume(self.effects)
+    }
+
+    fn volume(&self, channel: f32) -> f32 {
+        match self.muted {
+            true => 0.,
+            false => self.master * channel,
+        }
+    }
+
+    /// Moves a volume by whole steps, or flips the mute switch for any step on it
+    pub fn adjust(&mut self, setting: AudioSetting, steps: i32) {
+        let volume = match setting {
+            AudioSetting::Master => &mut self.master,
+            AudioSetting::Music => &mut self.music,
+            AudioSetting::Effects => &mut self.effects,
+            AudioSetting::Mute => {
+                if steps != 0 {
+                    self.muted = !self.muted;
+                }
+                return;
+            }
+        };
+        // Rounded to the step, so repeated presses don't drift off it
+        let stepped = (*volume / VOLUME_STEP).round() + steps as f32;
+        *volume = (stepped * VOLUME_STEP).clamp(0., 1.);
+    }
+
+    fn row_text(&self, setting: AudioSetting) -> String {
+        let volume = match setting {
+            AudioSetting::Master => self.master,
+            AudioSetting::Music => self.music,
+            AudioSetting::Effects => self.effects,
+            AudioSetting::Mute => {
+                let state = match self.muted {
+                    true => "On",
+                    false => "Off",
+                };
+                return format!("Mute: {state}");
+            }
+        };
+        let filled = (volume * SLIDER_WIDTH as f32).round() as usize;
+        format!(
+            "{:?} [{}{}] {:.0}%",
+            setting,
+            "#".repeat(filled),
+            "-".repeat(SLIDER_WIDTH - filled),
+            volume * 100.
+        )
+    }
+}
+
+/// The audio screen is open, with this row picked
+#[derive(Resource)]
+pub struct AudioScreen {
+    selected: usize,
+}
+
+#[derive(Component)]
+pub struct AudioScreenMarker;
+
+#[derive(Component)]
+pub struct AudioSettingsText;
+
+fn spawn_audio_hint(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(75.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                "[A] Audio settings",
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert(MainMenuMarker);
+}
+
+fn audio_screen_text(settings: &AudioSettings, selected: usize) -> String {
+    let rows = AudioSetting::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, setting)| {
+            let cursor = match i == selected {
+                true => "> ",
+                false => "  ",
+            };
+            format!("{cursor}{}", settings.row_text(*setting))
+        })
+        .collect::<Vec<_>>();
+    format!(
+        "{}\n\n[Up/Down] Choose  [Left/Right] Adjust  [Enter] Toggle mute\n[A/Esc] Back",
+        rows.join("\n")
+    )
+}
+
+fn open_audio_screen(
+    mut commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    screen: Option<Res<AudioScreen>>,
+    settings: Res<AudioSettings>,
+    asset_server: Res<AssetServer>,
+    lifecycle: Res<State<GameLifecycleState>>,
+) {
+    if screen.is_some() || !inputs.just_pressed(KeyCode::KeyA) {
+        return;
+    }
+    // Nothing under the screen should see the key that opened it
+    inputs.reset_all();
+    commands.insert_resource(AudioScreen { selected: 0 });
+    let alphbeta = asset_server.load("alphbeta.ttf");
+    let jupitercrash = asset_server.load("jupiterc.ttf");
+    // The pause menu already darkens the battlefield
+    let backdrop = match lifecycle.get() {
+        GameLifecycleState::MainMenu => Color::rgba(0., 0., 0., 0.8),
+        _ => Color::rgba(0., 0., 0., 0.5),
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: backdrop.into(),
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(AudioScreenMarker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                style: Style {
+                    padding: UiRect::bottom(Val::Px(30.)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Audio",
+                    TextStyle {
+                        font: jupitercrash,
+                        font_size: 56.,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+            parent.spawn((
+                TextBundle {
+                    text: Text::from_section(
+                        audio_screen_text(&settings, 0),
+                        TextStyle {
+                            font: alphbeta,
+                            font_size: 24.,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    ..default()
+                },
+                AudioSettingsText,
+            ));
+        });
+}
+
+/// The screen takes every key while it's open, so the menu under it stays put
+fn handle_audio_screen(
+    commands: Commands,
+    mut inputs: ResMut<ButtonInput<KeyCode>>,
+    mut screen: ResMut<AudioScreen>,
+    mut settings: ResMut<AudioSettings>,
+    mut text: Query<&mut Text, With<AudioSettingsText>>,
+    screens: Query<Entity, With<AudioScreenMarker>>,
+) {
+    let rows = AudioSetting::ALL.len();
+    if inputs.just_pressed(KeyCode::Escape) || inputs.just_pressed(KeyCode::KeyA) {
+        inputs.reset_all();
+        close_audio_screen(commands, screens);
+        return;
+    }
+    if inputs.just_pressed(KeyCode::ArrowUp) {
+        screen.selected = (screen.selected + rows - 1) % rows;
+    }
+    if inputs.just_pressed(KeyCode::ArrowDown) {
+        screen.selected = (screen.selected + 1) % rows;
+    }
+    let setting = AudioSetting::ALL[screen.selected];
+    let steps = match (
+        inputs.just_pressed(KeyCode::ArrowLeft),
+        inputs.just_pressed(KeyCode::ArrowRight),
+        inputs.just_pressed(KeyCode::Enter),
+    ) {
+        (_, _, true) if setting == AudioSetting::Mute => 1,
+        (true, false, _) => -1,
+        (false, true, _) => 1,
+        _ => 0,
+    };
+    if steps != 0 {
+        settings.adjust(setting, steps);
+        settings.save();
+    }
+    inputs.reset_all();
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = audio_screen_text(&settings, screen.selected);
+    }
+}
+
+fn close_audio_screen(mut commands: Commands, screens: Query<Entity, With<AudioScreenMarker>>) {
+    commands.remove_resource::<AudioScreen>();
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::SoundChannel;
+
+    #[test]
+    fn audio_settings_scale_each_channel_and_stay_in_range() {
+        let mut settings = AudioSettings {
+            master: 0.5,
+            music: 0.8,
+            effects: 1.,
+            muted: false,
+        };
+        assert!((SoundChannel::Music.volume(&settings) - 0.4).abs() < 1e-5);
+        assert_eq!(SoundChannel::Effects.volume(&settings), 0.5);
+
+        settings.adjust(AudioSetting::Effects, 3);
+        assert_eq!(settings.effects, 1.);
+        for _ in 0..12 {
+            settings.adjust(AudioSetting::Master, -1);
+        }
+        assert_eq!(settings.master, 0.);
+        settings.adjust(AudioSetting::Master, 3);
+        assert!((settings.master - 0.3).abs() < 1e-5);
+
+        settings.adjust(AudioSetting::Mute, 1);
+        assert!(settings.muted);
+        assert_eq!(settings.music_volume(), 0.);
+        assert_eq!(settings.effects_volume(), 0.);
+        settings.adjust(AudioSetting::Mute, -1);
+        assert!(!settings.muted);
+
+        let saved = ron::to_string(&settings).unwrap();
+        assert_eq!(ron::from_str::<AudioSettings>(&saved).unwrap(), settings);
+        // Older or hand-trimmed files fill in what's missing
+        assert_eq!(
+            ron::from_str::<AudioSettings>("(music: 0.2)").unwrap(),
+            AudioSettings {
+                music: 0.2,
+                ..AudioSettings::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Wave mode, for players who'd rather fight in rounds. With it on, the steady stream of enemies
+//! from `spawn_ships` stops and the [`WaveDirector`] sends them in numbered waves instead, each one
+//! laid out by [`wave_composition`]. Clearing a wave pays [`WAVE_BONUS`] for every wave so far and
+//! buys [`WAVE_BREATHER`] before the next. [V] on the main menu turns it on.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    asset::AssetServer,
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        event::EventWriter,
+        query::{With, Without},
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            IntoSystemConfigs, OnEnter, OnExit,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    prelude::{default, App},
+    render::color::Color,
+    text::{Text, TextStyle},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+    window::Window,
+};
+
+use crate::{
+    boss::BossFight,
+    dialogue::Dialogue,
+    gameplay::{
+        focus_position, spawn_enemy, CameraView, Captured, GameState, GameplaySet,
+        MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker, PlayerMarker,
+        PlayerScore, ShipType, Spacecraft, WarpIn, WarpPortalTexture,
+    },
+    practice::CapturePractice,
+    range::TargetRange,
+    score::{ScoreEvent, ScoreSource},
+    seed::RunSeed,
+    settings::Settings,
+    spectate::Spectating,
+    stats::{RunStats, TimelineEvent},
+    GameLifecycleState, MainMenuMarker,
+};
+
+/// Quiet time between clearing one wave and the next one warping in
+pub const WAVE_BREATHER: Duration = Duration::from_secs(8);
+/// Time to get your bearings before the first wave
+pub const FIRST_WAVE_DELAY: Duration = Duration::from_secs(3);
+/// Points for clearing a wave, multiplied by its number
+pub const WAVE_BONUS: u32 = 25;
+/// Most of the small fry a wave brings, however far in
+const MAX_WAVE_FIGHTERS: u32 = 6;
+
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameLifecycleState::MainMenu), spawn_wave_mode_text)
+            .add_systems(
+                Update,
+                toggle_wave_mode.run_if(in_state(GameLifecycleState::MainMenu)),
+            )
+            .add_systems(OnEnter(GameLifecycleState::Game), start_waves)
+            .add_systems(OnExit(GameLifecycleState::Game), stop_waves)
+            .add_systems(
+                Update,
+                run_waves
+                    .run_if(resource_exists::<WaveDirector>)
+                    .run_if(not(resource_exists::<BossFight>))
+                    .run_if(not(resource_exists::<CapturePractice>))
+                    .run_if(not(resource_exists::<TargetRange>))
+                    .in_set(GameplaySet::Simulation)
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            );
+    }
+}
+
+/// The ships making up wave `wave`, counting from 1. Bigger hulls join in as the waves go on.
+pub fn wave_composition(wave: u32) -> Vec<ShipType> {
+    [
+        (ShipType::Ship1, (2 + wave).min(MAX_WAVE_FIGHTERS)),
+        (ShipType::Ship2, wave / 2),
+        (ShipType::Ship3, wave / 3),
+        (ShipType::Ship4, wave / 5),
+        (ShipType::Ship5, wave / 7),
+        (ShipType::Ship6, wave / 10),
+    ]
+    .into_iter()
+    .flat_map(|(ship, count)| std::iter::repeat_n(ship, count as usize))
+    .collect()
+}
+
+/// A ship sent in by the [`WaveDirector`], from its warp portal on. Only these hold a wave open.
+#[derive(Component)]
+pub struct WaveMember;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WaveEvent {
+    Incoming(u32),
+    Cleared(u32),
+}
+
+enum WavePhase {
+    Breather(Timer),
+    Fighting,
+}
+
+/// Where the run is in its waves. Only there while wave mode is on.
+#[derive(Resource)]
+pub struct WaveDirector {
+    /// The wave being fought, or the last one cleared during a breather
+    pub wave: u32,
+    phase: WavePhase,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            phase: WavePhase::Breather(Timer::new(FIRST_WAVE_DELAY, TimerMode::Once)),
+        }
+    }
+}
+
+impl WaveDirector {
+    pub fn fighting(&self) -> bool {
+        matches!(self.phase, WavePhase::Fighting)
+    }
+
+    /// Moves the waves along given how many enemies are still out, saying when one starts or ends
+    pub fn tick(&mut self, delta: Duration, enemies_left: usize) -> Option<WaveEvent> {
+        match &mut self.phase {
+            WavePhase::Fighting if enemies_left == 0 => {
+                self.phase = WavePhase::Breather(Timer::new(WAVE_BREATHER, TimerMode::Once));
+                Some(WaveEvent::Cleared(self.wave))
+            }
+            WavePhase::Fighting => None,
+            WavePhase::Breather(timer) => {
+                if !timer.tick(delta).finished() {
+                    return None;
+                }
+                self.wave += 1;
+                self.phase = WavePhase::Fighting;
+                Some(WaveEvent::Incoming(self.wave))
+            }
+        }
+    }
+}
+
+fn start_waves(mut commands: Commands, settings: Res<Settings>) {
+    if settings.wave_mode {
+        commands.insert_resource(WaveDirector::default());
+    }
+}
+
+fn stop_waves(mut commands: Commands) {
+    commands.remove_resource::<WaveDirector>();
+}
+
+/// A wave is cleared once its ships are all gone, taken or waiting on the capture choice. Anything
+/// else out there, like a convoy or a carrier's drones, doesn't hold it up.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn run_waves(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut director: ResMut<WaveDirector>,
+    enemies: Query<
+        (),
+        (
+            With<WaveMember>,
+            With<Spacecraft>,
+            Without<PlayerMarker>,
+            Without<Captured>,
+            Without<MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker>,
+        ),
+    >,
+    warping: Query<(), (With<WaveMember>, With<WarpIn>)>,
+    player: Query<&Spacecraft, With<PlayerMarker>>,
+    spectating: Option<Res<Spectating>>,
+    portal_texture: Res<WarpPortalTexture>,
+    camera: Query<&Transform, With<Camera2d>>,
+    window: Query<&Window>,
+    score: Res<PlayerScore>,
+    mut stats: ResMut<RunStats>,
+    mut score_events: EventWriter<ScoreEvent>,
+    mut dialogue: ResMut<Dialogue>,
+    mut seed: ResMut<RunSeed>,
+) {
+    let Some(focus) = focus_position(&player, &spectating) else {
+        return;
+    };
+    let enemies_left = enemies.iter().count() + warping.iter().count();
+    match director.tick(time.delta(), enemies_left) {
+        Some(WaveEvent::Incoming(wave)) => {
+            let view = CameraView::find(&camera, &window);
+            for ship in wave_composition(wave) {
+                let portal = spawn_enemy(
+                    &mut commands,
+                    focus,
+                    ship,
+                    &portal_texture,
+                    view.as_ref(),
+                    seed.rng(),
+                );
+                commands.entity(portal).insert(WaveMember);
+                stats.record(&score, TimelineEvent::EnemySpawned { ship });
+            }
+            dialogue.queue_lines([format!("Wave {wave} incoming.").into()]);
+        }
+        Some(WaveEvent::Cleared(wave)) => {
+            score_events.send(ScoreEvent {
+                source: ScoreSource::Objectives,
+                points: WAVE_BONUS * wave,
+            });
+            dialogue.queue_lines([format!("Wave {wave} cleared. Catch your breath.").into()]);
+        }
+        None => {}
+    }
+}
+
+#[derive(Component)]
+pub struct WaveModeTextMarker;
+
+fn wave_mode_text(settings: &Settings) -> String {
+    let state = match settings.wave_mode {
+        true => "On",
+        false => "Off",
+    };
+    format!("[V] Waves: {state}")
+}
+
+fn spawn_wave_mode_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(345.),
+                right: Val::Px(15.),
+                ..default()
+            },
+            ..TextBundle::from_section(
+                wave_mode_text(&settings),
+                TextStyle {
+                    font: asset_server.load("alphbeta.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            )
+        })
+        .insert((MainMenuMarker, WaveModeTextMarker));
+}
+
+/// Also catches the settings being swapped out by a profile switch
+fn toggle_wave_mode(
+    inputs: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut label: Query<&mut Text, With<WaveModeTextMarker>>,
+) {
+    if inputs.just_pressed(KeyCode::KeyV) {
+        settings.wave_mode = !settings.wave_mode;
+    }
+    if settings.is_changed() {
+        if let Ok(mut label) = label.get_single_mut() {
+            label.sections[0].value = wave_mode_text(&settings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{testing::test_app, CarryoverEnemyPoints};
+    use crate::ui::{update_incoming_ui, EnemiesRemainingMarker};
+    use bevy::{ecs::entity::Entity, math::Vec2, text::Text};
+
+    #[test]
+    fn waves_grow_and_give_a_breather_once_cleared() {
+        assert_eq!(wave_composition(1), vec![ShipType::Ship1; 3]);
+        assert!(wave_composition(10).contains(&ShipType::Ship6));
+        assert!(wave_composition(5).len() > wave_composition(2).len());
+
+        let mut director = WaveDirector::default();
+        assert_eq!(director.tick(FIRST_WAVE_DELAY / 2, 0), None);
+        assert_eq!(
+            director.tick(FIRST_WAVE_DELAY, 0),
+            Some(WaveEvent::Incoming(1))
+        );
+        // Nothing happens while the wave is still out
+        assert_eq!(director.tick(WAVE_BREATHER, 3), None);
+        assert!(director.fighting());
+        assert_eq!(
+            director.tick(Duration::ZERO, 0),
+            Some(WaveEvent::Cleared(1))
+        );
+        assert!(!director.fighting());
+        assert_eq!(director.tick(WAVE_BREATHER / 2, 0), None);
+        assert_eq!(
+            director.tick(WAVE_BREATHER, 0),
+            Some(WaveEvent::Incoming(2))
+        );
+    }
+
+    #[test]
+    fn only_the_ships_a_wave_sent_hold_it_open() {
+        let mut app = test_app();
+        app.insert_resource(WaveDirector::default())
+            .insert_resource(Dialogue::init())
+            .insert_resource(RunSeed::new(7))
+            .insert_resource(CarryoverEnemyPoints(0))
+            .add_systems(Update, (run_waves, update_incoming_ui).chain());
+        let readout = app
+            .world
+            .spawn((Text::from_section("", default()), EnemiesRemainingMarker))
+            .id();
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ZERO),
+            PlayerMarker,
+        ));
+        for _ in 0..FIRST_WAVE_DELAY.as_secs() + 1 {
+            app.update();
+        }
+        assert!(app.world.resource::<WaveDirector>().fighting());
+        let mut portals = app
+            .world
+            .query_filtered::<Entity, (With<WarpIn>, With<WaveMember>)>();
+        let portals: Vec<_> = portals.iter(&app.world).collect();
+        assert!(!portals.is_empty());
+
+        // The wave warps in and is put down, bar one left disabled, while a stray enemy flies on
+        for portal in portals {
+            app.world.despawn(portal);
+        }
+        app.world.spawn((
+            Spacecraft::from_template(ShipType::Ship1, Vec2::ONE),
+            WaveMember,
+            MyFateLiesInTheBalanceAndIWouldReallyAppreciateIfIfYouDidntKillMeMarker,
+        ));
+        app.world
+            .spawn(Spacecraft::from_template(ShipType::Ship2, -Vec2::ONE));
+        app.update();
+        let director = app.world.resource::<WaveDirector>();
+        assert!(!director.fighting());
+        assert_eq!(director.wave, 1);
+        let readout = &app.world.get::<Text>(readout).unwrap().sections[0].value;
+        assert_eq!(readout, "Wave 1  Enemies: 2");
+    }
+}
@@ -0,0 +1,331 @@
+//! Clouds of dust drift slowly about the arena. Ships inside one are hard to make out, so they
+//! dim, and AI ships have to get much closer to pick them out, letting the player duck into one
+//! to shake a pack off. The nebula's haze thins how far anything can see as well, the thicker it
+//! gets. The clouds come from the run's seed, so a replayed run has the same weather.
+
+use bevy::{
+    app::{Plugin, Startup, Update},
+    asset::{Assets, Handle},
+    ecs::{
+        component::Component,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    math::{Quat, Vec2},
+    prelude::{default, App},
+    render::{
+        color::Color,
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::Image,
+    },
+    sprite::{Sprite, SpriteBundle},
+    time::Time,
+    transform::components::Transform,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    gameplay::{
+        remember_persistent_entities, GameState, GameplaySet, Spacecraft, BORDER_WARNING_RADIUS,
+        PIXELS_PER_UNIT,
+    },
+    nebula::{nebula_tint, Danger},
+    practice::CapturePractice,
+    range::TargetRange,
+    seed::{roll_run_seed, RunSeed},
+    GameLifecycleState,
+};
+
+const DUST_CLOUDS: usize = 6;
+/// How much of a ship can be made out right in the middle of a cloud
+pub const DUST_MIN_VISIBILITY: f32 = 0.2;
+/// How much the nebula at its thickest takes off visibility everywhere
+pub const NEBULA_HAZE: f32 = 0.25;
+/// Clouds start at least this far out, so nobody spawns into one
+const CLEAR_START_RADIUS: f32 = 2.;
+const DUST_COLOR: Color = Color::rgb(0.75, 0.7, 0.6);
+const DUST_LAYERS: usize = 3;
+const DUST_LAYER_ALPHA: f32 = 0.22;
+const DUST_TEXTURE_SIZE: u32 = 64;
+/// Kept apart from the spawn rolls, so the weather doesn't change where enemies come in
+const DUST_SALT: u64 = 0xD057;
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, make_dust_texture)
+            .add_systems(
+                OnEnter(GameLifecycleState::Game),
+                spawn_dust_clouds
+                    .after(remember_persistent_entities)
+                    .after(roll_run_seed),
+            )
+            .add_systems(
+                Update,
+                drift_dust_clouds
+                    .in_set(GameplaySet::Simulation)
+                    .run_if(in_state(GameLifecycleState::Game))
+                    .run_if(in_state(GameState::Regular)),
+            )
+            .add_systems(
+                Update,
+                (draw_dust_clouds, dim_ships_in_dust)
+                    .in_set(GameplaySet::Presentation)
+                    .run_if(in_state(GameLifecycleState::Game)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DustCloud {
+    pub centre: Vec2,
+    pub radius: f32,
+    /// World units per second
+    pub drift: Vec2,
+}
+
+impl DustCloud {
+    /// Thickest in the middle, thinning out to nothing at the edge
+    pub fn visibility_at(&self, position: Vec2) -> f32 {
+        let depth = position.distance(self.centre) / self.radius;
+        match depth < 1. {
+            true => DUST_MIN_VISIBILITY + (1. - DUST_MIN_VISIBILITY) * depth * depth,
+            false => 1.,
+        }
+    }
+}
+
+/// The run's dust, rolled fresh with every seed
+#[derive(Resource, Default)]
+pub struct DustClouds(pub Vec<DustCloud>);
+
+impl DustClouds {
+    pub fn generate(seed: u32) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed as u64 ^ DUST_SALT);
+        Self(
+            (0..DUST_CLOUDS)
+                .map(|_| {
+                    let angle = rng.gen_range(0. ..std::f32::consts::TAU);
+                    let distance = rng.gen_range(CLEAR_START_RADIUS..BORDER_WARNING_RADIUS - 1.);
+                    let heading = rng.gen_range(0. ..std::f32::consts::TAU);
+                    DustCloud {
+                        centre: Vec2::from_angle(angle) * distance,
+                        radius: rng.gen_range(0.8..1.6),
+                        drift: Vec2::from_angle(heading) * rng.gen_range(0.02..0.05),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Where clouds overlap it's thicker, though never worse than the middle of one
+    pub fn visibility_at(&self, position: Vec2) -> f32 {
+        self.0
+            .iter()
+            .map(|cloud| cloud.visibility_at(position))
+            .product::<f32>()
+            .max(DUST_MIN_VISIBILITY)
+    }
+}
+
+/// How much of a ship here can be made out, from 1 in clear space, counting the nebula's haze
+pub fn visibility(position: Vec2, dust: Option<&DustClouds>, danger: f32) -> f32 {
+    let dust = dust.map_or(1., |dust| dust.visibility_at(position));
+    dust * (1. - NEBULA_HAZE * danger)
+}
+
+/// Whichever end is deeper in the dust decides how well the two ships see each other
+pub fn sight_between(observer: Vec2, target: Vec2, dust: Option<&DustClouds>, danger: f32) -> f32 {
+    visibility(observer, dust, danger).min(visibility(target, dust, danger))
+}
+
+#[derive(Resource)]
+pub struct DustTexture(pub Handle<Image>);
+
+/// One layer of a cloud, turning and wandering slowly around its middle
+#[derive(Component)]
+pub struct DustLayer {
+    pub cloud: usize,
+    pub layer: usize,
+}
+
+/// A soft round puff, fading out from the middle
+fn make_dust_texture(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = DUST_TEXTURE_SIZE;
+    let half = size as f32 / 2.;
+    let data = (0..size * size)
+        .flat_map(|pixel| {
+            let offset = Vec2::new((pixel % size) as f32, (pixel / size) as f32) - half + 0.5;
+            let falloff = (1. - offset.length() / half).max(0.);
+            [255, 255, 255, (falloff * falloff * 255.) as u8]
+        })
+        .collect();
+    let image = Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    commands.insert_resource(DustTexture(images.add(image)));
+}
+
+/// The range and capture practice are kept clear, as they're there to learn on
+fn spawn_dust_clouds(
+    mut commands: Commands,
+    seed: Res<RunSeed>,
+    texture: Res<DustTexture>,
+    range: Option<Res<TargetRange>>,
+    practice: Option<Res<CapturePractice>>,
+) {
+    let clouds = match range.is_some() || practice.is_some() {
+        true => DustClouds::default(),
+        false => DustClouds::generate(seed.seed),
+    };
+    for (cloud_index, cloud) in clouds.0.iter().enumerate() {
+        for layer in 0..DUST_LAYERS {
+            // Each layer a little bigger than the last, so the edge is ragged rather than a disc
+            let size = 2. * cloud.radius * (1. + 0.15 * layer as f32) * PIXELS_PER_UNIT;
+            commands.spawn((
+                SpriteBundle {
+                    texture: texture.0.clone(),
+                    sprite: Sprite {
+                        custom_size: Some(size),
+                        color: DUST_COLOR.with_a(DUST_LAYER_ALPHA),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        (cloud.centre * PIXELS_PER_UNIT).extend(20.),
+                    ),
+                    ..default()
+                },
+                DustLayer {
+                    cloud: cloud_index,
+                    layer,
+                },
+            ));
+        }
+    }
+    commands.insert_resource(clouds);
+}
+
+/// Clouds drifting out past the warning ring turn back in, so the arena keeps its weather
+pub fn drift_dust_clouds(time: Res<Time>, mut clouds: ResMut<DustClouds>) {
+    for cloud in clouds.0.iter_mut() {
+        cloud.centre += cloud.drift * time.delta_seconds();
+        let outward = cloud.centre.normalize_or_zero();
+        if cloud.centre.length() > BORDER_WARNING_RADIUS && cloud.drift.dot(outward) > 0. {
+            cloud.drift -= 2. * cloud.drift.dot(outward) * outward;
+        }
+    }
+}
+
+/// Each layer turns and wanders at its own rate, so the cloud seems to roll
+fn draw_dust_clouds(
+    time: Res<Time>,
+    clouds: Option<Res<DustClouds>>,
+    danger: Option<Res<Danger>>,
+    mut layers: Query<(&DustLayer, &mut Transform, &mut Sprite)>,
+) {
+    let Some(clouds) = clouds else {
+        return;
+    };
+    let elapsed = time.elapsed_seconds();
+    let tint = nebula_tint(danger.map_or(0., |danger| danger.0));
+    let color = Color::rgba(
+        DUST_COLOR.r() * tint.r(),
+        DUST_COLOR.g() * tint.g(),
+        DUST_COLOR.b() * tint.b(),
+        DUST_LAYER_ALPHA,
+    );
+    for (layer, mut transform, mut sprite) in layers.iter_mut() {
+        let Some(cloud) = clouds.0.get(layer.cloud) else {
+            continue;
+        };
+        let rate = 0.05 * (layer.layer as f32 + 1.);
+        let phase = layer.cloud as f32 * 1.7 + layer.layer as f32 * 2.1;
+        let wander = Vec2::from_angle(elapsed * rate + phase) * cloud.radius * 0.2;
+        let position = (cloud.centre + wander) * PIXELS_PER_UNIT;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        let turn = match layer.layer % 2 {
+            0 => 1.,
+            _ => -1.,
+        };
+        transform.rotation = Quat::from_rotation_z(turn * elapsed * rate + phase);
+        sprite.color = color;
+    }
+}
+
+/// Only the alpha is touched, so a variant's tint shows through
+fn dim_ships_in_dust(
+    clouds: Option<Res<DustClouds>>,
+    mut ships: Query<(&Spacecraft, &mut Sprite)>,
+) {
+    let Some(clouds) = clouds else {
+        return;
+    };
+    for (ship, mut sprite) in ships.iter_mut() {
+        sprite.color.set_a(clouds.visibility_at(ship.position));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{handle_npc_logic, testing::test_app, NPCLogic, ShipType};
+    use crate::settings::Settings;
+    use std::{f32::consts::PI, time::Duration};
+
+    #[test]
+    fn enemies_lose_the_player_in_the_dust_and_follow_the_trail() {
+        let cloud = DustCloud {
+            centre: Vec2::ZERO,
+            radius: 1.,
+            drift: Vec2::ZERO,
+        };
+        let dust = DustClouds(vec![cloud]);
+        assert_eq!(dust.visibility_at(Vec2::new(0., 2.)), 1.);
+        assert!(dust.visibility_at(Vec2::new(0., 0.5)) < dust.visibility_at(Vec2::new(0., 0.9)));
+        // Clear space, but deep in the nebula
+        let hazy = sight_between(Vec2::new(0., 3.), Vec2::new(0., 4.), Some(&dust), 1.);
+        assert!((hazy - (1. - NEBULA_HAZE)).abs() < 1e-5);
+
+        let mut app = test_app();
+        app.insert_resource(Settings::default())
+            .insert_resource(dust)
+            .add_systems(Update, handle_npc_logic);
+        app.update();
+        let mut craft = Spacecraft::from_template(ShipType::Ship1, Vec2::new(0., 1.5));
+        craft.heading = PI;
+        craft.weapon_cooldown.tick(Duration::from_secs(10));
+        let enemy = app.world.spawn((craft, NPCLogic::new(Vec2::ZERO))).id();
+        app.update();
+        // Well inside the range it would see them from in clear space
+        assert!(app
+            .world
+            .get::<NPCLogic>(enemy)
+            .unwrap()
+            .last_seen()
+            .is_none());
+
+        app.insert_resource(DustClouds::default());
+        app.update();
+        let logic = app.world.get::<NPCLogic>(enemy).unwrap();
+        assert_eq!(logic.last_seen(), Some(Vec2::ZERO));
+
+        // Lost again, it heads for the last sighting until it's reached
+        let mut logic = NPCLogic::new(Vec2::ZERO);
+        assert!(logic.track(Vec2::ZERO, Vec2::new(0., 2.), 1.));
+        assert!(!logic.track(Vec2::ZERO, Vec2::new(0., 4.), 0.2));
+        assert_eq!(logic.last_seen(), Some(Vec2::new(0., 2.)));
+        assert!(!logic.track(Vec2::new(0., 1.9), Vec2::new(0., 4.), 0.2));
+        assert!(logic.last_seen().is_none());
+    }
+}
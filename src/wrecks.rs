@@ -0,0 +1,253 @@
+//! Big enemy hulls don't vanish the moment they're shot down. Their wreck hangs where they died for
+//! a while and stops bullets from either side, so a fresh kill makes for cover. It only takes so
+//! much though, and crumbles after [`WRECK_DURABILITY`] hits or once [`WRECK_LIFETIME`] is up.
+//! Wrecks sit in [`WRECK_GROUP`], which only meets bullets, so ships fly straight through them.
+
+use std::time::Duration;
+
+use bevy::{
+    app::{Plugin, Update},
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnExit},
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::{Quat, Vec3},
+    prelude::{default, App},
+    render::color::Color,
+    sprite::{Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+};
+use bevy_rapier2d::{
+    geometry::{ActiveCollisionTypes, ActiveEvents, ActiveHooks, Collider, CollisionGroups},
+    pipeline::CollisionEvent,
+};
+use rand::Rng;
+
+use crate::{
+    events::{Allegiance, ShipDestroyed},
+    gameplay::{
+        collide_bullets, Bullet, GameState, GameplaySet, ShipProfile, ShipTextures, ShipType,
+        SpentBullets, BULLET_GROUP, PIXELS_PER_UNIT, WRECK_GROUP,
+    },
+    GameLifecycleState,
+};
+
+/// Smallest hull that leaves a wreck behind
+pub const WRECK_TIER: u8 = 4;
+/// Hits a wreck soaks up before it crumbles
+pub const WRECK_DURABILITY: u32 = 8;
+/// How long a wreck holds together if nobody shoots it apart
+pub const WRECK_LIFETIME: Duration = Duration::from_secs(12);
+const WRECK_COLOR: Color = Color::rgb(0.35, 0.3, 0.3);
+
+pub struct WreckagePlugin;
+
+impl Plugin for WreckagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                hit_wrecks
+                    .after(collide_bullets)
+                    .in_set(GameplaySet::Collision),
+                (crumble_wrecks, leave_wrecks)
+                    .chain()
+                    .in_set(GameplaySet::Cleanup),
+            )
+                .run_if(in_state(GameLifecycleState::Game))
+                .run_if(in_state(GameState::Regular)),
+        )
+        .add_systems(OnExit(GameLifecycleState::Game), clear_wrecks);
+    }
+}
+
+/// What's left of a big ship, for a little while
+#[derive(Component)]
+pub struct Wreck {
+    pub durability: u32,
+    lifetime: Timer,
+}
+
+impl Default for Wreck {
+    fn default() -> Self {
+        Self {
+            durability: WRECK_DURABILITY,
+            lifetime: Timer::new(WRECK_LIFETIME, TimerMode::Once),
+        }
+    }
+}
+
+impl Wreck {
+    /// Takes a bullet, and says whether that was the one that broke it up
+    pub fn absorb(&mut self) -> bool {
+        self.durability = self.durability.saturating_sub(1);
+        self.durability == 0
+    }
+
+    pub fn crumbled(&self) -> bool {
+        self.durability == 0 || self.lifetime.finished()
+    }
+}
+
+pub fn leaves_wreck(ship_type: ShipType) -> bool {
+    ship_type.tier() >= WRECK_TIER
+}
+
+fn leave_wrecks(
+    mut commands: Commands,
+    mut destroyed: EventReader<ShipDestroyed>,
+    textures: Res<ShipTextures>,
+) {
+    let mut rand = rand::thread_rng();
+    for ship in destroyed.read() {
+        if ship.allegiance != Allegiance::Enemy || !leaves_wreck(ship.ship_type) {
+            continue;
+        }
+        let heading = rand.gen_range(0. ..std::f32::consts::TAU);
+        commands.spawn((
+            SpriteBundle {
+                texture: textures.texture(ship.ship_type),
+                sprite: Sprite {
+                    color: WRECK_COLOR,
+                    ..default()
+                },
+                transform: Transform::from_translation(
+                    (ship.position * PIXELS_PER_UNIT).extend(4.),
+                )
+                .with_scale(Vec3::splat(
+                    ShipProfile::of(ship.ship_type, None).relative_scale,
+                ))
+                .with_rotation(Quat::from_rotation_z(heading)),
+                ..default()
+            },
+            Collider::cuboid(40., 20.),
+            ActiveEvents::all(),
+            ActiveHooks::all(),
+            ActiveCollisionTypes::STATIC_STATIC,
+            CollisionGroups::new(WRECK_GROUP, BULLET_GROUP),
+            Wreck::default(),
+            Name::new("Wreck"),
+        ));
+    }
+}
+
+/// Bullets stop dead against a wreck, whoever fired them, unless they've already hit a ship
+pub fn hit_wrecks(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut wrecks: Query<&mut Wreck>,
+    bullets: Query<(), With<Bullet>>,
+    mut spent: ResMut<SpentBullets>,
+) {
+    for event in collision_events.read() {
+        if let CollisionEvent::Started(a, b, _) = event {
+            for (bullet, target) in [(*a, *b), (*b, *a)] {
+                if spent.0.contains(&bullet) || !bullets.contains(bullet) {
+                    continue;
+                }
+                if let Ok(mut wreck) = wrecks.get_mut(target) {
+                    wreck.absorb();
+                    commands.entity(bullet).despawn();
+                    spent.0.push(bullet);
+                }
+            }
+        }
+    }
+}
+
+/// Wrecks fade as they take damage and age, and go once they've crumbled
+pub fn crumble_wrecks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut wrecks: Query<(Entity, &mut Wreck, Option<&mut Sprite>)>,
+) {
+    for (entity, mut wreck, sprite) in wrecks.iter_mut() {
+        wreck.lifetime.tick(time.delta());
+        if wreck.crumbled() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if let Some(mut sprite) = sprite {
+            let integrity = wreck.durability as f32 / WRECK_DURABILITY as f32;
+            let fade = (wreck.lifetime.remaining_secs() / 3.).min(1.);
+            sprite.color = WRECK_COLOR.with_a(0.4 + 0.6 * integrity.min(fade));
+        }
+    }
+}
+
+fn clear_wrecks(mut commands: Commands, wrecks: Query<Entity, With<Wreck>>) {
+    for entity in wrecks.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{
+        testing::{stray_bullet, test_app},
+        Spacecraft,
+    };
+    use crate::status::ApplyStatus;
+    use bevy::math::Vec2;
+    use bevy_rapier2d::rapier::geometry::CollisionEventFlags;
+
+    #[test]
+    fn wrecks_soak_up_a_few_hits_then_crumble() {
+        assert!(leaves_wreck(ShipType::Carrier));
+        assert!(!leaves_wreck(ShipType::Drone));
+
+        let mut wreck = Wreck::default();
+        for _ in 1..WRECK_DURABILITY {
+            assert!(!wreck.absorb());
+        }
+        assert!(wreck.absorb());
+        assert!(wreck.crumbled());
+
+        let mut app = test_app();
+        app.add_systems(Update, crumble_wrecks);
+        let shot_up = app.world.spawn(wreck).id();
+        let fresh = app.world.spawn(Wreck::default()).id();
+        app.update();
+        assert!(app.world.get_entity(shot_up).is_none());
+        assert!(app.world.get_entity(fresh).is_some());
+
+        // Left alone, it still breaks up in the end
+        for _ in 0..WRECK_LIFETIME.as_secs() {
+            app.update();
+        }
+        assert!(app.world.get_entity(fresh).is_none());
+
+        // A bullet that lands on a ship in front of a wreck is spent on the ship alone, even before
+        // its despawn has gone through
+        app.add_event::<CollisionEvent>()
+            .add_event::<ApplyStatus>()
+            .add_systems(
+                Update,
+                (collide_bullets, hit_wrecks).chain_ignore_deferred(),
+            );
+        let wreck = app.world.spawn(Wreck::default()).id();
+        let ship = app
+            .world
+            .spawn(Spacecraft::from_template(ShipType::Ship3, Vec2::ZERO))
+            .id();
+        let shooter = app.world.spawn_empty().id();
+        let bullet = app.world.spawn(stray_bullet(shooter)).id();
+        for (a, b) in [(ship, bullet), (bullet, wreck)] {
+            app.world
+                .send_event(CollisionEvent::Started(a, b, CollisionEventFlags::empty()));
+        }
+        app.update();
+        assert!(app.world.get_entity(bullet).is_none());
+        assert_eq!(
+            app.world.get::<Wreck>(wreck).unwrap().durability,
+            WRECK_DURABILITY
+        );
+    }
+}